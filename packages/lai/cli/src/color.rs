@@ -0,0 +1,134 @@
+use nu_ansi_term::Color;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+
+/// Whether output is colored, decided once at startup and passed to display code.
+/// stdout and stderr are checked separately since either may be redirected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorConfig {
+    enabled: bool,
+    stderr_enabled: bool,
+}
+
+impl ColorConfig {
+    /// Colors are off with `--no-color`, a non-empty `NO_COLOR` (https://no-color.org),
+    /// or when the stream being written is not a terminal
+    pub fn new(no_color_flag: bool) -> Self {
+        Self::detect(
+            no_color_flag,
+            env::var_os("NO_COLOR"),
+            io::stdout().is_terminal(),
+            io::stderr().is_terminal(),
+        )
+    }
+
+    fn detect(
+        no_color_flag: bool,
+        no_color_env: Option<OsString>,
+        stdout_tty: bool,
+        stderr_tty: bool,
+    ) -> Self {
+        let env_disabled = no_color_env.is_some_and(|v| !v.is_empty());
+        let allowed = !no_color_flag && !env_disabled;
+        Self {
+            enabled: allowed && stdout_tty,
+            stderr_enabled: allowed && stderr_tty,
+        }
+    }
+
+    /// Never colors, so tests can compare output strings
+    #[cfg(test)]
+    pub fn plain() -> Self {
+        Self {
+            enabled: false,
+            stderr_enabled: false,
+        }
+    }
+
+    fn paint(&self, color: Color, text: impl Display) -> String {
+        paint_if(self.enabled, color, text)
+    }
+
+    /// Assistant replies
+    pub fn response(&self, text: impl Display) -> String {
+        self.paint(Color::Cyan, text)
+    }
+
+    /// Error messages, which are written to stderr
+    pub fn error(&self, text: impl Display) -> String {
+        paint_if(self.stderr_enabled, Color::Red, text)
+    }
+
+    /// Field names and section headers
+    pub fn label(&self, text: impl Display) -> String {
+        self.paint(Color::Yellow, text)
+    }
+
+    /// Loading indicator dots
+    pub fn progress(&self, text: impl Display) -> String {
+        self.paint(Color::Green, text)
+    }
+
+    /// An exit code, green for success and red otherwise
+    pub fn exit_code(&self, code: i32) -> String {
+        if code == 0 {
+            self.paint(Color::Green, code)
+        } else {
+            self.paint(Color::Red, code)
+        }
+    }
+}
+
+fn paint_if(enabled: bool, color: Color, text: impl Display) -> String {
+    if enabled {
+        color.paint(text.to_string()).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_honors_flag_env_and_terminal() {
+        assert!(ColorConfig::detect(false, None, true, true).enabled);
+        assert!(!ColorConfig::detect(true, None, true, true).enabled);
+        assert!(!ColorConfig::detect(true, None, true, true).stderr_enabled);
+        assert!(!ColorConfig::detect(false, Some("1".into()), true, true).enabled);
+        assert!(!ColorConfig::detect(false, Some("1".into()), true, true).stderr_enabled);
+        // An empty NO_COLOR does not disable colors
+        assert!(ColorConfig::detect(false, Some("".into()), true, true).enabled);
+        assert!(!ColorConfig::detect(false, None, false, true).enabled);
+
+        // Piping stdout keeps errors on the terminal colored, and the reverse
+        let piped_stdout = ColorConfig::detect(false, None, false, true);
+        assert!(piped_stdout.stderr_enabled);
+        let piped_stderr = ColorConfig::detect(false, None, true, false);
+        assert!(piped_stderr.enabled);
+        assert!(!piped_stderr.stderr_enabled);
+    }
+
+    #[test]
+    fn test_paint_only_when_enabled() {
+        let colors = ColorConfig::detect(false, None, true, true);
+        assert_eq!(colors.error("boom"), "\x1b[31mboom\x1b[0m");
+        assert_eq!(colors.exit_code(0), "\x1b[32m0\x1b[0m");
+        assert_eq!(colors.exit_code(2), "\x1b[31m2\x1b[0m");
+
+        // error() follows stderr; everything else follows stdout
+        let stdout_only = ColorConfig::detect(false, None, true, false);
+        assert_eq!(stdout_only.error("boom"), "boom");
+        assert_eq!(stdout_only.exit_code(2), "\x1b[31m2\x1b[0m");
+        let stderr_only = ColorConfig::detect(false, None, false, true);
+        assert_eq!(stderr_only.error("boom"), "\x1b[31mboom\x1b[0m");
+        assert_eq!(stderr_only.label("Model"), "Model");
+
+        let plain = ColorConfig::plain();
+        assert_eq!(plain.error("boom"), "boom");
+        assert_eq!(plain.exit_code(2), "2");
+    }
+}
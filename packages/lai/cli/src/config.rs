@@ -0,0 +1,206 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const DEFAULT_IPC_PORT: u16 = 39871;
+pub const DEFAULT_IPC_TIMEOUT_SECS: u64 = 10;
+
+/// Keys accepted by `lai config get/set`
+pub const CONFIG_KEYS: &[&str] = &[
+    "default_provider",
+    "default_model",
+    "ipc_port",
+    "ipc_timeout_secs",
+    "output_format",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+/// Persistent CLI settings stored at `$XDG_CONFIG_HOME/lai/config.toml`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CliConfig {
+    pub default_provider: Option<String>,
+    pub default_model: Option<String>,
+    pub ipc_port: u16,
+    pub ipc_timeout_secs: u64,
+    pub output_format: OutputFormat,
+}
+
+impl Default for CliConfig {
+    fn default() -> Self {
+        Self {
+            default_provider: None,
+            default_model: None,
+            ipc_port: DEFAULT_IPC_PORT,
+            ipc_timeout_secs: DEFAULT_IPC_TIMEOUT_SECS,
+            output_format: OutputFormat::Text,
+        }
+    }
+}
+
+impl CliConfig {
+    /// Load the config file, creating it with defaults if it doesn't exist
+    pub fn load() -> Result<Self, String> {
+        let path = config_path()?;
+        if !path.exists() {
+            let config = Self::default();
+            config.save()?;
+            return Ok(config);
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn ipc_addr(&self) -> String {
+        format!("127.0.0.1:{}", self.ipc_port)
+    }
+
+    pub fn ipc_timeout(&self) -> Duration {
+        Duration::from_secs(self.ipc_timeout_secs)
+    }
+
+    /// Get a config value as a string; unset optional values are empty
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        let value = match key {
+            "default_provider" => self.default_provider.clone().unwrap_or_default(),
+            "default_model" => self.default_model.clone().unwrap_or_default(),
+            "ipc_port" => self.ipc_port.to_string(),
+            "ipc_timeout_secs" => self.ipc_timeout_secs.to_string(),
+            "output_format" => self.output_format.as_str().to_string(),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
+
+    /// Set a config value from a string, validating its type.
+    /// An empty value clears `default_provider` / `default_model`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let optional = |v: &str| (!v.is_empty()).then(|| v.to_string());
+        match key {
+            "default_provider" => self.default_provider = optional(value),
+            "default_model" => self.default_model = optional(value),
+            "ipc_port" => {
+                self.ipc_port = match value.parse::<u16>() {
+                    Ok(port) if port > 0 => port,
+                    _ => return Err(format!("ipc_port must be 1-65535, got '{}'", value)),
+                }
+            }
+            "ipc_timeout_secs" => {
+                self.ipc_timeout_secs = match value.parse::<u64>() {
+                    Ok(secs) if secs > 0 => secs,
+                    _ => {
+                        return Err(format!(
+                            "ipc_timeout_secs must be a positive integer, got '{}'",
+                            value
+                        ))
+                    }
+                }
+            }
+            "output_format" => {
+                self.output_format = OutputFormat::from_str(value, true).map_err(|_| {
+                    format!("output_format must be 'text' or 'json', got '{}'", value)
+                })?
+            }
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> String {
+    format!(
+        "Unknown config key '{}'. Valid keys: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+/// Resolve `$XDG_CONFIG_HOME/lai/config.toml`, falling back to `~/.config`
+pub fn config_path() -> Result<PathBuf, String> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config"))
+            .ok_or_else(|| "Could not determine config directory (HOME not set)".to_string())?,
+    };
+    Ok(base.join("lai").join("config.toml"))
+}
+
+static CONFIG: OnceLock<CliConfig> = OnceLock::new();
+
+/// Config for this invocation, loaded once. Falls back to defaults if the file is unusable.
+pub fn current() -> &'static CliConfig {
+    CONFIG.get_or_init(|| {
+        CliConfig::load().unwrap_or_else(|e| {
+            eprintln!("Warning: {}; using default settings", e);
+            CliConfig::default()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_validates_values() {
+        let mut config = CliConfig::default();
+
+        config.set("ipc_port", "40000").unwrap();
+        config.set("output_format", "JSON").unwrap();
+        config.set("default_model", "gpt-4").unwrap();
+        assert_eq!(config.get("ipc_port").unwrap(), "40000");
+        assert_eq!(config.get("output_format").unwrap(), "json");
+        assert_eq!(config.default_model.as_deref(), Some("gpt-4"));
+
+        assert!(config.set("ipc_port", "70000").is_err());
+        assert!(config.set("ipc_timeout_secs", "0").is_err());
+        assert!(config.set("output_format", "yaml").is_err());
+        assert!(config.set("no_such_key", "x").is_err());
+
+        config.set("default_model", "").unwrap();
+        assert_eq!(config.default_model, None);
+    }
+
+    #[test]
+    fn test_partial_file_uses_defaults() {
+        let config: CliConfig = toml::from_str("default_provider = \"ollama\"").unwrap();
+        assert_eq!(config.default_provider.as_deref(), Some("ollama"));
+        assert_eq!(config.ipc_port, DEFAULT_IPC_PORT);
+        assert_eq!(config.output_format, OutputFormat::Text);
+
+        let roundtrip: CliConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(roundtrip, config);
+    }
+}
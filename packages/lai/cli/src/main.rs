@@ -1,14 +1,31 @@
-use clap::{Parser, Subcommand};
+mod color;
+mod config;
+mod repl;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use color::ColorConfig;
+use config::{CliConfig, OutputFormat, CONFIG_KEYS};
 use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
 use std::env;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::net::TcpStream;
 use std::process::{Command, Stdio};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // Performance optimizations
-const IPC_TIMEOUT: Duration = Duration::from_secs(10);
 const BUFFER_SIZE: usize = 4096;
+/// Wire prefix the server uses for LZ4-compressed responses
+const COMPRESSED_PREFIX: &str = "z:";
+/// Features advertised to the server with every request
+const CAPABILITIES: &[&str] = &["lz4"];
+/// Longest title shown in the `lai history` table
+const HISTORY_TITLE_WIDTH: usize = 40;
+/// Read timeout while streaming a reply; longer than the server's 120 s idle limit
+/// so its error frame arrives first
+const STREAM_READ_TIMEOUT: Duration = Duration::from_secs(130);
 
 #[derive(Parser)]
 #[command(name = "lai")]
@@ -23,6 +40,10 @@ Examples:
   lai last
   lai capture \"npm test\" --analyze
   lai capture \"make build\" --timeout 60 --ai-analyze
+  lai diff <conversation-a> <conversation-b> --output json
+  lai history --limit 5 --provider openai
+  lai repl --model gpt-4
+  lai config set default_model gpt-4
   DEV_MODE=1 lai create \"Test assistant message\"
 
 For more information, see: https://github.com/tbmobb813/Linux-AI-Assistant---Project
@@ -30,6 +51,9 @@ For more information, see: https://github.com/tbmobb813/Linux-AI-Assistant---Pro
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Disable colored output (also honored via the NO_COLOR environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -53,6 +77,16 @@ enum Commands {
         /// Read from stdin if no message provided
         #[arg(long, default_value_t = false)]
         stdin: bool,
+        /// Print the response as it is generated (--stream=false waits for the whole reply)
+        #[arg(
+            long,
+            default_value_t = true,
+            action = ArgAction::Set,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        stream: bool,
     },
     /// Alias for 'ask' - send a question to the AI assistant
     Chat {
@@ -73,6 +107,16 @@ enum Commands {
         /// Read from stdin
         #[arg(long, default_value_t = false)]
         stdin: bool,
+        /// Print the response as it is generated
+        #[arg(
+            long,
+            default_value_t = true,
+            action = ArgAction::Set,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        stream: bool,
     },
     /// Analyze text from stdin (e.g., cat error.log | lai analyze)
     Analyze {
@@ -121,6 +165,66 @@ enum Commands {
         #[arg(long, default_value_t = false)]
         ai_analyze: bool,
     },
+    /// Compare the messages of two conversations
+    Diff {
+        /// First conversation (ID or title prefix)
+        conversation_a: String,
+        /// Second conversation (ID or title prefix)
+        conversation_b: String,
+        /// Only compare the messages at this zero-based position
+        #[arg(long)]
+        message_index: Option<usize>,
+        /// Output format (defaults to the configured output_format)
+        #[arg(long, value_enum)]
+        output: Option<OutputFormat>,
+    },
+    /// List recent conversations
+    History {
+        /// Maximum number of conversations to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Output format (defaults to json when output_format is json, else table)
+        #[arg(long, value_enum)]
+        format: Option<HistoryFormat>,
+        /// Only show conversations that use this provider
+        #[arg(long)]
+        provider: Option<String>,
+    },
+    /// Start an interactive chat session (/new, /history, /model <name>, /quit)
+    Repl {
+        /// Override the default model
+        #[arg(long)]
+        model: Option<String>,
+        /// Override the default provider
+        #[arg(long)]
+        provider: Option<String>,
+    },
+    /// View or change persistent CLI settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Set a config value (an empty value clears default_provider/default_model)
+    Set {
+        /// One of: default_provider, default_model, ipc_port, ipc_timeout_secs, output_format
+        key: String,
+        value: String,
+    },
+    /// Print a config value
+    Get { key: String },
+    /// Print all config values and the config file location
+    List,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum HistoryFormat {
+    Table,
+    Json,
+    Plain,
 }
 
 #[derive(Deserialize)]
@@ -140,6 +244,37 @@ struct Message {
     tokens_used: Option<i64>,
 }
 
+#[derive(Deserialize, Serialize, Clone)]
+struct Conversation {
+    id: String,
+    title: String,
+    model: String,
+    provider: String,
+    created_at: i64,
+    updated_at: i64,
+}
+
+#[derive(Deserialize)]
+struct ConversationMessages {
+    conversation: Conversation,
+    messages: Vec<Message>,
+}
+
+#[derive(Serialize)]
+struct DiffLine {
+    tag: &'static str,
+    line: String,
+}
+
+#[derive(Serialize)]
+struct MessageDiff {
+    index: usize,
+    role_a: Option<String>,
+    role_b: Option<String>,
+    status: &'static str,
+    changes: Vec<DiffLine>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct CaptureResult {
     command: String,
@@ -154,6 +289,7 @@ struct CaptureResult {
 
 fn main() {
     let cli = Cli::parse();
+    let colors = ColorConfig::new(cli.no_color);
 
     match &cli.command {
         Commands::Ask {
@@ -163,6 +299,7 @@ fn main() {
             new,
             gui,
             stdin,
+            stream,
         }
         | Commands::Chat {
             message,
@@ -171,11 +308,15 @@ fn main() {
             new,
             gui,
             stdin,
+            stream,
         } => {
             // Get message from argument or stdin
             let msg = if *stdin || message.is_none() {
                 read_stdin().unwrap_or_else(|e| {
-                    eprintln!("Failed to read from stdin: {}", e);
+                    eprintln!(
+                        "{}",
+                        colors.error(format_args!("Failed to read from stdin: {}", e))
+                    );
                     std::process::exit(1);
                 })
             } else {
@@ -183,11 +324,19 @@ fn main() {
             };
 
             if msg.is_empty() {
-                eprintln!("No message provided. Use --stdin to read from stdin, or provide a message argument.");
+                eprintln!("{}", colors.error("No message provided. Use --stdin to read from stdin, or provide a message argument."));
                 std::process::exit(1);
             }
 
-            handle_ask(&msg, model.as_deref(), provider.as_deref(), *new, *gui);
+            handle_ask(
+                &msg,
+                model.as_deref(),
+                provider.as_deref(),
+                *new,
+                *gui,
+                *stream,
+                colors,
+            );
         }
         Commands::Analyze {
             prompt,
@@ -196,12 +345,18 @@ fn main() {
             gui,
         } => {
             let stdin_content = read_stdin().unwrap_or_else(|e| {
-                eprintln!("Failed to read from stdin: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to read from stdin: {}", e))
+                );
                 std::process::exit(1);
             });
 
             if stdin_content.is_empty() {
-                eprintln!("No input from stdin. Usage: cat file.txt | lai analyze");
+                eprintln!(
+                    "{}",
+                    colors.error("No input from stdin. Usage: cat file.txt | lai analyze")
+                );
                 std::process::exit(1);
             }
 
@@ -217,11 +372,16 @@ fn main() {
                 provider.as_deref(),
                 false,
                 *gui,
+                true,
+                colors,
             );
         }
         Commands::Notify { message } => {
             if let Err(e) = send_ipc("notify", Some(message.as_str()), None) {
-                eprintln!("Failed to send notify: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to send notify: {}", e))
+                );
                 std::process::exit(1);
             }
         }
@@ -231,32 +391,38 @@ fn main() {
                     if let Some(data) = response.data {
                         match serde_json::from_value::<Message>(data) {
                             Ok(message) => {
-                                println!("{}", message.content);
+                                println!("{}", colors.response(&message.content));
                             }
                             Err(e) => {
-                                eprintln!("Failed to parse message: {}", e);
+                                eprintln!(
+                                    "{}",
+                                    colors.error(format_args!("Failed to parse message: {}", e))
+                                );
                                 std::process::exit(1);
                             }
                         }
                     } else {
-                        eprintln!("No data returned");
+                        eprintln!("{}", colors.error("No data returned"));
                         std::process::exit(1);
                     }
                 } else {
                     if let Some(data) = response.data {
                         if let Some(error) = data.get("error") {
-                            eprintln!("Error: {}", error);
+                            eprintln!("{}", colors.error(format_args!("Error: {}", error)));
                         } else {
-                            eprintln!("Error: {}", data);
+                            eprintln!("{}", colors.error(format_args!("Error: {}", data)));
                         }
                     } else {
-                        eprintln!("Unknown error");
+                        eprintln!("{}", colors.error("Unknown error"));
                     }
                     std::process::exit(1);
                 }
             }
             Err(e) => {
-                eprintln!("Failed to get last response: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to get last response: {}", e))
+                );
                 std::process::exit(1);
             }
         },
@@ -276,7 +442,10 @@ fn main() {
                 );
             }
             if let Err(e) = send_ipc("create", None, Some(serde_json::Value::Object(payload))) {
-                eprintln!("Failed to send create: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to send create: {}", e))
+                );
                 std::process::exit(1);
             } else {
                 // Ask for the created message back and print it
@@ -285,18 +454,34 @@ fn main() {
                         if resp.status == "ok" {
                             if let Some(data) = resp.data {
                                 match serde_json::from_value::<Message>(data) {
-                                    Ok(msg) => println!("{}", msg.content),
-                                    Err(e) => eprintln!("Failed to parse message: {}", e),
+                                    Ok(msg) => println!("{}", colors.response(&msg.content)),
+                                    Err(e) => eprintln!(
+                                        "{}",
+                                        colors
+                                            .error(format_args!("Failed to parse message: {}", e))
+                                    ),
                                 }
                             } else {
-                                eprintln!("No message data returned after creation.");
+                                eprintln!(
+                                    "{}",
+                                    colors.error("No message data returned after creation.")
+                                );
                             }
                         } else {
-                            eprintln!("Failed to fetch last message: status '{}'", resp.status);
+                            eprintln!(
+                                "{}",
+                                colors.error(format_args!(
+                                    "Failed to fetch last message: status '{}'",
+                                    resp.status
+                                ))
+                            );
                         }
                     }
                     Err(e) => {
-                        eprintln!("Failed to fetch last message: {}", e);
+                        eprintln!(
+                            "{}",
+                            colors.error(format_args!("Failed to fetch last message: {}", e))
+                        );
                     }
                 }
             }
@@ -310,16 +495,73 @@ fn main() {
         } => match execute_command(command, cwd.as_deref(), *timeout) {
             Ok(result) => {
                 if *analyze || *ai_analyze {
-                    display_capture_analysis(&result, *ai_analyze);
+                    display_capture_analysis(&result, *ai_analyze, colors);
                 } else {
-                    display_capture_result(&result);
+                    display_capture_result(&result, colors);
                 }
             }
             Err(e) => {
-                eprintln!("Failed to execute command: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to execute command: {}", e))
+                );
                 std::process::exit(1);
             }
         },
+        Commands::Diff {
+            conversation_a,
+            conversation_b,
+            message_index,
+            output,
+        } => {
+            let output = output.unwrap_or(config::current().output_format);
+            if let Err(e) = handle_diff(
+                conversation_a,
+                conversation_b,
+                *message_index,
+                output,
+                colors,
+            ) {
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to diff conversations: {}", e))
+                );
+                std::process::exit(1);
+            }
+        }
+        Commands::History {
+            limit,
+            format,
+            provider,
+        } => {
+            let format = format.unwrap_or(match config::current().output_format {
+                OutputFormat::Json => HistoryFormat::Json,
+                OutputFormat::Text => HistoryFormat::Table,
+            });
+            if let Err(e) = handle_history(*limit, format, provider.as_deref(), colors) {
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to list conversations: {}", e))
+                );
+                std::process::exit(1);
+            }
+        }
+        Commands::Repl { model, provider } => {
+            // Command-line flags take precedence over configured defaults
+            let config = config::current();
+            let model = model.clone().or_else(|| config.default_model.clone());
+            let provider = provider.clone().or_else(|| config.default_provider.clone());
+            if let Err(e) = repl::run(model, provider, colors) {
+                eprintln!("{}", colors.error(format_args!("REPL error: {}", e)));
+                std::process::exit(1);
+            }
+        }
+        Commands::Config { action } => {
+            if let Err(e) = handle_config(action) {
+                eprintln!("{}", colors.error(format_args!("Config error: {}", e)));
+                std::process::exit(1);
+            }
+        }
     }
 }
 
@@ -331,37 +573,48 @@ struct IpcMessage<'a> {
     message: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     payload: Option<serde_json::Value>,
+    capabilities: &'a [&'a str],
 }
 
-fn send_ipc(
-    kind: &str,
-    message: Option<&str>,
-    payload: Option<serde_json::Value>,
-) -> Result<(), String> {
-    let addr = "127.0.0.1:39871";
+/// Connect to the assistant's IPC server with the configured timeouts
+fn connect_ipc() -> Result<TcpStream, String> {
+    let addr = config::current().ipc_addr();
+    let timeout = config::current().ipc_timeout();
 
     // Optimized connection with timeouts and buffering
-    let socket_addr = addr.parse().map_err(|e| format!("Failed to parse address '{}': {}", addr, e))?;
-    let mut stream = TcpStream::connect_timeout(&socket_addr, IPC_TIMEOUT)
+    let socket_addr = addr
+        .parse()
+        .map_err(|e| format!("Failed to parse address '{}': {}", addr, e))?;
+    let stream = TcpStream::connect_timeout(&socket_addr, timeout)
         .map_err(|e| format!("connect {} failed: {}", addr, e))?;
 
     // Set timeouts for read/write operations
     stream
-        .set_read_timeout(Some(IPC_TIMEOUT))
+        .set_read_timeout(Some(timeout))
         .map_err(|e| format!("set read timeout failed: {}", e))?;
     stream
-        .set_write_timeout(Some(IPC_TIMEOUT))
+        .set_write_timeout(Some(timeout))
         .map_err(|e| format!("set write timeout failed: {}", e))?;
 
     // Disable Nagle's algorithm for lower latency
     stream
         .set_nodelay(true)
         .map_err(|e| format!("set nodelay failed: {}", e))?;
+    Ok(stream)
+}
 
+/// Write one request line to an open IPC connection
+fn write_ipc_message(
+    stream: &mut TcpStream,
+    kind: &str,
+    message: Option<&str>,
+    payload: Option<serde_json::Value>,
+) -> Result<(), String> {
     let body = IpcMessage {
         kind,
         message,
         payload,
+        capabilities: CAPABILITIES,
     };
 
     // Serialize once and reuse
@@ -371,7 +624,16 @@ fn send_ipc(
     stream
         .write_all(message_bytes.as_bytes())
         .map_err(|e| e.to_string())?;
-    stream.flush().map_err(|e| e.to_string())?;
+    stream.flush().map_err(|e| e.to_string())
+}
+
+fn send_ipc(
+    kind: &str,
+    message: Option<&str>,
+    payload: Option<serde_json::Value>,
+) -> Result<(), String> {
+    let mut stream = connect_ipc()?;
+    write_ipc_message(&mut stream, kind, message, payload)?;
 
     // Read acknowledgment with buffered reader
     let mut reader = BufReader::with_capacity(BUFFER_SIZE, stream);
@@ -385,43 +647,417 @@ fn send_ipc_with_response(
     message: Option<&str>,
     payload: Option<serde_json::Value>,
 ) -> Result<IpcResponse, String> {
-    let addr = "127.0.0.1:39871";
+    let mut stream = connect_ipc()?;
+    write_ipc_message(&mut stream, kind, message, payload)?;
 
-    // Optimized connection setup
-    let mut stream = TcpStream::connect_timeout(&addr.parse().unwrap(), IPC_TIMEOUT)
-        .map_err(|e| format!("connect {} failed: {}", addr, e))?;
+    // Read response with optimized buffering
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, stream);
+    let mut line = String::with_capacity(512);
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
 
-    // Configure timeouts
-    stream
-        .set_read_timeout(Some(IPC_TIMEOUT))
-        .map_err(|e| format!("set read timeout failed: {}", e))?;
-    stream
-        .set_write_timeout(Some(IPC_TIMEOUT))
-        .map_err(|e| format!("set write timeout failed: {}", e))?;
-    stream
-        .set_nodelay(true)
-        .map_err(|e| format!("set nodelay failed: {}", e))?;
+    let json = decode_response_line(line.trim_end())?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse response: {}", e))
+}
 
-    let body = IpcMessage {
-        kind,
-        message,
-        payload,
+/// Inflate a response line if the server sent it LZ4-compressed
+fn decode_response_line(line: &str) -> Result<String, String> {
+    let Some(encoded) = line.strip_prefix(COMPRESSED_PREFIX) else {
+        return Ok(line.to_string());
     };
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Failed to decode compressed response: {}", e))?;
+    let raw = lz4_flex::decompress_size_prepended(&bytes)
+        .map_err(|e| format!("Failed to decompress response: {}", e))?;
+    String::from_utf8(raw).map_err(|e| format!("Failed to decompress response: {}", e))
+}
 
-    let json = serde_json::to_string(&body).map_err(|e| e.to_string())?;
-    let message_bytes = format!("{}\n", json);
+/// One line of an `ask_stream` reply. A line with a `status` is a plain response
+/// instead, e.g. `no_stream`.
+#[derive(Deserialize, Debug, Default)]
+struct StreamFrame {
+    chunk: Option<String>,
+    #[serde(default)]
+    done: bool,
+    conversation_id: Option<String>,
+    error: Option<String>,
+    status: Option<String>,
+    data: Option<serde_json::Value>,
+}
 
-    stream
-        .write_all(message_bytes.as_bytes())
-        .map_err(|e| e.to_string())?;
-    stream.flush().map_err(|e| e.to_string())?;
+/// Why an `ask_stream` reply ended without a `done` frame
+#[derive(Debug, PartialEq)]
+enum StreamError {
+    /// The connection closed before the server sent anything, e.g. it timed out an idle
+    /// connection; the request can safely be retried
+    NotStarted,
+    /// The server can't stream this request; nothing was sent to the provider
+    NoStream,
+    Failed(String),
+}
 
-    // Read response with optimized buffering
-    let mut reader = BufReader::with_capacity(BUFFER_SIZE, stream);
-    let mut line = String::with_capacity(512);
-    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+/// Read `ask_stream` frames, passing each chunk to `on_chunk` as it arrives.
+/// Returns the conversation ID from the final `done` frame.
+fn read_stream_frames<R: BufRead>(
+    reader: &mut R,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<Option<String>, StreamError> {
+    let mut started = false;
+    let mut line = String::with_capacity(256);
+    loop {
+        line.clear();
+        let closed = match reader.read_line(&mut line) {
+            Ok(0) => Err("connection closed".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = closed {
+            return Err(if started {
+                StreamError::Failed(format!("Stream interrupted: {}", e))
+            } else {
+                StreamError::NotStarted
+            });
+        }
+        started = true;
+
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let frame: StreamFrame = decode_response_line(trimmed)
+            .and_then(|json| {
+                serde_json::from_str(&json).map_err(|e| format!("Invalid stream frame: {}", e))
+            })
+            .map_err(StreamError::Failed)?;
+        match frame.status.as_deref() {
+            Some("error") => {
+                return Err(StreamError::Failed(
+                    frame
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.get("error"))
+                        .and_then(|e| e.as_str())
+                        .unwrap_or("request failed")
+                        .to_string(),
+                ))
+            }
+            // Includes servers without ask_stream, which acknowledge it like an unknown type
+            Some(_) => return Err(StreamError::NoStream),
+            None => {}
+        }
+        if let Some(error) = frame.error {
+            return Err(StreamError::Failed(error));
+        }
+        if let Some(chunk) = &frame.chunk {
+            on_chunk(chunk);
+        }
+        if frame.done {
+            return Ok(frame.conversation_id);
+        }
+    }
+}
+
+/// Send an IPC request and return its data, turning error responses into `Err`
+fn request_data(
+    kind: &str,
+    payload: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let response = send_ipc_with_response(kind, None, payload)?;
+    if response.status != "ok" {
+        return Err(response
+            .data
+            .as_ref()
+            .and_then(|d| d.get("error"))
+            .and_then(|e| e.as_str())
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| format!("request failed: {}", response.status)));
+    }
+    response.data.ok_or_else(|| "No data returned".to_string())
+}
+
+fn fetch_conversation_messages(conversation_id: &str) -> Result<ConversationMessages, String> {
+    let data = request_data(
+        "messages",
+        Some(serde_json::json!({ "conversation_id": conversation_id })),
+    )?;
+    serde_json::from_value(data).map_err(|e| format!("Failed to parse messages: {}", e))
+}
+
+/// Resolve a conversation by exact ID, falling back to a unique title prefix match
+fn resolve_conversation(query: &str) -> Result<ConversationMessages, String> {
+    if let Ok(found) = fetch_conversation_messages(query) {
+        return Ok(found);
+    }
+
+    let data = request_data(
+        "list_conversations",
+        Some(serde_json::json!({ "title_prefix": query })),
+    )?;
+    let matches: Vec<Conversation> = serde_json::from_value(data)
+        .map_err(|e| format!("Failed to parse conversations: {}", e))?;
+
+    match matches.as_slice() {
+        [] => Err(format!("No conversation matches '{}'", query)),
+        [only] => fetch_conversation_messages(&only.id),
+        many => Err(format!(
+            "'{}' matches {} conversations: {}",
+            query,
+            many.len(),
+            many.iter()
+                .map(|c| format!("{} ({})", c.title, c.id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Align messages by position and compute a line diff for each pair
+fn diff_messages(a: &[Message], b: &[Message], only_index: Option<usize>) -> Vec<MessageDiff> {
+    let indices: Vec<usize> = match only_index {
+        Some(i) => vec![i],
+        None => (0..a.len().max(b.len())).collect(),
+    };
+
+    indices
+        .into_iter()
+        .filter_map(|index| {
+            let ma = a.get(index);
+            let mb = b.get(index);
+            let status = match (ma, mb) {
+                (None, None) => return None,
+                (Some(_), None) => "removed",
+                (None, Some(_)) => "added",
+                (Some(x), Some(y)) if x.role == y.role && x.content == y.content => "unchanged",
+                _ => "changed",
+            };
 
-    serde_json::from_str(&line).map_err(|e| format!("Failed to parse response: {}", e))
+            let old = ma.map(|m| m.content.as_str()).unwrap_or("");
+            let new = mb.map(|m| m.content.as_str()).unwrap_or("");
+            let changes = TextDiff::from_lines(old, new)
+                .iter_all_changes()
+                .map(|change| DiffLine {
+                    tag: match change.tag() {
+                        ChangeTag::Equal => "equal",
+                        ChangeTag::Delete => "delete",
+                        ChangeTag::Insert => "insert",
+                    },
+                    line: change.value().trim_end_matches('\n').to_string(),
+                })
+                .collect();
+
+            Some(MessageDiff {
+                index,
+                role_a: ma.map(|m| m.role.clone()),
+                role_b: mb.map(|m| m.role.clone()),
+                status,
+                changes,
+            })
+        })
+        .collect()
+}
+
+fn handle_diff(
+    query_a: &str,
+    query_b: &str,
+    message_index: Option<usize>,
+    output: OutputFormat,
+    colors: ColorConfig,
+) -> Result<(), String> {
+    let a = resolve_conversation(query_a)?;
+    let b = resolve_conversation(query_b)?;
+
+    let diffs = diff_messages(&a.messages, &b.messages, message_index);
+    if let (Some(i), true) = (message_index, diffs.is_empty()) {
+        return Err(format!("Neither conversation has a message at index {}", i));
+    }
+
+    if output == OutputFormat::Json {
+        let json = serde_json::json!({
+            "conversation_a": a.conversation,
+            "conversation_b": b.conversation,
+            "messages": diffs,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        colors.label(format_args!(
+            "--- {} ({})",
+            a.conversation.title, a.conversation.id
+        ))
+    );
+    println!(
+        "{}",
+        colors.label(format_args!(
+            "+++ {} ({})",
+            b.conversation.title, b.conversation.id
+        ))
+    );
+
+    let mut identical = true;
+    for diff in diffs.iter().filter(|d| d.status != "unchanged") {
+        identical = false;
+        let roles = match (&diff.role_a, &diff.role_b) {
+            (Some(ra), Some(rb)) if ra == rb => ra.clone(),
+            (Some(ra), Some(rb)) => format!("{} -> {}", ra, rb),
+            (Some(r), None) | (None, Some(r)) => r.clone(),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{}",
+            colors.label(format_args!(
+                "@@ message {}: {} ({}) @@",
+                diff.index, roles, diff.status
+            ))
+        );
+        for change in &diff.changes {
+            let sign = match change.tag {
+                "delete" => '-',
+                "insert" => '+',
+                _ => ' ',
+            };
+            println!("{}{}", sign, change.line);
+        }
+    }
+
+    if identical {
+        println!("No differences found");
+    }
+
+    Ok(())
+}
+
+fn handle_history(
+    limit: usize,
+    format: HistoryFormat,
+    provider: Option<&str>,
+    colors: ColorConfig,
+) -> Result<(), String> {
+    let data = request_data(
+        "history",
+        Some(serde_json::json!({ "limit": limit, "provider": provider })),
+    )?;
+    let conversations: Vec<Conversation> = serde_json::from_value(data)
+        .map_err(|e| format!("Failed to parse conversations: {}", e))?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let output = render_history(&conversations, format, now, colors)?;
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+    Ok(())
+}
+
+/// Age of a timestamp (seconds) in its largest whole unit, e.g. `5m ago`
+fn relative_time(timestamp: i64, now: i64) -> String {
+    let secs = (now - timestamp).max(0);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn truncate_title(title: &str) -> String {
+    if title.chars().count() <= HISTORY_TITLE_WIDTH {
+        return title.to_string();
+    }
+    let kept: String = title.chars().take(HISTORY_TITLE_WIDTH - 1).collect();
+    format!("{}…", kept)
+}
+
+fn render_history(
+    conversations: &[Conversation],
+    format: HistoryFormat,
+    now: i64,
+    colors: ColorConfig,
+) -> Result<String, String> {
+    match format {
+        HistoryFormat::Json => {
+            serde_json::to_string_pretty(conversations).map_err(|e| e.to_string())
+        }
+        HistoryFormat::Plain => Ok(conversations
+            .iter()
+            .map(|c| format!("{}\t{}", c.id, c.title))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        HistoryFormat::Table => {
+            if conversations.is_empty() {
+                return Ok("No conversations found".to_string());
+            }
+            let mut rows = vec![[
+                "ID".to_string(),
+                "TITLE".to_string(),
+                "PROVIDER".to_string(),
+                "MODEL".to_string(),
+                "UPDATED".to_string(),
+            ]];
+            rows.extend(conversations.iter().map(|c| {
+                [
+                    c.id.clone(),
+                    truncate_title(&c.title),
+                    c.provider.clone(),
+                    c.model.clone(),
+                    relative_time(c.updated_at, now),
+                ]
+            }));
+
+            let mut widths = [0usize; 5];
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            // Pad before coloring so escape codes don't count toward column widths
+            Ok(rows
+                .iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let line = row
+                        .iter()
+                        .zip(widths)
+                        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                        .collect::<Vec<_>>()
+                        .join("  ");
+                    let line = line.trim_end();
+                    if i == 0 {
+                        colors.label(line)
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+    }
+}
+
+fn handle_config(action: &ConfigAction) -> Result<(), String> {
+    let mut config = CliConfig::load()?;
+    match action {
+        ConfigAction::Set { key, value } => {
+            config.set(key, value)?;
+            config.save()?;
+            println!("{} = {}", key, config.get(key)?);
+        }
+        ConfigAction::Get { key } => println!("{}", config.get(key)?),
+        ConfigAction::List => {
+            println!("# {}", config::config_path()?.display());
+            for key in CONFIG_KEYS {
+                println!("{} = {}", key, config.get(key)?);
+            }
+        }
+    }
+    Ok(())
 }
 
 fn execute_command(
@@ -437,13 +1073,13 @@ fn execute_command(
             .to_string()
     });
 
-    // Parse command into parts (simple shell-like parsing)
-    let parts: Vec<&str> = command.split_whitespace().collect();
+    // Parse command into parts honouring shell quoting
+    let parts = parse_command(command)?;
     if parts.is_empty() {
         return Err("Empty command".to_string());
     }
 
-    let mut cmd = Command::new(parts[0]);
+    let mut cmd = Command::new(&parts[0]);
     if parts.len() > 1 {
         cmd.args(&parts[1..]);
     }
@@ -512,6 +1148,86 @@ fn execute_command(
     })
 }
 
+/// Split a command line into arguments following POSIX shell tokenisation rules:
+/// single quotes are literal, inside double quotes a backslash only escapes `$`,
+/// `` ` ``, `"`, `\` and newline, and an unquoted backslash escapes the next character.
+fn parse_command(input: &str) -> Result<Vec<String>, String> {
+    enum State {
+        Normal,
+        SingleQuoted,
+        DoubleQuoted,
+    }
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Tracks whether a word has started, so that `""` still yields an empty argument
+    let mut in_word = false;
+    let mut state = State::Normal;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match state {
+            State::Normal => match c {
+                '\'' => {
+                    state = State::SingleQuoted;
+                    in_word = true;
+                }
+                '"' => {
+                    state = State::DoubleQuoted;
+                    in_word = true;
+                }
+                '\\' => match chars.next() {
+                    // Escaped newline is a line continuation
+                    Some('\n') => {}
+                    Some(next) => {
+                        current.push(next);
+                        in_word = true;
+                    }
+                    None => return Err("Trailing backslash in command".to_string()),
+                },
+                c if c.is_whitespace() => {
+                    if in_word {
+                        args.push(std::mem::take(&mut current));
+                        in_word = false;
+                    }
+                }
+                c => {
+                    current.push(c);
+                    in_word = true;
+                }
+            },
+            State::SingleQuoted => match c {
+                '\'' => state = State::Normal,
+                c => current.push(c),
+            },
+            State::DoubleQuoted => match c {
+                '"' => state = State::Normal,
+                '\\' => match chars.next() {
+                    Some('\n') => {}
+                    Some(next @ ('$' | '`' | '"' | '\\')) => current.push(next),
+                    Some(next) => {
+                        current.push('\\');
+                        current.push(next);
+                    }
+                    None => return Err("Unterminated double quote in command".to_string()),
+                },
+                c => current.push(c),
+            },
+        }
+    }
+
+    match state {
+        State::SingleQuoted => Err("Unterminated single quote in command".to_string()),
+        State::DoubleQuoted => Err("Unterminated double quote in command".to_string()),
+        State::Normal => {
+            if in_word {
+                args.push(current);
+            }
+            Ok(args)
+        }
+    }
+}
+
 fn analyze_error_output(stderr: &str, _stdout: &str, exit_code: Option<i32>) -> String {
     let mut analysis = Vec::new();
 
@@ -562,7 +1278,20 @@ fn read_stdin() -> Result<String, String> {
     Ok(content.trim().to_string())
 }
 
-fn handle_ask(message: &str, model: Option<&str>, provider: Option<&str>, new: bool, gui: bool) {
+fn handle_ask(
+    message: &str,
+    model: Option<&str>,
+    provider: Option<&str>,
+    new: bool,
+    gui: bool,
+    stream: bool,
+    colors: ColorConfig,
+) {
+    // Command-line flags take precedence over configured defaults
+    let config = config::current();
+    let model = model.or(config.default_model.as_deref());
+    let provider = provider.or(config.default_provider.as_deref());
+
     let payload = serde_json::json!({
         "prompt": message,
         "model": model,
@@ -571,8 +1300,23 @@ fn handle_ask(message: &str, model: Option<&str>, provider: Option<&str>, new: b
         "gui": gui,
     });
 
+    if stream && !gui {
+        match ask_streaming(payload.clone(), colors) {
+            Ok(true) => return,
+            // Fall back to asking and fetching the finished reply
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("{}", colors.error(e));
+                std::process::exit(1);
+            }
+        }
+    }
+
     if let Err(e) = send_ipc("ask", None, Some(payload)) {
-        eprintln!("Failed to send ask: {}", e);
+        eprintln!(
+            "{}",
+            colors.error(format_args!("Failed to send ask: {}", e))
+        );
         std::process::exit(1);
     }
 
@@ -584,7 +1328,7 @@ fn handle_ask(message: &str, model: Option<&str>, provider: Option<&str>, new: b
         eprint!("Processing");
         for _ in 0..3 {
             std::thread::sleep(Duration::from_millis(300));
-            eprint!(".");
+            eprint!("{}", colors.progress("."));
         }
         eprintln!();
 
@@ -595,24 +1339,33 @@ fn handle_ask(message: &str, model: Option<&str>, provider: Option<&str>, new: b
                     if let Some(data) = response.data {
                         match serde_json::from_value::<Message>(data) {
                             Ok(msg) => {
-                                println!("\n{}", msg.content);
+                                println!("\n{}", colors.response(&msg.content));
                             }
                             Err(e) => {
-                                eprintln!("Failed to parse response: {}", e);
+                                eprintln!(
+                                    "{}",
+                                    colors.error(format_args!("Failed to parse response: {}", e))
+                                );
                                 std::process::exit(1);
                             }
                         }
                     } else {
-                        eprintln!("No response data");
+                        eprintln!("{}", colors.error("No response data"));
                         std::process::exit(1);
                     }
                 } else {
-                    eprintln!("Request failed: {}", response.status);
+                    eprintln!(
+                        "{}",
+                        colors.error(format_args!("Request failed: {}", response.status))
+                    );
                     std::process::exit(1);
                 }
             }
             Err(e) => {
-                eprintln!("Failed to get response: {}", e);
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!("Failed to get response: {}", e))
+                );
                 std::process::exit(1);
             }
         }
@@ -621,38 +1374,78 @@ fn handle_ask(message: &str, model: Option<&str>, provider: Option<&str>, new: b
     }
 }
 
-fn display_capture_result(result: &CaptureResult) {
-    println!("Command: {}", result.command);
-    println!("Working Directory: {}", result.working_dir);
-    println!("Execution Time: {}ms", result.execution_time_ms);
+/// Ask with `ask_stream`, printing the reply as it arrives. Returns false without
+/// printing anything if the server can't stream it.
+fn ask_streaming(payload: serde_json::Value, colors: ColorConfig) -> Result<bool, String> {
+    let mut stream = connect_ipc().map_err(|e| format!("Failed to send ask: {}", e))?;
+    stream
+        .set_read_timeout(Some(STREAM_READ_TIMEOUT))
+        .map_err(|e| format!("set read timeout failed: {}", e))?;
+    write_ipc_message(&mut stream, "ask_stream", None, Some(payload))
+        .map_err(|e| format!("Failed to send ask: {}", e))?;
+
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, stream);
+    let mut stdout = io::stdout();
+    let mut response = String::new();
+    let result = read_stream_frames(&mut reader, |chunk| {
+        response.push_str(chunk);
+        print!("{}", colors.response(chunk));
+        let _ = stdout.flush();
+    });
+    if !response.is_empty() && !response.ends_with('\n') {
+        println!();
+    }
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(StreamError::NoStream) => Ok(false),
+        Err(StreamError::NotStarted) => {
+            Err("The assistant closed the connection without replying".to_string())
+        }
+        Err(StreamError::Failed(e)) => Err(format!("Request failed: {}", e)),
+    }
+}
+
+fn display_capture_result(result: &CaptureResult, colors: ColorConfig) {
+    println!("{} {}", colors.label("Command:"), result.command);
+    println!(
+        "{} {}",
+        colors.label("Working Directory:"),
+        result.working_dir
+    );
+    println!(
+        "{} {}ms",
+        colors.label("Execution Time:"),
+        result.execution_time_ms
+    );
 
     if result.timed_out {
-        println!("Status: TIMED OUT");
+        println!("{} {}", colors.label("Status:"), colors.error("TIMED OUT"));
     } else if let Some(code) = result.exit_code {
-        println!("Exit Code: {}", code);
+        println!("{} {}", colors.label("Exit Code:"), colors.exit_code(code));
     }
 
     if !result.stdout.is_empty() {
-        println!("\n--- STDOUT ---");
+        println!("\n{}", colors.label("--- STDOUT ---"));
         println!("{}", result.stdout);
     }
 
     if !result.stderr.is_empty() {
-        println!("\n--- STDERR ---");
+        println!("\n{}", colors.label("--- STDERR ---"));
         println!("{}", result.stderr);
     }
 
     if let Some(summary) = &result.error_summary {
-        println!("\n--- ANALYSIS ---");
+        println!("\n{}", colors.label("--- ANALYSIS ---"));
         println!("{}", summary);
     }
 }
 
-fn display_capture_analysis(result: &CaptureResult, use_ai: bool) {
-    display_capture_result(result);
+fn display_capture_analysis(result: &CaptureResult, use_ai: bool, colors: ColorConfig) {
+    display_capture_result(result, colors);
 
     if use_ai {
-        println!("\n--- AI ANALYSIS ---");
+        println!("\n{}", colors.label("--- AI ANALYSIS ---"));
 
         // Create a formatted analysis request
         let analysis_prompt = format!(
@@ -679,19 +1472,28 @@ fn display_capture_analysis(result: &CaptureResult, use_ai: bool) {
                         Ok(last_response) => {
                             if let Some(data) = last_response.data {
                                 if let Ok(message) = serde_json::from_value::<Message>(data) {
-                                    println!("{}", message.content);
+                                    println!("{}", colors.response(&message.content));
                                 } else {
-                                    println!("Failed to parse AI response");
+                                    println!("{}", colors.error("Failed to parse AI response"));
                                 }
                             }
                         }
-                        Err(e) => println!("Failed to get AI analysis: {}", e),
+                        Err(e) => println!(
+                            "{}",
+                            colors.error(format_args!("Failed to get AI analysis: {}", e))
+                        ),
                     }
                 } else {
-                    println!("AI analysis failed: {}", response.status);
+                    println!(
+                        "{}",
+                        colors.error(format_args!("AI analysis failed: {}", response.status))
+                    );
                 }
             }
-            Err(e) => println!("Failed to request AI analysis: {}", e),
+            Err(e) => println!(
+                "{}",
+                colors.error(format_args!("Failed to request AI analysis: {}", e))
+            ),
         }
     }
 }
@@ -706,12 +1508,14 @@ mod tests {
             kind: "test",
             message: Some("hello"),
             payload: Some(serde_json::json!({"key": "value"})),
+            capabilities: CAPABILITIES,
         };
 
         let json = serde_json::to_string(&msg).expect("Serialization should work");
         assert!(json.contains("\"type\":\"test\""));
         assert!(json.contains("\"message\":\"hello\""));
         assert!(json.contains("\"key\":\"value\""));
+        assert!(json.contains("\"capabilities\":[\"lz4\"]"));
     }
 
     #[test]
@@ -803,6 +1607,217 @@ mod tests {
         assert!(!capture.timed_out);
     }
 
+    #[test]
+    fn test_parse_command_double_quotes() {
+        let parts = parse_command(r#"echo "hello world""#).unwrap();
+        assert_eq!(parts, vec!["echo", "hello world"]);
+    }
+
+    #[test]
+    fn test_parse_command_single_quotes() {
+        let parts = parse_command("grep -E 'foo bar'").unwrap();
+        assert_eq!(parts, vec!["grep", "-E", "foo bar"]);
+    }
+
+    #[test]
+    fn test_parse_command_escapes() {
+        let parts = parse_command(r#"echo hello\ world "a \"quoted\" \n" '\n' """#).unwrap();
+        assert_eq!(
+            parts,
+            vec!["echo", "hello world", r#"a "quoted" \n"#, r"\n", ""]
+        );
+    }
+
+    #[test]
+    fn test_parse_command_unterminated_quote() {
+        assert!(parse_command("echo 'oops").is_err());
+        assert!(parse_command(r#"echo "oops"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_compressed_response() {
+        let json = format!(
+            r#"{{"status":"ok","data":{{"content":"{}"}}}}"#,
+            "x".repeat(5000)
+        );
+        let packed = lz4_flex::compress_prepend_size(json.as_bytes());
+        let line = format!("{}{}", COMPRESSED_PREFIX, BASE64.encode(packed));
+
+        assert_eq!(decode_response_line(&line).unwrap(), json);
+        assert_eq!(
+            decode_response_line(r#"{"status":"ok"}"#).unwrap(),
+            r#"{"status":"ok"}"#
+        );
+        assert!(decode_response_line("z:not-base64!").is_err());
+    }
+
+    fn test_message(role: &str, content: &str) -> Message {
+        Message {
+            id: "id".to_string(),
+            conversation_id: "conv".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: 0,
+            tokens_used: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_messages_aligns_by_index() {
+        let a = vec![
+            test_message("user", "hello"),
+            test_message("assistant", "line one\nline two"),
+        ];
+        let b = vec![
+            test_message("user", "hello"),
+            test_message("assistant", "line one\nline 2"),
+            test_message("user", "thanks"),
+        ];
+
+        let diffs = diff_messages(&a, &b, None);
+        let statuses: Vec<&str> = diffs.iter().map(|d| d.status).collect();
+        assert_eq!(statuses, vec!["unchanged", "changed", "added"]);
+
+        let changed = &diffs[1].changes;
+        assert!(changed
+            .iter()
+            .any(|c| c.tag == "delete" && c.line == "line two"));
+        assert!(changed
+            .iter()
+            .any(|c| c.tag == "insert" && c.line == "line 2"));
+    }
+
+    #[test]
+    fn test_diff_messages_single_index() {
+        let a = vec![test_message("user", "a"), test_message("assistant", "b")];
+        let b = vec![test_message("user", "a")];
+
+        let diffs = diff_messages(&a, &b, Some(1));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].status, "removed");
+        assert!(diff_messages(&a, &b, Some(5)).is_empty());
+    }
+
+    fn test_conversation(id: &str, title: &str, provider: &str, updated_at: i64) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            title: title.to_string(),
+            model: "gpt-4o".to_string(),
+            provider: provider.to_string(),
+            created_at: updated_at,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(relative_time(1000, 1030), "just now");
+        assert_eq!(relative_time(1000, 1000 + 5 * 60), "5m ago");
+        assert_eq!(relative_time(1000, 1000 + 3 * 3600), "3h ago");
+        assert_eq!(relative_time(1000, 1000 + 2 * 86400), "2d ago");
+        assert_eq!(relative_time(2000, 1000), "just now");
+    }
+
+    #[test]
+    fn test_render_history_table() {
+        let now = 100_000;
+        let conversations = vec![
+            test_conversation("a1", "Short", "openai", now - 120),
+            test_conversation("b22", &"x".repeat(60), "ollama", now - 7200),
+        ];
+        let table = render_history(
+            &conversations,
+            HistoryFormat::Table,
+            now,
+            ColorConfig::plain(),
+        )
+        .unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("ID   TITLE"));
+        assert!(lines[1].starts_with("a1   Short"));
+        assert!(lines[1].ends_with("2m ago"));
+        assert!(lines[2].contains(&format!("{}…", "x".repeat(HISTORY_TITLE_WIDTH - 1))));
+        // Columns line up across rows (counted in characters, the title has a `…`)
+        let column = |line: &str, needle: &str| line[..line.find(needle).unwrap()].chars().count();
+        let provider_col = column(lines[0], "PROVIDER");
+        assert_eq!(column(lines[1], "openai"), provider_col);
+        assert_eq!(column(lines[2], "ollama"), provider_col);
+
+        assert_eq!(
+            render_history(&[], HistoryFormat::Table, now, ColorConfig::plain()).unwrap(),
+            "No conversations found"
+        );
+    }
+
+    #[test]
+    fn test_render_history_json_and_plain() {
+        let conversations = vec![
+            test_conversation("a1", "First", "openai", 10),
+            test_conversation("b2", "Second", "anthropic", 20),
+        ];
+        let json = render_history(
+            &conversations,
+            HistoryFormat::Json,
+            30,
+            ColorConfig::plain(),
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[1]["provider"], "anthropic");
+        assert_eq!(
+            render_history(&[], HistoryFormat::Json, 30, ColorConfig::plain()).unwrap(),
+            "[]"
+        );
+
+        let plain = render_history(
+            &conversations,
+            HistoryFormat::Plain,
+            30,
+            ColorConfig::plain(),
+        )
+        .unwrap();
+        assert_eq!(plain, "a1\tFirst\nb2\tSecond");
+    }
+
+    #[test]
+    fn test_read_stream_frames() {
+        let reply =
+            "{\"chunk\":\"Hel\"}\n{\"chunk\":\"lo\"}\n{\"done\":true,\"conversation_id\":\"c1\"}\n";
+        let mut text = String::new();
+        let id = read_stream_frames(&mut reply.as_bytes(), |c| text.push_str(c)).unwrap();
+        assert_eq!(text, "Hello");
+        assert_eq!(id.as_deref(), Some("c1"));
+
+        let failed = "{\"chunk\":\"Hel\"}\n{\"error\":\"rate limited\"}\n";
+        assert_eq!(
+            read_stream_frames(&mut failed.as_bytes(), |_| {}),
+            Err(StreamError::Failed("rate limited".to_string()))
+        );
+        assert_eq!(
+            read_stream_frames(&mut "".as_bytes(), |_| {}),
+            Err(StreamError::NotStarted)
+        );
+        assert!(matches!(
+            read_stream_frames(&mut "{\"chunk\":\"Hel\"}\n".as_bytes(), |_| {}),
+            Err(StreamError::Failed(_))
+        ));
+
+        let mut called = false;
+        assert_eq!(
+            read_stream_frames(&mut "{\"status\":\"no_stream\"}\n".as_bytes(), |_| {
+                called = true
+            }),
+            Err(StreamError::NoStream)
+        );
+        assert!(!called);
+        let refused = "{\"status\":\"error\",\"data\":{\"error\":\"Message too large\"}}\n";
+        assert_eq!(
+            read_stream_frames(&mut refused.as_bytes(), |_| {}),
+            Err(StreamError::Failed("Message too large".to_string()))
+        );
+    }
+
     // Integration test that requires a running backend
     #[test]
     #[ignore] // Ignored by default since it requires backend to be running
@@ -0,0 +1,323 @@
+use crate::color::ColorConfig;
+use crate::{
+    config, connect_ipc, fetch_conversation_messages, read_stream_frames, write_ipc_message,
+    StreamError, STREAM_READ_TIMEOUT,
+};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::io::{self, BufReader, IsTerminal, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+/// Commands handled by the REPL itself, offered for tab completion
+const SLASH_COMMANDS: &[&str] = &["/new", "/history", "/model", "/quit"];
+const PROMPT: &str = "lai> ";
+
+/// One line typed at the REPL prompt
+#[derive(Debug, PartialEq)]
+enum ReplInput {
+    Empty,
+    Prompt(String),
+    New,
+    History,
+    Quit,
+    /// `/model` alone shows the current model
+    Model(Option<String>),
+    Unknown(String),
+}
+
+fn parse_repl_input(line: &str) -> ReplInput {
+    let line = line.trim();
+    if line.is_empty() {
+        return ReplInput::Empty;
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return ReplInput::Prompt(line.to_string());
+    };
+
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+    match (name, arg) {
+        ("new", None) => ReplInput::New,
+        ("history", None) => ReplInput::History,
+        ("quit" | "exit", None) => ReplInput::Quit,
+        ("model", arg) => ReplInput::Model(arg.map(|a| a.to_string())),
+        _ => ReplInput::Unknown(line.to_string()),
+    }
+}
+
+/// Completions for a slash command being typed at `pos`, replacing from the line start
+fn complete_slash_command(line: &str, pos: usize) -> (usize, Vec<String>) {
+    let typed = &line[..pos];
+    if !typed.starts_with('/') || typed.contains(char::is_whitespace) {
+        return (pos, Vec::new());
+    }
+    let matches = SLASH_COMMANDS
+        .iter()
+        .filter(|c| c.starts_with(typed))
+        .map(|c| c.to_string())
+        .collect();
+    (0, matches)
+}
+
+struct ReplHelper;
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_slash_command(line, pos))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// A chat session over one long-lived IPC connection
+struct ReplSession {
+    connection: Option<BufReader<TcpStream>>,
+    conversation_id: Option<String>,
+    model: Option<String>,
+    provider: Option<String>,
+}
+
+impl ReplSession {
+    /// Send `prompt` and stream the reply to `on_chunk`. Reconnects once if the
+    /// server dropped the idle connection before replying.
+    fn ask(&mut self, prompt: &str, mut on_chunk: impl FnMut(&str)) -> Result<(), String> {
+        let payload = serde_json::json!({
+            "prompt": prompt,
+            "conversation_id": self.conversation_id,
+            "model": self.model,
+            "provider": self.provider,
+        });
+
+        for attempt in 0..2 {
+            let mut reader = match self.connection.take() {
+                Some(reader) if attempt == 0 => reader,
+                _ => {
+                    let stream = connect_ipc()?;
+                    stream
+                        .set_read_timeout(Some(STREAM_READ_TIMEOUT))
+                        .map_err(|e| format!("set read timeout failed: {}", e))?;
+                    BufReader::new(stream)
+                }
+            };
+            if write_ipc_message(reader.get_mut(), "ask_stream", None, Some(payload.clone()))
+                .is_err()
+            {
+                continue;
+            }
+            match read_stream_frames(&mut reader, &mut on_chunk) {
+                Ok(conversation_id) => {
+                    if conversation_id.is_some() {
+                        self.conversation_id = conversation_id;
+                    }
+                    self.connection = Some(reader);
+                    return Ok(());
+                }
+                Err(StreamError::NotStarted) => continue,
+                Err(StreamError::NoStream) => {
+                    self.connection = Some(reader);
+                    return Err("This provider can't stream replies; use lai ask".to_string());
+                }
+                Err(StreamError::Failed(e)) => {
+                    // Error frames leave the connection usable
+                    self.connection = Some(reader);
+                    return Err(e);
+                }
+            }
+        }
+        Err("The assistant closed the connection; is the app running?".to_string())
+    }
+
+    fn print_history(&self, colors: ColorConfig) -> Result<(), String> {
+        let Some(conversation_id) = &self.conversation_id else {
+            println!("No messages yet");
+            return Ok(());
+        };
+        let found = fetch_conversation_messages(conversation_id)?;
+        println!("{}", colors.label(&found.conversation.title));
+        for message in &found.messages {
+            let content = if message.role == "assistant" {
+                colors.response(&message.content)
+            } else {
+                message.content.clone()
+            };
+            println!(
+                "\n{}\n{}",
+                colors.label(format_args!("[{}]", message.role)),
+                content
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `repl_history` next to the CLI config file
+fn history_path() -> Option<PathBuf> {
+    config::config_path()
+        .ok()
+        .map(|path| path.with_file_name("repl_history"))
+}
+
+/// Run the interactive session until `/quit`, Ctrl-D or Ctrl-C
+pub fn run(
+    model: Option<String>,
+    provider: Option<String>,
+    colors: ColorConfig,
+) -> Result<(), String> {
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+        Editor::new().map_err(|e| format!("Failed to start line editor: {}", e))?;
+    editor.set_helper(Some(ReplHelper));
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing file just means there is no history yet
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = ReplSession {
+        connection: None,
+        conversation_id: None,
+        model,
+        provider,
+    };
+    let tty = io::stdout().is_terminal();
+    println!("Type a message, or /new, /history, /model <name>, /quit");
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("Failed to read input: {}", e)),
+        };
+        let input = parse_repl_input(&line);
+        if input != ReplInput::Empty {
+            let _ = editor.add_history_entry(line.trim());
+        }
+
+        match input {
+            ReplInput::Empty => {}
+            ReplInput::Quit => break,
+            ReplInput::New => {
+                session.conversation_id = None;
+                println!("Started a new conversation");
+            }
+            ReplInput::History => {
+                if let Err(e) = session.print_history(colors) {
+                    eprintln!(
+                        "{}",
+                        colors.error(format_args!("Failed to load history: {}", e))
+                    );
+                }
+            }
+            ReplInput::Model(Some(name)) => {
+                println!("Model set to {}", name);
+                session.model = Some(name);
+            }
+            ReplInput::Model(None) => match &session.model {
+                Some(name) => println!("Model: {}", name),
+                None => println!("Model: provider default"),
+            },
+            ReplInput::Unknown(command) => {
+                eprintln!(
+                    "{}",
+                    colors.error(format_args!(
+                        "Unknown command {}. Commands: {}",
+                        command,
+                        SLASH_COMMANDS.join(", ")
+                    ))
+                );
+            }
+            ReplInput::Prompt(prompt) => {
+                let mut stdout = io::stdout();
+                // Hide the cursor while the reply is written in place
+                if tty {
+                    let _ = write!(stdout, "\x1b[?25l");
+                }
+                let result = session.ask(&prompt, |chunk| {
+                    let _ = write!(stdout, "{}", colors.response(chunk));
+                    let _ = stdout.flush();
+                });
+                if tty {
+                    let _ = write!(stdout, "\x1b[?25h");
+                }
+                let _ = writeln!(stdout);
+                if let Err(e) = result {
+                    eprintln!("{}", colors.error(format_args!("Error: {}", e)));
+                }
+            }
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("Warning: failed to save REPL history: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repl_input() {
+        assert_eq!(parse_repl_input("   "), ReplInput::Empty);
+        assert_eq!(
+            parse_repl_input("  what is a monad? "),
+            ReplInput::Prompt("what is a monad?".to_string())
+        );
+        assert_eq!(parse_repl_input("/new"), ReplInput::New);
+        assert_eq!(parse_repl_input("/history"), ReplInput::History);
+        assert_eq!(parse_repl_input("/quit"), ReplInput::Quit);
+        assert_eq!(parse_repl_input("/exit"), ReplInput::Quit);
+        assert_eq!(
+            parse_repl_input("/model  gpt-4o "),
+            ReplInput::Model(Some("gpt-4o".to_string()))
+        );
+        assert_eq!(parse_repl_input("/model"), ReplInput::Model(None));
+        assert_eq!(
+            parse_repl_input("/new chat"),
+            ReplInput::Unknown("/new chat".to_string())
+        );
+        assert_eq!(
+            parse_repl_input("/help"),
+            ReplInput::Unknown("/help".to_string())
+        );
+    }
+
+    #[test]
+    fn test_complete_slash_command() {
+        assert_eq!(
+            complete_slash_command("/h", 2),
+            (0, vec!["/history".to_string()])
+        );
+        assert_eq!(complete_slash_command("/", 1).1.len(), SLASH_COMMANDS.len());
+        assert!(complete_slash_command("/model gpt", 10).1.is_empty());
+        assert!(complete_slash_command("hello", 5).1.is_empty());
+    }
+}
@@ -1,22 +1,34 @@
-use crate::database::{conversations::*, Database};
+use crate::database::{
+    conversations::*, event_log::EventLog, messages::Message, tags::Tag, Database,
+};
+use chrono::{Datelike, Local, NaiveTime, TimeZone};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::State;
 
 #[tauri::command]
 pub async fn create_conversation(
     db: State<'_, Database>,
+    app: tauri::AppHandle,
     title: String,
     model: String,
     provider: String,
     system_prompt: Option<String>,
 ) -> Result<Conversation, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     let new_conv = NewConversation {
         title,
         model,
         provider,
         system_prompt,
     };
-    Conversation::create(&conn, new_conv).map_err(|e| e.to_string())
+    let created = Conversation::create(&conn, new_conv).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(created)
 }
 
 #[tauri::command]
@@ -24,17 +36,178 @@ pub async fn get_conversation(
     db: State<'_, Database>,
     id: String,
 ) -> Result<Option<Conversation>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Conversation::get_by_id(&conn, &id).map_err(|e| e.to_string())
 }
 
+/// A conversation loaded together with everything the chat view shows
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversationWithMessages {
+    pub conversation: Conversation,
+    pub messages: Vec<Message>,
+    /// Only filled in when requested
+    pub total_tokens: Option<i64>,
+    pub tags: Vec<Tag>,
+}
+
+fn load_conversation_with_messages(
+    conn: &rusqlite::Connection,
+    id: &str,
+    include_token_count: bool,
+) -> Result<ConversationWithMessages, String> {
+    let conversation = Conversation::get_by_id(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Conversation not found".to_string())?;
+    let messages = Message::get_by_conversation(conn, id).map_err(|e| e.to_string())?;
+    let tags = Tag::get_for_conversation(conn, id).map_err(|e| e.to_string())?;
+
+    // Summed from the loaded messages rather than with a fourth query
+    let total_tokens =
+        include_token_count.then(|| messages.iter().filter_map(|m| m.tokens_used).sum::<i64>());
+
+    Ok(ConversationWithMessages {
+        conversation,
+        messages,
+        total_tokens,
+        tags,
+    })
+}
+
+/// Fetch a conversation, its messages and its tags under a single lock
+#[tauri::command]
+pub async fn get_conversation_with_messages(
+    db: State<'_, Database>,
+    id: String,
+    include_token_count: bool,
+) -> Result<ConversationWithMessages, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    load_conversation_with_messages(&conn, &id, include_token_count)
+}
+
 #[tauri::command]
 pub async fn get_all_conversations(
     db: State<'_, Database>,
     limit: i64,
 ) -> Result<Vec<Conversation>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Conversation::get_all(&conn, limit).map_err(|e| e.to_string())
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_unarchived(&conn, limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_archived_conversations(
+    db: State<'_, Database>,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_archived(&conn, limit).map_err(|e| e.to_string())
+}
+
+/// Conversations that have no messages, for cleaning up abandoned drafts
+#[tauri::command]
+pub async fn get_conversations_without_messages(
+    db: State<'_, Database>,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_empty(&conn, limit).map_err(|e| e.to_string())
+}
+
+/// Soft-delete every conversation without messages and return how many were removed
+#[tauri::command]
+pub async fn delete_empty_conversations(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+) -> Result<usize, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let deleted = Conversation::delete_empty(&conn).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    if deleted > 0 {
+        crate::tray::refresh_recent_conversations(&app);
+    }
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub async fn get_conversations_by_date_range(
+    db: State<'_, Database>,
+    from: i64,
+    to: i64,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_by_date_range(&conn, from, to, limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_conversations_by_provider(
+    db: State<'_, Database>,
+    provider: String,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_by_provider(&conn, &provider, limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_conversations_by_model(
+    db: State<'_, Database>,
+    model: String,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_by_model(&conn, &model, limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_provider_usage_stats(
+    db: State<'_, Database>,
+) -> Result<Vec<ProviderUsage>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::provider_usage_stats(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_conversations_summary_stats(
+    db: State<'_, Database>,
+    conversation_ids: Vec<String>,
+) -> Result<Vec<ConversationSummary>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::summary_stats(&conn, &conversation_ids).map_err(|e| e.to_string())
+}
+
+/// Unix timestamp of local midnight `days_back` days before today
+fn local_day_start(days_back: i64) -> Result<i64, String> {
+    let date = Local::now().date_naive() - chrono::Duration::days(days_back);
+    Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .ok_or_else(|| format!("Could not resolve local midnight for {}", date))
+}
+
+#[tauri::command]
+pub async fn get_conversations_created_today(
+    db: State<'_, Database>,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let from = local_day_start(0)?;
+    let to = Local::now().timestamp();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_by_date_range(&conn, from, to, limit).map_err(|e| e.to_string())
+}
+
+/// Conversations created since Monday 00:00 local time
+#[tauri::command]
+pub async fn get_conversations_created_this_week(
+    db: State<'_, Database>,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let days_since_monday = Local::now().weekday().num_days_from_monday() as i64;
+    let from = local_day_start(days_since_monday)?;
+    let to = Local::now().timestamp();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::get_by_date_range(&conn, from, to, limit).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -43,20 +216,91 @@ pub async fn update_conversation_title(
     id: String,
     title: String,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Conversation::update_title(&conn, &id, &title).map_err(|e| e.to_string())
 }
 
+/// Switch a conversation's model or provider mid-conversation, or change its
+/// system prompt. Omitted fields are unchanged; an empty `system_prompt` clears it.
+#[tauri::command]
+pub async fn update_conversation_settings(
+    db: State<'_, Database>,
+    id: String,
+    model: Option<String>,
+    provider: Option<String>,
+    system_prompt: Option<String>,
+) -> Result<Conversation, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let system_prompt = system_prompt
+        .as_deref()
+        .map(|prompt| Some(prompt).filter(|p| !p.is_empty()));
+    Conversation::update_settings(
+        &conn,
+        &id,
+        model.as_deref(),
+        provider.as_deref(),
+        system_prompt,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Conversation not found: {}", id),
+        e => e.to_string(),
+    })
+}
+
 #[tauri::command]
-pub async fn delete_conversation(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Conversation::delete(&conn, &id).map_err(|e| e.to_string())
+pub async fn delete_conversation(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::delete(&conn, &id).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn restore_conversation(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Conversation::restore(&conn, &id).map_err(|e| e.to_string())
+pub async fn archive_conversation(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::archive(&conn, &id).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unarchive_conversation(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::unarchive(&conn, &id).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn restore_conversation(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::restore(&conn, &id).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(())
 }
 
 #[tauri::command]
@@ -65,27 +309,106 @@ pub async fn search_conversations(
     query: String,
     limit: i64,
 ) -> Result<Vec<Conversation>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Conversation::search(&conn, &query, limit).map_err(|e| e.to_string())
 }
 
+/// Combined filter: every tag in `tag_ids`, an optional title substring and an
+/// optional provider
 #[tauri::command]
-pub async fn cleanup_conversations(db: State<'_, Database>) -> Result<String, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+pub async fn search_conversations_by_tag_and_query(
+    db: State<'_, Database>,
+    tag_ids: Vec<String>,
+    query: Option<String>,
+    provider: Option<String>,
+    limit: i64,
+) -> Result<Vec<Conversation>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Conversation::search_filtered(
+        &conn,
+        &tag_ids,
+        query.as_deref(),
+        provider.as_deref(),
+        limit,
+    )
+    .map_err(|e| e.to_string())
+}
 
-    // Get all conversations and count them
-    let all_conversations = Conversation::get_all(&conn, 10000) // Get up to 10k conversations for cleanup
-        .map_err(|e| e.to_string())?;
+/// Archive every conversation; they stay accessible and can be unarchived
+#[tauri::command]
+pub async fn archive_all_conversations(db: State<'_, Database>) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let archived = Conversation::archive_all(&conn).map_err(|e| e.to_string())?;
+    Ok(format!("Archived {} conversations", archived))
+}
 
-    let mut deleted_count = 0;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupResult {
+    pub conversations_deleted: usize,
+    pub messages_deleted: usize,
+    pub bytes_freed: u64,
+}
 
-    // Mark all conversations as deleted (soft delete)
-    for conv in &all_conversations {
-        Conversation::delete(&conn, &conv.id).map_err(|e| e.to_string())?;
-        deleted_count += 1;
-    }
+fn database_size(conn: &rusqlite::Connection) -> Result<u64, String> {
+    conn.query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|size| size.max(0) as u64)
+    .map_err(|e| e.to_string())
+}
+
+/// Permanently delete conversations and messages that were soft-deleted more than
+/// `older_than_days` days ago, then VACUUM to give the space back to the OS
+pub fn permanently_cleanup(
+    conn: &rusqlite::Connection,
+    older_than_days: u64,
+) -> Result<CleanupResult, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    let cutoff = now.saturating_sub((older_than_days as i64).saturating_mul(86_400));
+
+    let size_before = database_size(conn)?;
+    let (conversations_deleted, messages_deleted) =
+        Conversation::purge_deleted_before(conn, cutoff).map_err(|e| e.to_string())?;
+    conn.execute_batch("VACUUM").map_err(|e| e.to_string())?;
+    let size_after = database_size(conn)?;
+
+    Ok(CleanupResult {
+        conversations_deleted,
+        messages_deleted,
+        bytes_freed: size_before.saturating_sub(size_after),
+    })
+}
+
+#[tauri::command]
+pub async fn permanently_cleanup_conversations(
+    db: State<'_, Database>,
+    older_than_days: u64,
+) -> Result<CleanupResult, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    permanently_cleanup(&conn, older_than_days)
+}
 
-    Ok(format!("Deleted {} conversations", deleted_count))
+#[tauri::command]
+pub async fn duplicate_conversation(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    source_id: String,
+    new_title: Option<String>,
+) -> Result<Conversation, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let duplicate = Conversation::duplicate(&conn, &source_id, new_title).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Conversation not found: {}", source_id),
+        e => e.to_string(),
+    })?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(duplicate)
 }
 
 #[tauri::command]
@@ -95,14 +418,19 @@ pub async fn create_conversation_branch(
     branch_point_message_id: String,
     title: String,
 ) -> Result<Conversation, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Conversation::create_branch(
         &conn,
         &parent_conversation_id,
         &branch_point_message_id,
         title,
     )
-    .map_err(|e| e.to_string())
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            format!("Conversation not found: {}", parent_conversation_id)
+        }
+        e => e.to_string(),
+    })
 }
 
 #[tauri::command]
@@ -110,6 +438,512 @@ pub async fn get_conversation_branches(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<Vec<Conversation>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Conversation::get_branches(&conn, &conversation_id).map_err(|e| e.to_string())
 }
+
+/// Keywords kept in a title generated from message content
+const TITLE_KEYWORD_COUNT: usize = 5;
+
+/// Common English and chat filler words that never make a useful title keyword
+const TITLE_STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "all", "also", "and", "any", "are", "because", "been",
+    "before", "being", "below", "between", "both", "but", "can", "could", "did", "does", "doing",
+    "don", "down", "during", "each", "few", "for", "from", "further", "get", "got", "had", "has",
+    "have", "having", "help", "her", "here", "hers", "him", "his", "how", "into", "its", "just",
+    "know", "like", "make", "may", "might", "more", "most", "much", "must", "need", "not", "now",
+    "off", "once", "only", "other", "our", "ours", "out", "over", "own", "please", "same",
+    "should", "some", "such", "than", "thank", "thanks", "that", "the", "their", "theirs", "them",
+    "then", "there", "these", "they", "this", "those", "through", "too", "under", "until", "use",
+    "using", "very", "want", "was", "way", "were", "what", "when", "where", "which", "while",
+    "who", "whom", "why", "will", "with", "would", "you", "your", "yours",
+];
+
+/// Lowercase words of three or more characters, split on anything that isn't
+/// alphanumeric, without stopwords or bare numbers
+fn title_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 3)
+        .filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !TITLE_STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Top `TITLE_KEYWORD_COUNT` terms by term frequency times smoothed inverse document
+/// frequency, title-cased and kept in the order they first appear
+fn keyword_title(terms: &[String], mut idf: impl FnMut(&str) -> f64) -> Option<String> {
+    // term -> (frequency, first position)
+    let mut frequencies: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (position, term) in terms.iter().enumerate() {
+        frequencies.entry(term).or_insert((0, position)).0 += 1;
+    }
+
+    let mut scored: Vec<(&str, f64, usize)> = frequencies
+        .into_iter()
+        .map(|(term, (tf, first))| (term, tf as f64 * idf(term), first))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.cmp(&b.2)));
+    scored.truncate(TITLE_KEYWORD_COUNT);
+    scored.sort_by_key(|(_, _, first)| *first);
+
+    let words: Vec<String> = scored
+        .into_iter()
+        .map(|(term, _, _)| {
+            let mut chars = term.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Title a conversation from keywords of its first two user messages, weighting
+/// terms by how rare they are across all messages (via an fts5vocab view of the
+/// search index). Saves and returns the new title.
+pub fn generate_title(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+) -> Result<String, String> {
+    let messages =
+        Message::get_by_conversation(conn, conversation_id).map_err(|e| e.to_string())?;
+    let text = messages
+        .iter()
+        .filter(|m| m.role == "user")
+        .take(2)
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Temp tables live only as long as this connection, so no migration is needed
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS temp.messages_fts_vocab
+         USING fts5vocab(main, messages_fts, row)",
+    )
+    .map_err(|e| e.to_string())?;
+    let total_docs: i64 = conn
+        .query_row("SELECT COUNT(*) FROM messages_fts", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let mut doc_freq = conn
+        .prepare("SELECT doc FROM temp.messages_fts_vocab WHERE term = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let idf = |term: &str| {
+        let docs: i64 = doc_freq.query_row([term], |row| row.get(0)).unwrap_or(0);
+        ((1 + total_docs) as f64 / (1 + docs) as f64).ln() + 1.0
+    };
+    let title = keyword_title(&title_terms(&text), idf)
+        .ok_or_else(|| "Not enough message content to generate a title".to_string())?;
+
+    Conversation::update_title(conn, conversation_id, &title).map_err(|e| e.to_string())?;
+    Ok(title)
+}
+
+#[tauri::command]
+pub async fn generate_title_from_content(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let title = generate_title(&conn, &conversation_id)?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(title)
+}
+
+/// Only the most recent undoable events of a conversation can be undone
+const UNDO_STACK_DEPTH: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoResult {
+    pub action_reversed: String,
+    pub entity_id: String,
+    pub entity_type: String,
+}
+
+/// Reverse the newest not-yet-undone title change, message edit or message deletion
+/// in a conversation. The reversal bypasses the logging model methods and is
+/// recorded as an `undone` event pointing at the original, so it cannot itself be
+/// undone and the log stays a complete history.
+fn undo_last(conn: &rusqlite::Connection, conversation_id: &str) -> Result<UndoResult, String> {
+    let event = conn
+        .query_row(
+            "SELECT id, entity_type, entity_id, event_type, payload
+             FROM (
+                 SELECT e.id, e.entity_type, e.entity_id, e.event_type, e.payload,
+                        e.timestamp, e.rowid AS seq
+                 FROM event_log e
+                 WHERE (e.entity_type = 'conversation' AND e.entity_id = ?1
+                        AND e.event_type = 'title_updated')
+                    OR (e.entity_type = 'message' AND e.event_type IN ('updated', 'deleted')
+                        AND e.entity_id IN (SELECT id FROM messages WHERE conversation_id = ?1))
+                 ORDER BY e.timestamp DESC, e.rowid DESC
+                 LIMIT ?2
+             ) stack
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM event_log u
+                 WHERE u.event_type = 'undone' AND json_extract(u.payload, '$.event_id') = stack.id
+             )
+             ORDER BY timestamp DESC, seq DESC
+             LIMIT 1",
+            rusqlite::params![conversation_id, UNDO_STACK_DEPTH],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (event_id, entity_type, entity_id, event_type, payload) =
+        event.ok_or_else(|| format!("Nothing to undo in conversation {}", conversation_id))?;
+    let payload: serde_json::Value = serde_json::from_str(&payload).map_err(|e| e.to_string())?;
+    let previous = || {
+        payload["previous"]
+            .as_str()
+            .ok_or_else(|| format!("Event {} has no previous value to restore", event_id))
+    };
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let action_reversed = match (entity_type.as_str(), event_type.as_str()) {
+        ("conversation", "title_updated") => {
+            tx.execute(
+                "UPDATE conversations SET title = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![previous()?, now, entity_id],
+            )
+            .map_err(|e| e.to_string())?;
+            "title_updated"
+        }
+        ("message", "updated") => {
+            tx.execute(
+                "UPDATE messages SET content = ?1 WHERE id = ?2",
+                rusqlite::params![previous()?, entity_id],
+            )
+            .map_err(|e| e.to_string())?;
+            "message_updated"
+        }
+        ("message", "deleted") => {
+            tx.execute(
+                "UPDATE messages SET deleted = 0, deleted_at = NULL WHERE id = ?1",
+                rusqlite::params![entity_id],
+            )
+            .map_err(|e| e.to_string())?;
+            "message_deleted"
+        }
+        _ => unreachable!("undo stack only selects undoable events"),
+    };
+    if entity_type == "message" {
+        Conversation::touch(&tx, conversation_id).map_err(|e| e.to_string())?;
+    }
+    EventLog::record(
+        &tx,
+        &entity_type,
+        &entity_id,
+        "undone",
+        &serde_json::json!({ "event_id": event_id, "event_type": event_type }),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(UndoResult {
+        action_reversed: action_reversed.to_string(),
+        entity_id,
+        entity_type,
+    })
+}
+
+/// Undo the latest title change, message edit or message deletion in a conversation
+#[tauri::command]
+pub async fn undo_last_action(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<UndoResult, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let result = undo_last(&conn, &conversation_id)?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_helpers::{add_test_message, create_test_conversation};
+    use crate::database::{messages::NewMessage, tags::NewTag};
+
+    #[test]
+    fn conversation_with_messages_fills_every_field() {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().unwrap();
+
+        let conv = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Combined".to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: None,
+            },
+        )
+        .unwrap();
+        for (role, tokens) in [("user", 5), ("assistant", 7)] {
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conv.id.clone(),
+                    role: role.to_string(),
+                    content: format!("{} says hi", role),
+                    tokens_used: Some(tokens),
+                },
+            )
+            .unwrap();
+        }
+        let tag = Tag::create(
+            &conn,
+            NewTag {
+                name: "work".to_string(),
+                color: None,
+            },
+        )
+        .unwrap();
+        Tag::add_to_conversation(&conn, &conv.id, &tag.id).unwrap();
+
+        let loaded = load_conversation_with_messages(&conn, &conv.id, true).unwrap();
+        assert_eq!(loaded.conversation.title, "Combined");
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.total_tokens, Some(12));
+        assert_eq!(loaded.tags.len(), 1);
+        assert_eq!(loaded.tags[0].name, "work");
+
+        let without_count = load_conversation_with_messages(&conn, &conv.id, false).unwrap();
+        assert_eq!(without_count.total_tokens, None);
+        assert!(load_conversation_with_messages(&conn, "missing", false).is_err());
+    }
+
+    #[test]
+    fn title_from_content_uses_rare_keywords() {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().unwrap();
+
+        let mut ids = Vec::new();
+        for title in ["New chat", "Other chat"] {
+            let conv = Conversation::create(
+                &conn,
+                NewConversation {
+                    title: title.to_string(),
+                    model: "gpt-test".to_string(),
+                    provider: "local".to_string(),
+                    system_prompt: None,
+                },
+            )
+            .unwrap();
+            ids.push(conv.id);
+        }
+        let add = |conversation_id: &str, role: &str, content: &str| {
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conversation_id.to_string(),
+                    role: role.to_string(),
+                    content: content.to_string(),
+                    tokens_used: None,
+                },
+            )
+            .unwrap();
+        };
+        add(
+            &ids[0],
+            "user",
+            "How do I configure the Tokio runtime worker threads in my Rust service?",
+        );
+        add(
+            &ids[0],
+            "assistant",
+            "Use the builder to set worker threads.",
+        );
+        add(
+            &ids[0],
+            "user",
+            "The Tokio runtime still panics when the service starts.",
+        );
+        add(
+            &ids[0],
+            "user",
+            "A third question about databases is ignored.",
+        );
+        // "rust" is common elsewhere, so it ranks below the rarer terms
+        for _ in 0..5 {
+            add(&ids[1], "user", "Rust Rust Rust");
+        }
+
+        let title = generate_title(&conn, &ids[0]).unwrap();
+        let words: Vec<&str> = title.split(' ').collect();
+        assert_eq!(words.len(), TITLE_KEYWORD_COUNT);
+        for keyword in ["Tokio", "Runtime", "Service"] {
+            assert!(
+                words.contains(&keyword),
+                "{} missing from {}",
+                keyword,
+                title
+            );
+        }
+        assert!(!words.contains(&"Rust"));
+        assert!(!words.contains(&"Databases"));
+        assert!(!words.contains(&"The"));
+
+        let saved = Conversation::get_by_id(&conn, &ids[0]).unwrap().unwrap();
+        assert_eq!(saved.title, title);
+
+        let empty = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Empty".to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: None,
+            },
+        )
+        .unwrap();
+        assert!(generate_title(&conn, &empty.id).is_err());
+    }
+
+    #[test]
+    fn undo_reverses_title_message_update_and_delete() {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().unwrap();
+        let conv = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Original".to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: None,
+            },
+        )
+        .unwrap();
+        let msg = |content: &str| {
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conv.id.clone(),
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                    tokens_used: None,
+                },
+            )
+            .unwrap()
+        };
+        let edited = msg("draft");
+        let removed = msg("keep me");
+        assert!(undo_last(&conn, &conv.id).is_err());
+
+        Conversation::update_title(&conn, &conv.id, "Renamed").unwrap();
+        Message::update(&conn, &edited.id, "final").unwrap();
+        Message::delete(&conn, &removed.id).unwrap();
+
+        let undone = undo_last(&conn, &conv.id).unwrap();
+        assert_eq!(undone.action_reversed, "message_deleted");
+        assert_eq!(undone.entity_id, removed.id);
+        assert!(Message::get_by_id(&conn, &removed.id, false)
+            .unwrap()
+            .is_some());
+
+        let undone = undo_last(&conn, &conv.id).unwrap();
+        assert_eq!(undone.action_reversed, "message_updated");
+        assert_eq!(undone.entity_type, "message");
+        let content = Message::get_by_id(&conn, &edited.id, false)
+            .unwrap()
+            .unwrap()
+            .content;
+        assert_eq!(content, "draft");
+
+        let undone = undo_last(&conn, &conv.id).unwrap();
+        assert_eq!(undone.action_reversed, "title_updated");
+        assert_eq!(undone.entity_type, "conversation");
+        let title = Conversation::get_by_id(&conn, &conv.id)
+            .unwrap()
+            .unwrap()
+            .title;
+        assert_eq!(title, "Original");
+
+        // Undoing doesn't log undoable events, so there is nothing left
+        assert!(undo_last(&conn, &conv.id).is_err());
+
+        // Only the newest UNDO_STACK_DEPTH edits can be undone
+        for i in 0..=UNDO_STACK_DEPTH {
+            Conversation::update_title(&conn, &conv.id, &format!("Title {}", i)).unwrap();
+        }
+        for _ in 0..UNDO_STACK_DEPTH {
+            undo_last(&conn, &conv.id).unwrap();
+        }
+        assert!(undo_last(&conn, &conv.id).is_err());
+        let title = Conversation::get_by_id(&conn, &conv.id)
+            .unwrap()
+            .unwrap()
+            .title;
+        assert_eq!(title, "Title 0");
+    }
+
+    #[test]
+    fn permanent_cleanup_removes_old_soft_deleted_rows() {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let mut ids = Vec::new();
+        for title in ["old", "recent", "kept"] {
+            let conv = create_test_conversation(&conn, title);
+            for content in ["searchable one", "searchable two"] {
+                add_test_message(&conn, &conv.id, "user", content);
+            }
+            ids.push(conv.id);
+        }
+
+        // "old" was deleted 40 days ago, "recent" just now; one message in "kept" long ago
+        Conversation::delete(&conn, &ids[0]).expect("delete old");
+        Conversation::delete(&conn, &ids[1]).expect("delete recent");
+        conn.execute(
+            "UPDATE conversations SET deleted_at = deleted_at - 40 * 86400 WHERE id = ?1",
+            [&ids[0]],
+        )
+        .expect("age conv");
+        let kept_msg = Message::get_by_conversation(&conn, &ids[2]).expect("msgs")[0]
+            .id
+            .clone();
+        Message::delete(&conn, &kept_msg).expect("delete msg");
+        conn.execute(
+            "UPDATE messages SET deleted_at = deleted_at - 40 * 86400 WHERE id = ?1",
+            [&kept_msg],
+        )
+        .expect("age msg");
+
+        let result = permanently_cleanup(&conn, 30).expect("cleanup");
+        assert_eq!(result.conversations_deleted, 1);
+        assert_eq!(result.messages_deleted, 3);
+
+        assert!(!Conversation::exists(&conn, &ids[0]).expect("exists"));
+        assert!(Conversation::exists(&conn, &ids[1]).expect("exists"));
+        assert!(!Message::exists(&conn, &kept_msg).expect("exists"));
+        let fts_rows: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'searchable'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("fts count");
+        assert_eq!(fts_rows, 3);
+    }
+}
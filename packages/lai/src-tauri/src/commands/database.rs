@@ -0,0 +1,118 @@
+use crate::database::event_log::{EventLog, EventLogEntry};
+use crate::database::migrations::{self, MigrationInfo};
+use crate::database::Database;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// One row of `PRAGMA foreign_key_check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    /// `None` for WITHOUT ROWID tables
+    pub rowid: Option<i64>,
+    pub parent: String,
+    pub fkid: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub integrity_ok: bool,
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    /// What to do about a failed integrity check, if anything
+    pub suggestion: Option<String>,
+}
+
+/// Run `PRAGMA integrity_check` and `PRAGMA foreign_key_check` on `conn`
+pub fn integrity_report(conn: &Connection) -> rusqlite::Result<IntegrityReport> {
+    let integrity_errors: Vec<String> = conn
+        .prepare("PRAGMA integrity_check")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter(|line| !matches!(line, Ok(l) if l == "ok"))
+        .collect::<rusqlite::Result<_>>()?;
+    let foreign_key_violations = conn
+        .prepare("PRAGMA foreign_key_check")?
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                rowid: row.get(1)?,
+                parent: row.get(2)?,
+                fkid: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let integrity_ok = integrity_errors.is_empty();
+    Ok(IntegrityReport {
+        integrity_ok,
+        integrity_errors,
+        foreign_key_violations,
+        suggestion: (!integrity_ok)
+            .then(|| "The database file is damaged; restore it from a backup".to_string()),
+    })
+}
+
+/// All schema migrations with their applied state, for diagnosing upgrade issues
+#[tauri::command]
+pub async fn list_pending_migrations(
+    db: State<'_, Database>,
+) -> Result<Vec<MigrationInfo>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    migrations::list_migrations(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_schema_version(db: State<'_, Database>) -> Result<u32, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    migrations::schema_version(&conn).map_err(|e| e.to_string())
+}
+
+/// Check the database file for corruption and dangling foreign keys, e.g. after a crash
+#[tauri::command]
+pub async fn check_database_integrity(db: State<'_, Database>) -> Result<IntegrityReport, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    integrity_report(&conn).map_err(|e| e.to_string())
+}
+
+/// Recorded conversation and message changes, newest first
+#[tauri::command]
+pub async fn get_event_log(
+    db: State<'_, Database>,
+    entity_id: Option<String>,
+    since: Option<i64>,
+    limit: usize,
+) -> Result<Vec<EventLogEntry>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    EventLog::list(&conn, entity_id.as_deref(), since, limit as i64).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn reports_foreign_key_violations() {
+        let db = Database::new(PathBuf::from(":memory:")).unwrap();
+        let conn = db.conn().unwrap();
+
+        let report = integrity_report(&conn).unwrap();
+        assert!(report.integrity_ok);
+        assert!(report.foreign_key_violations.is_empty());
+        assert!(report.suggestion.is_none());
+
+        conn.execute("PRAGMA foreign_keys = OFF", []).unwrap();
+        conn.execute(
+            "INSERT INTO messages (id, conversation_id, role, content, timestamp) VALUES ('m1', 'missing', 'user', 'hi', 0)",
+            [],
+        )
+        .unwrap();
+
+        let report = integrity_report(&conn).unwrap();
+        assert!(report.integrity_ok);
+        assert_eq!(report.foreign_key_violations.len(), 1);
+        assert_eq!(report.foreign_key_violations[0].table, "messages");
+        assert_eq!(report.foreign_key_violations[0].parent, "conversations");
+    }
+}
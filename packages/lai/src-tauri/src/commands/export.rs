@@ -1,12 +1,16 @@
 use crate::database::{
     conversations::{Conversation, NewConversationWithId},
     messages::{Message, NewMessageWithId},
+    tags::Tag,
     Database,
 };
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL;
+use base64::Engine;
 use comrak::{markdown_to_html, ComrakOptions};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use printpdf::*;
 use serde::{Deserialize, Serialize};
-use std::io::BufWriter;
+use std::io::{BufWriter, Read, Write};
 use tauri::{Manager, State};
 
 #[derive(Serialize, Deserialize)]
@@ -37,57 +41,70 @@ pub struct ExportData {
     pub conversations: Vec<ExportedConversation>,
 }
 
-#[tauri::command]
-pub fn export_conversations_json(
-    db: State<'_, Database>,
+/// Get conversations to export (all if none specified); unknown IDs are skipped
+fn conversations_to_export(
+    conn: &rusqlite::Connection,
     conversation_ids: Option<Vec<String>>,
-) -> Result<String, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-
-    // Get conversations to export (all if none specified)
-    let conversations = if let Some(ids) = conversation_ids {
+) -> Result<Vec<Conversation>, String> {
+    if let Some(ids) = conversation_ids {
         let mut result = Vec::new();
         for id in ids {
-            match Conversation::get_by_id(&conn, &id) {
+            match Conversation::get_by_id(conn, &id) {
                 Ok(Some(conv)) => result.push(conv),
                 Ok(None) => continue,
                 Err(e) => return Err(format!("Failed to get conversation {}: {}", id, e)),
             }
         }
-        result
+        Ok(result)
     } else {
-        Conversation::get_all(&conn, 1000) // Get up to 1000 conversations
-            .map_err(|e| format!("Failed to get conversations: {}", e))?
-    };
-
-    let mut exported_conversations = Vec::new();
+        Conversation::get_all(conn, 1000) // Get up to 1000 conversations
+            .map_err(|e| format!("Failed to get conversations: {}", e))
+    }
+}
 
-    for conv in conversations {
-        let messages = Message::get_by_conversation(&conn, &conv.id)
-            .map_err(|e| format!("Failed to get messages for conversation {}: {}", conv.id, e))?;
+/// A conversation with its non-deleted messages in export form
+fn export_conversation(
+    conn: &rusqlite::Connection,
+    conv: Conversation,
+) -> Result<ExportedConversation, String> {
+    let messages = Message::get_by_conversation(conn, &conv.id)
+        .map_err(|e| format!("Failed to get messages for conversation {}: {}", conv.id, e))?;
+
+    let exported_messages: Vec<ExportedMessage> = messages
+        .into_iter()
+        .map(|msg| ExportedMessage {
+            id: msg.id,
+            role: msg.role,
+            content: msg.content,
+            timestamp: msg.timestamp,
+            tokens_used: msg.tokens_used,
+        })
+        .collect();
+
+    Ok(ExportedConversation {
+        id: conv.id,
+        title: conv.title,
+        provider: conv.provider,
+        model: conv.model,
+        system_prompt: conv.system_prompt,
+        created_at: conv.created_at,
+        updated_at: conv.updated_at,
+        messages: exported_messages,
+    })
+}
 
-        let exported_messages: Vec<ExportedMessage> = messages
-            .into_iter()
-            .map(|msg| ExportedMessage {
-                id: msg.id,
-                role: msg.role,
-                content: msg.content,
-                timestamp: msg.timestamp,
-                tokens_used: msg.tokens_used,
-            })
-            .collect();
+#[tauri::command]
+pub fn export_conversations_json(
+    db: State<'_, Database>,
+    conversation_ids: Option<Vec<String>>,
+) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let conversations = conversations_to_export(&conn, conversation_ids)?;
 
-        exported_conversations.push(ExportedConversation {
-            id: conv.id,
-            title: conv.title,
-            provider: conv.provider,
-            model: conv.model,
-            system_prompt: conv.system_prompt,
-            created_at: conv.created_at,
-            updated_at: conv.updated_at,
-            messages: exported_messages,
-        });
-    }
+    let exported_conversations = conversations
+        .into_iter()
+        .map(|conv| export_conversation(&conn, conv))
+        .collect::<Result<Vec<_>, String>>()?;
 
     let export_data = ExportData {
         version: "1.0.0".to_string(),
@@ -104,7 +121,7 @@ pub fn export_conversation_markdown(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<String, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     let conversation = Conversation::get_by_id(&conn, &conversation_id)
         .map_err(|e| format!("Failed to get conversation: {}", e))?
@@ -149,6 +166,143 @@ pub fn export_conversation_markdown(
     Ok(markdown)
 }
 
+fn format_created(timestamp: i64, pattern: &str) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format(pattern).to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Notion only nests lists two levels deep
+const NOTION_MAX_LIST_DEPTH: usize = 1;
+
+/// Adapt message Markdown for Notion's importer: code fences always name a
+/// language and list items deeper than two levels are flattened
+fn notion_normalize(content: &str) -> String {
+    let mut in_code = false;
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            if !in_code && trimmed.trim_end() == "```" {
+                lines.push(format!(
+                    "{}```plaintext",
+                    &line[..line.len() - trimmed.len()]
+                ));
+            } else {
+                lines.push(line.to_string());
+            }
+            in_code = !in_code;
+            continue;
+        }
+
+        let is_list_item = trimmed.starts_with("- ")
+            || trimmed.starts_with("* ")
+            || trimmed.starts_with("+ ")
+            || trimmed
+                .split_once(". ")
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if !in_code && is_list_item {
+            let indent: usize = line[..line.len() - trimmed.len()]
+                .chars()
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum();
+            let depth = (indent / 2).min(NOTION_MAX_LIST_DEPTH);
+            lines.push(format!("{}{}", "  ".repeat(depth), trimmed));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Markdown tuned for pasting into Notion
+#[tauri::command]
+pub fn export_conversation_notion(
+    db: State<'_, Database>,
+    conversation_id: String,
+) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+
+    let conversation = Conversation::get_by_id(&conn, &conversation_id)
+        .map_err(|e| format!("Failed to get conversation: {}", e))?
+        .ok_or_else(|| "Conversation not found".to_string())?;
+
+    let messages = Message::get_by_conversation(&conn, &conversation_id)
+        .map_err(|e| format!("Failed to get messages: {}", e))?;
+
+    let mut markdown = String::new();
+    markdown.push_str(&format!("# {}\n\n", conversation.title));
+    markdown.push_str(&format!(
+        "> 📎 Note: {} · {} · {}\n\n",
+        conversation.provider,
+        conversation.model,
+        format_created(conversation.created_at, "%Y-%m-%d %H:%M UTC")
+    ));
+
+    for msg in messages {
+        markdown.push_str("---\n\n");
+        match msg.role.as_str() {
+            "user" => markdown.push_str("### 👤 User\n\n"),
+            "assistant" => markdown.push_str("### 🤖 Assistant\n\n"),
+            // System prompts read best as a callout rather than a turn
+            "system" => {
+                let callout = msg.content.lines().collect::<Vec<_>>().join("\n> ");
+                markdown.push_str(&format!("> 📎 Note: {}\n\n", callout));
+                continue;
+            }
+            other => markdown.push_str(&format!("### {}\n\n", other)),
+        }
+
+        markdown.push_str(&format!("{}\n\n", notion_normalize(&msg.content)));
+
+        if let Some(tokens) = msg.tokens_used {
+            markdown.push_str(&format!("*Tokens used: {}*\n\n", tokens));
+        }
+    }
+
+    Ok(markdown)
+}
+
+/// Markdown with YAML front-matter (`title`, `date`, `model`, `provider`, `tags`) for Obsidian
+#[tauri::command]
+pub fn export_conversation_obsidian(
+    db: State<'_, Database>,
+    conversation_id: String,
+) -> Result<String, String> {
+    let (conversation, tags) = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        let conversation = Conversation::get_by_id(&conn, &conversation_id)
+            .map_err(|e| format!("Failed to get conversation: {}", e))?
+            .ok_or_else(|| "Conversation not found".to_string())?;
+        let tags = Tag::get_for_conversation(&conn, &conversation_id)
+            .map_err(|e| format!("Failed to get tags: {}", e))?;
+        (conversation, tags)
+    };
+
+    // JSON strings are valid double-quoted YAML scalars
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string());
+
+    let mut front_matter = String::from("---\n");
+    front_matter.push_str(&format!("title: {}\n", quote(&conversation.title)));
+    front_matter.push_str(&format!(
+        "date: {}\n",
+        format_created(conversation.created_at, "%Y-%m-%d")
+    ));
+    front_matter.push_str(&format!("model: {}\n", quote(&conversation.model)));
+    front_matter.push_str(&format!("provider: {}\n", quote(&conversation.provider)));
+    front_matter.push_str("tags:\n  - lai\n");
+    for tag in tags {
+        // Obsidian tags cannot contain spaces
+        let name = tag.name.split_whitespace().collect::<Vec<_>>().join("-");
+        front_matter.push_str(&format!("  - {}\n", quote(&name)));
+    }
+    front_matter.push_str("---\n\n");
+
+    let body = export_conversation_markdown(db, conversation_id)?;
+    Ok(front_matter + &body)
+}
+
 #[tauri::command]
 pub fn export_conversation_html(
     db: State<'_, Database>,
@@ -337,7 +491,7 @@ pub fn export_conversation_pdf(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<Vec<u8>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     let conversation = Conversation::get_by_id(&conn, &conversation_id)
         .map_err(|e| format!("Failed to get conversation: {}", e))?
@@ -607,66 +761,197 @@ pub async fn save_export_file(
 
     Ok(path.to_string_lossy().to_string())
 }
-#[tauri::command]
-pub fn import_conversations_json(
-    db: State<'_, Database>,
-    json_content: String,
-) -> Result<String, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+/// What to do when an imported conversation ID already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the existing conversation and ignore the imported one
+    Skip,
+    /// Delete the existing conversation and import the new one in its place
+    Overwrite,
+    /// Add imported messages that the existing conversation doesn't have yet
+    Merge,
+    /// Import as a new conversation with fresh IDs and an " (imported)" title suffix
+    Rename,
+}
 
-    let export_data: ExportData =
-        serde_json::from_str(&json_content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+impl ConflictResolution {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "merge" => Ok(Self::Merge),
+            "rename" => Ok(Self::Rename),
+            other => Err(format!(
+                "Invalid conflict resolution '{}'. Supported: skip, overwrite, merge, rename",
+                other
+            )),
+        }
+    }
+}
 
-    let mut imported_count = 0;
-    let mut skipped_count = 0;
+/// How one conflicting conversation was handled
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConflictRecord {
+    pub conversation_id: String,
+    pub title: String,
+    /// `skipped`, `overwritten`, `merged` or `renamed`
+    pub action: String,
+    /// ID the conversation was imported under when renamed
+    pub new_id: Option<String>,
+    pub messages_imported: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportResult {
+    pub imported_count: usize,
+    pub skipped_count: usize,
+    pub resolved_conflicts: Vec<ConflictRecord>,
+}
+
+/// Insert a conversation and its messages. With `fresh_ids`, every row gets a new
+/// UUID so the copy can sit alongside the original.
+fn insert_exported_conversation(
+    conn: &rusqlite::Connection,
+    conv: ExportedConversation,
+    title: String,
+    fresh_ids: bool,
+) -> Result<(String, usize), String> {
+    let id = if fresh_ids {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        conv.id.clone()
+    };
+
+    let conversation = NewConversationWithId {
+        id: id.clone(),
+        title,
+        provider: conv.provider,
+        model: conv.model,
+        system_prompt: conv.system_prompt,
+        created_at: conv.created_at,
+        updated_at: conv.updated_at,
+    };
+
+    Conversation::create_with_id(conn, conversation)
+        .map_err(|e| format!("Failed to create conversation {}: {}", conv.id, e))?;
+
+    let messages: Vec<NewMessageWithId> = conv
+        .messages
+        .into_iter()
+        .map(|msg| NewMessageWithId {
+            id: if fresh_ids {
+                uuid::Uuid::new_v4().to_string()
+            } else {
+                msg.id
+            },
+            conversation_id: id.clone(),
+            role: msg.role,
+            content: msg.content,
+            timestamp: msg.timestamp,
+            tokens_used: msg.tokens_used,
+        })
+        .collect();
+    let count = Message::create_batch(conn, messages)
+        .map_err(|e| format!("Failed to import messages for {}: {}", conv.id, e))?
+        .len();
+
+    Ok((id, count))
+}
+
+/// Import exported conversations in one transaction, resolving ID conflicts
+/// (including soft-deleted conversations) with `strategy`
+pub fn import_export_data(
+    conn: &rusqlite::Connection,
+    export_data: ExportData,
+    strategy: ConflictResolution,
+) -> Result<ImportResult, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut result = ImportResult {
+        imported_count: 0,
+        skipped_count: 0,
+        resolved_conflicts: Vec::new(),
+    };
 
     for conv in export_data.conversations {
-        // Check if conversation already exists
-        if Conversation::get_by_id(&conn, &conv.id)
-            .map_err(|e| e.to_string())?
-            .is_some()
-        {
-            skipped_count += 1;
+        if !Conversation::exists(&tx, &conv.id).map_err(|e| e.to_string())? {
+            let title = conv.title.clone();
+            insert_exported_conversation(&tx, conv, title, false)?;
+            result.imported_count += 1;
             continue;
         }
 
-        // Create conversation
-        let conversation = NewConversationWithId {
-            id: conv.id.clone(),
-            title: conv.title,
-            provider: conv.provider,
-            model: conv.model,
-            system_prompt: conv.system_prompt,
-            created_at: conv.created_at,
-            updated_at: conv.updated_at,
+        let conversation_id = conv.id.clone();
+        let title = conv.title.clone();
+        let (action, new_id, messages_imported) = match strategy {
+            ConflictResolution::Skip => {
+                result.skipped_count += 1;
+                ("skipped", None, 0)
+            }
+            ConflictResolution::Overwrite => {
+                Conversation::purge(&tx, &conv.id).map_err(|e| e.to_string())?;
+                let (_, count) = insert_exported_conversation(&tx, conv, title.clone(), false)?;
+                result.imported_count += 1;
+                ("overwritten", None, count)
+            }
+            ConflictResolution::Merge => {
+                let mut new_messages = Vec::new();
+                for msg in conv.messages {
+                    if Message::exists(&tx, &msg.id).map_err(|e| e.to_string())? {
+                        continue;
+                    }
+                    new_messages.push(NewMessageWithId {
+                        id: msg.id,
+                        conversation_id: conv.id.clone(),
+                        role: msg.role,
+                        content: msg.content,
+                        timestamp: msg.timestamp,
+                        tokens_used: msg.tokens_used,
+                    });
+                }
+                let count = Message::create_batch(&tx, new_messages)
+                    .map_err(|e| format!("Failed to merge messages for {}: {}", conv.id, e))?
+                    .len();
+                if count > 0 {
+                    Conversation::touch(&tx, &conv.id).map_err(|e| e.to_string())?;
+                }
+                ("merged", None, count)
+            }
+            ConflictResolution::Rename => {
+                let renamed = format!("{} (imported)", conv.title);
+                let (id, count) = insert_exported_conversation(&tx, conv, renamed, true)?;
+                result.imported_count += 1;
+                ("renamed", Some(id), count)
+            }
         };
 
-        Conversation::create_with_id(&conn, conversation)
-            .map_err(|e| format!("Failed to create conversation {}: {}", conv.id, e))?;
-
-        // Import messages
-        for msg in conv.messages {
-            let msg_id = msg.id.clone();
-            let message = NewMessageWithId {
-                id: msg.id,
-                conversation_id: conv.id.clone(),
-                role: msg.role,
-                content: msg.content,
-                timestamp: msg.timestamp,
-                tokens_used: msg.tokens_used,
-            };
+        result.resolved_conflicts.push(ConflictRecord {
+            conversation_id,
+            title,
+            action: action.to_string(),
+            new_id,
+            messages_imported,
+        });
+    }
 
-            Message::create_with_id(&conn, message)
-                .map_err(|e| format!("Failed to create message {}: {}", msg_id, e))?;
-        }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
 
-        imported_count += 1;
-    }
+/// Import a JSON export. `conflict_resolution` is one of `skip`, `overwrite`,
+/// `merge` or `rename` and applies to conversations whose ID already exists.
+#[tauri::command]
+pub fn import_conversations_json(
+    db: State<'_, Database>,
+    json_content: String,
+    conflict_resolution: String,
+) -> Result<ImportResult, String> {
+    let strategy = ConflictResolution::parse(&conflict_resolution)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+
+    let export_data: ExportData =
+        serde_json::from_str(&json_content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
 
-    Ok(format!(
-        "Import completed: {} conversations imported, {} skipped (already exist)",
-        imported_count, skipped_count
-    ))
+    import_export_data(&conn, export_data, strategy)
 }
 
 #[tauri::command]
@@ -738,7 +1023,20 @@ pub async fn save_single_conversation_export(
             let content = export_conversation_pdf(db.clone(), conversation_id)?;
             (Ok(content), "pdf")
         }
-        _ => return Err("Invalid format. Supported: json, markdown, html, pdf".to_string()),
+        "notion" => {
+            let content = export_conversation_notion(db.clone(), conversation_id)?;
+            (Ok(content.into_bytes()), "md")
+        }
+        "obsidian" => {
+            let content = export_conversation_obsidian(db.clone(), conversation_id)?;
+            (Ok(content.into_bytes()), "md")
+        }
+        _ => {
+            return Err(
+                "Invalid format. Supported: json, markdown, html, pdf, notion, obsidian"
+                    .to_string(),
+            )
+        }
     };
 
     let content_bytes = content_result.map_err(|e| format!("Failed to generate content: {}", e))?;
@@ -792,3 +1090,486 @@ pub async fn save_export_file_bytes(
 
     Ok(path.to_string_lossy().to_string())
 }
+
+/// JSONL layouts accepted by fine-tuning tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingDataFormat {
+    /// One `{"messages": [{role, content}]}` object per conversation
+    OpenAi,
+    /// One `{"instruction", "output"}` object built from the last user/assistant pair
+    Alpaca,
+}
+
+impl TrainingDataFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "openai" => Ok(Self::OpenAi),
+            "alpaca" => Ok(Self::Alpaca),
+            other => Err(format!(
+                "Invalid training data format '{}'. Supported: openai, alpaca",
+                other
+            )),
+        }
+    }
+}
+
+/// Build one JSONL line per conversation. System messages without content are
+/// dropped, and conversations with fewer than `min_message_count` remaining
+/// messages (or no user/assistant pair, for alpaca) are left out.
+pub fn export_as_training_data(
+    conn: &rusqlite::Connection,
+    conversation_ids: Option<Vec<String>>,
+    format: TrainingDataFormat,
+    min_message_count: Option<usize>,
+) -> Result<String, String> {
+    let conversations = conversations_to_export(conn, conversation_ids)?;
+    let min_message_count = min_message_count.unwrap_or(1);
+
+    let mut jsonl = String::new();
+    for conv in conversations {
+        let messages: Vec<Message> = Message::get_by_conversation(conn, &conv.id)
+            .map_err(|e| format!("Failed to get messages for conversation {}: {}", conv.id, e))?
+            .into_iter()
+            .filter(|m| !(m.role == "system" && m.content.trim().is_empty()))
+            .collect();
+        if messages.len() < min_message_count {
+            continue;
+        }
+
+        let record = match format {
+            TrainingDataFormat::OpenAi => serde_json::json!({
+                "messages": messages
+                    .iter()
+                    .map(|m| serde_json::json!({ "role": m.role, "content": m.content }))
+                    .collect::<Vec<_>>(),
+            }),
+            TrainingDataFormat::Alpaca => {
+                let pair = messages
+                    .windows(2)
+                    .rev()
+                    .find(|w| w[0].role == "user" && w[1].role == "assistant");
+                let Some([instruction, output]) = pair else {
+                    continue;
+                };
+                serde_json::json!({
+                    "instruction": instruction.content,
+                    "output": output.content,
+                })
+            }
+        };
+
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize training data: {}", e))?;
+        jsonl.push_str(&line);
+        jsonl.push('\n');
+    }
+
+    Ok(jsonl)
+}
+
+#[tauri::command]
+pub fn export_conversation_as_training_data(
+    db: State<'_, Database>,
+    conversation_ids: Option<Vec<String>>,
+    format: String,
+    min_message_count: Option<usize>,
+) -> Result<String, String> {
+    let format = TrainingDataFormat::parse(&format)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    export_as_training_data(&conn, conversation_ids, format, min_message_count)
+}
+
+/// Share links carry the whole conversation in the URL fragment, so it never reaches the server
+const SHARE_LINK_PREFIX: &str = "https://lai.app/share#";
+
+/// Largest decompressed payload a share link may carry; zlib shrinks repetitive
+/// input a thousandfold, so a short link could otherwise expand to gigabytes
+const MAX_SHARE_JSON_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Share link payload: the exported conversation plus an optional expiry, kept
+/// flat so the frontend can read it as an `ExportedConversation`
+#[derive(Serialize, Deserialize)]
+struct SharedConversation {
+    #[serde(flatten)]
+    conversation: ExportedConversation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+/// Encode a conversation as zlib-compressed, base64url JSON. With `max_messages`
+/// only the most recent messages are included.
+pub fn generate_share_link(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+    max_messages: Option<usize>,
+    expiry_secs: Option<u64>,
+    now: i64,
+) -> Result<String, String> {
+    let conv = Conversation::get_by_id(conn, conversation_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let mut conversation = export_conversation(conn, conv)?;
+    if let Some(max) = max_messages {
+        let skip = conversation.messages.len().saturating_sub(max);
+        conversation.messages.drain(..skip);
+    }
+
+    let shared = SharedConversation {
+        conversation,
+        expires_at: expiry_secs.map(|secs| now.saturating_add(secs as i64)),
+    };
+    let json = serde_json::to_vec(&shared)
+        .map_err(|e| format!("Failed to serialize conversation: {}", e))?;
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map(|compressed| format!("{}{}", SHARE_LINK_PREFIX, BASE64_URL.encode(compressed)))
+        .map_err(|e| format!("Failed to compress conversation: {}", e))
+}
+
+/// Reverse `generate_share_link`. Accepts the full link or just its fragment.
+pub fn decode_share_payload(encoded: &str, now: i64) -> Result<ExportedConversation, String> {
+    let payload = encoded
+        .trim()
+        .rsplit_once('#')
+        .map_or(encoded.trim(), |(_, fragment)| fragment);
+    let compressed = BASE64_URL
+        .decode(payload)
+        .map_err(|e| format!("Invalid share link: {}", e))?;
+
+    let mut json = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .take(MAX_SHARE_JSON_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Invalid share link: {}", e))?;
+    if json.len() as u64 > MAX_SHARE_JSON_BYTES {
+        return Err("Share link is too large".to_string());
+    }
+    let shared: SharedConversation =
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid share link: {}", e))?;
+
+    if shared
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return Err("Share link has expired".to_string());
+    }
+    Ok(shared.conversation)
+}
+
+#[tauri::command]
+pub fn generate_conversation_share_link(
+    db: State<'_, Database>,
+    conversation_id: String,
+    max_messages: Option<usize>,
+    expiry_secs: Option<u64>,
+) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    generate_share_link(
+        &conn,
+        &conversation_id,
+        max_messages,
+        expiry_secs,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+#[tauri::command]
+pub fn decode_share_link(encoded: String) -> Result<ExportedConversation, String> {
+    decode_share_payload(&encoded, chrono::Utc::now().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notion_normalize_tags_code_and_flattens_lists() {
+        let input =
+            "```\nlet x = 1;\n```\n```rust\nfn f() {}\n```\n- a\n  - b\n    - c\n        - d";
+        let expected =
+            "```plaintext\nlet x = 1;\n```\n```rust\nfn f() {}\n```\n- a\n  - b\n  - c\n  - d";
+        assert_eq!(notion_normalize(input), expected);
+    }
+
+    fn export_fixture(title: &str, message_ids: &[&str]) -> ExportData {
+        ExportData {
+            version: "1.0".to_string(),
+            export_timestamp: 0,
+            conversations: vec![ExportedConversation {
+                id: "conv-1".to_string(),
+                title: title.to_string(),
+                provider: "local".to_string(),
+                model: "gpt-test".to_string(),
+                system_prompt: None,
+                created_at: 10,
+                updated_at: 10,
+                messages: message_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| ExportedMessage {
+                        id: id.to_string(),
+                        role: "user".to_string(),
+                        content: format!("{} {}", title, id),
+                        timestamp: 10 + i as i64,
+                        tokens_used: None,
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    fn seeded_db() -> Database {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        {
+            let conn = db.conn().unwrap();
+            let result = import_export_data(
+                &conn,
+                export_fixture("Original", &["m1", "m2"]),
+                ConflictResolution::Skip,
+            )
+            .expect("seed import");
+            assert_eq!(result.imported_count, 1);
+            assert!(result.resolved_conflicts.is_empty());
+        }
+        db
+    }
+
+    #[test]
+    fn import_conflict_skip_keeps_existing() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+        let result = import_export_data(
+            &conn,
+            export_fixture("Changed", &["m1", "m3"]),
+            ConflictResolution::Skip,
+        )
+        .unwrap();
+
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(result.resolved_conflicts[0].action, "skipped");
+        let conv = Conversation::get_by_id(&conn, "conv-1").unwrap().unwrap();
+        assert_eq!(conv.title, "Original");
+        assert_eq!(
+            Message::get_by_conversation(&conn, "conv-1").unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn import_conflict_overwrite_replaces_existing() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+        let result = import_export_data(
+            &conn,
+            export_fixture("Changed", &["m3"]),
+            ConflictResolution::Overwrite,
+        )
+        .unwrap();
+
+        assert_eq!(result.resolved_conflicts[0].action, "overwritten");
+        assert_eq!(result.resolved_conflicts[0].messages_imported, 1);
+        let conv = Conversation::get_by_id(&conn, "conv-1").unwrap().unwrap();
+        assert_eq!(conv.title, "Changed");
+        let messages = Message::get_by_conversation(&conn, "conv-1").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, "m3");
+        assert!(!Message::exists(&conn, "m1").unwrap());
+    }
+
+    #[test]
+    fn import_conflict_merge_adds_only_new_messages() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+        let result = import_export_data(
+            &conn,
+            export_fixture("Changed", &["m1", "m2", "m3"]),
+            ConflictResolution::Merge,
+        )
+        .unwrap();
+
+        assert_eq!(result.imported_count, 0);
+        assert_eq!(result.resolved_conflicts[0].action, "merged");
+        assert_eq!(result.resolved_conflicts[0].messages_imported, 1);
+        let conv = Conversation::get_by_id(&conn, "conv-1").unwrap().unwrap();
+        assert_eq!(conv.title, "Original");
+        let messages = Message::get_by_conversation(&conn, "conv-1").unwrap();
+        let ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2", "m3"]);
+    }
+
+    #[test]
+    fn import_conflict_rename_creates_copy() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+        let result = import_export_data(
+            &conn,
+            export_fixture("Original", &["m1", "m2"]),
+            ConflictResolution::Rename,
+        )
+        .unwrap();
+
+        let record = &result.resolved_conflicts[0];
+        assert_eq!(record.action, "renamed");
+        let new_id = record.new_id.clone().unwrap();
+        assert_ne!(new_id, "conv-1");
+        let copy = Conversation::get_by_id(&conn, &new_id).unwrap().unwrap();
+        assert_eq!(copy.title, "Original (imported)");
+        assert_eq!(
+            Message::get_by_conversation(&conn, &new_id).unwrap().len(),
+            2
+        );
+        assert_eq!(
+            Message::get_by_conversation(&conn, "conv-1").unwrap().len(),
+            2
+        );
+
+        assert!(ConflictResolution::parse("replace").is_err());
+    }
+
+    fn training_db() -> Database {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        {
+            let conn = db.conn().unwrap();
+            let conversation = |id: &str, messages: &[(&str, &str)]| ExportedConversation {
+                id: id.to_string(),
+                title: id.to_string(),
+                provider: "local".to_string(),
+                model: "gpt-test".to_string(),
+                system_prompt: None,
+                created_at: 10,
+                updated_at: 10,
+                messages: messages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (role, content))| ExportedMessage {
+                        id: format!("{}-m{}", id, i),
+                        role: role.to_string(),
+                        content: content.to_string(),
+                        timestamp: 10 + i as i64,
+                        tokens_used: None,
+                    })
+                    .collect(),
+            };
+            let data = ExportData {
+                version: "1.0".to_string(),
+                export_timestamp: 0,
+                conversations: vec![
+                    conversation(
+                        "long",
+                        &[
+                            ("system", ""),
+                            ("user", "first question"),
+                            ("assistant", "first answer"),
+                            ("user", "second question"),
+                            ("assistant", "second answer"),
+                        ],
+                    ),
+                    conversation("short", &[("user", "hi"), ("assistant", "hello")]),
+                ],
+            };
+            import_export_data(&conn, data, ConflictResolution::Skip).expect("seed import");
+        }
+        db
+    }
+
+    #[test]
+    fn training_data_openai_has_one_line_per_conversation() {
+        let db = training_db();
+        let conn = db.conn().unwrap();
+
+        let jsonl = export_as_training_data(&conn, None, TrainingDataFormat::OpenAi, None).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        for line in jsonl.lines() {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            let messages = record["messages"].as_array().unwrap();
+            assert!(messages.iter().all(|m| m["role"] != "system"));
+            assert!(messages.iter().all(|m| m["content"].is_string()));
+        }
+
+        let filtered =
+            export_as_training_data(&conn, None, TrainingDataFormat::OpenAi, Some(3)).unwrap();
+        assert_eq!(filtered.lines().count(), 1);
+        let record: serde_json::Value = serde_json::from_str(filtered.trim()).unwrap();
+        assert_eq!(record["messages"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn training_data_alpaca_uses_last_pair() {
+        let db = training_db();
+        let conn = db.conn().unwrap();
+
+        let jsonl = export_as_training_data(
+            &conn,
+            Some(vec!["long".to_string()]),
+            TrainingDataFormat::Alpaca,
+            None,
+        )
+        .unwrap();
+        assert_eq!(jsonl.lines().count(), 1);
+        let record: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(record["instruction"], "second question");
+        assert_eq!(record["output"], "second answer");
+
+        assert!(TrainingDataFormat::parse("sharegpt").is_err());
+    }
+
+    #[test]
+    fn share_link_round_trips() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+
+        let link = generate_share_link(&conn, "conv-1", None, None, 100).unwrap();
+        let encoded = link.strip_prefix(SHARE_LINK_PREFIX).unwrap();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+
+        let decoded = decode_share_payload(&link, 100).unwrap();
+        assert_eq!(decoded.id, "conv-1");
+        assert_eq!(decoded.title, "Original");
+        let ids: Vec<&str> = decoded.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2"]);
+        // The bare fragment decodes the same way
+        assert_eq!(
+            decode_share_payload(encoded, 100).unwrap().messages.len(),
+            2
+        );
+
+        let latest = generate_share_link(&conn, "conv-1", Some(1), None, 100).unwrap();
+        let decoded = decode_share_payload(&latest, 100).unwrap();
+        assert_eq!(decoded.messages.len(), 1);
+        assert_eq!(decoded.messages[0].id, "m2");
+
+        assert!(generate_share_link(&conn, "missing", None, None, 100).is_err());
+        assert!(decode_share_payload("not-a-share-link!", 100).is_err());
+    }
+
+    #[test]
+    fn share_link_expires() {
+        let db = seeded_db();
+        let conn = db.conn().unwrap();
+
+        let link = generate_share_link(&conn, "conv-1", None, Some(60), 1_000).unwrap();
+        assert!(decode_share_payload(&link, 1_059).is_ok());
+        assert_eq!(
+            decode_share_payload(&link, 1_060).err().unwrap(),
+            "Share link has expired"
+        );
+    }
+
+    #[test]
+    fn share_link_rejects_oversized_payload() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&vec![b' '; MAX_SHARE_JSON_BYTES as usize + 1])
+            .unwrap();
+        let encoded = BASE64_URL.encode(encoder.finish().unwrap());
+        assert_eq!(
+            decode_share_payload(&encoded, 100).err().unwrap(),
+            "Share link is too large"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::commands::export::{export_conversation_markdown, export_conversations_json};
+use crate::database::conversations::Conversation;
+use crate::database::export_schedules::{ExportSchedule, NewExportSchedule};
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+/// How often the background task looks for due schedules
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledExportComplete {
+    pub schedule_id: String,
+    pub path: String,
+}
+
+/// Seconds between runs for a schedule pattern
+fn interval_secs(pattern: &str) -> Option<i64> {
+    match pattern {
+        "hourly" => Some(60 * 60),
+        "daily" => Some(24 * 60 * 60),
+        "weekly" => Some(7 * 24 * 60 * 60),
+        _ => None,
+    }
+}
+
+fn extension_for(format: &str) -> Option<&'static str> {
+    match format {
+        "json" => Some("json"),
+        "markdown" => Some("md"),
+        _ => None,
+    }
+}
+
+fn validate_schedule(schedule: &NewExportSchedule) -> Result<(), String> {
+    if extension_for(&schedule.format).is_none() {
+        return Err(format!(
+            "Unsupported export format '{}'. Use 'json' or 'markdown'",
+            schedule.format
+        ));
+    }
+    if interval_secs(&schedule.cron_pattern).is_none() {
+        return Err(format!(
+            "Unsupported schedule '{}'. Use 'hourly', 'daily' or 'weekly'",
+            schedule.cron_pattern
+        ));
+    }
+    if schedule.destination_dir.trim().is_empty() {
+        return Err("Destination directory is required".to_string());
+    }
+    Ok(())
+}
+
+/// A schedule is due if it has never run or its interval has elapsed
+fn is_due(schedule: &ExportSchedule, now: i64) -> bool {
+    if !schedule.is_active {
+        return false;
+    }
+    match (interval_secs(&schedule.cron_pattern), schedule.last_run_at) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(interval), Some(last)) => now - last >= interval,
+    }
+}
+
+/// Export all conversations in the schedule's format and write them to
+/// `destination_dir/<timestamp>.<ext>`
+fn run_export(app: &tauri::AppHandle, schedule: &ExportSchedule) -> Result<PathBuf, String> {
+    let ext = extension_for(&schedule.format)
+        .ok_or_else(|| format!("Unsupported export format '{}'", schedule.format))?;
+
+    let content = if schedule.format == "json" {
+        export_conversations_json(app.state::<Database>(), None)?
+    } else {
+        let ids: Vec<String> = {
+            let db = app.state::<Database>();
+            let conn = db.conn().map_err(|e| e.to_string())?;
+            Conversation::get_all(&conn, 1000)
+                .map_err(|e| format!("Failed to get conversations: {}", e))?
+                .into_iter()
+                .map(|c| c.id)
+                .collect()
+        };
+        let mut documents = Vec::with_capacity(ids.len());
+        for id in ids {
+            documents.push(export_conversation_markdown(app.state::<Database>(), id)?);
+        }
+        documents.join("\n")
+    };
+
+    let dir = PathBuf::from(&schedule.destination_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let filename = format!("{}.{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"), ext);
+    let path = dir.join(filename);
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Run every active schedule whose interval has elapsed
+fn run_due_exports(app: &tauri::AppHandle) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let due: Vec<ExportSchedule> = {
+        let db = app.state::<Database>();
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        ExportSchedule::get_active(&conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|s| is_due(s, now))
+            .collect()
+    };
+
+    for schedule in due {
+        let result = run_export(app, &schedule);
+
+        // Record the attempt even on failure so a broken destination isn't retried every minute
+        {
+            let db = app.state::<Database>();
+            let conn = db.conn().map_err(|e| e.to_string())?;
+            ExportSchedule::set_last_run(&conn, &schedule.id, now).map_err(|e| e.to_string())?;
+        }
+
+        match result {
+            Ok(path) => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.emit(
+                        "export://scheduled-complete",
+                        ScheduledExportComplete {
+                            schedule_id: schedule.id.clone(),
+                            path: path.to_string_lossy().to_string(),
+                        },
+                    );
+                }
+            }
+            Err(e) => eprintln!("Scheduled export {} failed: {}", schedule.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Spawn the background task that checks for due exports every minute
+pub fn start_export_scheduler(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let app = app.clone();
+            let result = tokio::task::spawn_blocking(move || run_due_exports(&app)).await;
+            match result {
+                Ok(Err(e)) => eprintln!("Export scheduler error: {}", e),
+                Err(e) => eprintln!("Export scheduler task failed: {}", e),
+                Ok(Ok(())) => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn create_export_schedule(
+    db: State<'_, Database>,
+    format: String,
+    destination_dir: String,
+    cron_pattern: String,
+) -> Result<ExportSchedule, String> {
+    let new_schedule = NewExportSchedule {
+        format,
+        destination_dir,
+        cron_pattern,
+    };
+    validate_schedule(&new_schedule)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ExportSchedule::create(&conn, new_schedule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_export_schedules(db: State<'_, Database>) -> Result<Vec<ExportSchedule>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ExportSchedule::get_all(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_export_schedule(
+    db: State<'_, Database>,
+    id: String,
+) -> Result<Option<ExportSchedule>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ExportSchedule::get_by_id(&conn, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_export_schedule(
+    db: State<'_, Database>,
+    id: String,
+    format: String,
+    destination_dir: String,
+    cron_pattern: String,
+    is_active: bool,
+) -> Result<(), String> {
+    let schedule = NewExportSchedule {
+        format,
+        destination_dir,
+        cron_pattern,
+    };
+    validate_schedule(&schedule)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ExportSchedule::update(&conn, &id, &schedule, is_active).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_export_schedule(db: State<'_, Database>, id: String) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ExportSchedule::delete(&conn, &id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(pattern: &str, last_run_at: Option<i64>) -> ExportSchedule {
+        ExportSchedule {
+            id: "s1".to_string(),
+            format: "json".to_string(),
+            destination_dir: "/tmp/exports".to_string(),
+            cron_pattern: pattern.to_string(),
+            last_run_at,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn due_after_interval_elapses() {
+        let now = 1_000_000;
+        assert!(is_due(&schedule("hourly", None), now));
+        assert!(!is_due(&schedule("hourly", Some(now - 3599)), now));
+        assert!(is_due(&schedule("hourly", Some(now - 3600)), now));
+        assert!(!is_due(&schedule("weekly", Some(now - 86_400)), now));
+        assert!(!is_due(&schedule("every-5-min", None), now));
+
+        let mut inactive = schedule("daily", None);
+        inactive.is_active = false;
+        assert!(!is_due(&inactive, now));
+    }
+}
@@ -1,6 +1,184 @@
-use tauri::command;
+use crate::commands::database::integrity_report;
+use crate::database::{migrations, Database};
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+use sysinfo::Disks;
+use tauri::{command, AppHandle, Manager};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[command]
 pub fn ping() -> Result<String, String> {
     Ok("ok".into())
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub database_ok: bool,
+    pub database_latency_ms: Option<u64>,
+    pub database_integrity_ok: bool,
+    pub schema_version: Option<u32>,
+    pub pending_migrations: Vec<u32>,
+    pub ipc_server_ok: bool,
+    pub ipc_port: u16,
+    pub shortcut_manager_ok: bool,
+    pub filesystem_ok: bool,
+    pub memory_available_mb: Option<u64>,
+    pub disk_free_mb: Option<u64>,
+    pub issues: Vec<String>,
+}
+
+/// Run a blocking check on the blocking pool, giving up after `CHECK_TIMEOUT`
+async fn run_check<T, F>(check: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, tokio::task::spawn_blocking(check)).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(format!("check failed: {}", e)),
+        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    }
+}
+
+fn check_database(app: &AppHandle) -> Result<u64, String> {
+    let db = app.state::<Database>();
+    let start = Instant::now();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(start.elapsed().as_millis() as u64)
+}
+
+/// Integrity and foreign key check, with the problems found reported as an error
+fn check_integrity(app: &AppHandle) -> Result<(), String> {
+    let db = app.state::<Database>();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let report = integrity_report(&conn).map_err(|e| e.to_string())?;
+    let mut problems = report.integrity_errors;
+    if !report.foreign_key_violations.is_empty() {
+        problems.push(format!(
+            "{} foreign key violation(s)",
+            report.foreign_key_violations.len()
+        ));
+    }
+    problems.extend(report.suggestion);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+/// Current schema version and the versions of any migrations not yet applied
+fn check_schema(app: &AppHandle) -> Result<(u32, Vec<u32>), String> {
+    let db = app.state::<Database>();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let version = migrations::schema_version(&conn).map_err(|e| e.to_string())?;
+    let pending = migrations::list_migrations(&conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|m| !m.is_applied)
+        .map(|m| m.version)
+        .collect();
+    Ok((version, pending))
+}
+
+fn check_ipc_server() -> Result<(), String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], crate::ipc::IPC_PORT));
+    TcpStream::connect_timeout(&addr, CHECK_TIMEOUT)
+        .map(|_| ())
+        .map_err(|e| format!("connect {} failed: {}", addr, e))
+}
+
+fn check_filesystem(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let probe = dir.join(".health_check");
+    std::fs::write(&probe, b"ok").map_err(|e| format!("{} not writeable: {}", dir.display(), e))?;
+    std::fs::remove_file(&probe).map_err(|e| e.to_string())
+}
+
+fn check_disk_free(app: &AppHandle) -> Result<u64, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let disks = Disks::new_with_refreshed_list();
+    // Use the most specific mount point containing the app data directory
+    disks
+        .list()
+        .iter()
+        .filter(|d| dir.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space() / 1024 / 1024)
+        .ok_or_else(|| format!("no disk found for {}", dir.display()))
+}
+
+/// Check every subsystem in parallel and collect a structured report.
+/// Each check is limited to 2 seconds; failures are listed in `issues`.
+#[command]
+pub async fn deep_health_check(app: AppHandle) -> Result<HealthReport, String> {
+    let (db_app, integrity_app, schema_app, fs_app, disk_app) = (
+        app.clone(),
+        app.clone(),
+        app.clone(),
+        app.clone(),
+        app.clone(),
+    );
+    let (database, integrity, schema, ipc, shortcuts, filesystem, memory, disk) = tokio::join!(
+        run_check(move || check_database(&db_app)),
+        run_check(move || check_integrity(&integrity_app)),
+        run_check(move || check_schema(&schema_app)),
+        run_check(check_ipc_server),
+        run_check(|| Ok(crate::commands::shortcuts::is_shortcut_manager_initialized())),
+        run_check(move || check_filesystem(&fs_app)),
+        run_check(
+            || crate::commands::performance::get_system_metrics().map(|m| m
+                .memory_usage
+                .available_memory
+                / 1024
+                / 1024)
+        ),
+        run_check(move || check_disk_free(&disk_app)),
+    );
+
+    let mut issues = Vec::new();
+    let mut record = |name: &str, error: Option<String>| {
+        if let Some(e) = error {
+            issues.push(format!("{}: {}", name, e));
+        }
+    };
+    record("database", database.as_ref().err().cloned());
+    record("database integrity", integrity.as_ref().err().cloned());
+    match &schema {
+        Ok((_, pending)) if !pending.is_empty() => record(
+            "schema",
+            Some(format!("{} pending migration(s)", pending.len())),
+        ),
+        Ok(_) => {}
+        Err(e) => record("schema", Some(e.clone())),
+    }
+    record("ipc server", ipc.as_ref().err().cloned());
+    match &shortcuts {
+        Ok(true) => {}
+        Ok(false) => record("shortcut manager", Some("not initialized".to_string())),
+        Err(e) => record("shortcut manager", Some(e.clone())),
+    }
+    record("filesystem", filesystem.as_ref().err().cloned());
+    record("memory", memory.as_ref().err().cloned());
+    record("disk", disk.as_ref().err().cloned());
+
+    Ok(HealthReport {
+        database_ok: database.is_ok(),
+        database_latency_ms: database.ok(),
+        database_integrity_ok: integrity.is_ok(),
+        schema_version: schema.as_ref().ok().map(|(version, _)| *version),
+        pending_migrations: schema.map(|(_, pending)| pending).unwrap_or_default(),
+        ipc_server_ok: ipc.is_ok(),
+        ipc_port: crate::ipc::IPC_PORT,
+        shortcut_manager_ok: shortcuts == Ok(true),
+        filesystem_ok: filesystem.is_ok(),
+        memory_available_mb: memory.ok(),
+        disk_free_mb: disk.ok(),
+        issues,
+    })
+}
@@ -1,6 +1,242 @@
+use crate::commands::provider::{stream_replacement, ProviderMessage};
+use crate::database::conversations::{Conversation, NewConversationWithId};
 use crate::database::{messages::*, Database};
 use rusqlite::Connection;
-use tauri::State;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager, State};
+
+/// Rough token estimate (~4 characters per token) used for context budgeting
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Format messages as a background-context block. When `max_tokens` is set, the oldest
+/// messages are dropped first until the remaining ones fit the budget.
+fn format_conversation_context(messages: &[Message], max_tokens: Option<usize>) -> String {
+    let lines: Vec<String> = messages
+        .iter()
+        .map(|m| format!("{}: {}", m.role, m.content))
+        .collect();
+
+    let mut start = 0;
+    if let Some(max) = max_tokens {
+        let mut total: usize = lines.iter().map(|l| estimate_tokens(l)).sum();
+        while start < lines.len() && total > max {
+            total -= estimate_tokens(&lines[start]);
+            start += 1;
+        }
+    }
+
+    format!(
+        "[Previous conversation]\n{}\n[End context]",
+        lines[start..].join("\n\n")
+    )
+}
+
+/// Token cost of a stored message: its recorded `tokens_used`, else ~4 chars per token
+fn message_tokens(message: &Message) -> usize {
+    message
+        .tokens_used
+        .map(|t| t.max(0) as usize)
+        .unwrap_or_else(|| estimate_tokens(&message.content))
+}
+
+/// Newest messages (in chronological order) that fit within `budget`, stopping at
+/// the first one that doesn't so the kept history stays contiguous
+fn take_recent(messages: &[Message], budget: usize) -> Vec<Message> {
+    let mut used = 0;
+    let mut kept: Vec<Message> = messages
+        .iter()
+        .rev()
+        .take_while(|m| {
+            used += message_tokens(m);
+            used <= budget
+        })
+        .cloned()
+        .collect();
+    kept.reverse();
+    kept
+}
+
+/// Longest line of the summary built for dropped messages
+const SUMMARY_LINE_CHARS: usize = 100;
+
+/// Build a system message outlining `dropped`, trimmed to fit `budget` tokens
+fn summarize_messages(dropped: &[Message], budget: usize) -> Option<Message> {
+    let first = dropped.first()?;
+    let mut summary = format!(
+        "Summary of {} earlier message{}:",
+        dropped.len(),
+        if dropped.len() == 1 { "" } else { "s" }
+    );
+    for m in dropped {
+        let line: String = m
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(SUMMARY_LINE_CHARS)
+            .collect();
+        let next = format!("\n- {}: {}", m.role, line);
+        if estimate_tokens(&summary) + estimate_tokens(&next) > budget {
+            summary.push_str("\n- …");
+            break;
+        }
+        summary.push_str(&next);
+    }
+
+    Some(Message {
+        id: format!("summary-{}", first.conversation_id),
+        conversation_id: first.conversation_id.clone(),
+        role: "system".to_string(),
+        content: summary,
+        timestamp: first.timestamp,
+        tokens_used: None,
+    })
+}
+
+/// Trim `messages` (system messages first, then chronological) to `max_tokens` using
+/// `strategy`:
+/// - `recent`: newest messages only
+/// - `important`: system messages, which act as pinned, then the newest of the rest
+/// - `summary`: newest messages in 3/4 of the budget, preceded by a summary of the rest
+fn select_context_window(
+    messages: Vec<Message>,
+    max_tokens: usize,
+    strategy: &str,
+) -> Result<Vec<Message>, String> {
+    match strategy {
+        "recent" => Ok(take_recent(&messages, max_tokens)),
+        "important" => {
+            let (pinned, rest): (Vec<Message>, Vec<Message>) =
+                messages.into_iter().partition(|m| m.role == "system");
+            let mut used = 0;
+            let mut kept: Vec<Message> = pinned
+                .into_iter()
+                .filter(|m| {
+                    let tokens = message_tokens(m);
+                    let fits = used + tokens <= max_tokens;
+                    if fits {
+                        used += tokens;
+                    }
+                    fits
+                })
+                .collect();
+            kept.extend(take_recent(&rest, max_tokens - used));
+            Ok(kept)
+        }
+        "summary" => {
+            let recent = take_recent(&messages, max_tokens * 3 / 4);
+            let dropped = &messages[..messages.len() - recent.len()];
+            let recent_tokens: usize = recent.iter().map(message_tokens).sum();
+            let mut window: Vec<Message> = summarize_messages(dropped, max_tokens - recent_tokens)
+                .into_iter()
+                .collect();
+            window.extend(recent);
+            Ok(window)
+        }
+        other => Err(format!(
+            "Unknown context strategy '{}'. Use 'recent', 'important' or 'summary'",
+            other
+        )),
+    }
+}
+
+/// A fenced code block pulled out of message content
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageWithCodeBlocks {
+    pub message: Message,
+    pub code_blocks: Vec<CodeBlock>,
+}
+
+/// Extract triple-backtick blocks line by line. An unterminated block at the end
+/// (e.g. a truncated response) is still returned.
+fn extract_code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(Option<String>, Vec<&str>)> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        match current.as_mut() {
+            None => {
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    let language = info.split_whitespace().next().map(String::from);
+                    current = Some((language, Vec::new()));
+                }
+            }
+            Some((_, lines)) => {
+                if trimmed == "```" {
+                    let (language, lines) = current.take().unwrap();
+                    blocks.push(CodeBlock {
+                        language,
+                        content: lines.join("\n"),
+                    });
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+
+    if let Some((language, lines)) = current {
+        blocks.push(CodeBlock {
+            language,
+            content: lines.join("\n"),
+        });
+    }
+    blocks
+}
+
+/// Messages that contain at least one code block, in conversation order
+fn messages_with_code_blocks(messages: Vec<Message>) -> Vec<MessageWithCodeBlocks> {
+    messages
+        .into_iter()
+        .filter_map(|message| {
+            let code_blocks = extract_code_blocks(&message.content);
+            (!code_blocks.is_empty()).then_some(MessageWithCodeBlocks {
+                message,
+                code_blocks,
+            })
+        })
+        .collect()
+}
+
+/// All code blocks as separate fenced sections, optionally limited to one language
+/// (case-insensitive; untagged blocks never match a filter)
+fn format_code_blocks(messages: &[MessageWithCodeBlocks], language_filter: Option<&str>) -> String {
+    let filter = language_filter
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_lowercase);
+
+    messages
+        .iter()
+        .flat_map(|m| &m.code_blocks)
+        .filter(|block| match &filter {
+            Some(filter) => block
+                .language
+                .as_deref()
+                .is_some_and(|l| l.to_lowercase() == *filter),
+            None => true,
+        })
+        .map(|block| {
+            format!(
+                "```{}\n{}\n```",
+                block.language.as_deref().unwrap_or(""),
+                block.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
 
 // Helper function for synchronous access (used by IPC)
 pub fn get_last_assistant_message_sync(conn: &Connection) -> Result<Option<Message>, String> {
@@ -51,7 +287,7 @@ pub async fn create_message(
     content: String,
     tokens_used: Option<i64>,
 ) -> Result<Message, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     let new_msg = NewMessage {
         conversation_id,
         role,
@@ -60,6 +296,7 @@ pub async fn create_message(
     };
     // Create the user message
     let created = Message::create(&conn, new_msg).map_err(|e| e.to_string())?;
+    crate::commands::webhooks::dispatch_message_created(&conn, &created);
 
     // Dev helper: if DEV_ECHO_RESPONSES=1 is set, automatically create an assistant reply
     // This is handy for local development to test end-to-end flow without an LLM provider.
@@ -77,22 +314,166 @@ pub async fn create_message(
     Ok(created)
 }
 
+/// Read plain text from the system clipboard, rejecting empty or non-text contents
+fn read_clipboard_text(app: &tauri::AppHandle) -> Result<String, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| format!("Clipboard does not contain text: {}", e))?;
+    if text.trim().is_empty() {
+        return Err("Clipboard is empty".to_string());
+    }
+    Ok(text)
+}
+
+/// Insert pre-built messages (with IDs and timestamps) in one transaction, e.g.
+/// for bulk imports. Webhooks are not dispatched for batch inserts.
+#[tauri::command]
+pub async fn create_message_batch(
+    db: State<'_, Database>,
+    messages: Vec<NewMessageWithId>,
+) -> Result<Vec<Message>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Message::create_batch(&conn, messages).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_message_from_clipboard(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+    role: String,
+) -> Result<Message, String> {
+    let content = read_clipboard_text(&app)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let new_msg = NewMessage {
+        conversation_id,
+        role,
+        content,
+        tokens_used: None,
+    };
+    let created = Message::create(&conn, new_msg).map_err(|e| e.to_string())?;
+    crate::commands::webhooks::dispatch_message_created(&conn, &created);
+    Ok(created)
+}
+
+/// Save the clipboard as a user message and hand it to the frontend's ask flow.
+/// The `cli://ask` payload carries `message_id` since the message is already stored.
+#[tauri::command]
+pub async fn paste_clipboard_and_ask(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<(), String> {
+    let message =
+        create_message_from_clipboard(db, app.clone(), conversation_id, "user".to_string()).await?;
+    app.emit(
+        "cli://ask",
+        serde_json::json!({
+            "prompt": message.content,
+            "conversation_id": message.conversation_id,
+            "message_id": message.id,
+        }),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Messages from a conversation trimmed to fit `max_tokens`, for building provider requests
+#[tauri::command]
+pub async fn get_message_context_window(
+    db: State<'_, Database>,
+    conversation_id: String,
+    max_tokens: usize,
+    strategy: String,
+) -> Result<Vec<Message>, String> {
+    let messages = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        Message::get_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())?
+    };
+    select_context_window(messages, max_tokens, &strategy)
+}
+
 #[tauri::command]
 pub async fn get_conversation_messages(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<Vec<Message>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Message::get_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())
 }
 
+/// One page of a conversation's messages; `page` is zero-based
+#[derive(Debug, Serialize)]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub total_count: i64,
+    pub page: usize,
+    pub page_size: usize,
+    pub has_more: bool,
+}
+
+#[tauri::command]
+pub async fn get_conversation_messages_paginated(
+    db: State<'_, Database>,
+    conversation_id: String,
+    page: usize,
+    page_size: usize,
+) -> Result<MessagePage, String> {
+    if page_size == 0 {
+        return Err("page_size must be greater than zero".to_string());
+    }
+    let offset = page
+        .checked_mul(page_size)
+        .ok_or_else(|| "page is out of range".to_string())?;
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let total_count =
+        Message::count_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())?;
+    let messages = Message::get_page(&conn, &conversation_id, page_size as i64, offset as i64)
+        .map_err(|e| e.to_string())?;
+    let has_more = ((offset + messages.len()) as i64) < total_count;
+
+    Ok(MessagePage {
+        messages,
+        total_count,
+        page,
+        page_size,
+        has_more,
+    })
+}
+
+/// Messages sent before `before_timestamp`, for loading older history while scrolling up
+#[tauri::command]
+pub async fn get_conversation_messages_before(
+    db: State<'_, Database>,
+    conversation_id: String,
+    before_timestamp: i64,
+    limit: usize,
+) -> Result<Vec<Message>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Message::get_before(&conn, &conversation_id, before_timestamp, limit as i64)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_message(
+    db: State<'_, Database>,
+    id: String,
+    include_deleted: Option<bool>,
+) -> Result<Option<Message>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Message::get_by_id(&conn, &id, include_deleted.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_last_messages(
     db: State<'_, Database>,
     conversation_id: String,
     n: i64,
 ) -> Result<Vec<Message>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Message::get_last_n(&conn, &conversation_id, n).map_err(|e| e.to_string())
 }
 
@@ -101,9 +482,69 @@ pub async fn search_messages(
     db: State<'_, Database>,
     query: String,
     limit: i64,
-) -> Result<Vec<Message>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Message::search(&conn, &query, limit).map_err(|e| e.to_string())
+) -> Result<Vec<SearchedMessage>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Message::search_ranked(&conn, &query, limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_messages_in_conversation(
+    db: State<'_, Database>,
+    conversation_id: String,
+    query: String,
+    limit: i64,
+) -> Result<Vec<SearchedMessage>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Message::search_within_conversation(&conn, &conversation_id, &query, limit)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_messages_with_code_blocks(
+    db: State<'_, Database>,
+    conversation_id: String,
+) -> Result<Vec<MessageWithCodeBlocks>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let messages =
+        Message::get_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())?;
+    Ok(messages_with_code_blocks(messages))
+}
+
+#[tauri::command]
+pub async fn export_code_blocks(
+    db: State<'_, Database>,
+    conversation_id: String,
+    language_filter: Option<String>,
+) -> Result<String, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let messages =
+        Message::get_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())?;
+    Ok(format_code_blocks(
+        &messages_with_code_blocks(messages),
+        language_filter.as_deref(),
+    ))
+}
+
+/// Keep the history up to `split_after_message_id` and continue the rest in a new conversation
+#[tauri::command]
+pub async fn split_conversation_at_message(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+    split_after_message_id: String,
+) -> Result<(Conversation, Conversation), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let split = Conversation::split_at_message(&conn, &conversation_id, &split_after_message_id)
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                format!("Conversation not found: {}", conversation_id)
+            }
+            e => e.to_string(),
+        })?;
+    drop(conn);
+
+    crate::tray::refresh_recent_conversations(&app);
+    Ok(split)
 }
 
 #[tauri::command]
@@ -112,13 +553,13 @@ pub async fn update_message(
     id: String,
     content: String,
 ) -> Result<Message, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Message::update(&conn, &id, &content).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn delete_message(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Message::delete(&conn, &id).map_err(|e| e.to_string())
 }
 
@@ -127,7 +568,7 @@ pub async fn get_conversation_token_count(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<i64, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Message::get_conversation_token_count(&conn, &conversation_id).map_err(|e| e.to_string())
 }
 
@@ -135,7 +576,7 @@ pub async fn get_conversation_token_count(
 pub async fn get_last_assistant_message(
     db: State<'_, Database>,
 ) -> Result<Option<Message>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     // Get the most recently updated conversation
     let conversations = crate::database::conversations::Conversation::get_all(&conn, 1)
@@ -175,3 +616,287 @@ pub async fn get_last_assistant_message(
         None => Ok(None),
     }
 }
+
+#[tauri::command]
+pub async fn create_message_with_conversation_context(
+    db: State<'_, Database>,
+    conversation_id: String,
+    referenced_conversation_id: String,
+    user_message: String,
+    model: Option<String>,
+    provider: Option<String>,
+    max_context_tokens: Option<usize>,
+) -> Result<Message, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+
+    let referenced = Conversation::get_by_id(&conn, &referenced_conversation_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Referenced conversation not found".to_string())?;
+    let referenced_messages = Message::get_by_conversation(&conn, &referenced_conversation_id)
+        .map_err(|e| e.to_string())?;
+
+    // Start the target conversation if it doesn't exist yet, inheriting model and
+    // provider from the referenced conversation unless overridden
+    if Conversation::get_by_id(&conn, &conversation_id)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        Conversation::create_with_id(
+            &conn,
+            NewConversationWithId {
+                id: conversation_id.clone(),
+                title: format!("Re: {}", referenced.title),
+                model: model.unwrap_or(referenced.model),
+                provider: provider.unwrap_or(referenced.provider),
+                system_prompt: None,
+                created_at: now,
+                updated_at: now,
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let context = format_conversation_context(&referenced_messages, max_context_tokens);
+    let new_msg = NewMessage {
+        conversation_id,
+        role: "user".to_string(),
+        content: format!("{}\n\n{}", context, user_message),
+        tokens_used: None,
+    };
+    Message::create(&conn, new_msg).map_err(|e| e.to_string())
+}
+
+/// Add a system message to an existing conversation. Only one is allowed unless
+/// `allow_multiple_system` is set.
+#[tauri::command]
+pub async fn insert_system_message(
+    db: State<'_, Database>,
+    conversation_id: String,
+    content: String,
+    allow_multiple_system: Option<bool>,
+) -> Result<Message, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+
+    if Conversation::get_by_id(&conn, &conversation_id)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err("Conversation not found".to_string());
+    }
+
+    if !allow_multiple_system.unwrap_or(false)
+        && Message::count_by_role(&conn, &conversation_id, "system").map_err(|e| e.to_string())? > 0
+    {
+        return Err("Conversation already has a system message".to_string());
+    }
+
+    let new_msg = NewMessage {
+        conversation_id,
+        role: "system".to_string(),
+        content,
+        tokens_used: None,
+    };
+    Message::create(&conn, new_msg).map_err(|e| e.to_string())
+}
+
+/// Stream a fresh reply in place of the last assistant reply, which is only removed
+/// once the new one has arrived in full. Returns the stream session ID; chunks arrive
+/// via `provider-stream-chunk`.
+#[tauri::command]
+pub async fn regenerate_message(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    conversation_id: String,
+    model: Option<String>,
+    provider: Option<String>,
+) -> Result<String, String> {
+    let (provider, model, messages, replaced_id) = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+
+        let conversation = Conversation::get_by_id(&conn, &conversation_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Conversation not found".to_string())?;
+        let provider = provider.unwrap_or(conversation.provider);
+        let model = model.unwrap_or(conversation.model);
+
+        let mut history =
+            Message::get_by_conversation(&conn, &conversation_id).map_err(|e| e.to_string())?;
+        let last_assistant = history
+            .iter()
+            .rposition(|m| m.role == "assistant")
+            .ok_or_else(|| "No assistant message to regenerate".to_string())?;
+        let replaced = history.remove(last_assistant);
+
+        let mut messages = Vec::with_capacity(history.len() + 1);
+        if let Some(system_prompt) = conversation.system_prompt {
+            messages.push(ProviderMessage::new("system", system_prompt));
+        }
+        messages.extend(
+            history
+                .into_iter()
+                .map(|m| ProviderMessage::new(m.role, m.content)),
+        );
+
+        (provider, model, messages, replaced.id)
+    };
+
+    // Starting the stream resolves the provider, API key and rate limit, so a failure
+    // here leaves the conversation untouched. The blocking HTTP clients need their own thread.
+    let stream_app = app.clone();
+    let stream_conversation_id = conversation_id.clone();
+    let session_id = tokio::task::spawn_blocking(move || {
+        stream_replacement(
+            stream_app,
+            stream_conversation_id,
+            replaced_id,
+            &provider,
+            Some(model),
+            messages,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if let Some(w) = app.get_webview_window("main") {
+        let _ = w.emit(
+            "regeneration-started",
+            serde_json::json!({"session_id": session_id, "conversation_id": conversation_id}),
+        );
+    }
+
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: &str, content: &str) -> Message {
+        Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: "conv".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: 0,
+            tokens_used: None,
+        }
+    }
+
+    #[test]
+    fn test_format_conversation_context() {
+        let messages = vec![msg("user", "hello"), msg("assistant", "hi there")];
+        let context = format_conversation_context(&messages, None);
+        assert_eq!(
+            context,
+            "[Previous conversation]\nuser: hello\n\nassistant: hi there\n[End context]"
+        );
+    }
+
+    #[test]
+    fn test_format_conversation_context_drops_oldest_first() {
+        let messages = vec![
+            msg("user", &"a".repeat(400)),
+            msg("assistant", "short reply"),
+        ];
+        let context = format_conversation_context(&messages, Some(10));
+        assert!(!context.contains("aaaa"));
+        assert!(context.contains("assistant: short reply"));
+    }
+
+    fn sized(role: &str, tokens: i64, timestamp: i64) -> Message {
+        Message {
+            tokens_used: Some(tokens),
+            timestamp,
+            ..msg(role, &format!("{} message at {}", role, timestamp))
+        }
+    }
+
+    fn history() -> Vec<Message> {
+        vec![
+            sized("system", 10, 0),
+            sized("user", 40, 1),
+            sized("assistant", 40, 2),
+            sized("user", 30, 3),
+            sized("assistant", 20, 4),
+        ]
+    }
+
+    #[test]
+    fn test_context_window_recent() {
+        let window = select_context_window(history(), 60, "recent").unwrap();
+        let stamps: Vec<i64> = window.iter().map(|m| m.timestamp).collect();
+        assert_eq!(stamps, vec![3, 4]);
+
+        // NULL tokens_used falls back to 4 chars per token
+        let estimated = vec![msg("user", &"x".repeat(40)), msg("user", "tiny")];
+        let window = select_context_window(estimated, 5, "recent").unwrap();
+        assert_eq!(window.len(), 1);
+        assert_eq!(window[0].content, "tiny");
+    }
+
+    #[test]
+    fn test_context_window_important_keeps_system() {
+        let window = select_context_window(history(), 55, "important").unwrap();
+        let stamps: Vec<i64> = window.iter().map(|m| m.timestamp).collect();
+        assert_eq!(stamps, vec![0, 4]);
+        assert_eq!(window[0].role, "system");
+    }
+
+    #[test]
+    fn test_context_window_summary() {
+        let window = select_context_window(history(), 80, "summary").unwrap();
+        assert_eq!(window.len(), 3);
+        assert_eq!(window[0].role, "system");
+        assert!(window[0]
+            .content
+            .starts_with("Summary of 3 earlier messages:"));
+        assert_eq!(window[1].timestamp, 3);
+        assert_eq!(window[2].timestamp, 4);
+
+        let all = select_context_window(history(), 1000, "summary").unwrap();
+        assert_eq!(all.len(), 5);
+
+        assert!(select_context_window(history(), 80, "bogus").is_err());
+    }
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let content = "Here you go:\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\nand a shell one:\n  ```\nls -la\n  ```\n```Python title\nprint(1)";
+        let blocks = extract_code_blocks(content);
+        assert_eq!(
+            blocks,
+            vec![
+                CodeBlock {
+                    language: Some("rust".to_string()),
+                    content: "fn main() {\n    println!(\"hi\");\n}".to_string(),
+                },
+                CodeBlock {
+                    language: None,
+                    content: "ls -la".to_string(),
+                },
+                CodeBlock {
+                    language: Some("Python".to_string()),
+                    content: "print(1)".to_string(),
+                },
+            ]
+        );
+
+        let messages = vec![msg("user", "no code here"), msg("assistant", content)];
+        let with_code = messages_with_code_blocks(messages);
+        assert_eq!(with_code.len(), 1);
+        assert_eq!(with_code[0].message.role, "assistant");
+
+        assert_eq!(
+            format_code_blocks(&with_code, Some("python")),
+            "```Python\nprint(1)\n```"
+        );
+        assert_eq!(
+            format_code_blocks(&with_code, None).matches("```").count(),
+            6
+        );
+    }
+}
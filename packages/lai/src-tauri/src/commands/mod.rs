@@ -3,7 +3,9 @@
 // own file (conversations.rs, messages.rs, settings.rs).
 
 pub mod conversations;
+pub mod database;
 pub mod export;
+pub mod export_schedules;
 pub mod git;
 pub mod health;
 pub mod messages;
@@ -16,6 +18,7 @@ pub mod settings;
 pub mod shortcuts;
 pub mod tags;
 pub mod updater;
+pub mod webhooks;
 pub mod window;
 pub mod workspace_templates;
 
@@ -1,7 +1,10 @@
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
-use std::sync::{Mutex, OnceLock};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Pid, ProcessRefreshKind, RefreshKind, System};
+use tauri::Manager;
 
 static SYSTEM: OnceLock<Mutex<System>> = OnceLock::new();
 static LAST_UPDATE: OnceLock<Mutex<Instant>> = OnceLock::new();
@@ -115,11 +118,23 @@ pub async fn get_performance_metrics() -> Result<SystemMetrics, String> {
     get_system_metrics()
 }
 
+#[tauri::command]
+pub async fn get_ipc_metrics(
+    app: tauri::AppHandle,
+) -> Result<crate::ipc::IpcServerMetricsInner, String> {
+    app.state::<crate::ipc::IpcServerMetrics>().snapshot()
+}
+
+#[tauri::command]
+pub async fn reset_ipc_metrics(app: tauri::AppHandle) -> Result<(), String> {
+    app.state::<crate::ipc::IpcServerMetrics>().reset()
+}
+
 #[tauri::command]
 pub async fn get_database_metrics(
     db: tauri::State<'_, crate::database::Database>,
 ) -> Result<DatabaseMetrics, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     // Get conversation count
     let conversation_count: i64 = conn
@@ -164,3 +179,597 @@ pub async fn get_full_performance_snapshot(
 
     Ok(PerformanceSnapshot { system, database })
 }
+
+/// Blended USD price per 1K tokens (average of input and output rates). These are
+/// averaged from `TOKEN_PRICING` in `src/components/CostBadge.tsx`, and nothing checks
+/// the two agree: update both by hand when a price changes.
+const TOKEN_PRICING: &[(&str, f64)] = &[
+    ("gpt-4", 0.045),
+    ("gpt-4-turbo", 0.02),
+    ("gpt-3.5-turbo", 0.00175),
+    ("claude-3-opus", 0.045),
+    ("claude-3-sonnet", 0.009),
+    ("claude-3-haiku", 0.00075),
+    ("gemini-pro", 0.000375),
+];
+/// Average of the cost badge's fallback rates, for models missing from the table
+const DEFAULT_PRICE_PER_1K: f64 = 0.0015;
+
+fn price_per_1k(provider: &str, model: &str) -> f64 {
+    // Local models cost nothing to run
+    if provider == "ollama" || provider == "local" {
+        return 0.0;
+    }
+    TOKEN_PRICING
+        .iter()
+        .find(|(name, _)| *name == model)
+        .map(|(_, price)| *price)
+        .unwrap_or(DEFAULT_PRICE_PER_1K)
+}
+
+/// SQLite date modifiers that truncate a unix timestamp to the start of its period.
+/// Weeks start on Monday.
+fn period_modifiers(granularity: &str) -> Result<&'static str, String> {
+    match granularity {
+        "day" => Ok("'start of day'"),
+        "week" => Ok("'start of day', 'weekday 0', '-6 days'"),
+        "month" => Ok("'start of month'"),
+        other => Err(format!(
+            "Invalid granularity '{}': expected day, week, or month",
+            other
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageByPeriod {
+    pub period_start: i64,
+    pub message_count: i64,
+    pub conversation_count: i64,
+    pub total_tokens: i64,
+    /// Message count per provider
+    pub provider_breakdown: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostByPeriod {
+    pub period_start: i64,
+    pub total_tokens: i64,
+    pub estimated_cost: f64,
+    /// Estimated cost per provider
+    pub provider_breakdown: HashMap<String, f64>,
+}
+
+struct UsageRow {
+    period_start: i64,
+    provider: String,
+    model: String,
+    message_count: i64,
+    conversation_count: i64,
+    total_tokens: i64,
+}
+
+/// Per-period rows grouped by provider and model, oldest period first
+fn usage_rows(
+    conn: &Connection,
+    granularity: &str,
+    since: Option<i64>,
+) -> Result<Vec<UsageRow>, String> {
+    let sql = format!(
+        "SELECT CAST(strftime('%s', m.timestamp, 'unixepoch', {}) AS INTEGER) AS period,
+                c.provider, c.model,
+                COUNT(m.id), COUNT(DISTINCT m.conversation_id), COALESCE(SUM(m.tokens_used), 0)
+         FROM messages m
+         JOIN conversations c ON c.id = m.conversation_id
+         WHERE m.deleted = 0 AND c.deleted = 0 AND (?1 IS NULL OR m.timestamp >= ?1)
+         GROUP BY period, c.provider, c.model
+         ORDER BY period ASC",
+        period_modifiers(granularity)?
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok(UsageRow {
+                period_start: row.get(0)?,
+                provider: row.get(1)?,
+                model: row.get(2)?,
+                message_count: row.get(3)?,
+                conversation_count: row.get(4)?,
+                total_tokens: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn usage_over_time(
+    conn: &Connection,
+    granularity: &str,
+    since: Option<i64>,
+) -> Result<Vec<UsageByPeriod>, String> {
+    let mut periods: Vec<UsageByPeriod> = Vec::new();
+    for row in usage_rows(conn, granularity, since)? {
+        if periods.last().map(|p| p.period_start) != Some(row.period_start) {
+            periods.push(UsageByPeriod {
+                period_start: row.period_start,
+                message_count: 0,
+                conversation_count: 0,
+                total_tokens: 0,
+                provider_breakdown: HashMap::new(),
+            });
+        }
+        let period = periods.last_mut().expect("period was just pushed");
+        period.message_count += row.message_count;
+        // A conversation has a single provider and model, so per-group counts don't overlap
+        period.conversation_count += row.conversation_count;
+        period.total_tokens += row.total_tokens;
+        *period.provider_breakdown.entry(row.provider).or_insert(0) += row.message_count;
+    }
+    Ok(periods)
+}
+
+pub fn cost_over_time(
+    conn: &Connection,
+    granularity: &str,
+    since: Option<i64>,
+) -> Result<Vec<CostByPeriod>, String> {
+    let mut periods: Vec<CostByPeriod> = Vec::new();
+    for row in usage_rows(conn, granularity, since)? {
+        if periods.last().map(|p| p.period_start) != Some(row.period_start) {
+            periods.push(CostByPeriod {
+                period_start: row.period_start,
+                total_tokens: 0,
+                estimated_cost: 0.0,
+                provider_breakdown: HashMap::new(),
+            });
+        }
+        let period = periods.last_mut().expect("period was just pushed");
+        let cost = row.total_tokens as f64 / 1000.0 * price_per_1k(&row.provider, &row.model);
+        period.total_tokens += row.total_tokens;
+        period.estimated_cost += cost;
+        *period.provider_breakdown.entry(row.provider).or_insert(0.0) += cost;
+    }
+    Ok(periods)
+}
+
+#[tauri::command]
+pub async fn get_usage_over_time(
+    db: tauri::State<'_, crate::database::Database>,
+    granularity: String,
+    since: Option<i64>,
+) -> Result<Vec<UsageByPeriod>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    usage_over_time(&conn, &granularity, since)
+}
+
+#[tauri::command]
+pub async fn get_cost_over_time(
+    db: tauri::State<'_, crate::database::Database>,
+    granularity: String,
+    since: Option<i64>,
+) -> Result<Vec<CostByPeriod>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    cost_over_time(&conn, &granularity, since)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTokenStat {
+    pub conversation_id: String,
+    pub title: String,
+    pub total_tokens: i64,
+    pub message_count: i64,
+    pub avg_tokens_per_message: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyTokenUsage {
+    /// UTC date as `YYYY-MM-DD`
+    pub date: String,
+    pub total_tokens: i64,
+    pub message_count: i64,
+}
+
+/// Conversations with the highest cumulative token usage, largest first
+pub fn top_token_conversations(
+    conn: &Connection,
+    limit: usize,
+) -> Result<Vec<ConversationTokenStat>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT conversations.id, conversations.title,
+                    COALESCE(SUM(m.tokens_used), 0), COUNT(m.id)
+             FROM conversations
+             JOIN messages m ON m.conversation_id = conversations.id AND m.deleted = 0
+             WHERE conversations.deleted = 0
+             GROUP BY conversations.id
+             ORDER BY SUM(m.tokens_used) DESC
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit as i64], |row| {
+            let total_tokens: i64 = row.get(2)?;
+            let message_count: i64 = row.get(3)?;
+            Ok(ConversationTokenStat {
+                conversation_id: row.get(0)?,
+                title: row.get(1)?,
+                total_tokens,
+                message_count,
+                avg_tokens_per_message: total_tokens as f64 / message_count.max(1) as f64,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Token and message totals per UTC day, oldest first
+pub fn daily_token_usage(
+    conn: &Connection,
+    since: Option<i64>,
+) -> Result<Vec<DailyTokenUsage>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(m.timestamp, 'unixepoch') AS day,
+                    COALESCE(SUM(m.tokens_used), 0), COUNT(m.id)
+             FROM messages m
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE m.deleted = 0 AND c.deleted = 0 AND (?1 IS NULL OR m.timestamp >= ?1)
+             GROUP BY day
+             ORDER BY day ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok(DailyTokenUsage {
+                date: row.get(0)?,
+                total_tokens: row.get(1)?,
+                message_count: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_top_token_conversations(
+    db: tauri::State<'_, crate::database::Database>,
+    limit: usize,
+) -> Result<Vec<ConversationTokenStat>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    top_token_conversations(&conn, limit)
+}
+
+#[tauri::command]
+pub async fn get_daily_token_usage(
+    db: tauri::State<'_, crate::database::Database>,
+    since: Option<i64>,
+) -> Result<Vec<DailyTokenUsage>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    daily_token_usage(&conn, since)
+}
+
+/// Used by `benchmark_provider` when no prompt is given
+const DEFAULT_BENCHMARK_PROMPT: &str = "Say hello.";
+/// Upper bound on benchmark iterations, since each one is a billable request
+const MAX_BENCHMARK_ITERATIONS: u32 = 50;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkResult {
+    pub provider: String,
+    pub model: String,
+    pub iterations: u32,
+    pub avg_latency_ms: f64,
+    pub p50_latency_ms: f64,
+    pub p95_latency_ms: f64,
+    pub min_latency_ms: f64,
+    pub max_latency_ms: f64,
+    /// Estimated from the prompt and replies; providers' usage fields aren't exposed
+    pub total_tokens_used: u64,
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn nearest_rank(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Average, p50, p95, min and max of `samples`, using nearest-rank percentiles
+fn latency_summary(samples: &[f64]) -> (f64, f64, f64, f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0, 0.0, 0.0, 0.0);
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let avg = sorted.iter().sum::<f64>() / sorted.len() as f64;
+    (
+        avg,
+        nearest_rank(&sorted, 50.0),
+        nearest_rank(&sorted, 95.0),
+        sorted[0],
+        sorted[sorted.len() - 1],
+    )
+}
+
+/// Time `iterations` sequential one-shot generations against `provider`
+#[tauri::command]
+pub async fn benchmark_provider(
+    app: tauri::AppHandle,
+    provider: String,
+    model: String,
+    test_prompt: Option<String>,
+    iterations: u32,
+) -> Result<BenchmarkResult, String> {
+    if iterations == 0 || iterations > MAX_BENCHMARK_ITERATIONS {
+        return Err(format!(
+            "iterations must be between 1 and {}",
+            MAX_BENCHMARK_ITERATIONS
+        ));
+    }
+    let prompt = test_prompt.unwrap_or_else(|| DEFAULT_BENCHMARK_PROMPT.to_string());
+
+    // The generate functions block, so keep them off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        use crate::commands::provider::{
+            generate_with_provider, token_count_estimate, ProviderMessage,
+        };
+
+        let mut latencies = Vec::with_capacity(iterations as usize);
+        let mut total_tokens_used = 0u64;
+        for i in 0..iterations {
+            let messages = vec![ProviderMessage::new("user", prompt.clone())];
+            let start = Instant::now();
+            let reply = generate_with_provider(&app, &provider, messages, Some(model.clone()))
+                .map_err(|e| format!("Iteration {} failed: {}", i + 1, e))?;
+            latencies.push(start.elapsed().as_secs_f64() * 1000.0);
+
+            let exchange = vec![
+                ProviderMessage::new("user", prompt.clone()),
+                ProviderMessage::new("assistant", reply),
+            ];
+            total_tokens_used +=
+                token_count_estimate(exchange, model.clone())?.estimated_tokens as u64;
+        }
+
+        let (avg, p50, p95, min, max) = latency_summary(&latencies);
+        Ok(BenchmarkResult {
+            provider,
+            model,
+            iterations,
+            avg_latency_ms: avg,
+            p50_latency_ms: p50,
+            p95_latency_ms: p95,
+            min_latency_ms: min,
+            max_latency_ms: max,
+            total_tokens_used,
+        })
+    })
+    .await
+    .map_err(|e| format!("Benchmark task failed: {}", e))?
+}
+
+/// Most provider calls kept for latency stats; older ones are dropped first
+const MAX_PROVIDER_CALL_RECORDS: usize = 1000;
+
+/// One completed blocking provider request
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProviderCallRecord {
+    pub provider: String,
+    pub model: String,
+    pub latency_ms: f64,
+    /// Estimated tokens in the reply
+    pub token_count: u64,
+    pub timestamp: i64,
+}
+
+/// Managed state holding the most recent provider calls since startup
+#[derive(Clone, Default)]
+pub struct ProviderLatencyStore(Arc<Mutex<VecDeque<ProviderCallRecord>>>);
+
+impl ProviderLatencyStore {
+    pub fn record(&self, record: ProviderCallRecord) {
+        if let Ok(mut records) = self.0.lock() {
+            if records.len() >= MAX_PROVIDER_CALL_RECORDS {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+
+    /// Latencies of the recorded calls, optionally for one provider only
+    fn latencies(&self, provider: Option<&str>) -> Result<Vec<f64>, String> {
+        let records = self.0.lock().map_err(|e| e.to_string())?;
+        Ok(records
+            .iter()
+            .filter(|r| match provider {
+                Some(p) => r.provider == p,
+                None => true,
+            })
+            .map(|r| r.latency_ms)
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LatencyStats {
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub sample_count: usize,
+}
+
+fn latency_stats(samples: &[f64]) -> LatencyStats {
+    if samples.is_empty() {
+        return LatencyStats::default();
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    LatencyStats {
+        avg_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        p50_ms: nearest_rank(&sorted, 50.0),
+        p95_ms: nearest_rank(&sorted, 95.0),
+        p99_ms: nearest_rank(&sorted, 99.0),
+        max_ms: sorted[sorted.len() - 1],
+        sample_count: sorted.len(),
+    }
+}
+
+/// Round-trip latency of recent blocking provider calls (all providers when `provider` is None)
+#[tauri::command]
+pub async fn get_response_latency_stats(
+    store: tauri::State<'_, ProviderLatencyStore>,
+    provider: Option<String>,
+) -> Result<LatencyStats, String> {
+    Ok(latency_stats(&store.latencies(provider.as_deref())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn usage_groups_by_period_and_provider() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        // 2024-01-01 (Monday) and 2024-01-03 fall in the same week but different days
+        let day1 = 1_704_067_200;
+        let day3 = day1 + 2 * 86_400;
+        for (conv, provider, model) in [("c1", "openai", "gpt-4"), ("c2", "ollama", "llama3.2")] {
+            conn.execute(
+                "INSERT INTO conversations (id, title, created_at, updated_at, model, provider)
+                 VALUES (?1, 't', 0, 0, ?2, ?3)",
+                params![conv, model, provider],
+            )
+            .expect("insert conv");
+        }
+        for (id, conv, ts) in [("m1", "c1", day1), ("m2", "c1", day3), ("m3", "c2", day3)] {
+            conn.execute(
+                "INSERT INTO messages (id, conversation_id, role, content, timestamp, tokens_used)
+                 VALUES (?1, ?2, 'user', 'x', ?3, 1000)",
+                params![id, conv, ts],
+            )
+            .expect("insert msg");
+        }
+
+        let daily = usage_over_time(&conn, "day", None).expect("daily");
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].period_start, day1);
+        assert_eq!(daily[1].message_count, 2);
+        assert_eq!(daily[1].conversation_count, 2);
+        assert_eq!(daily[1].provider_breakdown.get("ollama"), Some(&1));
+
+        let weekly = usage_over_time(&conn, "week", None).expect("weekly");
+        assert_eq!(weekly.len(), 1);
+        assert_eq!(weekly[0].period_start, day1);
+        assert_eq!(weekly[0].total_tokens, 3000);
+
+        let cost = cost_over_time(&conn, "month", Some(day3)).expect("cost");
+        assert_eq!(cost.len(), 1);
+        assert!((cost[0].estimated_cost - 0.045).abs() < 1e-9);
+        assert_eq!(cost[0].provider_breakdown.get("ollama"), Some(&0.0));
+
+        assert!(usage_over_time(&conn, "year", None).is_err());
+    }
+
+    #[test]
+    fn token_stats_rank_conversations_and_group_days() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let day1 = 1_704_067_200;
+        let day2 = day1 + 86_400;
+        for (conv, title) in [("c1", "small"), ("c2", "big"), ("c3", "empty")] {
+            conn.execute(
+                "INSERT INTO conversations (id, title, created_at, updated_at, model, provider)
+                 VALUES (?1, ?2, 0, 0, 'gpt-4', 'openai')",
+                params![conv, title],
+            )
+            .expect("insert conv");
+        }
+        for (id, conv, ts, tokens) in [
+            ("m1", "c1", day1, Some(100)),
+            ("m2", "c2", day1, Some(400)),
+            ("m3", "c2", day2, Some(200)),
+            ("m4", "c2", day2, None),
+        ] {
+            conn.execute(
+                "INSERT INTO messages (id, conversation_id, role, content, timestamp, tokens_used)
+                 VALUES (?1, ?2, 'user', 'x', ?3, ?4)",
+                params![id, conv, ts, tokens],
+            )
+            .expect("insert msg");
+        }
+
+        let top = top_token_conversations(&conn, 10).expect("top");
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].title, "big");
+        assert_eq!(top[0].total_tokens, 600);
+        assert_eq!(top[0].message_count, 3);
+        assert!((top[0].avg_tokens_per_message - 200.0).abs() < 1e-9);
+        assert_eq!(top_token_conversations(&conn, 1).expect("top 1").len(), 1);
+
+        let daily = daily_token_usage(&conn, None).expect("daily");
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2024-01-01");
+        assert_eq!(daily[0].total_tokens, 500);
+        assert_eq!(daily[1].message_count, 2);
+        assert_eq!(
+            daily_token_usage(&conn, Some(day2)).expect("since").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn latency_summary_uses_nearest_rank() {
+        let samples: Vec<f64> = (1..=20).rev().map(|v| v as f64 * 10.0).collect();
+        let (avg, p50, p95, min, max) = latency_summary(&samples);
+        assert_eq!(avg, 105.0);
+        assert_eq!(p50, 100.0);
+        assert_eq!(p95, 190.0);
+        assert_eq!(min, 10.0);
+        assert_eq!(max, 200.0);
+
+        let (avg, p50, p95, _, _) = latency_summary(&[42.0]);
+        assert_eq!((avg, p50, p95), (42.0, 42.0, 42.0));
+    }
+
+    fn call(provider: &str, latency_ms: f64) -> ProviderCallRecord {
+        ProviderCallRecord {
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            latency_ms,
+            token_count: 0,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_provider_latency_store() {
+        let store = ProviderLatencyStore::default();
+        for i in 1..=MAX_PROVIDER_CALL_RECORDS + 10 {
+            store.record(call("openai", i as f64));
+        }
+        store.record(call("ollama", 5000.0));
+
+        // Oldest calls were evicted once the cap was reached
+        let openai = latency_stats(&store.latencies(Some("openai")).unwrap());
+        assert_eq!(openai.sample_count, MAX_PROVIDER_CALL_RECORDS - 1);
+        assert_eq!(openai.max_ms, 1010.0);
+        assert_eq!(openai.p50_ms, 511.0);
+        assert_eq!(openai.p99_ms, 1001.0);
+
+        let all = latency_stats(&store.latencies(None).unwrap());
+        assert_eq!(all.sample_count, MAX_PROVIDER_CALL_RECORDS);
+        assert_eq!(all.max_ms, 5000.0);
+
+        assert_eq!(latency_stats(&[]), LatencyStats::default());
+    }
+}
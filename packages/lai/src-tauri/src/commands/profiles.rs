@@ -1,4 +1,7 @@
-use crate::database::{profiles::NewProfile, profiles::Profile, Database};
+use crate::database::{
+    profiles::{NewProfile, Profile, ProfileStats},
+    Database,
+};
 use tauri::State;
 
 #[tauri::command]
@@ -6,31 +9,31 @@ pub async fn create_profile(
     db: State<'_, Database>,
     profile_data: NewProfile,
 ) -> Result<Profile, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::create(&conn, profile_data).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_profile(db: State<'_, Database>, id: String) -> Result<Option<Profile>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::get_by_id(&conn, &id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_all_profiles(db: State<'_, Database>) -> Result<Vec<Profile>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::get_all(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_active_profile(db: State<'_, Database>) -> Result<Option<Profile>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::get_active(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn set_active_profile(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::set_active(&conn, &id).map_err(|e| e.to_string())
 }
 
@@ -40,12 +43,63 @@ pub async fn update_profile(
     id: String,
     profile_data: NewProfile,
 ) -> Result<Profile, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::update(&conn, &id, profile_data).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn delete_profile(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Profile::delete(&conn, &id).map_err(|e| e.to_string())
 }
+
+#[tauri::command]
+pub async fn duplicate_profile(
+    db: State<'_, Database>,
+    source_id: String,
+    new_name: String,
+) -> Result<Profile, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Profile::duplicate(&conn, &source_id, &new_name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reset_profile_to_defaults(
+    db: State<'_, Database>,
+    id: String,
+) -> Result<Profile, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Profile::reset_to_defaults(&conn, &id).map_err(|e| e.to_string())
+}
+
+/// Set or clear the sampling temperature used for a profile's conversations
+#[tauri::command]
+pub async fn set_profile_temperature(
+    db: State<'_, Database>,
+    id: String,
+    temperature: Option<f64>,
+) -> Result<Profile, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Profile::set_temperature(&conn, &id, temperature).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_conversation_profile(
+    db: State<'_, Database>,
+    conversation_id: String,
+    profile_id: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Profile::assign_to_conversation(&conn, &conversation_id, &profile_id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "Conversation not found".to_string(),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_profile_conversation_stats(
+    db: State<'_, Database>,
+) -> Result<Vec<ProfileStats>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Profile::conversation_stats(&conn).map_err(|e| e.to_string())
+}
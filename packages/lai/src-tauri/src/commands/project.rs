@@ -1,13 +1,68 @@
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs;
 use std::path::PathBuf;
-use std::sync::{mpsc, Mutex, OnceLock};
-use tauri::Emitter;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{Emitter, State};
 
-static WATCHER: OnceLock<Mutex<Option<RecommendedWatcher>>> = OnceLock::new();
-static IGNORE_PATTERNS: OnceLock<Mutex<Option<Gitignore>>> = OnceLock::new();
+/// Root, file watcher and ignore rules of the open project
+#[derive(Default)]
+pub struct ProjectStateInner {
+    pub root: Option<PathBuf>,
+    pub watcher: Option<RecommendedWatcher>,
+    pub gitignore: Option<Gitignore>,
+}
+
+/// Managed state shared by the project commands and the watcher's event thread.
+/// Everything lives behind one lock so replacing and stopping the watcher can't race.
+#[derive(Clone, Default)]
+pub struct ProjectState(Arc<Mutex<ProjectStateInner>>);
+
+impl ProjectState {
+    fn lock(&self) -> Result<MutexGuard<'_, ProjectStateInner>, String> {
+        self.0.lock().map_err(|e| e.to_string())
+    }
+
+    /// Swap in a new project, returning the previous watcher so it can be
+    /// dropped (which stops it) after the lock is released
+    fn open(
+        &self,
+        root: PathBuf,
+        gitignore: Gitignore,
+        watcher: Option<RecommendedWatcher>,
+    ) -> Result<Option<RecommendedWatcher>, String> {
+        let mut inner = self.lock()?;
+        inner.root = Some(root);
+        inner.gitignore = Some(gitignore);
+        Ok(std::mem::replace(&mut inner.watcher, watcher))
+    }
+
+    fn stop_watch(&self) -> Result<(), String> {
+        let watcher = self.lock()?.watcher.take();
+        drop(watcher);
+        Ok(())
+    }
+
+    fn clear_ignore_patterns(&self) -> Result<(), String> {
+        self.lock()?.gitignore = None;
+        Ok(())
+    }
+
+    fn root(&self) -> Result<PathBuf, String> {
+        self.lock()?
+            .root
+            .clone()
+            .ok_or_else(|| "Project root is not set".to_string())
+    }
+
+    fn gitignore(&self) -> Result<Option<Gitignore>, String> {
+        Ok(self.lock()?.gitignore.clone())
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileMatch {
@@ -18,6 +73,18 @@ pub struct FileMatch {
     pub context_after: Vec<String>,
     pub file_type: String,
     pub score: f32,
+    pub file_size_bytes: Option<u64>,
+    /// Last modification time in seconds since the Unix epoch
+    pub modified_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileStat {
+    pub path: String,
+    pub size_bytes: u64,
+    pub extension: String,
+    /// Last modification time in seconds since the Unix epoch
+    pub modified_at: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -49,14 +116,11 @@ fn build_gitignore(patterns: &[String], root: &PathBuf) -> Result<Gitignore, Str
         .map_err(|e| format!("Failed to build gitignore: {}", e))
 }
 
-fn should_ignore_path(path: &PathBuf, root: &PathBuf) -> bool {
-    let ignore_cell = IGNORE_PATTERNS.get_or_init(|| Mutex::new(None));
-    if let Ok(guard) = ignore_cell.lock() {
-        if let Some(ref gitignore) = *guard {
-            // Get relative path from project root
-            if let Ok(relative_path) = path.strip_prefix(root) {
-                return gitignore.matched(relative_path, path.is_dir()).is_ignore();
-            }
+fn should_ignore_path(path: &PathBuf, root: &PathBuf, gitignore: Option<&Gitignore>) -> bool {
+    if let Some(gitignore) = gitignore {
+        // Get relative path from project root
+        if let Ok(relative_path) = path.strip_prefix(root) {
+            return gitignore.matched(relative_path, path.is_dir()).is_ignore();
         }
     }
     false
@@ -142,6 +206,21 @@ fn is_text_file(file_type: &str) -> bool {
     )
 }
 
+/// Size in bytes and modification time (seconds since epoch), when available
+fn file_metadata(path: &PathBuf) -> (Option<u64>, Option<u64>) {
+    match fs::metadata(path) {
+        Ok(meta) => {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            (Some(meta.len()), modified)
+        }
+        Err(_) => (None, None),
+    }
+}
+
 fn search_file_content(path: &PathBuf, query: &str, case_sensitive: bool) -> Vec<FileMatch> {
     let mut matches = Vec::new();
 
@@ -192,6 +271,8 @@ fn search_file_content(path: &PathBuf, query: &str, case_sensitive: bool) -> Vec
                     context_after,
                     file_type: get_file_type(path),
                     score,
+                    file_size_bytes: None,
+                    modified_at: None,
                 });
             }
         }
@@ -202,22 +283,27 @@ fn search_file_content(path: &PathBuf, query: &str, case_sensitive: bool) -> Vec
 
 fn walk_directory(
     root: &PathBuf,
+    gitignore: Option<&Gitignore>,
     query: &str,
     case_sensitive: bool,
     max_results: usize,
+    modified_after: Option<u64>,
 ) -> Result<SearchResult, String> {
     let start_time = std::time::Instant::now();
     let mut all_matches = Vec::new();
     let mut files_searched = 0;
 
+    #[allow(clippy::too_many_arguments)]
     fn visit_dir(
         dir: &PathBuf,
         root: &PathBuf,
+        gitignore: Option<&Gitignore>,
         query: &str,
         case_sensitive: bool,
         matches: &mut Vec<FileMatch>,
         files_searched: &mut usize,
         max_results: usize,
+        modified_after: Option<u64>,
     ) -> Result<(), String> {
         if matches.len() >= max_results {
             return Ok(());
@@ -234,7 +320,7 @@ fn walk_directory(
             let path = entry.path();
 
             // Skip ignored paths
-            if should_ignore_path(&path, root) {
+            if should_ignore_path(&path, root, gitignore) {
                 continue;
             }
 
@@ -242,14 +328,25 @@ fn walk_directory(
                 visit_dir(
                     &path,
                     root,
+                    gitignore,
                     query,
                     case_sensitive,
                     matches,
                     files_searched,
                     max_results,
+                    modified_after,
                 )?;
             } else if path.is_file() {
+                // Only stat up front when filtering; otherwise wait until something matches
+                let metadata = modified_after.map(|_| file_metadata(&path));
+                if let (Some(after), Some((_, modified))) = (modified_after, metadata) {
+                    if modified.is_none_or(|m| m < after) {
+                        continue;
+                    }
+                }
+
                 let file_type = get_file_type(&path);
+                let first_match = matches.len();
 
                 // Search filename
                 let filename = path
@@ -281,6 +378,8 @@ fn walk_directory(
                         } else {
                             0.9
                         },
+                        file_size_bytes: None,
+                        modified_at: None,
                     });
                 }
 
@@ -290,6 +389,14 @@ fn walk_directory(
                     matches.extend(content_matches);
                     *files_searched += 1;
                 }
+
+                if matches.len() > first_match {
+                    let (size, modified) = metadata.unwrap_or_else(|| file_metadata(&path));
+                    for m in &mut matches[first_match..] {
+                        m.file_size_bytes = size;
+                        m.modified_at = modified;
+                    }
+                }
             }
         }
 
@@ -299,11 +406,13 @@ fn walk_directory(
     visit_dir(
         root,
         root,
+        gitignore,
         query,
         case_sensitive,
         &mut all_matches,
         &mut files_searched,
         max_results,
+        modified_after,
     )?;
 
     // Sort by score (highest first) and take top results
@@ -328,6 +437,7 @@ fn walk_directory(
 pub fn set_project_root(
     path: String,
     patterns: Option<Vec<String>>,
+    state: State<'_, ProjectState>,
     app: tauri::AppHandle,
 ) -> Result<(), String> {
     let root = PathBuf::from(path);
@@ -352,20 +462,10 @@ pub fn set_project_root(
     // Build gitignore patterns
     let gitignore = build_gitignore(&ignore_patterns, &root)?;
 
-    // Store ignore patterns
-    let ignore_cell = IGNORE_PATTERNS.get_or_init(|| Mutex::new(None));
-    if let Ok(mut guard) = ignore_cell.lock() {
-        *guard = Some(gitignore);
-    }
-
-    // Stop existing watcher
-    if let Some(cell) = WATCHER.get() {
-        if let Ok(mut guard) = cell.lock() {
-            if let Some(_w) = guard.take() {
-                // Dropping here stops the previous watcher completely
-            }
-        }
-    }
+    // Remember the root so file operations can be confined to it
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve project root: {}", e))?;
 
     let (tx, rx) = mpsc::channel::<Result<Event, notify::Error>>();
     let mut watcher = RecommendedWatcher::new(tx, Config::default())
@@ -374,24 +474,22 @@ pub fn set_project_root(
         .watch(&root, RecursiveMode::Recursive)
         .map_err(|e| format!("watch path failed: {}", e))?;
 
-    // store watcher
-    let _ = WATCHER.get_or_init(|| Mutex::new(None));
-    if let Some(cell) = WATCHER.get() {
-        if let Ok(mut guard) = cell.lock() {
-            *guard = Some(watcher);
-        }
-    }
+    // Dropping the previous watcher stops it completely
+    let previous = state.open(root.clone(), gitignore, Some(watcher))?;
+    drop(previous);
 
-    // spawn receiver thread emitting events
+    // spawn receiver thread emitting events; it ends when the watcher is dropped
     let app_handle = app.clone();
     let project_root = root.clone();
+    let project_state = state.inner().clone();
     std::thread::spawn(move || {
         while let Ok(ev) = rx.recv() {
             if let Ok(event) = ev {
+                let gitignore = project_state.gitignore().unwrap_or_default();
                 let paths: Vec<String> = event
                     .paths
                     .into_iter()
-                    .filter(|path| !should_ignore_path(path, &project_root))
+                    .filter(|path| !should_ignore_path(path, &project_root, gitignore.as_ref()))
                     .map(|p| p.to_string_lossy().to_string())
                     .collect();
 
@@ -407,25 +505,125 @@ pub fn set_project_root(
 }
 
 #[tauri::command]
-pub fn update_ignore_patterns(_patterns: Vec<String>) -> Result<(), String> {
+pub fn update_ignore_patterns(
+    _patterns: Vec<String>,
+    state: State<'_, ProjectState>,
+) -> Result<(), String> {
     // For now, we'll just clear the current patterns
     // They'll be rebuilt when set_project_root is called again
-    let ignore_cell = IGNORE_PATTERNS.get_or_init(|| Mutex::new(None));
-    if let Ok(mut guard) = ignore_cell.lock() {
-        *guard = None;
-    }
-    Ok(())
+    state.clear_ignore_patterns()
 }
 
 #[tauri::command]
-pub fn stop_project_watch() -> Result<(), String> {
-    if let Some(cell) = WATCHER.get() {
-        if let Ok(mut guard) = cell.lock() {
-            if let Some(_w) = guard.take() {
-                // dropping stops the watcher
+pub fn stop_project_watch(state: State<'_, ProjectState>) -> Result<(), String> {
+    state.stop_watch()
+}
+
+/// Used when `watch_file` is called without `debounce_ms`
+const DEFAULT_FILE_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Single-file watchers started by `watch_file`, keyed by watch ID.
+/// Removing an entry drops the watcher, which stops it.
+#[derive(Default)]
+pub struct FileWatches(Mutex<HashMap<String, RecommendedWatcher>>);
+
+/// Emit `file://changed` once per burst of changes to `file`, after `debounce`
+/// has passed without another one
+fn forward_file_changes(
+    rx: mpsc::Receiver<Result<Event, notify::Error>>,
+    app: tauri::AppHandle,
+    watch_id: String,
+    file: PathBuf,
+    debounce: Duration,
+) {
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let next = match deadline {
+            Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(Ok(event)) => {
+                let changed = (event.kind.is_modify() || event.kind.is_create())
+                    && event.paths.iter().any(|p| p == &file);
+                if changed {
+                    deadline = Some(Instant::now() + debounce);
+                }
             }
+            Ok(Err(_)) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                deadline = None;
+                let timestamp = std::time::SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let _ = app.emit(
+                    "file://changed",
+                    serde_json::json!({
+                        "watch_id": watch_id,
+                        "path": file.to_string_lossy(),
+                        "timestamp": timestamp,
+                    }),
+                );
+            }
+            // The watcher was dropped by `unwatch_file`
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
     }
+}
+
+#[tauri::command]
+pub fn watch_file(
+    app: tauri::AppHandle,
+    watches: State<'_, FileWatches>,
+    path: String,
+    debounce_ms: Option<u64>,
+) -> Result<String, String> {
+    let file = PathBuf::from(&path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    if !file.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    // Editors often save by replacing the file, which ends a watch on the file
+    // itself, so watch its directory and filter for the file's path instead
+    let dir = file
+        .parent()
+        .ok_or_else(|| format!("File has no parent directory: {}", path))?
+        .to_path_buf();
+
+    let (tx, rx) = mpsc::channel::<Result<Event, notify::Error>>();
+    let mut watcher = RecommendedWatcher::new(tx, Config::default())
+        .map_err(|e| format!("watcher init failed: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("watch path failed: {}", e))?;
+
+    let watch_id = uuid::Uuid::new_v4().to_string();
+    watches
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(watch_id.clone(), watcher);
+
+    let debounce = debounce_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_FILE_WATCH_DEBOUNCE);
+    let id = watch_id.clone();
+    std::thread::spawn(move || forward_file_changes(rx, app, id, file, debounce));
+
+    Ok(watch_id)
+}
+
+#[tauri::command]
+pub fn unwatch_file(watches: State<'_, FileWatches>, watch_id: String) -> Result<(), String> {
+    let watcher = watches
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&watch_id)
+        .ok_or_else(|| format!("No file watch with ID {}", watch_id))?;
+    drop(watcher);
     Ok(())
 }
 
@@ -434,20 +632,32 @@ pub fn search_project_files(
     query: String,
     case_sensitive: Option<bool>,
     max_results: Option<usize>,
+    filter_modified_after: Option<u64>,
+    state: State<'_, ProjectState>,
 ) -> Result<SearchResult, String> {
     if query.trim().is_empty() {
         return Err("Search query cannot be empty".to_string());
     }
 
-    // Get the current project root from the file watcher
-    // For now, we'll use the current working directory if no project is set
-    let project_root =
-        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
+    // Search the open project, falling back to the current working directory
+    let project_root = match state.root() {
+        Ok(root) => root,
+        Err(_) => std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?,
+    };
+    let gitignore = state.gitignore()?;
 
     let case_sensitive = case_sensitive.unwrap_or(false);
     let max_results = max_results.unwrap_or(100);
 
-    walk_directory(&project_root, &query, case_sensitive, max_results)
+    walk_directory(
+        &project_root,
+        gitignore.as_ref(),
+        &query,
+        case_sensitive,
+        max_results,
+        filter_modified_after,
+    )
 }
 
 #[tauri::command]
@@ -456,6 +666,8 @@ pub fn search_project_files_in_path(
     query: String,
     case_sensitive: Option<bool>,
     max_results: Option<usize>,
+    filter_modified_after: Option<u64>,
+    state: State<'_, ProjectState>,
 ) -> Result<SearchResult, String> {
     if query.trim().is_empty() {
         return Err("Search query cannot be empty".to_string());
@@ -469,11 +681,445 @@ pub fn search_project_files_in_path(
     let case_sensitive = case_sensitive.unwrap_or(false);
     let max_results = max_results.unwrap_or(100);
 
-    walk_directory(&search_path, &query, case_sensitive, max_results)
+    let gitignore = state.gitignore()?;
+    walk_directory(
+        &search_path,
+        gitignore.as_ref(),
+        &query,
+        case_sensitive,
+        max_results,
+        filter_modified_after,
+    )
+}
+
+/// Heap entry ordered by its ranking key, with the path as a tie-breaker
+struct RankedFile {
+    key: u64,
+    stat: FileStat,
+}
+
+impl PartialEq for RankedFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for RankedFile {}
+
+impl PartialOrd for RankedFile {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedFile {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key
+            .cmp(&other.key)
+            .then_with(|| self.stat.path.cmp(&other.stat.path))
+    }
+}
+
+/// Call `visit` for every regular file under `root`, skipping ignored paths.
+/// Unreadable subdirectories are skipped; only an unreadable root is an error.
+fn for_each_file<F>(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+    mut visit: F,
+) -> Result<(), String>
+where
+    F: FnMut(PathBuf),
+{
+    if !root.is_dir() {
+        return Err("path does not exist or is not a directory".into());
+    }
+
+    let mut pending = vec![root.clone()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == *root => return Err(format!("Failed to read directory: {}", e)),
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if should_ignore_path(&path, root, gitignore) {
+                continue;
+            }
+            // file_type() does not follow symlinks, which avoids walking into cycles
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                visit(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Walk `root` (skipping ignored paths) and keep the `limit` files accepted by
+/// `include` with the largest key, returned highest first
+fn top_files_by<I, F>(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+    limit: usize,
+    include: I,
+    key: F,
+) -> Result<Vec<FileStat>, String>
+where
+    I: Fn(&PathBuf) -> bool,
+    F: Fn(&FileStat) -> u64,
+{
+    if !root.is_dir() {
+        return Err("path does not exist or is not a directory".into());
+    }
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Min-heap of the best `limit` entries seen so far
+    let mut heap: BinaryHeap<Reverse<RankedFile>> = BinaryHeap::with_capacity(limit + 1);
+    for_each_file(root, gitignore, |path| {
+        if !include(&path) {
+            return;
+        }
+        let (size, modified_at) = file_metadata(&path);
+        let stat = FileStat {
+            path: path.to_string_lossy().to_string(),
+            size_bytes: size.unwrap_or(0),
+            extension: get_file_type(&path),
+            modified_at,
+        };
+        heap.push(Reverse(RankedFile {
+            key: key(&stat),
+            stat,
+        }));
+        if heap.len() > limit {
+            heap.pop();
+        }
+    })?;
+
+    // Ascending order of Reverse is descending order of the key
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.stat)
+        .collect())
+}
+
+fn largest_files(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+    limit: usize,
+) -> Result<Vec<FileStat>, String> {
+    top_files_by(root, gitignore, limit, |_| true, |stat| stat.size_bytes)
+}
+
+fn recently_modified_files(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+    limit: usize,
+) -> Result<Vec<FileStat>, String> {
+    top_files_by(
+        root,
+        gitignore,
+        limit,
+        |_| true,
+        |stat| stat.modified_at.unwrap_or(0),
+    )
+}
+
+/// Used by `list_files_by_extension` when `max_results` is not given
+const DEFAULT_EXTENSION_LIST_LIMIT: usize = 1000;
+
+/// Paths of files whose extension is in `extensions` (case-insensitive, leading
+/// dot optional), most recently modified first
+fn files_by_extension(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+    extensions: &[String],
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let wanted: Vec<String> = extensions
+        .iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect();
+    let include = |path: &PathBuf| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| wanted.contains(&ext.to_lowercase()))
+    };
+
+    let files = top_files_by(root, gitignore, limit, include, |stat| {
+        stat.modified_at.unwrap_or(0)
+    })?;
+    Ok(files.into_iter().map(|stat| stat.path).collect())
+}
+
+/// Number of files per lowercase extension; files without one count as "unknown"
+fn extension_counts(
+    root: &PathBuf,
+    gitignore: Option<&Gitignore>,
+) -> Result<HashMap<String, usize>, String> {
+    let mut counts = HashMap::new();
+    for_each_file(root, gitignore, |path| {
+        *counts.entry(get_file_type(&path)).or_insert(0) += 1;
+    })?;
+    Ok(counts)
+}
+
+#[tauri::command]
+pub fn get_largest_files(
+    path: String,
+    limit: usize,
+    state: State<'_, ProjectState>,
+) -> Result<Vec<FileStat>, String> {
+    let gitignore = state.gitignore()?;
+    largest_files(&PathBuf::from(path), gitignore.as_ref(), limit)
+}
+
+#[tauri::command]
+pub fn get_recently_modified_files(
+    path: String,
+    limit: usize,
+    state: State<'_, ProjectState>,
+) -> Result<Vec<FileStat>, String> {
+    let gitignore = state.gitignore()?;
+    recently_modified_files(&PathBuf::from(path), gitignore.as_ref(), limit)
+}
+
+#[tauri::command]
+pub fn list_files_by_extension(
+    path: String,
+    extensions: Vec<String>,
+    max_results: Option<usize>,
+    state: State<'_, ProjectState>,
+) -> Result<Vec<String>, String> {
+    let gitignore = state.gitignore()?;
+    files_by_extension(
+        &PathBuf::from(path),
+        gitignore.as_ref(),
+        &extensions,
+        max_results.unwrap_or(DEFAULT_EXTENSION_LIST_LIMIT),
+    )
+}
+
+#[tauri::command]
+pub fn count_files_by_extension(
+    path: String,
+    state: State<'_, ProjectState>,
+) -> Result<HashMap<String, usize>, String> {
+    let gitignore = state.gitignore()?;
+    extension_counts(&PathBuf::from(path), gitignore.as_ref())
+}
+
+/// Resolve `path` (absolute or relative to `root`) and ensure it stays inside `root`.
+/// The path and any of its parent directories may not exist yet; the deepest existing
+/// ancestor is canonicalized so symlinks can't escape the root.
+fn resolve_within_root(root: &PathBuf, path: &str) -> Result<PathBuf, String> {
+    let invalid = || format!("Invalid path: {}", path);
+    let candidate = root.join(path);
+    let file_name = candidate.file_name().ok_or_else(invalid)?;
+
+    let mut ancestor = candidate.parent().ok_or_else(invalid)?;
+    let mut missing = Vec::new();
+    while !ancestor.exists() {
+        missing.push(ancestor.file_name().ok_or_else(invalid)?);
+        ancestor = ancestor.parent().ok_or_else(invalid)?;
+    }
+
+    let mut resolved = ancestor
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    resolved.extend(missing.iter().rev());
+    resolved.push(file_name);
+
+    if !resolved.starts_with(root) || resolved == *root {
+        return Err(format!("Path is outside the project root: {}", path));
+    }
+    Ok(resolved)
+}
+
+/// Like `resolve_within_root`, but for paths that will be written to. Writing follows a
+/// symlink at the final component, so an existing link must also resolve inside the root.
+fn resolve_write_target(root: &PathBuf, path: &str) -> Result<PathBuf, String> {
+    let target = resolve_within_root(root, path)?;
+    let is_symlink = fs::symlink_metadata(&target)
+        .map(|meta| meta.file_type().is_symlink())
+        .unwrap_or(false);
+    if is_symlink {
+        // A dangling link fails to canonicalize, and writing would create its destination
+        let linked = target
+            .canonicalize()
+            .map_err(|_| format!("Path is outside the project root: {}", path))?;
+        if !linked.starts_with(root) || linked == *root {
+            return Err(format!("Path is outside the project root: {}", path));
+        }
+    }
+    Ok(target)
+}
+
+#[tauri::command]
+pub fn rename_project_file(
+    old_path: String,
+    new_path: String,
+    state: State<'_, ProjectState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let root = state.root()?;
+    let from = resolve_within_root(&root, &old_path)?;
+    let to = resolve_within_root(&root, &new_path)?;
+
+    if !from.exists() {
+        return Err(format!("File does not exist: {}", old_path));
+    }
+    if to.exists() {
+        return Err(format!("Destination already exists: {}", new_path));
+    }
+
+    fs::rename(&from, &to).map_err(|e| format!("Failed to rename: {}", e))?;
+
+    let _ = app.emit(
+        "project://file-renamed",
+        serde_json::json!({
+            "old": from.to_string_lossy(),
+            "new": to.to_string_lossy(),
+        }),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_project_file(
+    path: String,
+    to_trash: bool,
+    state: State<'_, ProjectState>,
+) -> Result<(), String> {
+    let root = state.root()?;
+    let target = resolve_within_root(&root, &path)?;
+
+    if !target.exists() {
+        return Err(format!("File does not exist: {}", path));
+    }
+
+    if to_trash {
+        trash::delete(&target).map_err(|e| format!("Failed to move to trash: {}", e))
+    } else if target.is_dir() {
+        fs::remove_dir_all(&target).map_err(|e| format!("Failed to delete: {}", e))
+    } else {
+        fs::remove_file(&target).map_err(|e| format!("Failed to delete: {}", e))
+    }
+}
+
+const RUST_STRUCT_TEMPLATE: &str = "#[derive(Debug, Clone)]
+pub struct {{name}} {}
+
+impl {{name}} {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+";
+
+const PYTHON_CLASS_TEMPLATE: &str = "class {{name}}:
+    def __init__(self):
+        pass
+";
+
+const REACT_COMPONENT_TEMPLATE: &str = "interface {{name}}Props {}
+
+export default function {{name}}({}: {{name}}Props) {
+  return <div>{{name}}</div>;
+}
+";
+
+/// Look up a built-in template by name, falling back to one matching the file extension
+fn template_for(template: Option<&str>, extension: &str) -> Result<Option<&'static str>, String> {
+    match template {
+        Some("rust-struct") => Ok(Some(RUST_STRUCT_TEMPLATE)),
+        Some("python-class") => Ok(Some(PYTHON_CLASS_TEMPLATE)),
+        Some("react-component") => Ok(Some(REACT_COMPONENT_TEMPLATE)),
+        Some(other) => Err(format!(
+            "Unknown template '{}'. Available: rust-struct, python-class, react-component",
+            other
+        )),
+        None => Ok(match extension {
+            "rs" => Some(RUST_STRUCT_TEMPLATE),
+            "py" => Some(PYTHON_CLASS_TEMPLATE),
+            "tsx" | "jsx" => Some(REACT_COMPONENT_TEMPLATE),
+            _ => None,
+        }),
+    }
+}
+
+/// Convert a file stem like `user_profile` or `user-profile` to `UserProfile`
+fn to_pascal_case(stem: &str) -> String {
+    stem.split(['_', '-', ' ', '.'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn create_project_file(
+    path: String,
+    content: Option<String>,
+    template: Option<String>,
+    overwrite: Option<bool>,
+    state: State<'_, ProjectState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let root = state.root()?;
+    let target = resolve_write_target(&root, &path)?;
+
+    if target.is_dir() {
+        return Err(format!("Path is a directory: {}", path));
+    }
+    if target.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("File already exists: {}", path));
+    }
+
+    // Explicit content wins; otherwise render a template (or leave the file empty)
+    let body = match content {
+        Some(content) => content,
+        None => {
+            let name = target
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(to_pascal_case)
+                .unwrap_or_default();
+            template_for(template.as_deref(), &get_file_type(&target))?
+                .map(|t| t.replace("{{name}}", &name))
+                .unwrap_or_default()
+        }
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directories: {}", e))?;
+    }
+    fs::write(&target, body).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let _ = app.emit(
+        "project://file-created",
+        serde_json::json!({ "path": target.to_string_lossy() }),
+    );
+    Ok(())
 }
 
 // Project type detection
-use crate::project::ProjectInfo;
+use crate::project::{ProjectInfo, ProjectType};
 
 #[tauri::command]
 pub async fn detect_project_type(path: Option<String>) -> Result<ProjectInfo, String> {
@@ -485,3 +1131,1049 @@ pub async fn detect_project_type(path: Option<String>) -> Result<ProjectInfo, St
 
     Ok(ProjectInfo::detect(&project_path))
 }
+
+// Building and testing
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuildResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+fn resolve_project_dir(path: Option<String>) -> Result<PathBuf, String> {
+    let dir = match path {
+        Some(p) => PathBuf::from(p),
+        None => std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?,
+    };
+    if !dir.is_dir() {
+        return Err("path does not exist or is not a directory".into());
+    }
+    Ok(dir)
+}
+
+/// Build tool for a Java project, shared by the build and test commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JavaBuildTool {
+    GradleWrapper,
+    Maven,
+    Gradle,
+}
+
+/// Prefer the project's own Gradle wrapper, then Maven when there is a `pom.xml`,
+/// then a system Gradle
+fn java_build_tool(dir: &std::path::Path) -> JavaBuildTool {
+    if dir.join("gradlew").exists() {
+        JavaBuildTool::GradleWrapper
+    } else if dir.join("pom.xml").exists() {
+        JavaBuildTool::Maven
+    } else {
+        JavaBuildTool::Gradle
+    }
+}
+
+/// Build command for the detected project type. Java and Python pick the tool
+/// from the marker file that is present.
+fn default_build_command(dir: &std::path::Path) -> Option<String> {
+    let command = match ProjectInfo::detect(dir).project_type {
+        ProjectType::Rust => "cargo build",
+        ProjectType::Node => "npm run build",
+        ProjectType::Python if dir.join("setup.py").exists() => "python setup.py build",
+        ProjectType::Python => "python -m build",
+        ProjectType::Go => "go build ./...",
+        ProjectType::Java => match java_build_tool(dir) {
+            JavaBuildTool::GradleWrapper => "./gradlew build",
+            JavaBuildTool::Maven => "mvn package",
+            JavaBuildTool::Gradle => "gradle build",
+        },
+        ProjectType::Ruby => "bundle exec rake build",
+        ProjectType::CSharp => "dotnet build",
+        ProjectType::Elixir => "mix compile",
+        ProjectType::Haskell => "cabal build",
+        ProjectType::Zig => "zig build",
+        ProjectType::Php | ProjectType::Unknown => return None,
+    };
+    Some(command.to_string())
+}
+
+/// Run `command` through `sh -c` in `dir` and wait for it to finish
+fn run_shell_command(dir: &std::path::Path, command: &str) -> Result<BuildResult, String> {
+    let start = std::time::Instant::now();
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("failed to run '{}': {}", command, e))?;
+
+    Ok(BuildResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        success: output.status.success(),
+    })
+}
+
+/// Build the project at `path` (defaults to the current directory) with the
+/// command detected from its type, or `command_override` when given.
+/// Emits `build://started` and `build://complete`.
+#[tauri::command]
+pub async fn build_project(
+    path: Option<String>,
+    command_override: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<BuildResult, String> {
+    let dir = resolve_project_dir(path)?;
+    let command = match command_override.filter(|c| !c.trim().is_empty()) {
+        Some(command) => command,
+        None => default_build_command(&dir).ok_or_else(|| {
+            "No default build command for this project type; pass a command override".to_string()
+        })?,
+    };
+
+    let path = dir.to_string_lossy().to_string();
+    let _ = app.emit(
+        "build://started",
+        serde_json::json!({ "path": path, "command": command }),
+    );
+
+    let result = tauri::async_runtime::spawn_blocking(move || run_shell_command(&dir, &command))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let success = result.as_ref().map(|r| r.success).unwrap_or(false);
+    let _ = app.emit(
+        "build://complete",
+        serde_json::json!({ "path": path, "success": success }),
+    );
+    result
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TestCounts {
+    pub passed: u64,
+    pub failed: u64,
+    pub skipped: u64,
+}
+
+/// Test runner program and arguments for the detected project type. `filter` is
+/// passed the way each runner expects to narrow the run to matching tests.
+fn default_test_command(
+    dir: &std::path::Path,
+    filter: Option<&str>,
+) -> Option<(String, Vec<String>)> {
+    let (program, mut args, filter_flag): (&str, Vec<&str>, Option<&str>) =
+        match ProjectInfo::detect(dir).project_type {
+            ProjectType::Rust => ("cargo", vec!["test"], None),
+            ProjectType::Node => ("npm", vec!["test", "--"], None),
+            ProjectType::Python => ("pytest", vec![], Some("-k")),
+            ProjectType::Go => ("go", vec!["test", "./..."], Some("-run")),
+            ProjectType::Java => match java_build_tool(dir) {
+                JavaBuildTool::GradleWrapper => ("./gradlew", vec!["test"], Some("--tests")),
+                JavaBuildTool::Maven => ("mvn", vec!["test"], None),
+                JavaBuildTool::Gradle => ("gradle", vec!["test"], Some("--tests")),
+            },
+            ProjectType::Ruby => ("bundle", vec!["exec", "rspec"], Some("-e")),
+            ProjectType::Php => ("vendor/bin/phpunit", vec![], Some("--filter")),
+            ProjectType::CSharp => ("dotnet", vec!["test"], Some("--filter")),
+            ProjectType::Elixir => ("mix", vec!["test"], None),
+            ProjectType::Haskell => ("cabal", vec!["test"], None),
+            ProjectType::Zig => ("zig", vec!["build", "test"], None),
+            ProjectType::Unknown => return None,
+        };
+
+    let mut filter_arg = None;
+    if let Some(filter) = filter.filter(|f| !f.trim().is_empty()) {
+        if program == "mvn" {
+            filter_arg = Some(format!("-Dtest={}", filter));
+        } else {
+            args.extend(filter_flag);
+            filter_arg = Some(filter.to_string());
+        }
+    }
+    if program == "npm" && filter_arg.is_none() {
+        args.pop();
+    }
+
+    let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+    args.extend(filter_arg);
+    Some((program.to_string(), args))
+}
+
+/// Add up `<n> passed`, `<n> failed` style pairs from one summary line
+fn add_word_counts(line: &str, counts: &mut TestCounts) {
+    let words: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '=')
+        .filter(|w| !w.is_empty())
+        .collect();
+    for pair in words.windows(2) {
+        let Ok(n) = pair[0].parse::<u64>() else {
+            continue;
+        };
+        let word = pair[1].trim_end_matches('.').to_lowercase();
+        match word.as_str() {
+            "passed" => counts.passed += n,
+            "failed" | "errors" | "error" => counts.failed += n,
+            "skipped" | "ignored" => counts.skipped += n,
+            _ => {}
+        }
+    }
+}
+
+/// Best-effort pass/fail/skip counts from cargo, go, Maven, pytest or Jest output
+fn parse_test_counts(output: &str) -> TestCounts {
+    let mut counts = TestCounts::default();
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+
+    // cargo prints one summary per test binary
+    let cargo: Vec<&&str> = lines
+        .iter()
+        .filter(|l| l.starts_with("test result:"))
+        .collect();
+    if !cargo.is_empty() {
+        for line in cargo {
+            add_word_counts(line, &mut counts);
+        }
+        return counts;
+    }
+
+    // Maven: "Tests run: 5, Failures: 1, Errors: 0, Skipped: 1"
+    if let Some(line) = lines.iter().rev().find(|l| l.contains("Tests run:")) {
+        let number_after = |label: &str| -> u64 {
+            line.split(label)
+                .nth(1)
+                .and_then(|rest| rest.trim().split(',').next())
+                .and_then(|n| n.trim().parse().ok())
+                .unwrap_or(0)
+        };
+        let run = number_after("Tests run:");
+        counts.failed = number_after("Failures:") + number_after("Errors:");
+        counts.skipped = number_after("Skipped:");
+        counts.passed = run.saturating_sub(counts.failed + counts.skipped);
+        return counts;
+    }
+
+    // go test -v
+    let go_count = |prefix: &str| lines.iter().filter(|l| l.starts_with(prefix)).count() as u64;
+    let go = TestCounts {
+        passed: go_count("--- PASS"),
+        failed: go_count("--- FAIL"),
+        skipped: go_count("--- SKIP"),
+    };
+    if go != TestCounts::default() {
+        return go;
+    }
+
+    // pytest and Jest end with a summary like "3 passed, 1 skipped"
+    if let Some(line) = lines
+        .iter()
+        .rev()
+        .find(|l| l.contains(" passed") || l.contains(" failed"))
+    {
+        add_word_counts(line, &mut counts);
+    }
+    counts
+}
+
+/// Forward each line of a test runner pipe as a `test-output-chunk` event,
+/// collecting it into `output` for the final summary
+fn stream_test_output<R: std::io::Read + Send + 'static>(
+    pipe: R,
+    stream: &'static str,
+    app: &tauri::AppHandle,
+    session_id: &str,
+    output: &Arc<Mutex<String>>,
+) -> std::thread::JoinHandle<()> {
+    use std::io::{BufRead, BufReader};
+
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    let output = output.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if let Ok(mut output) = output.lock() {
+                output.push_str(&line);
+                output.push('\n');
+            }
+            let _ = app.emit(
+                "test-output-chunk",
+                serde_json::json!({ "session_id": session_id, "stream": stream, "chunk": line }),
+            );
+        }
+    })
+}
+
+/// Run the project's tests in the background (defaults to the current directory)
+/// and return a session ID. Output lines arrive via `test-output-chunk`, then
+/// `test-output-end` and `test://result` with the parsed counts.
+#[tauri::command]
+pub async fn run_project_tests(
+    path: Option<String>,
+    test_filter: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    use std::process::{Command, Stdio};
+
+    let dir = resolve_project_dir(path)?;
+    let (program, args) = default_test_command(&dir, test_filter.as_deref())
+        .ok_or_else(|| "No default test command for this project type".to_string())?;
+
+    let mut child = Command::new(&program)
+        .args(&args)
+        .current_dir(&dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let session = session_id.clone();
+
+    std::thread::spawn(move || {
+        let output = Arc::new(Mutex::new(String::new()));
+        let readers = [
+            stdout.map(|pipe| stream_test_output(pipe, "stdout", &app, &session, &output)),
+            stderr.map(|pipe| stream_test_output(pipe, "stderr", &app, &session, &output)),
+        ];
+
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+        let exit_code = child.wait().ok().and_then(|status| status.code());
+
+        let counts = output
+            .lock()
+            .map(|output| parse_test_counts(&output))
+            .unwrap_or_default();
+        let _ = app.emit(
+            "test-output-end",
+            serde_json::json!({ "session_id": session, "exit_code": exit_code }),
+        );
+        let _ = app.emit(
+            "test://result",
+            serde_json::json!({
+                "session_id": session,
+                "passed": counts.passed,
+                "failed": counts.failed,
+                "skipped": counts.skipped,
+            }),
+        );
+    });
+
+    Ok(session_id)
+}
+
+// Dependency audits
+
+/// Longest an `npm audit` or `cargo audit` run may take
+const AUDIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One advisory affecting a dependency. `severity` is critical, high, moderate,
+/// low or unknown.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Vulnerability {
+    pub package: String,
+    pub version: Option<String>,
+    pub severity: String,
+    pub title: String,
+    pub advisory_id: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VulnerabilityReport {
+    pub project_type: ProjectType,
+    pub vulnerabilities: Vec<Vulnerability>,
+    pub critical_count: usize,
+    pub high_count: usize,
+    pub moderate_count: usize,
+}
+
+impl VulnerabilityReport {
+    fn new(project_type: ProjectType, vulnerabilities: Vec<Vulnerability>) -> Self {
+        let count = |severity: &str| {
+            vulnerabilities
+                .iter()
+                .filter(|v| v.severity == severity)
+                .count()
+        };
+        Self {
+            critical_count: count("critical"),
+            high_count: count("high"),
+            moderate_count: count("moderate"),
+            project_type,
+            vulnerabilities,
+        }
+    }
+}
+
+/// Advisories from `npm audit --json` (npm 7+). Packages that are only vulnerable
+/// through a dependency are skipped; the dependency carries the advisory.
+fn parse_npm_audit(json: &serde_json::Value) -> Vec<Vulnerability> {
+    let Some(packages) = json["vulnerabilities"].as_object() else {
+        return Vec::new();
+    };
+    packages
+        .iter()
+        .flat_map(|(name, entry)| {
+            entry["via"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|via| via.is_object())
+                .map(move |via| Vulnerability {
+                    package: name.clone(),
+                    version: entry["range"].as_str().map(String::from),
+                    severity: via["severity"]
+                        .as_str()
+                        .or(entry["severity"].as_str())
+                        .unwrap_or("unknown")
+                        .to_string(),
+                    title: via["title"].as_str().unwrap_or_default().to_string(),
+                    advisory_id: via["source"].as_u64().map(|id| id.to_string()),
+                    url: via["url"].as_str().map(String::from),
+                })
+        })
+        .collect()
+}
+
+/// Severity band of a CVSS v3 base vector such as `CVSS:3.1/AV:N/AC:L/...`
+fn cvss_severity(vector: &str) -> Option<&'static str> {
+    let metrics: HashMap<&str, &str> = vector
+        .split('/')
+        .skip(1)
+        .filter_map(|m| m.split_once(':'))
+        .collect();
+    let changed = *metrics.get("S")? == "C";
+    let weight = |metric: &str, table: &[(&str, f64)]| -> Option<f64> {
+        let value = metrics.get(metric)?;
+        table.iter().find(|(k, _)| k == value).map(|(_, w)| *w)
+    };
+    let impact_table = [("H", 0.56), ("L", 0.22), ("N", 0.0)];
+    let privileges = if changed {
+        [("N", 0.85), ("L", 0.68), ("H", 0.5)]
+    } else {
+        [("N", 0.85), ("L", 0.62), ("H", 0.27)]
+    };
+
+    let iss = 1.0
+        - (1.0 - weight("C", &impact_table)?)
+            * (1.0 - weight("I", &impact_table)?)
+            * (1.0 - weight("A", &impact_table)?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    let exploitability = 8.22
+        * weight("AV", &[("N", 0.85), ("A", 0.62), ("L", 0.55), ("P", 0.2)])?
+        * weight("AC", &[("L", 0.77), ("H", 0.44)])?
+        * weight("PR", &privileges)?
+        * weight("UI", &[("N", 0.85), ("R", 0.62)])?;
+
+    let score = if impact <= 0.0 {
+        0.0
+    } else {
+        let raw = impact + exploitability;
+        let raw = if changed { raw * 1.08 } else { raw };
+        (raw.min(10.0) * 10.0).ceil() / 10.0
+    };
+    Some(match score {
+        s if s >= 9.0 => "critical",
+        s if s >= 7.0 => "high",
+        s if s >= 4.0 => "moderate",
+        _ => "low",
+    })
+}
+
+/// Advisories from `cargo audit --json`, rated from their CVSS vector when present
+fn parse_cargo_audit(json: &serde_json::Value) -> Vec<Vulnerability> {
+    json["vulnerabilities"]["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            let advisory = &entry["advisory"];
+            Vulnerability {
+                package: entry["package"]["name"]
+                    .as_str()
+                    .or(advisory["package"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                version: entry["package"]["version"].as_str().map(String::from),
+                severity: advisory["cvss"]
+                    .as_str()
+                    .and_then(cvss_severity)
+                    .unwrap_or("unknown")
+                    .to_string(),
+                title: advisory["title"].as_str().unwrap_or_default().to_string(),
+                advisory_id: advisory["id"].as_str().map(String::from),
+                url: advisory["url"].as_str().map(String::from),
+            }
+        })
+        .collect()
+}
+
+fn read_pipe<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut text);
+        }
+        text
+    })
+}
+
+/// Run `program` in `dir` and return its stdout and stderr, killing it once
+/// `timeout` passes. A non-zero exit is not an error; audit tools use it to
+/// signal that vulnerabilities were found.
+fn output_with_timeout(
+    dir: &std::path::Path,
+    program: &str,
+    args: &[&str],
+    timeout: Duration,
+) -> Result<(String, String), String> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    // Read both pipes while waiting so large reports can't fill them and block the child
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let start = Instant::now();
+    while child
+        .try_wait()
+        .map_err(|e| format!("failed to poll {}: {}", program, e))?
+        .is_none()
+    {
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!(
+                "{} timed out after {}s",
+                program,
+                timeout.as_secs()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok((
+        stdout.join().unwrap_or_default(),
+        stderr.join().unwrap_or_default(),
+    ))
+}
+
+/// Audit the project's dependencies for known vulnerabilities with `npm audit`
+/// (Node.js) or `cargo audit` (Rust, needs cargo-audit installed). Defaults to the
+/// current directory and gives up after 30 seconds.
+#[tauri::command]
+pub async fn get_project_vulnerabilities(
+    path: Option<String>,
+) -> Result<VulnerabilityReport, String> {
+    let dir = resolve_project_dir(path)?;
+    let project_type = ProjectInfo::detect(&dir).project_type;
+    let (program, args, parse): (
+        &str,
+        [&str; 2],
+        fn(&serde_json::Value) -> Vec<Vulnerability>,
+    ) = match project_type {
+        ProjectType::Node => ("npm", ["audit", "--json"], parse_npm_audit),
+        ProjectType::Rust => ("cargo", ["audit", "--json"], parse_cargo_audit),
+        _ => {
+            return Err(
+                "Vulnerability audits are only supported for Node.js and Rust projects".into(),
+            )
+        }
+    };
+
+    let (stdout, stderr) = tauri::async_runtime::spawn_blocking(move || {
+        output_with_timeout(&dir, program, &args, AUDIT_TIMEOUT)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let json: serde_json::Value = serde_json::from_str(&stdout)
+        .map_err(|_| format!("{} audit produced no report: {}", program, stderr.trim()))?;
+    Ok(VulnerabilityReport::new(project_type, parse(&json)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_walk_directory_reports_metadata_and_filters_by_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        fs::write(root.join("notes.txt"), "needle in a haystack\n").unwrap();
+
+        let result = walk_directory(&root, None, "needle", false, 10, None).unwrap();
+        assert_eq!(result.matches.len(), 1);
+        let m = &result.matches[0];
+        assert_eq!(m.file_size_bytes, Some(21));
+        assert!(m.modified_at.is_some());
+
+        let future = m.modified_at.unwrap() + 3600;
+        let filtered = walk_directory(&root, None, "needle", false, 10, Some(future)).unwrap();
+        assert!(filtered.matches.is_empty());
+        assert_eq!(filtered.total_files_searched, 0);
+    }
+
+    #[test]
+    fn test_resolve_within_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+
+        let inside = resolve_within_root(&root, "src/new.rs").unwrap();
+        assert_eq!(inside, root.join("src/new.rs"));
+        assert!(resolve_within_root(&root, "../escape.txt").is_err());
+        assert!(resolve_within_root(&root, "src/../../escape.txt").is_err());
+        assert!(resolve_within_root(&root, "/etc/passwd").is_err());
+
+        let nested = resolve_within_root(&root, "src/deep/er/mod.rs").unwrap();
+        assert_eq!(nested, root.join("src/deep/er/mod.rs"));
+        assert!(resolve_within_root(&root, "missing/../../escape.txt").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_write_target_rejects_symlinks_out_of_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        let root = root.canonicalize().unwrap();
+        let outside = temp_dir.path().join("outside.txt");
+        fs::write(&outside, "secret").unwrap();
+        fs::write(root.join("real.txt"), "inside").unwrap();
+
+        std::os::unix::fs::symlink(&outside, root.join("escape.txt")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("gone.txt"), root.join("dangling.txt"))
+            .unwrap();
+        std::os::unix::fs::symlink(root.join("real.txt"), root.join("alias.txt")).unwrap();
+
+        assert!(resolve_within_root(&root, "escape.txt").is_ok());
+        assert!(resolve_write_target(&root, "escape.txt").is_err());
+        assert!(resolve_write_target(&root, "dangling.txt").is_err());
+        assert_eq!(
+            resolve_write_target(&root, "alias.txt").unwrap(),
+            root.join("alias.txt")
+        );
+        assert_eq!(
+            resolve_write_target(&root, "new.txt").unwrap(),
+            root.join("new.txt")
+        );
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_templates() {
+        assert_eq!(to_pascal_case("user_profile"), "UserProfile");
+        assert_eq!(to_pascal_case("nav-bar"), "NavBar");
+
+        let rust = template_for(None, "rs").unwrap().unwrap();
+        assert!(rust
+            .replace("{{name}}", "Foo")
+            .contains("pub struct Foo {}"));
+        assert!(template_for(None, "txt").unwrap().is_none());
+        assert!(template_for(Some("python-class"), "rs")
+            .unwrap()
+            .unwrap()
+            .starts_with("class {{name}}:"));
+        assert!(template_for(Some("cobol-program"), "cbl").is_err());
+    }
+
+    #[test]
+    fn test_get_largest_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("small.txt"), vec![b'a'; 10]).unwrap();
+        fs::write(root.join("src/big.rs"), vec![b'a'; 1000]).unwrap();
+        fs::write(root.join("medium.md"), vec![b'a'; 100]).unwrap();
+
+        let files = largest_files(&root.to_path_buf(), None, 2).unwrap();
+        let sizes: Vec<u64> = files.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(sizes, vec![1000, 100]);
+        assert_eq!(files[0].extension, "rs");
+        assert!(files[0].path.ends_with("big.rs"));
+    }
+
+    #[test]
+    fn test_get_recently_modified_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for (i, name) in ["old.txt", "newest.txt", "newer.txt"].iter().enumerate() {
+            let path = root.join(name);
+            fs::write(&path, "x").unwrap();
+            let offset = [0, 120, 60][i];
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(base + std::time::Duration::from_secs(offset))
+                .unwrap();
+        }
+
+        let files = recently_modified_files(&root.to_path_buf(), None, 3).unwrap();
+        let names: Vec<&str> = files
+            .iter()
+            .map(|f| f.path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["newest.txt", "newer.txt", "old.txt"]);
+    }
+
+    #[test]
+    fn test_files_by_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for (name, offset) in [
+            ("src/lib.rs", 60),
+            ("src/MAIN.RS", 120),
+            ("build.rs", 0),
+            ("app.ts", 30),
+            ("README", 10),
+            ("target/generated.rs", 90),
+        ] {
+            let path = root.join(name);
+            fs::write(&path, "x").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(base + std::time::Duration::from_secs(offset))
+                .unwrap();
+        }
+        let root = root.to_path_buf();
+        let gitignore = build_gitignore(&["target/".to_string()], &root).unwrap();
+
+        let files = files_by_extension(&root, Some(&gitignore), &[".rs".to_string()], 10).unwrap();
+        let names: Vec<&str> = files
+            .iter()
+            .map(|f| f.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["MAIN.RS", "lib.rs", "build.rs"]);
+
+        let limited = files_by_extension(
+            &root,
+            Some(&gitignore),
+            &["rs".to_string(), "TS".to_string()],
+            2,
+        )
+        .unwrap();
+        assert_eq!(limited.len(), 2);
+        assert!(limited[1].ends_with("lib.rs"));
+
+        let counts = extension_counts(&root, Some(&gitignore)).unwrap();
+        assert_eq!(counts.get("rs"), Some(&3));
+        assert_eq!(counts.get("ts"), Some(&1));
+        assert_eq!(counts.get("unknown"), Some(&1));
+        assert_eq!(counts.values().sum::<usize>(), 5);
+
+        assert!(extension_counts(&root.join("missing"), None).is_err());
+    }
+
+    fn watcher_for(root: &std::path::Path) -> RecommendedWatcher {
+        let (tx, _rx) = mpsc::channel::<Result<Event, notify::Error>>();
+        let mut watcher = RecommendedWatcher::new(tx, Config::default()).unwrap();
+        watcher.watch(root, RecursiveMode::Recursive).unwrap();
+        watcher
+    }
+
+    #[test]
+    fn test_project_state_open_and_stop() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let state = ProjectState::default();
+        assert!(state.root().is_err());
+
+        let gitignore = build_gitignore(&["*.log".to_string()], &root).unwrap();
+        let previous = state
+            .open(root.clone(), gitignore, Some(watcher_for(&root)))
+            .unwrap();
+        assert!(previous.is_none());
+        assert_eq!(state.root().unwrap(), root);
+        assert!(state.lock().unwrap().watcher.is_some());
+
+        // Reopening hands back the old watcher instead of leaking it
+        let gitignore = build_gitignore(&[], &root).unwrap();
+        let previous = state
+            .open(root.clone(), gitignore, Some(watcher_for(&root)))
+            .unwrap();
+        assert!(previous.is_some());
+
+        state.stop_watch().unwrap();
+        assert!(state.lock().unwrap().watcher.is_none());
+        // Stopping keeps the root so file commands still work
+        assert_eq!(state.root().unwrap(), root);
+        state.stop_watch().unwrap();
+    }
+
+    #[test]
+    fn test_project_state_concurrent_open_and_stop() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let state = ProjectState::default();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let state = state.clone();
+                let root = root.clone();
+                std::thread::spawn(move || {
+                    if i % 2 == 0 {
+                        let gitignore = build_gitignore(&[], &root).unwrap();
+                        state
+                            .open(root.clone(), gitignore, Some(watcher_for(&root)))
+                            .unwrap();
+                    } else {
+                        state.stop_watch().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        state.stop_watch().unwrap();
+        assert!(state.lock().unwrap().watcher.is_none());
+        assert_eq!(state.root().unwrap(), root);
+    }
+
+    #[test]
+    fn test_search_respects_stored_ignore_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::write(root.join("notes.txt"), "needle\n").unwrap();
+        fs::write(root.join("debug.log"), "needle\n").unwrap();
+
+        let state = ProjectState::default();
+        let gitignore = build_gitignore(&["*.log".to_string()], &root).unwrap();
+        state.open(root.clone(), gitignore, None).unwrap();
+
+        let gitignore = state.gitignore().unwrap();
+        let result = walk_directory(&root, gitignore.as_ref(), "needle", false, 10, None).unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert!(result.matches[0].path.ends_with("notes.txt"));
+
+        state.clear_ignore_patterns().unwrap();
+        let gitignore = state.gitignore().unwrap();
+        let result = walk_directory(&root, gitignore.as_ref(), "needle", false, 10, None).unwrap();
+        assert_eq!(result.matches.len(), 2);
+    }
+
+    #[test]
+    fn test_default_build_command_and_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(default_build_command(root), None);
+
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        assert_eq!(default_build_command(root).as_deref(), Some("cargo build"));
+
+        let java = TempDir::new().unwrap();
+        fs::write(java.path().join("pom.xml"), "<project/>").unwrap();
+        assert_eq!(
+            default_build_command(java.path()).as_deref(),
+            Some("mvn package")
+        );
+        // A Gradle wrapper wins over pom.xml for both building and testing
+        fs::write(java.path().join("gradlew"), "").unwrap();
+        assert_eq!(
+            default_build_command(java.path()).as_deref(),
+            Some("./gradlew build")
+        );
+        assert_eq!(
+            default_test_command(java.path(), None).unwrap().0,
+            "./gradlew"
+        );
+
+        let ok = run_shell_command(root, "echo built; echo warn >&2").unwrap();
+        assert!(ok.success);
+        assert_eq!(ok.exit_code, Some(0));
+        assert_eq!(ok.stdout.trim(), "built");
+        assert_eq!(ok.stderr.trim(), "warn");
+
+        let failed = run_shell_command(root, "exit 3").unwrap();
+        assert!(!failed.success);
+        assert_eq!(failed.exit_code, Some(3));
+    }
+
+    #[test]
+    fn test_default_test_command_applies_filter() {
+        let rust = TempDir::new().unwrap();
+        fs::write(
+            rust.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            default_test_command(rust.path(), Some("parses_")),
+            Some((
+                "cargo".to_string(),
+                vec!["test".to_string(), "parses_".to_string()]
+            ))
+        );
+
+        let python = TempDir::new().unwrap();
+        fs::write(python.path().join("requirements.txt"), "").unwrap();
+        let (program, args) = default_test_command(python.path(), Some("login")).unwrap();
+        assert_eq!(program, "pytest");
+        assert_eq!(args, vec!["-k", "login"]);
+
+        let node = TempDir::new().unwrap();
+        fs::write(node.path().join("package.json"), "{}").unwrap();
+        assert_eq!(
+            default_test_command(node.path(), None).unwrap().1,
+            vec!["test"]
+        );
+        assert_eq!(
+            default_test_command(node.path(), Some("Button")).unwrap().1,
+            vec!["test", "--", "Button"]
+        );
+
+        let empty = TempDir::new().unwrap();
+        assert!(default_test_command(empty.path(), None).is_none());
+    }
+
+    #[test]
+    fn test_parse_test_counts() {
+        let cargo = "test result: ok. 5 passed; 1 failed; 2 ignored; 0 measured\n\
+                     test result: ok. 3 passed; 0 failed; 0 ignored; 0 measured";
+        assert_eq!(
+            parse_test_counts(cargo),
+            TestCounts {
+                passed: 8,
+                failed: 1,
+                skipped: 2
+            }
+        );
+
+        let pytest = "tests/test_a.py ..s.F\n==== 3 passed, 1 failed, 1 skipped in 0.12s ====";
+        assert_eq!(
+            parse_test_counts(pytest),
+            TestCounts {
+                passed: 3,
+                failed: 1,
+                skipped: 1
+            }
+        );
+
+        let jest = "Test Suites: 1 failed, 1 total\nTests:       1 failed, 4 passed, 5 total";
+        assert_eq!(parse_test_counts(jest).passed, 4);
+
+        let go = "=== RUN   TestA\n--- PASS: TestA (0.00s)\n--- FAIL: TestB (0.00s)\nFAIL";
+        assert_eq!(
+            parse_test_counts(go),
+            TestCounts {
+                passed: 1,
+                failed: 1,
+                skipped: 0
+            }
+        );
+
+        let maven = "Tests run: 6, Failures: 1, Errors: 1, Skipped: 1";
+        assert_eq!(
+            parse_test_counts(maven),
+            TestCounts {
+                passed: 3,
+                failed: 2,
+                skipped: 1
+            }
+        );
+    }
+
+    #[test]
+    fn parses_npm_and_cargo_audit_reports() {
+        let npm = serde_json::json!({
+            "auditReportVersion": 2,
+            "vulnerabilities": {
+                "lodash": {
+                    "name": "lodash",
+                    "severity": "critical",
+                    "via": [{
+                        "source": 1096305,
+                        "name": "lodash",
+                        "title": "Prototype Pollution in lodash",
+                        "url": "https://github.com/advisories/GHSA-jf85-cpcp-j695",
+                        "severity": "critical",
+                        "range": "<4.17.12"
+                    }],
+                    "range": "<=4.17.20"
+                },
+                "some-wrapper": {
+                    "name": "some-wrapper",
+                    "severity": "critical",
+                    "via": ["lodash"],
+                    "range": "1.0.0"
+                }
+            }
+        });
+        let vulnerabilities = parse_npm_audit(&npm);
+        assert_eq!(vulnerabilities.len(), 1);
+        assert_eq!(vulnerabilities[0].package, "lodash");
+        assert_eq!(vulnerabilities[0].advisory_id.as_deref(), Some("1096305"));
+
+        let cargo = serde_json::json!({
+            "vulnerabilities": {
+                "found": true,
+                "count": 2,
+                "list": [
+                    {
+                        "advisory": {
+                            "id": "RUSTSEC-2021-0001",
+                            "package": "hyper",
+                            "title": "Request smuggling",
+                            "url": "https://example.com/hyper",
+                            "cvss": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:H/A:N"
+                        },
+                        "package": { "name": "hyper", "version": "0.14.9" }
+                    },
+                    {
+                        "advisory": { "id": "RUSTSEC-2020-0002", "title": "Unsound", "cvss": null },
+                        "package": { "name": "smallvec", "version": "1.6.0" }
+                    }
+                ]
+            }
+        });
+        let report = VulnerabilityReport::new(ProjectType::Rust, parse_cargo_audit(&cargo));
+        assert_eq!(report.vulnerabilities[0].severity, "high");
+        assert_eq!(report.vulnerabilities[0].version.as_deref(), Some("0.14.9"));
+        assert_eq!(report.vulnerabilities[1].severity, "unknown");
+        assert_eq!(report.high_count, 1);
+        assert_eq!(report.critical_count, 0);
+
+        assert_eq!(
+            cvss_severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some("critical")
+        );
+        assert_eq!(
+            cvss_severity("CVSS:3.1/AV:L/AC:H/PR:L/UI:R/S:U/C:L/I:N/A:N"),
+            Some("low")
+        );
+        assert_eq!(
+            cvss_severity("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:C/C:L/I:L/A:N"),
+            Some("moderate")
+        );
+    }
+}
@@ -1,44 +1,666 @@
-use serde::{Deserialize, Serialize};
+use crate::commands::performance::{ProviderCallRecord, ProviderLatencyStore};
+use crate::database::conversations::{Conversation, NewConversation};
+use crate::database::messages::{Message, NewMessage};
+use crate::database::profiles::Profile;
+use crate::database::provider_configs::ProviderConfig;
+use crate::database::{settings::Setting, Database};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tauri::Emitter;
 use tauri::Manager;
 
+/// One part of a message. In JSON a text part is a plain string and an image part is
+/// `{"url": ..., "detail": ...}`, where `url` is an https or base64 `data:` URL.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Image {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct ProviderMessage {
     pub role: String,
-    pub content: String,
+    /// Sent by the frontend either as a plain string or as a list of parts
+    #[serde(
+        deserialize_with = "deserialize_message_content",
+        serialize_with = "serialize_message_content"
+    )]
+    pub content: Vec<MessageContent>,
+}
+
+impl ProviderMessage {
+    /// A text-only message
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        ProviderMessage {
+            role: role.into(),
+            content: vec![MessageContent::Text(content.into())],
+        }
+    }
+
+    /// The text parts joined by newlines; image parts are left out
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|part| match part {
+                MessageContent::Text(text) => Some(text.as_str()),
+                MessageContent::Image { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn deserialize_message_content<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<MessageContent>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ContentInput {
+        Plain(String),
+        Parts(Vec<MessageContent>),
+    }
+
+    Ok(match ContentInput::deserialize(deserializer)? {
+        ContentInput::Plain(text) => vec![MessageContent::Text(text)],
+        ContentInput::Parts(parts) => parts,
+    })
+}
+
+/// Text-only content keeps the original plain string format
+fn serialize_message_content<S: Serializer>(
+    content: &[MessageContent],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match content {
+        [MessageContent::Text(text)] => serializer.serialize_str(text),
+        parts => parts.serialize(serializer),
+    }
+}
+
+/// Fail instead of silently dropping image parts for providers that are only sent text
+fn require_text_only(messages: &[ProviderMessage], provider: &str) -> Result<(), String> {
+    let has_image = messages.iter().any(|m| {
+        m.content
+            .iter()
+            .any(|part| matches!(part, MessageContent::Image { .. }))
+    });
+    if has_image {
+        return Err(format!(
+            "Images are not supported for provider: {}",
+            provider
+        ));
+    }
+    Ok(())
+}
+
+/// Chat completions `content`: a plain string for a single text part, as models
+/// without vision expect, otherwise the array of typed parts
+fn openai_content(content: &[MessageContent]) -> serde_json::Value {
+    match content {
+        [] => serde_json::json!(""),
+        [MessageContent::Text(text)] => serde_json::json!(text),
+        parts => parts
+            .iter()
+            .map(|part| match part {
+                MessageContent::Text(text) => serde_json::json!({"type": "text", "text": text}),
+                MessageContent::Image { url, detail } => serde_json::json!({
+                    "type": "image_url",
+                    "image_url": {"url": url, "detail": detail.as_deref().unwrap_or("auto")}
+                }),
+            })
+            .collect(),
+    }
+}
+
+/// Sampling temperature for OpenAI requests without a profile override
+const DEFAULT_OPENAI_TEMPERATURE: f64 = 0.7;
+
+/// Response length cap for Anthropic requests, which require one
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 1024;
+
+/// Optional sampling parameters for the generate commands. Unset fields keep the
+/// provider's defaults; fields a provider has no equivalent for are ignored.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GenerationConfig {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub frequency_penalty: Option<f64>,
+    pub presence_penalty: Option<f64>,
+    pub stop: Option<Vec<String>>,
+    /// OpenAI `response_format`, such as `{"type": "json_object"}`
+    pub response_format: Option<serde_json::Value>,
+}
+
+/// Insert `value` under `key` only when it is set
+fn insert_param<T: Serialize>(
+    params: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    value: &Option<T>,
+) {
+    if let Some(value) = value {
+        params.insert(key.to_string(), serde_json::json!(value));
+    }
+}
+
+/// Chat completion parameters; temperature falls back to `DEFAULT_OPENAI_TEMPERATURE`
+fn openai_generation_params(
+    config: &GenerationConfig,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut params = serde_json::Map::new();
+    let temperature = match config.temperature {
+        Some(temperature) => serde_json::json!(temperature),
+        None => serde_json::json!(DEFAULT_OPENAI_TEMPERATURE),
+    };
+    params.insert("temperature".to_string(), temperature);
+    insert_param(&mut params, "top_p", &config.top_p);
+    insert_param(&mut params, "max_tokens", &config.max_tokens);
+    insert_param(&mut params, "frequency_penalty", &config.frequency_penalty);
+    insert_param(&mut params, "presence_penalty", &config.presence_penalty);
+    insert_param(&mut params, "stop", &config.stop);
+    insert_param(&mut params, "response_format", &config.response_format);
+    params
+}
+
+/// Messages API parameters; the penalties have no Anthropic equivalent
+fn anthropic_generation_params(
+    config: &GenerationConfig,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut params = serde_json::Map::new();
+    params.insert(
+        "max_tokens".to_string(),
+        serde_json::json!(config.max_tokens.unwrap_or(DEFAULT_ANTHROPIC_MAX_TOKENS)),
+    );
+    insert_param(&mut params, "temperature", &config.temperature);
+    insert_param(&mut params, "top_p", &config.top_p);
+    insert_param(&mut params, "stop_sequences", &config.stop);
+    params
+}
+
+/// Gemini `generationConfig` object, or `None` when nothing is set
+fn gemini_generation_config(config: &GenerationConfig) -> Option<serde_json::Value> {
+    let mut params = serde_json::Map::new();
+    insert_param(&mut params, "temperature", &config.temperature);
+    insert_param(&mut params, "topP", &config.top_p);
+    insert_param(&mut params, "maxOutputTokens", &config.max_tokens);
+    insert_param(&mut params, "stopSequences", &config.stop);
+    insert_param(&mut params, "frequencyPenalty", &config.frequency_penalty);
+    insert_param(&mut params, "presencePenalty", &config.presence_penalty);
+    (!params.is_empty()).then_some(serde_json::Value::Object(params))
+}
+
+/// Used when no `base_url` is configured for OpenAI
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Models used when a generate command is called without one
+const DEFAULT_OPENAI_MODEL: &str = "gpt-3.5-turbo";
+const DEFAULT_OPENAI_VISION_MODEL: &str = "gpt-4o";
+const DEFAULT_ANTHROPIC_MODEL: &str = "claude-3-5-sonnet-20240620";
+const DEFAULT_GEMINI_MODEL: &str = "gemini-1.5-flash";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.2";
+
+const ANTHROPIC_MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
+/// Used when `provider_ratelimit_max_wait_ms` is not set
+const DEFAULT_RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Token bucket refilled continuously at `refill_per_second`, holding at most `capacity`
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_second: f64) -> Self {
+        Self {
+            capacity,
+            refill_per_second,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take a token if one is available, otherwise return how long until one will be
+    fn try_take(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_second,
+            ))
+        }
+    }
+}
+
+/// Per-provider request limits, managed as Tauri state.
+///
+/// Limits come from the `provider_ratelimit_<name>_rps` setting (requests per second,
+/// also used as the burst capacity unless `provider_ratelimit_<name>_burst` is set).
+/// Providers without a configured rate are not limited.
+#[derive(Default)]
+pub struct ProviderRateLimiter {
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+    pub provider: String,
+    pub capacity: f64,
+    pub available_tokens: f64,
+    pub refill_per_second: f64,
+}
+
+struct RateLimitConfig {
+    rps: Option<f64>,
+    burst: Option<f64>,
+    max_wait: Duration,
+}
+
+fn load_rate_limit_config(db: &Database, provider: &str) -> Result<RateLimitConfig, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let number = |key: String| -> Result<Option<f64>, String> {
+        Ok(Setting::get(&conn, &key)
+            .map_err(|e| e.to_string())?
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v > 0.0))
+    };
+
+    Ok(RateLimitConfig {
+        rps: number(format!("provider_ratelimit_{}_rps", provider))?,
+        burst: number(format!("provider_ratelimit_{}_burst", provider))?,
+        max_wait: number("provider_ratelimit_max_wait_ms".to_string())?
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(DEFAULT_RATE_LIMIT_MAX_WAIT),
+    })
+}
+
+impl ProviderRateLimiter {
+    /// Wait for a request token for `provider`, failing if none frees up within the
+    /// configured maximum wait
+    pub async fn acquire(&self, db: &Database, provider: &str) -> Result<(), String> {
+        let config = load_rate_limit_config(db, provider)?;
+        let Some(rps) = config.rps else {
+            // Not configured: drop any stale bucket so status reflects the settings
+            self.buckets
+                .lock()
+                .map_err(|e| e.to_string())?
+                .remove(provider);
+            return Ok(());
+        };
+        let capacity = config.burst.unwrap_or(rps).max(1.0);
+        let deadline = Instant::now() + config.max_wait;
+
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().map_err(|e| e.to_string())?;
+                let bucket = buckets
+                    .entry(provider.to_string())
+                    .or_insert_with(|| TokenBucket::new(capacity, rps));
+                // Pick up setting changes without resetting the current level
+                bucket.capacity = capacity;
+                bucket.refill_per_second = rps;
+                match bucket.try_take() {
+                    Ok(()) => return Ok(()),
+                    Err(wait) => wait,
+                }
+            };
+
+            if Instant::now() + wait > deadline {
+                return Err(format!(
+                    "Rate limit exceeded for {}: no request slot available within {}ms",
+                    provider,
+                    config.max_wait.as_millis()
+                ));
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn status(&self) -> Result<Vec<RateLimitStatus>, String> {
+        let mut buckets = self.buckets.lock().map_err(|e| e.to_string())?;
+        let mut status: Vec<RateLimitStatus> = buckets
+            .iter_mut()
+            .map(|(provider, bucket)| {
+                bucket.refill();
+                RateLimitStatus {
+                    provider: provider.clone(),
+                    capacity: bucket.capacity,
+                    available_tokens: bucket.tokens,
+                    refill_per_second: bucket.refill_per_second,
+                }
+            })
+            .collect();
+        status.sort_by(|a, b| a.provider.cmp(&b.provider));
+        Ok(status)
+    }
+}
+
+/// Block the calling thread until `provider` has a free request slot. Only for code
+/// already off the main thread; commands await `wait_for_rate_limit` instead.
+fn acquire_rate_limit(app: &tauri::AppHandle, provider: &str) -> Result<(), String> {
+    let limiter = app.state::<ProviderRateLimiter>();
+    let db = app.state::<Database>();
+    tauri::async_runtime::block_on(limiter.acquire(&db, provider))
+}
+
+/// Wait for a free request slot for `provider` without blocking a thread
+async fn wait_for_rate_limit(app: &tauri::AppHandle, provider: &str) -> Result<(), String> {
+    let limiter = app.state::<ProviderRateLimiter>();
+    let db = app.state::<Database>();
+    limiter.acquire(&db, provider).await
+}
+
+/// Store a successful blocking call's round-trip time (rate-limit waits excluded)
+/// and announce it as `provider://call-complete`
+fn record_provider_call(
+    app: &tauri::AppHandle,
+    provider: &str,
+    model: &str,
+    started: Instant,
+    reply: &str,
+) {
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if let Some(store) = app.try_state::<ProviderLatencyStore>() {
+        store.record(ProviderCallRecord {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            latency_ms,
+            token_count: approximate_tokens(reply) as u64,
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+    }
+    let _ = app.emit(
+        "provider://call-complete",
+        serde_json::json!({"provider": provider, "model": model, "latency_ms": latency_ms}),
+    );
+}
+
+#[tauri::command]
+pub async fn get_rate_limit_status(
+    limiter: tauri::State<'_, ProviderRateLimiter>,
+) -> Result<Vec<RateLimitStatus>, String> {
+    limiter.status()
+}
+
+/// Endpoint settings saved for `provider`; unset fields use the provider's defaults.
+/// Header values are masked since they often carry API keys.
+#[tauri::command]
+pub async fn get_provider_config(
+    db: tauri::State<'_, Database>,
+    provider: String,
+) -> Result<ProviderConfig, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ProviderConfig::get(&conn, &provider)
+        .map(|config| config.redacted())
+        .map_err(|e| e.to_string())
+}
+
+/// Save endpoint settings for `config.provider`, e.g. an Azure OpenAI or other
+/// OpenAI-compatible `base_url`
+#[tauri::command]
+pub async fn set_provider_config(
+    db: tauri::State<'_, Database>,
+    mut config: ProviderConfig,
+) -> Result<(), String> {
+    if config.provider.trim().is_empty() {
+        return Err("Provider name is required".to_string());
+    }
+    if let Some(url) = config.base_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!(
+                "Base URL must start with http:// or https://: {}",
+                url
+            ));
+        }
+    }
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    // Headers left masked keep their saved values
+    let saved = ProviderConfig::get(&conn, &config.provider).map_err(|e| e.to_string())?;
+    config.restore_redacted(&saved);
+    ProviderConfig::set(&conn, &config).map_err(|e| e.to_string())
+}
+
+/// Blocking one-shot generation with whichever provider is named
+pub fn generate_with_provider(
+    app: &tauri::AppHandle,
+    provider: &str,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+) -> Result<String, String> {
+    acquire_rate_limit(app, provider)?;
+    let config = GenerationConfig::default();
+    match provider {
+        "openai" => openai_reply(app, messages, model, config),
+        "anthropic" => anthropic_reply(app, messages, model, config),
+        "gemini" => gemini_reply(app, messages, model, config),
+        "ollama" => ollama_reply(app, messages, model),
+        other => Err(format!("Unsupported provider: {}", other)),
+    }
+}
+
+#[tauri::command]
+pub async fn provider_openai_generate(
+    app: tauri::AppHandle,
+    _conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    config: Option<GenerationConfig>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "openai").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        openai_reply(&app, messages, model, config.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Blocking OpenAI generation; the caller takes the rate-limit slot
+fn openai_reply(
+    app: &tauri::AppHandle,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    config: GenerationConfig,
+) -> Result<String, String> {
+    let model = model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
+    let started = Instant::now();
+    let endpoint = load_provider_config(app, "openai")?;
+    let content = openai_generate(messages, Some(model.clone()), &config, &endpoint)?;
+    record_provider_call(app, "openai", &model, started, &content);
+    Ok(content)
+}
+
+/// `data:` URL for a base64 image, guessing the type from its leading bytes.
+/// Input that already is a `data:` URL is returned unchanged.
+fn image_data_url(base64_image: &str) -> String {
+    let base64_image = base64_image.trim();
+    if base64_image.starts_with("data:") {
+        return base64_image.to_string();
+    }
+    let mime = if base64_image.starts_with("/9j/") {
+        "image/jpeg"
+    } else if base64_image.starts_with("R0lGOD") {
+        "image/gif"
+    } else if base64_image.starts_with("UklGR") {
+        "image/webp"
+    } else {
+        "image/png"
+    };
+    format!("data:{};base64,{}", mime, base64_image)
+}
+
+/// Ask a vision model about one base64-encoded image
+#[tauri::command]
+pub async fn provider_openai_vision_generate(
+    app: tauri::AppHandle,
+    base64_image: String,
+    prompt: String,
+    model: Option<String>,
+) -> Result<String, String> {
+    let messages = vec![ProviderMessage {
+        role: "user".to_string(),
+        content: vec![
+            MessageContent::Text(prompt),
+            MessageContent::Image {
+                url: image_data_url(&base64_image),
+                detail: None,
+            },
+        ],
+    }];
+    provider_openai_generate(
+        app,
+        String::new(),
+        messages,
+        Some(model.unwrap_or_else(|| DEFAULT_OPENAI_VISION_MODEL.to_string())),
+        None,
+    )
+    .await
+}
+
+/// Temperature saved on the profile, if any
+fn profile_temperature(db: &Database, profile_id: &str) -> Result<Option<f64>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    crate::database::profiles::Profile::get_temperature(&conn, profile_id)
+        .map_err(|e| format!("{}: {}", e, profile_id))
+}
+
+/// Put `system_prompt` ahead of the conversation, ignoring it when blank
+fn prepend_system_prompt(
+    system_prompt: String,
+    messages: Vec<ProviderMessage>,
+) -> Vec<ProviderMessage> {
+    if system_prompt.trim().is_empty() {
+        return messages;
+    }
+    std::iter::once(ProviderMessage::new("system", system_prompt))
+        .chain(messages)
+        .collect()
 }
 
+/// Like `provider_openai_generate`, but takes the system prompt separately and
+/// uses the temperature configured for `profile_id` when given
 #[tauri::command]
-pub fn provider_openai_generate(
+pub async fn provider_openai_generate_with_system(
+    app: tauri::AppHandle,
     _conversation_id: String,
+    system_prompt: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    profile_id: Option<String>,
+) -> Result<String, String> {
+    let config = GenerationConfig {
+        temperature: match profile_id {
+            Some(id) => profile_temperature(&app.state::<Database>(), &id)?,
+            None => None,
+        },
+        ..Default::default()
+    };
+
+    wait_for_rate_limit(&app, "openai").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        openai_reply(
+            &app,
+            prepend_system_prompt(system_prompt, messages),
+            model,
+            config,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Saved endpoint configuration for `provider`, or the defaults when none is saved
+fn load_provider_config(app: &tauri::AppHandle, provider: &str) -> Result<ProviderConfig, String> {
+    let db = app.state::<Database>();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ProviderConfig::get(&conn, provider).map_err(|e| e.to_string())
+}
+
+/// URL of `path` under `base_url`, defaulting to the public OpenAI API
+fn openai_url(endpoint: &ProviderConfig, path: &str) -> String {
+    let base_url = endpoint
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_OPENAI_BASE_URL);
+    format!("{}/{}", base_url.trim_end_matches('/'), path)
+}
+
+/// Add the endpoint's API version and extra headers to an OpenAI request
+fn with_openai_endpoint(
+    mut request: reqwest::blocking::RequestBuilder,
+    endpoint: &ProviderConfig,
+) -> reqwest::blocking::RequestBuilder {
+    // Azure OpenAI and some compatible servers select the API version by query string
+    if let Some(version) = endpoint.api_version.as_deref() {
+        request = request.query(&[("api-version", version)]);
+    }
+    for (name, value) in &endpoint.extra_headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    request
+}
+
+/// Blocking client honouring the endpoint's timeout
+fn openai_client(endpoint: &ProviderConfig) -> Result<reqwest::blocking::Client, String> {
+    let mut client = reqwest::blocking::Client::builder();
+    if let Some(secs) = endpoint.timeout_secs {
+        client = client.timeout(Duration::from_secs(secs.into()));
+    }
+    client.build().map_err(|e| e.to_string())
+}
+
+fn openai_generate(
     messages: Vec<ProviderMessage>,
     model: Option<String>,
+    config: &GenerationConfig,
+    endpoint: &ProviderConfig,
 ) -> Result<String, String> {
     // Read API key from environment
     let api_key =
         std::env::var("OPENAI_API_KEY").map_err(|_| "OPENAI_API_KEY not set".to_string())?;
 
-    let client = reqwest::blocking::Client::new();
-
-    let api_url = "https://api.openai.com/v1/chat/completions";
+    let client = openai_client(endpoint)?;
+    let api_url = openai_url(endpoint, "chat/completions");
 
     // Map our messages into the OpenAI chat format
     let msgs: Vec<serde_json::Value> = messages
         .into_iter()
-        .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
+        .map(|m| serde_json::json!({"role": m.role, "content": openai_content(&m.content)}))
         .collect();
 
-    let model_name = model.unwrap_or_else(|| "gpt-3.5-turbo".to_string());
-    let body = serde_json::json!({
+    let model_name = model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
+    let mut body = serde_json::json!({
         "model": model_name,
         "messages": msgs,
-        "temperature": 0.7
     });
+    body.as_object_mut()
+        .expect("request body is an object")
+        .extend(openai_generation_params(config));
 
-    let resp = client
-        .post(api_url)
-        .bearer_auth(api_key)
-        .json(&body)
+    let request = client.post(api_url).bearer_auth(api_key).json(&body);
+    let resp = with_openai_endpoint(request, endpoint)
         .send()
         .map_err(|e| format!("request error: {}", e))?;
 
@@ -51,9 +673,15 @@ pub fn provider_openai_generate(
         return Err(format!("OpenAI API returned {}: {}", status, json));
     }
 
-    let content = json["choices"]
-        .get(0)
-        .and_then(|c| c.get("message"))
+    let message = json["choices"].get(0).and_then(|c| c.get("message"));
+    // Structured outputs report a refusal instead of content
+    if let Some(refusal) = message
+        .and_then(|m| m.get("refusal"))
+        .and_then(|v| v.as_str())
+    {
+        return Err(format!("OpenAI refused the request: {}", refusal));
+    }
+    let content = message
         .and_then(|m| m.get("content"))
         .and_then(|v| v.as_str())
         .unwrap_or("")
@@ -62,6 +690,129 @@ pub fn provider_openai_generate(
     Ok(content)
 }
 
+/// Ask the model for JSON only. OpenAI's JSON mode requires the word "JSON" to
+/// appear in the messages, so this instruction is always added.
+fn with_json_instructions(
+    mut messages: Vec<ProviderMessage>,
+    schema: Option<&serde_json::Value>,
+) -> Vec<ProviderMessage> {
+    let mut instructions = "Respond only with a valid JSON object.".to_string();
+    if let Some(schema) = schema {
+        let pretty = serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+        instructions.push_str(&format!(
+            " The object must match this JSON schema:\n{}",
+            pretty
+        ));
+    }
+
+    match messages.first_mut() {
+        Some(first) if first.role == "system" => {
+            first.content = vec![MessageContent::Text(format!(
+                "{}\n\n{}",
+                first.text(),
+                instructions
+            ))];
+        }
+        _ => messages.insert(0, ProviderMessage::new("system", instructions)),
+    }
+    messages
+}
+
+/// `response_format` for structured outputs. A bare schema is wrapped, with `strict`
+/// only when asked for since strict mode rejects many schemas; an object that already
+/// has `name` and `schema` is used as the `json_schema` value as is.
+fn json_schema_response_format(schema: serde_json::Value, strict: bool) -> serde_json::Value {
+    let json_schema = if schema.get("name").is_some() && schema.get("schema").is_some() {
+        schema
+    } else {
+        serde_json::json!({
+            "name": "response",
+            "schema": schema,
+            "strict": strict
+        })
+    };
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": json_schema
+    })
+}
+
+fn parse_json_content(content: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(content.trim())
+        .map_err(|e| format!("Model response is not valid JSON: {}", e))
+}
+
+/// Generate with `response_format` set and parse the reply as JSON
+fn openai_generate_json(
+    app: &tauri::AppHandle,
+    messages: Vec<ProviderMessage>,
+    model_name: String,
+    response_format: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let endpoint = load_provider_config(app, "openai")?;
+    let config = GenerationConfig {
+        response_format: Some(response_format),
+        ..Default::default()
+    };
+    let content = openai_generate(messages, Some(model_name), &config, &endpoint)?;
+    parse_json_content(&content)
+}
+
+/// Generate with `response_format: json_object`, describing `schema` in the system prompt
+#[tauri::command]
+pub async fn provider_openai_json_mode(
+    app: tauri::AppHandle,
+    _conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    schema: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    wait_for_rate_limit(&app, "openai").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let messages = with_json_instructions(messages, schema.as_ref());
+        let model = model.unwrap_or_else(|| "gpt-4o".to_string());
+        let started = Instant::now();
+        let value = openai_generate_json(
+            &app,
+            messages,
+            model.clone(),
+            serde_json::json!({"type": "json_object"}),
+        )?;
+        record_provider_call(&app, "openai", &model, started, &value.to_string());
+        Ok(value)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Generate with `response_format: json_schema`, which needs gpt-4o-2024-08-06 or newer.
+/// `strict` turns on OpenAI's strict schema adherence for a bare schema.
+#[tauri::command]
+pub async fn provider_openai_structured_output(
+    app: tauri::AppHandle,
+    _conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    schema: serde_json::Value,
+    strict: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    wait_for_rate_limit(&app, "openai").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let model = model.unwrap_or_else(|| "gpt-4o-2024-08-06".to_string());
+        let started = Instant::now();
+        let value = openai_generate_json(
+            &app,
+            messages,
+            model.clone(),
+            json_schema_response_format(schema, strict.unwrap_or(false)),
+        )?;
+        record_provider_call(&app, "openai", &model, started, &value.to_string());
+        Ok(value)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 fn get_keyring_secret(service: &str) -> Option<String> {
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     {
@@ -114,29 +865,50 @@ pub fn get_api_key(provider: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn provider_anthropic_generate(
+pub async fn provider_anthropic_generate(
+    app: tauri::AppHandle,
     _conversation_id: String,
     messages: Vec<ProviderMessage>,
     model: Option<String>,
+    config: Option<GenerationConfig>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "anthropic").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        anthropic_reply(&app, messages, model, config.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Blocking Anthropic generation; the caller takes the rate-limit slot
+fn anthropic_reply(
+    app: &tauri::AppHandle,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    config: GenerationConfig,
 ) -> Result<String, String> {
+    require_text_only(&messages, "anthropic")?;
+    let started = Instant::now();
     let api_key = prefer_keyring_or_env("anthropic", "ANTHROPIC_API_KEY")?;
     let client = reqwest::blocking::Client::new();
-    let api_url = "https://api.anthropic.com/v1/messages";
     // Collapse messages into a single user prompt for simplicity
     let prompt = messages
         .into_iter()
-        .map(|m| format!("{}: {}", m.role, m.content))
+        .map(|m| format!("{}: {}", m.role, m.text()))
         .collect::<Vec<_>>()
         .join("\n\n");
-    let body = serde_json::json!({
-        "model": model.unwrap_or_else(|| "claude-3-5-sonnet-20240620".to_string()),
-        "max_tokens": 1024,
+    let model_name = model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string());
+    let mut body = serde_json::json!({
+        "model": model_name,
         "messages": [ { "role": "user", "content": prompt } ]
     });
+    body.as_object_mut()
+        .expect("request body is an object")
+        .extend(anthropic_generation_params(&config));
     let resp = client
-        .post(api_url)
+        .post(ANTHROPIC_MESSAGES_URL)
         .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
+        .header("anthropic-version", ANTHROPIC_API_VERSION)
         .json(&body)
         .send()
         .map_err(|e| format!("request error: {}", e))?;
@@ -153,17 +925,37 @@ pub fn provider_anthropic_generate(
         .and_then(|t| t.as_str())
         .unwrap_or("")
         .to_string();
+    record_provider_call(app, "anthropic", &model_name, started, &content);
     Ok(content)
 }
 
 #[tauri::command]
-pub fn provider_gemini_generate(
+pub async fn provider_gemini_generate(
+    app: tauri::AppHandle,
     _conversation_id: String,
     messages: Vec<ProviderMessage>,
     model: Option<String>,
+    config: Option<GenerationConfig>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "gemini").await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        gemini_reply(&app, messages, model, config.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Blocking Gemini generation; the caller takes the rate-limit slot
+fn gemini_reply(
+    app: &tauri::AppHandle,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    config: GenerationConfig,
 ) -> Result<String, String> {
+    require_text_only(&messages, "gemini")?;
+    let started = Instant::now();
     let api_key = prefer_keyring_or_env("gemini", "GEMINI_API_KEY")?;
-    let model_name = model.unwrap_or_else(|| "gemini-1.5-flash".to_string());
+    let model_name = model.unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string());
     let url = format!(
         "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
         model_name
@@ -171,12 +963,15 @@ pub fn provider_gemini_generate(
     let client = reqwest::blocking::Client::new();
     let text = messages
         .into_iter()
-        .map(|m| format!("{}: {}", m.role, m.content))
+        .map(|m| format!("{}: {}", m.role, m.text()))
         .collect::<Vec<_>>()
         .join("\n\n");
-    let body = serde_json::json!({
+    let mut body = serde_json::json!({
         "contents": [ { "parts": [ { "text": text } ] } ]
     });
+    if let Some(generation_config) = gemini_generation_config(&config) {
+        body["generationConfig"] = generation_config;
+    }
     let resp = client
         .post(&url)
         .header("Authorization", format!("Bearer {}", api_key))
@@ -199,56 +994,146 @@ pub fn provider_gemini_generate(
         .and_then(|t| t.as_str())
         .unwrap_or("")
         .to_string();
+    record_provider_call(app, "gemini", &model_name, started, &content);
     Ok(content)
 }
-#[tauri::command]
-pub fn provider_openai_stream(
-    app: tauri::AppHandle,
-    conversation_id: String,
-    messages: Vec<ProviderMessage>,
-    model: Option<String>,
-) -> Result<String, String> {
-    // Generate final content using existing generator (best-effort). If OPENAI_API_KEY
-    // is not present, fall back to a deterministic mock.
-    let final_content = match provider_openai_generate(conversation_id.clone(), messages, model) {
-        Ok(c) => c,
-        Err(_) => format!("Mock response to conversation {}", conversation_id),
-    };
+/// Something a stream produced, passed to a `StreamObserver` on the streaming thread
+#[derive(Clone, Copy)]
+enum StreamUpdate<'a> {
+    Chunk(&'a str),
+    /// Sent after `provider-stream-end`, with the completion token count if the provider reported one
+    End {
+        tokens_used: Option<i64>,
+        /// The complete reply, when the provider returned it whole and the chunks
+        /// were only split out for display
+        reply: Option<&'a str>,
+    },
+    /// Sent after `provider-stream-error`; no further updates follow
+    Error(&'a str),
+}
 
-    let session_id = uuid::Uuid::new_v4().to_string();
+type StreamObserver = Box<dyn FnMut(StreamUpdate<'_>) + Send>;
 
-    // Spawn a thread to emit chunks to the frontend via Tauri events.
-    let session_id_clone = session_id.clone();
-    std::thread::spawn(move || {
-        let parts: Vec<String> = final_content
-            .split_whitespace()
-            .map(|s| format!("{} ", s))
-            .collect();
+/// Report a stream that failed after it started as `provider-stream-error`
+fn fail_stream(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    observer: &mut Option<StreamObserver>,
+    error: String,
+) {
+    if let Some(w) = app.get_webview_window("main") {
+        let _ = w.emit(
+            "provider-stream-error",
+            serde_json::json!({"session_id": session_id, "error": error}),
+        );
+    }
+    if let Some(observer) = observer.as_mut() {
+        observer(StreamUpdate::Error(&error));
+    }
+}
+
+/// Split `text` into words for display, each keeping the whitespace that follows it
+/// so the chunks join back into the original text, newlines and indentation included
+fn display_chunks(text: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut after_space = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            after_space = true;
+        } else if after_space {
+            chunks.push(&text[start..i]);
+            start = i;
+            after_space = false;
+        }
+    }
+    if start < text.len() {
+        chunks.push(&text[start..]);
+    }
+    chunks
+}
 
-        for p in parts {
+/// Emit already generated content word by word as `session_id`'s stream
+fn spawn_chunk_stream(
+    app: tauri::AppHandle,
+    session_id: String,
+    final_content: String,
+    mut observer: Option<StreamObserver>,
+) {
+    std::thread::spawn(move || {
+        for p in display_chunks(&final_content) {
             // best-effort emit; ignore errors
-            let payload = serde_json::json!({"session_id": session_id_clone, "chunk": p});
+            let payload = serde_json::json!({"session_id": session_id, "chunk": p});
             if let Some(w) = app.get_webview_window("main") {
                 let _ = w.emit("provider-stream-chunk", payload.clone());
             }
+            if let Some(observer) = observer.as_mut() {
+                observer(StreamUpdate::Chunk(p));
+            }
             std::thread::sleep(std::time::Duration::from_millis(50));
         }
 
-        let payload = serde_json::json!({"session_id": session_id_clone});
+        let payload = serde_json::json!({"session_id": session_id});
         if let Some(w) = app.get_webview_window("main") {
             let _ = w.emit("provider-stream-end", payload.clone());
         }
+        if let Some(observer) = observer.as_mut() {
+            observer(StreamUpdate::End {
+                tokens_used: None,
+                reply: Some(&final_content),
+            });
+        }
     });
+}
 
-    Ok(session_id)
+#[tauri::command]
+pub async fn provider_openai_stream(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "openai").await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Generate final content using existing generator (best-effort). If OPENAI_API_KEY
+        // is not present, fall back to a deterministic mock.
+        let endpoint = load_provider_config(&app, "openai")?;
+        let final_content =
+            match openai_generate(messages, model, &GenerationConfig::default(), &endpoint) {
+                Ok(c) => c,
+                Err(_) => format!("Mock response to conversation {}", conversation_id),
+            };
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        spawn_chunk_stream(app, session_id.clone(), final_content, None);
+        Ok(session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-pub fn provider_ollama_generate(
+pub async fn provider_ollama_generate(
+    app: tauri::AppHandle,
     _conversation_id: String,
     messages: Vec<ProviderMessage>,
     model: Option<String>,
 ) -> Result<String, String> {
+    wait_for_rate_limit(&app, "ollama").await?;
+    tauri::async_runtime::spawn_blocking(move || ollama_reply(&app, messages, model))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Blocking Ollama generation; the caller takes the rate-limit slot
+fn ollama_reply(
+    app: &tauri::AppHandle,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+) -> Result<String, String> {
+    require_text_only(&messages, "ollama")?;
+    let started = Instant::now();
     let client = reqwest::blocking::Client::new();
 
     // Default Ollama endpoint - can be configured later
@@ -260,15 +1145,15 @@ pub fn provider_ollama_generate(
     let prompt = messages
         .into_iter()
         .map(|m| match m.role.as_str() {
-            "system" => format!("System: {}", m.content),
-            "user" => format!("Human: {}", m.content),
-            "assistant" => format!("Assistant: {}", m.content),
-            _ => format!("{}: {}", m.role, m.content),
+            "system" => format!("System: {}", m.text()),
+            "user" => format!("Human: {}", m.text()),
+            "assistant" => format!("Assistant: {}", m.text()),
+            _ => format!("{}: {}", m.role, m.text()),
         })
         .collect::<Vec<_>>()
         .join("\n\n");
 
-    let model_name = model.unwrap_or_else(|| "llama3.2".to_string());
+    let model_name = model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string());
 
     let body = serde_json::json!({
         "model": model_name,
@@ -292,17 +1177,39 @@ pub fn provider_ollama_generate(
     }
 
     let content = json["response"].as_str().unwrap_or("").to_string();
+    record_provider_call(app, "ollama", &model_name, started, &content);
 
     Ok(content)
 }
 
 #[tauri::command]
-pub fn provider_ollama_stream(
+pub async fn provider_ollama_stream(
     app: tauri::AppHandle,
     _conversation_id: String,
     messages: Vec<ProviderMessage>,
     model: Option<String>,
 ) -> Result<String, String> {
+    wait_for_rate_limit(&app, "ollama").await?;
+
+    // The blocking HTTP client can't be built on the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        spawn_ollama_stream(app, session_id.clone(), messages, model, None)?;
+        Ok(session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stream an Ollama completion as `session_id`, emitting each chunk as it arrives
+fn spawn_ollama_stream(
+    app: tauri::AppHandle,
+    session_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    mut observer: Option<StreamObserver>,
+) -> Result<(), String> {
+    require_text_only(&messages, "ollama")?;
     let client = reqwest::blocking::Client::new();
 
     let endpoint =
@@ -312,16 +1219,15 @@ pub fn provider_ollama_stream(
     let prompt = messages
         .into_iter()
         .map(|m| match m.role.as_str() {
-            "system" => format!("System: {}", m.content),
-            "user" => format!("Human: {}", m.content),
-            "assistant" => format!("Assistant: {}", m.content),
-            _ => format!("{}: {}", m.role, m.content),
+            "system" => format!("System: {}", m.text()),
+            "user" => format!("Human: {}", m.text()),
+            "assistant" => format!("Assistant: {}", m.text()),
+            _ => format!("{}: {}", m.role, m.text()),
         })
         .collect::<Vec<_>>()
         .join("\n\n");
 
-    let model_name = model.unwrap_or_else(|| "llama3.2".to_string());
-    let session_id = uuid::Uuid::new_v4().to_string();
+    let model_name = model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string());
 
     let body = serde_json::json!({
         "model": model_name,
@@ -330,15 +1236,28 @@ pub fn provider_ollama_stream(
     });
 
     // Spawn thread for streaming response
-    let session_id_clone = session_id.clone();
     std::thread::spawn(move || {
         let resp = match client.post(&api_url).json(&body).send() {
             Ok(r) => r,
-            Err(_) => return,
+            Err(e) => {
+                return fail_stream(
+                    &app,
+                    &session_id,
+                    &mut observer,
+                    format!("Ollama request error: {}", e),
+                )
+            }
         };
 
-        if !resp.status().is_success() {
-            return;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().unwrap_or_default();
+            return fail_stream(
+                &app,
+                &session_id,
+                &mut observer,
+                format!("Ollama API returned {}: {}", status, body),
+            );
         }
 
         let reader = std::io::BufReader::new(resp);
@@ -352,33 +1271,751 @@ pub fn provider_ollama_stream(
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
                 if let Some(response) = json["response"].as_str() {
                     let payload = serde_json::json!({
-                        "session_id": session_id_clone,
+                        "session_id": session_id,
                         "chunk": response
                     });
 
                     if let Some(w) = app.get_webview_window("main") {
                         let _ = w.emit("provider-stream-chunk", payload);
                     }
+                    if let Some(observer) = observer.as_mut() {
+                        observer(StreamUpdate::Chunk(response));
+                    }
                 }
 
                 // Check if this is the final response
                 if json["done"].as_bool().unwrap_or(false) {
                     let payload = serde_json::json!({
-                        "session_id": session_id_clone
+                        "session_id": session_id
                     });
 
                     if let Some(w) = app.get_webview_window("main") {
                         let _ = w.emit("provider-stream-end", payload);
                     }
-                    break;
+                    if let Some(observer) = observer.as_mut() {
+                        observer(StreamUpdate::End {
+                            tokens_used: json["eval_count"].as_i64(),
+                            reply: None,
+                        });
+                    }
+                    return;
+                }
+            }
+        }
+        fail_stream(
+            &app,
+            &session_id,
+            &mut observer,
+            "Ollama stream ended before completing".to_string(),
+        );
+    });
+    Ok(())
+}
+
+/// Payload of an SSE `data:` line, or `None` for event names, comments and blank lines
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim_start)
+}
+
+/// The parts of an Anthropic streaming event the app acts on
+#[derive(Debug, PartialEq)]
+enum AnthropicStreamEvent {
+    Text(String),
+    OutputTokens(i64),
+    Stop,
+    Error(String),
+}
+
+/// Interpret one Anthropic SSE payload. `ping`, `message_start` and the content block
+/// start/stop events carry nothing to forward and yield `None`.
+fn parse_anthropic_stream_event(data: &str) -> Option<AnthropicStreamEvent> {
+    let json: serde_json::Value = serde_json::from_str(data).ok()?;
+    match json["type"].as_str()? {
+        "content_block_delta" => json["delta"]["text"]
+            .as_str()
+            .map(|text| AnthropicStreamEvent::Text(text.to_string())),
+        "message_delta" => json["usage"]["output_tokens"]
+            .as_i64()
+            .map(AnthropicStreamEvent::OutputTokens),
+        "message_stop" => Some(AnthropicStreamEvent::Stop),
+        "error" => Some(AnthropicStreamEvent::Error(
+            json["error"]["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Split messages into Anthropic's top-level `system` prompt and the chat turns
+fn anthropic_messages(messages: Vec<ProviderMessage>) -> (Option<String>, Vec<serde_json::Value>) {
+    let mut system = Vec::new();
+    let mut turns = Vec::new();
+    for m in messages {
+        match m.role.as_str() {
+            "system" => system.push(m.text()),
+            "assistant" => {
+                turns.push(serde_json::json!({"role": "assistant", "content": m.text()}))
+            }
+            _ => turns.push(serde_json::json!({"role": "user", "content": m.text()})),
+        }
+    }
+    ((!system.is_empty()).then(|| system.join("\n\n")), turns)
+}
+
+#[tauri::command]
+pub async fn provider_anthropic_stream(
+    app: tauri::AppHandle,
+    _conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "anthropic").await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let api_key = prefer_keyring_or_env("anthropic", "ANTHROPIC_API_KEY")?;
+        let session_id = uuid::Uuid::new_v4().to_string();
+        spawn_anthropic_stream(app, session_id.clone(), api_key, messages, model, None)?;
+        Ok(session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stream an Anthropic reply as `session_id` from the Messages API's server-sent events.
+/// Failures after the request starts are reported as `provider-stream-error`.
+fn spawn_anthropic_stream(
+    app: tauri::AppHandle,
+    session_id: String,
+    api_key: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    mut observer: Option<StreamObserver>,
+) -> Result<(), String> {
+    require_text_only(&messages, "anthropic")?;
+    // Replies can take longer than the blocking client's default 30 s timeout
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {}", e))?;
+
+    let (system, turns) = anthropic_messages(messages);
+    let model_name = model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string());
+    let mut body = serde_json::json!({
+        "model": model_name,
+        "messages": turns,
+        "stream": true
+    });
+    if let Some(system) = system {
+        body["system"] = serde_json::json!(system);
+    }
+    body.as_object_mut()
+        .expect("request body is an object")
+        .extend(anthropic_generation_params(&GenerationConfig::default()));
+
+    std::thread::spawn(move || {
+        let emit = |event: &str, payload: serde_json::Value| {
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.emit(event, payload);
+            }
+        };
+        let resp = match client
+            .post(ANTHROPIC_MESSAGES_URL)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION)
+            .json(&body)
+            .send()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return fail_stream(
+                    &app,
+                    &session_id,
+                    &mut observer,
+                    format!("request error: {}", e),
+                )
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().unwrap_or_default();
+            return fail_stream(
+                &app,
+                &session_id,
+                &mut observer,
+                format!("Anthropic API returned {}: {}", status, body),
+            );
+        }
+
+        let reader = std::io::BufReader::new(resp);
+        use std::io::BufRead;
+
+        let mut tokens_used = None;
+        for line in reader.lines().map_while(Result::ok) {
+            let Some(data) = sse_data(&line) else {
+                continue;
+            };
+            match parse_anthropic_stream_event(data) {
+                Some(AnthropicStreamEvent::Text(text)) => {
+                    emit(
+                        "provider-stream-chunk",
+                        serde_json::json!({"session_id": session_id, "chunk": text}),
+                    );
+                    if let Some(observer) = observer.as_mut() {
+                        observer(StreamUpdate::Chunk(&text));
+                    }
+                }
+                Some(AnthropicStreamEvent::OutputTokens(tokens)) => tokens_used = Some(tokens),
+                Some(AnthropicStreamEvent::Stop) => {
+                    emit(
+                        "provider-stream-end",
+                        serde_json::json!({"session_id": session_id}),
+                    );
+                    if let Some(observer) = observer.as_mut() {
+                        observer(StreamUpdate::End {
+                            tokens_used,
+                            reply: None,
+                        });
+                    }
+                    return;
+                }
+                Some(AnthropicStreamEvent::Error(message)) => {
+                    return fail_stream(&app, &session_id, &mut observer, message)
                 }
+                None => {}
+            }
+        }
+        fail_stream(
+            &app,
+            &session_id,
+            &mut observer,
+            "stream closed before message_stop".to_string(),
+        );
+    });
+    Ok(())
+}
+
+/// Read Gemini `streamGenerateContent?alt=sse` events, passing each text part to
+/// `on_chunk`. Malformed lines are logged and skipped. Returns the last reported
+/// output token count.
+fn forward_gemini_sse<R: std::io::BufRead>(
+    reader: R,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<Option<i64>, String> {
+    let mut tokens_used = None;
+    let mut finished = false;
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("stream read error: {}", e))?;
+        let Some(data) = sse_data(&line) else {
+            continue;
+        };
+        let json: serde_json::Value = match serde_json::from_str(data) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("skipping malformed Gemini stream event: {}", e);
+                continue;
+            }
+        };
+        if let Some(text) = json["candidates"][0]["content"]["parts"][0]["text"].as_str() {
+            on_chunk(text);
+        }
+        if let Some(tokens) = json["usageMetadata"]["candidatesTokenCount"].as_i64() {
+            tokens_used = Some(tokens);
+        }
+        if json["candidates"][0]["finishReason"].is_string() {
+            finished = true;
+        }
+    }
+    // The last event carries a finishReason; without it the reply was cut off
+    if !finished {
+        return Err("Gemini stream ended before the reply finished".to_string());
+    }
+    Ok(tokens_used)
+}
+
+#[tauri::command]
+pub async fn provider_gemini_stream(
+    app: tauri::AppHandle,
+    _conversation_id: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+) -> Result<String, String> {
+    wait_for_rate_limit(&app, "gemini").await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let api_key = prefer_keyring_or_env("gemini", "GEMINI_API_KEY")?;
+        let session_id = uuid::Uuid::new_v4().to_string();
+        spawn_gemini_stream(app, session_id.clone(), api_key, messages, model, None)?;
+        Ok(session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stream a Gemini reply as `session_id`; `provider-stream-end` is sent when the
+/// server closes the event stream
+fn spawn_gemini_stream(
+    app: tauri::AppHandle,
+    session_id: String,
+    api_key: String,
+    messages: Vec<ProviderMessage>,
+    model: Option<String>,
+    mut observer: Option<StreamObserver>,
+) -> Result<(), String> {
+    require_text_only(&messages, "gemini")?;
+    // Replies can take longer than the blocking client's default 30 s timeout
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {}", e))?;
+
+    let model_name = model.unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string());
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse",
+        model_name
+    );
+    let text = messages
+        .into_iter()
+        .map(|m| format!("{}: {}", m.role, m.text()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let body = serde_json::json!({
+        "contents": [ { "parts": [ { "text": text } ] } ]
+    });
+
+    std::thread::spawn(move || {
+        let emit = |event: &str, payload: serde_json::Value| {
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.emit(event, payload);
+            }
+        };
+        let resp = match client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&body)
+            .send()
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return fail_stream(
+                    &app,
+                    &session_id,
+                    &mut observer,
+                    format!("request error: {}", e),
+                )
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().unwrap_or_default();
+            return fail_stream(
+                &app,
+                &session_id,
+                &mut observer,
+                format!("Gemini API returned {}: {}", status, body),
+            );
+        }
+
+        let forwarded = forward_gemini_sse(std::io::BufReader::new(resp), |text| {
+            emit(
+                "provider-stream-chunk",
+                serde_json::json!({"session_id": session_id, "chunk": text}),
+            );
+            if let Some(observer) = observer.as_mut() {
+                observer(StreamUpdate::Chunk(text));
             }
+        });
+        let tokens_used = match forwarded {
+            Ok(tokens_used) => tokens_used,
+            Err(e) => return fail_stream(&app, &session_id, &mut observer, e),
+        };
+
+        emit(
+            "provider-stream-end",
+            serde_json::json!({"session_id": session_id}),
+        );
+        if let Some(observer) = observer.as_mut() {
+            observer(StreamUpdate::End {
+                tokens_used,
+                reply: None,
+            });
         }
     });
+    Ok(())
+}
+
+/// The text to save when a stream ends: the provider's complete reply if it had
+/// one, otherwise the chunks received
+fn finished_reply<'a>(streamed: &'a str, reply: Option<&'a str>) -> &'a str {
+    reply.unwrap_or(streamed.trim_end())
+}
+
+/// Save a finished stream as the conversation's assistant reply, deleting the
+/// reply it `replaces` in the same transaction
+fn save_streamed_reply(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+    replaces: Option<&str>,
+    content: &str,
+    tokens_used: Option<i64>,
+) -> rusqlite::Result<Message> {
+    let tx = conn.unchecked_transaction()?;
+    if let Some(old_id) = replaces {
+        Message::delete(&tx, old_id)?;
+    }
+    let message = Message::create(
+        &tx,
+        NewMessage {
+            conversation_id: conversation_id.to_string(),
+            role: "assistant".to_string(),
+            content: content.to_string(),
+            tokens_used,
+        },
+    )?;
+    tx.commit()?;
+    Ok(message)
+}
+
+/// Accumulates a stream's chunks, emitting `message://streaming` with the content so
+/// far, and saves the full reply to the conversation once the stream ends
+fn conversation_stream_writer(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    session_id: String,
+    replaces: Option<String>,
+) -> StreamObserver {
+    let mut content = String::new();
+    Box::new(move |update: StreamUpdate<'_>| match update {
+        StreamUpdate::Chunk(chunk) => {
+            content.push_str(chunk);
+            let _ = app.emit(
+                "message://streaming",
+                serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "session_id": session_id,
+                    "partial_content": content,
+                }),
+            );
+        }
+        StreamUpdate::End { tokens_used, reply } => {
+            let db = app.state::<Database>();
+            let saved = db.conn().map_err(|e| e.to_string()).and_then(|conn| {
+                save_streamed_reply(
+                    &conn,
+                    &conversation_id,
+                    replaces.as_deref(),
+                    finished_reply(&content, reply),
+                    tokens_used,
+                )
+                .map_err(|e| e.to_string())
+            });
+            if let Err(e) = saved {
+                eprintln!(
+                    "failed to save streamed reply for {}: {}",
+                    conversation_id, e
+                );
+            }
+        }
+        StreamUpdate::Error(error) => {
+            eprintln!(
+                "stream for {} failed, reply not saved: {}",
+                conversation_id, error
+            );
+        }
+    })
+}
+
+/// Stream a reply from `provider` and save it to the conversation as an assistant
+/// message when the stream ends. Returns the stream's session ID.
+#[tauri::command]
+pub async fn stream_to_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    provider: String,
+    model: Option<String>,
+    messages: Vec<ProviderMessage>,
+) -> Result<String, String> {
+    // Starting an OpenAI stream waits for the whole reply, so keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        {
+            let db = app.state::<Database>();
+            let conn = db.conn().map_err(|e| e.to_string())?;
+            if !Conversation::exists(&conn, &conversation_id).map_err(|e| e.to_string())? {
+                return Err(format!("Conversation not found: {}", conversation_id));
+            }
+        }
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let writer = conversation_stream_writer(
+            app.clone(),
+            conversation_id.clone(),
+            session_id.clone(),
+            None,
+        );
+        start_stream(app, &provider, session_id.clone(), model, messages, writer)?;
+        Ok(session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
+/// Stream a new reply in place of the assistant message `replaced_message_id`. The
+/// old reply is only deleted, together with saving the new one, once the stream
+/// ends; a stream that fails to start or errors leaves the conversation as it was.
+/// Returns the stream's session ID.
+pub(crate) fn stream_replacement(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    replaced_message_id: String,
+    provider: &str,
+    model: Option<String>,
+    messages: Vec<ProviderMessage>,
+) -> Result<String, String> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let writer = conversation_stream_writer(
+        app.clone(),
+        conversation_id,
+        session_id.clone(),
+        Some(replaced_message_id),
+    );
+    start_stream(app, provider, session_id.clone(), model, messages, writer)?;
     Ok(session_id)
 }
 
+/// Providers `start_stream` can stream replies from
+const STREAMING_PROVIDERS: &[&str] = &["openai", "anthropic", "gemini", "ollama"];
+
+/// Start streaming a reply from `provider` as `session_id`, reporting progress to `observer`
+fn start_stream(
+    app: tauri::AppHandle,
+    provider: &str,
+    session_id: String,
+    model: Option<String>,
+    messages: Vec<ProviderMessage>,
+    observer: StreamObserver,
+) -> Result<(), String> {
+    match provider {
+        "openai" => {
+            acquire_rate_limit(&app, "openai")?;
+            // Unlike provider_openai_stream, don't fall back to a mock reply that would be saved
+            let endpoint = load_provider_config(&app, "openai")?;
+            let content =
+                openai_generate(messages, model, &GenerationConfig::default(), &endpoint)?;
+            spawn_chunk_stream(app, session_id, content, Some(observer));
+        }
+        "anthropic" => {
+            acquire_rate_limit(&app, "anthropic")?;
+            let api_key = prefer_keyring_or_env("anthropic", "ANTHROPIC_API_KEY")?;
+            spawn_anthropic_stream(app, session_id, api_key, messages, model, Some(observer))?;
+        }
+        "gemini" => {
+            acquire_rate_limit(&app, "gemini")?;
+            let api_key = prefer_keyring_or_env("gemini", "GEMINI_API_KEY")?;
+            spawn_gemini_stream(app, session_id, api_key, messages, model, Some(observer))?;
+        }
+        "ollama" => {
+            acquire_rate_limit(&app, "ollama")?;
+            spawn_ollama_stream(app, session_id, messages, model, Some(observer))?;
+        }
+        other => {
+            return Err(format!(
+                "Streaming is not supported for provider: {}",
+                other
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Progress of a prompt streamed with `stream_prompt`
+pub(crate) enum StreamEvent {
+    Chunk(String),
+    End,
+    Error(String),
+}
+
+/// Longest title given to a conversation started by `stream_prompt`
+const PROMPT_TITLE_CHARS: usize = 60;
+
+/// Title for a conversation started from `prompt`: its first line, shortened
+fn prompt_title(prompt: &str) -> String {
+    let line = prompt.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= PROMPT_TITLE_CHARS {
+        line.to_string()
+    } else {
+        let mut title: String = line.chars().take(PROMPT_TITLE_CHARS - 1).collect();
+        title.push('…');
+        title
+    }
+}
+
+fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "anthropic" => DEFAULT_ANTHROPIC_MODEL,
+        "gemini" => DEFAULT_GEMINI_MODEL,
+        "ollama" => DEFAULT_OLLAMA_MODEL,
+        _ => DEFAULT_OPENAI_MODEL,
+    }
+}
+
+/// Undo the rows `stream_prompt` saved for a prompt whose stream failed to start:
+/// the whole conversation when it was created for the prompt, else the user message
+fn discard_prompt(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+    created_conversation: bool,
+    user_message_id: &str,
+) -> rusqlite::Result<()> {
+    if created_conversation {
+        Conversation::purge(conn, conversation_id)
+    } else {
+        Message::purge(conn, user_message_id)
+    }
+}
+
+/// Send `prompt` as a user message and stream the reply to `events`, saving both to
+/// the conversation. Continues `conversation_id` when it exists, otherwise starts a
+/// new conversation with the given or active profile's provider and model.
+/// Returns the conversation ID, or `None` without saving anything if the provider
+/// can't stream. Nothing is left saved if the stream fails to start.
+pub(crate) fn stream_prompt(
+    app: &tauri::AppHandle,
+    prompt: &str,
+    conversation_id: Option<&str>,
+    provider: Option<String>,
+    model: Option<String>,
+    events: std::sync::mpsc::Sender<StreamEvent>,
+) -> Result<Option<String>, String> {
+    let (conversation_id, created_conversation, user_message_id, provider, model, messages) = {
+        let db = app.state::<Database>();
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        let existing = match conversation_id {
+            Some(id) => Conversation::get_by_id(&conn, id).map_err(|e| e.to_string())?,
+            None => None,
+        };
+        let profile = match existing {
+            Some(_) => None,
+            None => Profile::get_active(&conn).map_err(|e| e.to_string())?,
+        };
+
+        let provider = provider
+            .or_else(|| existing.as_ref().map(|c| c.provider.clone()))
+            .or_else(|| profile.as_ref().map(|p| p.default_provider.clone()))
+            .unwrap_or_else(|| "openai".to_string());
+        if !STREAMING_PROVIDERS.contains(&provider.as_str()) {
+            return Ok(None);
+        }
+        let model = model
+            .or_else(|| {
+                existing
+                    .as_ref()
+                    .filter(|c| c.provider == provider)
+                    .map(|c| c.model.clone())
+            })
+            .or_else(|| {
+                profile
+                    .as_ref()
+                    .filter(|p| p.default_provider == provider)
+                    .map(|p| p.default_model.clone())
+            })
+            .unwrap_or_else(|| default_model(&provider).to_string());
+
+        let created_conversation = existing.is_none();
+        let conversation = match existing {
+            Some(conversation) => conversation,
+            None => Conversation::create(
+                &conn,
+                NewConversation {
+                    title: prompt_title(prompt),
+                    model: model.clone(),
+                    provider: provider.clone(),
+                    system_prompt: profile.and_then(|p| p.system_prompt),
+                },
+            )
+            .map_err(|e| e.to_string())?,
+        };
+
+        let user_message = Message::create(
+            &conn,
+            NewMessage {
+                conversation_id: conversation.id.clone(),
+                role: "user".to_string(),
+                content: prompt.to_string(),
+                tokens_used: None,
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &conversation.system_prompt {
+            messages.push(ProviderMessage::new("system", system_prompt.as_str()));
+        }
+        messages.extend(
+            Message::get_by_conversation(&conn, &conversation.id)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|m| ProviderMessage::new(m.role, m.content)),
+        );
+        (
+            conversation.id,
+            created_conversation,
+            user_message.id,
+            provider,
+            model,
+            messages,
+        )
+    };
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let mut writer = conversation_stream_writer(
+        app.clone(),
+        conversation_id.clone(),
+        session_id.clone(),
+        None,
+    );
+    // The writer runs first so the reply is saved before the end is reported
+    let observer: StreamObserver = Box::new(move |update: StreamUpdate<'_>| {
+        writer(update);
+        let event = match update {
+            StreamUpdate::Chunk(chunk) => StreamEvent::Chunk(chunk.to_string()),
+            StreamUpdate::End { .. } => StreamEvent::End,
+            StreamUpdate::Error(error) => StreamEvent::Error(error.to_string()),
+        };
+        let _ = events.send(event);
+    });
+    if let Err(e) = start_stream(
+        app.clone(),
+        &provider,
+        session_id,
+        Some(model),
+        messages,
+        observer,
+    ) {
+        // Don't leave an unanswered user turn behind, e.g. when the API key is missing
+        let db = app.state::<Database>();
+        let discarded = db.conn().map_err(|e| e.to_string()).and_then(|conn| {
+            discard_prompt(
+                &conn,
+                &conversation_id,
+                created_conversation,
+                &user_message_id,
+            )
+            .map_err(|e| e.to_string())
+        });
+        if let Err(discard_error) = discarded {
+            eprintln!(
+                "failed to discard prompt for {}: {}",
+                conversation_id, discard_error
+            );
+        }
+        return Err(e);
+    }
+    Ok(Some(conversation_id))
+}
+
 #[tauri::command]
 pub fn ollama_list_models() -> Result<Vec<String>, String> {
     let client = reqwest::blocking::Client::new();
@@ -439,6 +2076,121 @@ pub fn ollama_pull_model(model: String) -> Result<String, String> {
     Ok(format!("Successfully pulled model: {}", model))
 }
 
+/// Build an `ollama-pull-progress` payload from one `/api/pull` NDJSON line
+fn pull_progress_payload(
+    session_id: &str,
+    model: &str,
+    line: &serde_json::Value,
+) -> serde_json::Value {
+    let completed = line["completed"].as_u64();
+    let total = line["total"].as_u64();
+    let percent = match (completed, total) {
+        (Some(done), Some(total)) if total > 0 => Some(done as f64 / total as f64 * 100.0),
+        _ => None,
+    };
+    serde_json::json!({
+        "session_id": session_id,
+        "model": model,
+        "status": line["status"].as_str().unwrap_or_default(),
+        "completed": completed,
+        "total": total,
+        "percent": percent,
+    })
+}
+
+/// Pull a model in the background, emitting `ollama-pull-progress` for each status
+/// line, then `ollama-pull-complete` or `ollama-pull-error`. Returns the session ID.
+#[tauri::command]
+pub fn ollama_pull_model_stream(app: tauri::AppHandle, model: String) -> Result<String, String> {
+    // Downloads can take far longer than the default request timeout
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let endpoint =
+        std::env::var("OLLAMA_ENDPOINT").unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let api_url = format!("{}/api/pull", endpoint);
+
+    let body = serde_json::json!({
+        "name": model,
+        "stream": true
+    });
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let session_id_clone = session_id.clone();
+    std::thread::spawn(move || {
+        let emit = |event: &str, payload: serde_json::Value| {
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.emit(event, payload);
+            }
+        };
+        let emit_error = |error: String| {
+            emit(
+                "ollama-pull-error",
+                serde_json::json!({
+                    "session_id": session_id_clone,
+                    "model": model,
+                    "error": error,
+                }),
+            )
+        };
+
+        let resp = match client.post(&api_url).json(&body).send() {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) => {
+                let status = r.status();
+                emit_error(format!(
+                    "Ollama pull failed {}: {}",
+                    status,
+                    r.text().unwrap_or_default()
+                ));
+                return;
+            }
+            Err(e) => {
+                emit_error(format!("Ollama pull request error: {}", e));
+                return;
+            }
+        };
+
+        let reader = std::io::BufReader::new(resp);
+        use std::io::BufRead;
+
+        for line in reader.lines().map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+
+            if let Some(error) = json["error"].as_str() {
+                emit_error(error.to_string());
+                return;
+            }
+
+            emit(
+                "ollama-pull-progress",
+                pull_progress_payload(&session_id_clone, &model, &json),
+            );
+
+            if json["status"].as_str() == Some("success") {
+                emit(
+                    "ollama-pull-complete",
+                    serde_json::json!({
+                        "session_id": session_id_clone,
+                        "model": model,
+                    }),
+                );
+                return;
+            }
+        }
+
+        emit_error("Ollama pull ended before completing".to_string());
+    });
+
+    Ok(session_id)
+}
+
 #[tauri::command]
 pub fn ollama_check_connection() -> Result<bool, String> {
     let client = reqwest::blocking::Client::new();
@@ -451,3 +2203,1189 @@ pub fn ollama_check_connection() -> Result<bool, String> {
         Err(_) => Ok(false),
     }
 }
+
+/// Per-request cap for provider health checks so one slow provider can't stall the dashboard
+const HEALTH_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub model: String,
+    pub is_available: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Send a lightweight request and report whether the provider answered successfully.
+async fn probe_provider(
+    provider: &str,
+    model: &str,
+    request: reqwest::RequestBuilder,
+) -> ProviderHealth {
+    let start = std::time::Instant::now();
+    let result = request.timeout(HEALTH_CHECK_TIMEOUT).send().await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let (is_available, latency_ms, error) = match result {
+        Ok(resp) if resp.status().is_success() => (true, Some(latency_ms), None),
+        Ok(resp) => (
+            false,
+            Some(latency_ms),
+            Some(format!("{} API returned {}", provider, resp.status())),
+        ),
+        Err(e) => (false, None, Some(format!("request error: {}", e))),
+    };
+
+    ProviderHealth {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        is_available,
+        latency_ms,
+        error,
+    }
+}
+
+async fn check_openai_health(
+    client: &reqwest::Client,
+    endpoint: &ProviderConfig,
+) -> Option<ProviderHealth> {
+    let api_key = prefer_keyring_or_env("openai", "OPENAI_API_KEY").ok()?;
+    let mut request = client
+        .get(openai_url(endpoint, "models"))
+        .bearer_auth(api_key);
+    if let Some(version) = endpoint.api_version.as_deref() {
+        request = request.query(&[("api-version", version)]);
+    }
+    for (name, value) in &endpoint.extra_headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    Some(probe_provider("openai", DEFAULT_OPENAI_MODEL, request).await)
+}
+
+async fn check_anthropic_health(client: &reqwest::Client) -> Option<ProviderHealth> {
+    let api_key = prefer_keyring_or_env("anthropic", "ANTHROPIC_API_KEY").ok()?;
+    let request = client
+        .get("https://api.anthropic.com/v1/models")
+        .header("x-api-key", api_key)
+        .header("anthropic-version", ANTHROPIC_API_VERSION);
+    Some(probe_provider("anthropic", DEFAULT_ANTHROPIC_MODEL, request).await)
+}
+
+async fn check_gemini_health(client: &reqwest::Client) -> Option<ProviderHealth> {
+    let api_key = prefer_keyring_or_env("gemini", "GEMINI_API_KEY").ok()?;
+    let request = client
+        .get("https://generativelanguage.googleapis.com/v1beta/models")
+        .header("x-goog-api-key", api_key);
+    Some(probe_provider("gemini", DEFAULT_GEMINI_MODEL, request).await)
+}
+
+async fn check_ollama_health() -> ProviderHealth {
+    let start = std::time::Instant::now();
+    // ollama_check_connection uses the blocking client, so keep it off the async workers
+    let check = tokio::task::spawn_blocking(ollama_check_connection);
+    let result = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let (is_available, latency_ms, error) = match result {
+        Ok(Ok(Ok(true))) => (true, Some(latency_ms), None),
+        Ok(Ok(Ok(false))) => (false, None, Some("Ollama is not reachable".to_string())),
+        Ok(Ok(Err(e))) => (false, None, Some(e)),
+        Ok(Err(e)) => (false, None, Some(format!("health check failed: {}", e))),
+        Err(_) => (
+            false,
+            None,
+            Some(format!(
+                "timed out after {}s",
+                HEALTH_CHECK_TIMEOUT.as_secs()
+            )),
+        ),
+    };
+
+    ProviderHealth {
+        provider: "ollama".to_string(),
+        model: DEFAULT_OLLAMA_MODEL.to_string(),
+        is_available,
+        latency_ms,
+        error,
+    }
+}
+
+/// Check every configured provider concurrently. Cloud providers without an API key are
+/// skipped; Ollama is always checked since it needs no key.
+#[tauri::command]
+pub async fn check_all_providers_health(
+    app: tauri::AppHandle,
+) -> Result<Vec<ProviderHealth>, String> {
+    let client = reqwest::Client::new();
+    let openai_endpoint = load_provider_config(&app, "openai")?;
+
+    let (openai, anthropic, gemini, ollama) = tokio::join!(
+        check_openai_health(&client, &openai_endpoint),
+        check_anthropic_health(&client),
+        check_gemini_health(&client),
+        check_ollama_health(),
+    );
+
+    Ok([openai, anthropic, gemini, Some(ollama)]
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+fn parse_embedding(value: &serde_json::Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+fn openai_embeddings(
+    model: Option<String>,
+    texts: Vec<String>,
+    endpoint: &ProviderConfig,
+) -> Result<Vec<Vec<f32>>, String> {
+    let api_key = prefer_keyring_or_env("openai", "OPENAI_API_KEY")?;
+    let client = openai_client(endpoint)?;
+    let api_url = openai_url(endpoint, "embeddings");
+
+    let body = serde_json::json!({
+        "model": model.unwrap_or_else(|| "text-embedding-3-small".to_string()),
+        "input": texts,
+    });
+
+    let request = client.post(api_url).bearer_auth(api_key).json(&body);
+    let resp = with_openai_endpoint(request, endpoint)
+        .send()
+        .map_err(|e| format!("request error: {}", e))?;
+
+    let status = resp.status();
+    let json: serde_json::Value = resp
+        .json()
+        .map_err(|e| format!("json parse error: {}", e))?;
+
+    if !status.is_success() {
+        return Err(format!("OpenAI API returned {}: {}", status, json));
+    }
+
+    // Results carry an explicit index; sort by it so output lines up with the input texts
+    let mut data: Vec<&serde_json::Value> = json["data"]
+        .as_array()
+        .ok_or_else(|| "OpenAI response missing data".to_string())?
+        .iter()
+        .collect();
+    data.sort_by_key(|d| d["index"].as_u64().unwrap_or(0));
+
+    data.into_iter()
+        .map(|d| {
+            parse_embedding(&d["embedding"])
+                .ok_or_else(|| "OpenAI response contained an invalid embedding".to_string())
+        })
+        .collect()
+}
+
+fn ollama_embeddings(model: Option<String>, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+    let client = reqwest::blocking::Client::new();
+    let endpoint =
+        std::env::var("OLLAMA_ENDPOINT").unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let api_url = format!("{}/api/embed", endpoint);
+
+    let body = serde_json::json!({
+        "model": model.unwrap_or_else(|| "nomic-embed-text".to_string()),
+        "input": texts,
+    });
+
+    let resp = client
+        .post(&api_url)
+        .json(&body)
+        .send()
+        .map_err(|e| format!("Ollama request error: {}", e))?;
+
+    let status = resp.status();
+    let json: serde_json::Value = resp
+        .json()
+        .map_err(|e| format!("json parse error: {}", e))?;
+
+    if !status.is_success() {
+        return Err(format!("Ollama API returned {}: {}", status, json));
+    }
+
+    json["embeddings"]
+        .as_array()
+        .ok_or_else(|| "Ollama response missing embeddings".to_string())?
+        .iter()
+        .map(|e| {
+            parse_embedding(e)
+                .ok_or_else(|| "Ollama response contained an invalid embedding".to_string())
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn generate_embeddings_batch(
+    app: tauri::AppHandle,
+    provider: String,
+    model: Option<String>,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+    wait_for_rate_limit(&app, &provider).await?;
+    let endpoint = load_provider_config(&app, &provider)?;
+
+    tauri::async_runtime::spawn_blocking(move || match provider.as_str() {
+        "openai" => openai_embeddings(model, texts, &endpoint),
+        "ollama" => ollama_embeddings(model, texts),
+        _ => Err(format!(
+            "Embeddings are not supported for provider: {}",
+            provider
+        )),
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn generate_embedding(
+    app: tauri::AppHandle,
+    provider: String,
+    model: Option<String>,
+    text: String,
+) -> Result<Vec<f32>, String> {
+    generate_embeddings_batch(app, provider, model, vec![text])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No embedding returned".to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenCountResult {
+    pub estimated_tokens: usize,
+    /// `"tiktoken"` for models with a known tokenizer, otherwise `"approximation"`
+    pub method: String,
+}
+
+/// Known context windows by model name prefix; longer prefixes win
+const CONTEXT_LIMITS: &[(&str, usize)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("claude-3", 200_000),
+    ("claude", 100_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-1.5-flash", 1_048_576),
+    ("gemini-pro", 32_760),
+    ("llama3.2", 131_072),
+    ("llama3.1", 131_072),
+    ("llama3", 8_192),
+    ("mistral", 32_768),
+];
+const DEFAULT_CONTEXT_LIMIT: usize = 4096;
+
+/// Heuristic for models without a public tokenizer: `words * 1.3 + punctuation * 0.5`
+fn approximate_tokens(text: &str) -> usize {
+    let words = text.split_whitespace().count() as f64;
+    let punctuation = text.chars().filter(|c| c.is_ascii_punctuation()).count() as f64;
+    (words * 1.3 + punctuation * 0.5).ceil() as usize
+}
+
+/// Count tokens with the model's tiktoken encoding, if it has one
+fn tiktoken_count(messages: &[ProviderMessage], model: &str) -> Option<usize> {
+    use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+
+    let bpe = match get_tokenizer(model)? {
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        Tokenizer::P50kBase => tiktoken_rs::p50k_base_singleton(),
+        Tokenizer::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    };
+    let bpe = bpe.lock();
+
+    // Chat formatting adds ~3 tokens per message plus 3 to prime the reply
+    let tokens = messages
+        .iter()
+        .map(|m| {
+            3 + bpe.encode_with_special_tokens(&m.role).len()
+                + bpe.encode_with_special_tokens(&m.text()).len()
+        })
+        .sum::<usize>()
+        + 3;
+    Some(tokens)
+}
+
+#[tauri::command]
+pub fn token_count_estimate(
+    messages: Vec<ProviderMessage>,
+    model: String,
+) -> Result<TokenCountResult, String> {
+    let result = match tiktoken_count(&messages, &model) {
+        Some(estimated_tokens) => TokenCountResult {
+            estimated_tokens,
+            method: "tiktoken".to_string(),
+        },
+        None => TokenCountResult {
+            estimated_tokens: messages.iter().map(|m| approximate_tokens(&m.text())).sum(),
+            method: "approximation".to_string(),
+        },
+    };
+    Ok(result)
+}
+
+/// Context window from `CONTEXT_LIMITS`, matching the longest known prefix
+fn known_context_limit(model: &str) -> Option<usize> {
+    CONTEXT_LIMITS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, limit)| *limit)
+}
+
+#[tauri::command]
+pub fn context_limit_for_model(model: String) -> Result<usize, String> {
+    Ok(known_context_limit(&model).unwrap_or(DEFAULT_CONTEXT_LIMIT))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelInfo {
+    pub id: String,
+    pub owned_by: String,
+    pub context_window: Option<usize>,
+}
+
+/// How long model lists fetched from provider APIs are reused
+const MODEL_LIST_TTL: Duration = Duration::from_secs(300);
+
+/// Model lists per provider and when they were fetched
+type ModelListCache = HashMap<String, (Instant, Vec<ModelInfo>)>;
+
+static MODEL_LIST_CACHE: OnceLock<Mutex<ModelListCache>> = OnceLock::new();
+
+/// Return the cached list for `provider` if it is fresh, otherwise call `fetch`
+/// and cache its result. Errors are not cached.
+fn cached_models(
+    provider: &str,
+    fetch: impl FnOnce() -> Result<Vec<ModelInfo>, String>,
+) -> Result<Vec<ModelInfo>, String> {
+    let cache = MODEL_LIST_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((fetched_at, models)) = cache.lock().map_err(|e| e.to_string())?.get(provider) {
+        if fetched_at.elapsed() < MODEL_LIST_TTL {
+            return Ok(models.clone());
+        }
+    }
+
+    // Fetch without holding the lock so other providers aren't blocked
+    let models = fetch()?;
+    cache
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(provider.to_string(), (Instant::now(), models.clone()));
+    Ok(models)
+}
+
+/// GET a model listing endpoint and return its JSON body
+fn fetch_model_list(
+    request: reqwest::blocking::RequestBuilder,
+    provider: &str,
+) -> Result<serde_json::Value, String> {
+    let resp = request
+        .send()
+        .map_err(|e| format!("{} request error: {}", provider, e))?;
+    let status = resp.status();
+    let json: serde_json::Value = resp
+        .json()
+        .map_err(|e| format!("json parse error: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("{} API returned {}: {}", provider, status, json));
+    }
+    Ok(json)
+}
+
+fn parse_openai_models(json: &serde_json::Value) -> Vec<ModelInfo> {
+    let mut models: Vec<ModelInfo> = json["data"]
+        .as_array()
+        .map(|data| data.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|model| {
+            let id = model["id"].as_str()?;
+            Some(ModelInfo {
+                id: id.to_string(),
+                owned_by: model["owned_by"].as_str().unwrap_or("openai").to_string(),
+                context_window: known_context_limit(id),
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+fn parse_anthropic_models(json: &serde_json::Value) -> Vec<ModelInfo> {
+    json["data"]
+        .as_array()
+        .map(|data| data.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|model| {
+            let id = model["id"].as_str()?;
+            Some(ModelInfo {
+                id: id.to_string(),
+                owned_by: "anthropic".to_string(),
+                context_window: known_context_limit(id),
+            })
+        })
+        .collect()
+}
+
+/// Gemini names models `models/<id>` and reports the input token limit itself
+fn parse_gemini_models(json: &serde_json::Value) -> Vec<ModelInfo> {
+    json["models"]
+        .as_array()
+        .map(|models| models.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|model| {
+            let name = model["name"].as_str()?;
+            let id = name.strip_prefix("models/").unwrap_or(name);
+            Some(ModelInfo {
+                id: id.to_string(),
+                owned_by: "google".to_string(),
+                context_window: model["inputTokenLimit"]
+                    .as_u64()
+                    .map(|limit| limit as usize)
+                    .or_else(|| known_context_limit(id)),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_openai_models(app: tauri::AppHandle) -> Result<Vec<ModelInfo>, String> {
+    openai_models(&load_provider_config(&app, "openai")?)
+}
+
+fn openai_models(endpoint: &ProviderConfig) -> Result<Vec<ModelInfo>, String> {
+    cached_models("openai", || {
+        let api_key = prefer_keyring_or_env("openai", "OPENAI_API_KEY")?;
+        let request = openai_client(endpoint)?
+            .get(openai_url(endpoint, "models"))
+            .bearer_auth(api_key);
+        let request = with_openai_endpoint(request, endpoint);
+        Ok(parse_openai_models(&fetch_model_list(request, "OpenAI")?))
+    })
+}
+
+#[tauri::command]
+pub fn list_anthropic_models() -> Result<Vec<ModelInfo>, String> {
+    cached_models("anthropic", || {
+        let api_key = prefer_keyring_or_env("anthropic", "ANTHROPIC_API_KEY")?;
+        let request = reqwest::blocking::Client::new()
+            .get("https://api.anthropic.com/v1/models")
+            .query(&[("limit", "1000")])
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION);
+        Ok(parse_anthropic_models(&fetch_model_list(
+            request,
+            "Anthropic",
+        )?))
+    })
+}
+
+#[tauri::command]
+pub fn list_gemini_models() -> Result<Vec<ModelInfo>, String> {
+    cached_models("gemini", || {
+        let api_key = prefer_keyring_or_env("gemini", "GEMINI_API_KEY")?;
+        let request = reqwest::blocking::Client::new()
+            .get("https://generativelanguage.googleapis.com/v1beta/models")
+            .query(&[("key", api_key.as_str()), ("pageSize", "1000")]);
+        Ok(parse_gemini_models(&fetch_model_list(request, "Gemini")?))
+    })
+}
+
+/// Models for any supported provider; Ollama lists the locally pulled models
+#[tauri::command]
+pub fn list_all_provider_models(
+    app: tauri::AppHandle,
+    provider: String,
+) -> Result<Vec<ModelInfo>, String> {
+    provider_models(&provider, &load_provider_config(&app, &provider)?)
+}
+
+/// Models for `provider`, reached through its saved endpoint settings
+fn provider_models(provider: &str, endpoint: &ProviderConfig) -> Result<Vec<ModelInfo>, String> {
+    match provider {
+        "openai" => openai_models(endpoint),
+        "anthropic" => list_anthropic_models(),
+        "gemini" => list_gemini_models(),
+        "ollama" => cached_models("ollama", || {
+            Ok(ollama_list_models()?
+                .into_iter()
+                .map(|id| ModelInfo {
+                    context_window: known_context_limit(&id),
+                    id,
+                    owned_by: "ollama".to_string(),
+                })
+                .collect())
+        }),
+        other => Err(format!("Unsupported provider: {}", other)),
+    }
+}
+
+/// Most clipboard entries kept in history
+const CLIPBOARD_HISTORY_LIMIT: usize = 20;
+/// Longer copies, such as whole files, are left out of the history
+const CLIPBOARD_ENTRY_MAX_BYTES: usize = 10 * 1024;
+const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Setting that opts in to clipboard history; it is off unless set to `true`
+pub const CLIPBOARD_HISTORY_ENABLED_KEY: &str = "clipboard_history_enabled";
+
+/// Copied text, oldest first
+static CLIPBOARD_HISTORY: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
+/// Whether a watcher should be running
+static CLIPBOARD_WATCHING: AtomicBool = AtomicBool::new(false);
+/// Bumped on every start and stop, so a stopped watcher's thread exits even if
+/// watching was turned back on before it noticed
+static CLIPBOARD_WATCHER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn clipboard_history_store() -> &'static Mutex<VecDeque<String>> {
+    CLIPBOARD_HISTORY.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Add `text` as the newest entry, dropping an older copy of the same text and
+/// the oldest entry once over the limit. Blank and oversized text is skipped.
+/// Returns false if nothing changed.
+fn record_clipboard_entry(history: &mut VecDeque<String>, text: String) -> bool {
+    if text.trim().is_empty()
+        || text.len() > CLIPBOARD_ENTRY_MAX_BYTES
+        || history.back() == Some(&text)
+    {
+        return false;
+    }
+    history.retain(|entry| entry != &text);
+    history.push_back(text);
+    while history.len() > CLIPBOARD_HISTORY_LIMIT {
+        history.pop_front();
+    }
+    true
+}
+
+/// Clipboard history, newest first
+pub fn clipboard_history() -> Vec<String> {
+    clipboard_history_store()
+        .lock()
+        .map(|history| history.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Whether a stored `clipboard_history_enabled` value turns the history on
+pub fn clipboard_history_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Start or stop recording the clipboard. Stopping also forgets what was recorded.
+pub fn set_clipboard_watching(app: &tauri::AppHandle, enabled: bool) {
+    let was_watching = CLIPBOARD_WATCHING.swap(enabled, Ordering::SeqCst);
+    if enabled == was_watching {
+        return;
+    }
+    let generation = CLIPBOARD_WATCHER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if enabled {
+        start_clipboard_watcher(app.clone(), generation);
+    } else {
+        if let Ok(mut history) = clipboard_history_store().lock() {
+            history.clear();
+        }
+        crate::tray::refresh_tray_menu(app);
+    }
+}
+
+/// Start watching the clipboard at launch if the history setting is on
+pub fn init_clipboard_watcher(app: &tauri::AppHandle) {
+    let value = app.state::<Database>().conn().ok().and_then(|conn| {
+        Setting::get(&conn, CLIPBOARD_HISTORY_ENABLED_KEY)
+            .ok()
+            .flatten()
+    });
+    set_clipboard_watching(app, clipboard_history_enabled(value.as_deref()));
+}
+
+/// Poll the clipboard in a background thread, recording new text entries and
+/// refreshing the tray's Clipboard History submenu when they change, until
+/// watching is stopped
+fn start_clipboard_watcher(app: tauri::AppHandle, generation: u64) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    std::thread::spawn(move || {
+        let mut last_seen: Option<String> = None;
+        loop {
+            std::thread::sleep(CLIPBOARD_POLL_INTERVAL);
+            if CLIPBOARD_WATCHER_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let Ok(text) = app.clipboard().read_text() else {
+                continue;
+            };
+            if last_seen.as_ref() == Some(&text) {
+                continue;
+            }
+            last_seen = Some(text.clone());
+
+            let changed = match clipboard_history_store().lock() {
+                Ok(mut history) => record_clipboard_entry(&mut history, text),
+                Err(_) => false,
+            };
+            if changed {
+                crate::tray::refresh_tray_menu(&app);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_clipboard_history() -> Result<Vec<String>, String> {
+    Ok(clipboard_history())
+}
+
+#[tauri::command]
+pub fn clear_clipboard_history(app: tauri::AppHandle) -> Result<(), String> {
+    clipboard_history_store()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clear();
+    crate::tray::refresh_tray_menu(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_message_accepts_string_or_parts() {
+        let plain: ProviderMessage =
+            serde_json::from_str(r#"{"role": "user", "content": "hi"}"#).unwrap();
+        assert_eq!(plain.content, vec![MessageContent::Text("hi".to_string())]);
+        assert_eq!(openai_content(&plain.content), serde_json::json!("hi"));
+        assert_eq!(
+            serde_json::to_value(&plain).unwrap(),
+            serde_json::json!({"role": "user", "content": "hi"})
+        );
+
+        let vision: ProviderMessage = serde_json::from_str(
+            r#"{"role": "user", "content": ["What is this?", {"url": "https://example.com/cat.png", "detail": "low"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(vision.text(), "What is this?");
+        assert_eq!(
+            openai_content(&vision.content),
+            serde_json::json!([
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png", "detail": "low"}}
+            ])
+        );
+
+        assert_eq!(
+            image_data_url("/9j/4AAQ"),
+            "data:image/jpeg;base64,/9j/4AAQ"
+        );
+        assert_eq!(
+            image_data_url("iVBORw0KGgo"),
+            "data:image/png;base64,iVBORw0KGgo"
+        );
+        assert_eq!(
+            image_data_url("data:image/gif;base64,R0lG"),
+            "data:image/gif;base64,R0lG"
+        );
+    }
+
+    #[test]
+    fn forwards_gemini_sse_chunks_and_skips_malformed_lines() {
+        let stream = r#"data: {"candidates":[{"content":{"parts":[{"text":"Hel"}],"role":"model"}}]}
+
+data: {not json
+
+data: {"candidates":[{"content":{"parts":[{"text":"lo"}],"role":"model"},"finishReason":"STOP"}],"usageMetadata":{"candidatesTokenCount":2}}
+"#;
+        let mut chunks = Vec::new();
+        let tokens = forward_gemini_sse(stream.as_bytes(), |text| chunks.push(text.to_string()));
+        assert_eq!(chunks, vec!["Hel", "lo"]);
+        assert_eq!(tokens, Ok(Some(2)));
+    }
+
+    #[test]
+    fn image_parts_are_rejected_for_text_only_providers() {
+        let text = vec![ProviderMessage::new("user", "describe this")];
+        assert!(require_text_only(&text, "ollama").is_ok());
+
+        let with_image = vec![ProviderMessage {
+            role: "user".to_string(),
+            content: vec![
+                MessageContent::Text("describe this".to_string()),
+                MessageContent::Image {
+                    url: "data:image/png;base64,iVBOR".to_string(),
+                    detail: None,
+                },
+            ],
+        }];
+        assert_eq!(
+            require_text_only(&with_image, "anthropic").unwrap_err(),
+            "Images are not supported for provider: anthropic"
+        );
+    }
+
+    #[test]
+    fn gemini_sse_reports_truncated_and_failed_streams() {
+        struct Disconnect;
+        impl std::io::Read for Disconnect {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::ConnectionReset.into())
+            }
+        }
+
+        let partial = r#"data: {"candidates":[{"content":{"parts":[{"text":"Hel"}]}}]}
+"#;
+        assert_eq!(
+            forward_gemini_sse(partial.as_bytes(), |_| {}),
+            Err("Gemini stream ended before the reply finished".to_string())
+        );
+
+        let mut chunks = Vec::new();
+        let reader = std::io::BufReader::new(std::io::Read::chain(partial.as_bytes(), Disconnect));
+        let result = forward_gemini_sse(reader, |text| chunks.push(text.to_string()));
+        assert!(result.unwrap_err().starts_with("stream read error"));
+        assert_eq!(chunks, vec!["Hel"]);
+    }
+
+    #[test]
+    fn parses_anthropic_sse_stream() {
+        let stream = r#"event: message_start
+data: {"type":"message_start","message":{"id":"msg_1"}}
+
+event: ping
+data: {"type": "ping"}
+
+event: content_block_delta
+data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}
+
+event: message_delta
+data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":12}}
+
+event: message_stop
+data: {"type":"message_stop"}
+"#;
+        let events: Vec<AnthropicStreamEvent> = stream
+            .lines()
+            .filter_map(sse_data)
+            .filter_map(parse_anthropic_stream_event)
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                AnthropicStreamEvent::Text("Hello".to_string()),
+                AnthropicStreamEvent::OutputTokens(12),
+                AnthropicStreamEvent::Stop,
+            ]
+        );
+        assert_eq!(
+            parse_anthropic_stream_event(
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+            ),
+            Some(AnthropicStreamEvent::Error("Overloaded".to_string()))
+        );
+
+        let (system, turns) = anthropic_messages(vec![
+            ProviderMessage::new("system", "Be brief"),
+            ProviderMessage::new("user", "Hi"),
+        ]);
+        assert_eq!(system.as_deref(), Some("Be brief"));
+        assert_eq!(
+            turns,
+            vec![serde_json::json!({"role": "user", "content": "Hi"})]
+        );
+    }
+
+    #[test]
+    fn parses_provider_model_lists() {
+        let openai = serde_json::json!({"data": [
+            {"id": "gpt-4o", "owned_by": "system"},
+            {"id": "dall-e-3", "owned_by": "openai"}
+        ]});
+        let models = parse_openai_models(&openai);
+        assert_eq!(models[0].id, "dall-e-3");
+        assert_eq!(models[0].context_window, None);
+        assert_eq!(models[1].owned_by, "system");
+        assert_eq!(models[1].context_window, known_context_limit("gpt-4o"));
+
+        let anthropic = serde_json::json!({"data": [{"id": "claude-3-5-sonnet-20241022"}]});
+        assert_eq!(parse_anthropic_models(&anthropic)[0].owned_by, "anthropic");
+
+        let gemini = serde_json::json!({"models": [
+            {"name": "models/gemini-1.5-pro", "inputTokenLimit": 2_000_000}
+        ]});
+        let models = parse_gemini_models(&gemini);
+        assert_eq!(models[0].id, "gemini-1.5-pro");
+        assert_eq!(models[0].context_window, Some(2_000_000));
+        assert!(parse_gemini_models(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn model_lists_are_cached() {
+        let model = ModelInfo {
+            id: "cached-model".to_string(),
+            owned_by: "test".to_string(),
+            context_window: None,
+        };
+        let first = cached_models("cache-test", || Ok(vec![model.clone()])).unwrap();
+        let second = cached_models("cache-test", || Err("should not fetch".to_string())).unwrap();
+        assert_eq!(first, second);
+        assert!(cached_models("cache-test-error", || Err("offline".to_string())).is_err());
+        assert!(provider_models("mystery", &ProviderConfig::default()).is_err());
+    }
+
+    #[test]
+    fn token_bucket_allows_burst_then_waits() {
+        let mut bucket = TokenBucket::new(2.0, 1.0);
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_ok());
+
+        let wait = bucket.try_take().expect_err("bucket should be empty");
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn token_count_uses_tiktoken_for_openai_models() {
+        let messages = vec![ProviderMessage::new("user", "hello world")];
+
+        let openai = token_count_estimate(messages, "gpt-4".to_string()).unwrap();
+        assert_eq!(openai.method, "tiktoken");
+        // 3 framing + "user" (1) + "hello world" (2) + 3 reply priming
+        assert_eq!(openai.estimated_tokens, 9);
+
+        let other = token_count_estimate(
+            vec![ProviderMessage::new("user", "Hello, world!")],
+            "llama3.2".to_string(),
+        )
+        .unwrap();
+        assert_eq!(other.method, "approximation");
+        // 2 words * 1.3 + 2 punctuation * 0.5 = 3.6
+        assert_eq!(other.estimated_tokens, 4);
+    }
+
+    #[test]
+    fn context_limit_prefers_longest_prefix() {
+        assert_eq!(context_limit_for_model("gpt-4".into()).unwrap(), 8_192);
+        assert_eq!(
+            context_limit_for_model("gpt-4o-mini".into()).unwrap(),
+            128_000
+        );
+        assert_eq!(
+            context_limit_for_model("mystery-model".into()).unwrap(),
+            4096
+        );
+    }
+
+    #[test]
+    fn clipboard_history_dedupes_and_caps() {
+        let mut history = VecDeque::new();
+        assert!(record_clipboard_entry(&mut history, "a".to_string()));
+        assert!(!record_clipboard_entry(&mut history, "a".to_string()));
+        assert!(!record_clipboard_entry(&mut history, "   ".to_string()));
+        assert!(!record_clipboard_entry(
+            &mut history,
+            "x".repeat(CLIPBOARD_ENTRY_MAX_BYTES + 1)
+        ));
+        assert!(record_clipboard_entry(&mut history, "b".to_string()));
+        assert!(record_clipboard_entry(&mut history, "a".to_string()));
+        assert_eq!(history, ["b", "a"]);
+
+        for i in 0..30 {
+            record_clipboard_entry(&mut history, i.to_string());
+        }
+        assert_eq!(history.len(), CLIPBOARD_HISTORY_LIMIT);
+        assert_eq!(history.back().map(String::as_str), Some("29"));
+    }
+
+    #[test]
+    fn clipboard_history_is_off_unless_enabled() {
+        assert!(!clipboard_history_enabled(None));
+        assert!(!clipboard_history_enabled(Some("false")));
+        assert!(!clipboard_history_enabled(Some("")));
+        assert!(clipboard_history_enabled(Some("true")));
+        assert!(clipboard_history_enabled(Some(" TRUE ")));
+    }
+
+    #[test]
+    fn pull_progress_reports_percent_when_sized() {
+        let line = serde_json::json!({"status": "downloading", "completed": 25, "total": 100});
+        let payload = pull_progress_payload("s1", "llama3.2", &line);
+        assert_eq!(payload["status"], "downloading");
+        assert_eq!(payload["percent"], 25.0);
+
+        let line = serde_json::json!({"status": "verifying sha256 digest"});
+        let payload = pull_progress_payload("s1", "llama3.2", &line);
+        assert!(payload["percent"].is_null());
+        assert!(payload["total"].is_null());
+    }
+
+    #[test]
+    fn json_mode_adds_instructions_and_schema() {
+        let schema =
+            serde_json::json!({"type": "object", "properties": {"ok": {"type": "boolean"}}});
+        let messages =
+            with_json_instructions(vec![ProviderMessage::new("user", "status?")], Some(&schema));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "system");
+        assert!(messages[0].text().contains("JSON"));
+        assert!(messages[0].text().contains("\"boolean\""));
+
+        let messages =
+            with_json_instructions(vec![ProviderMessage::new("system", "Be terse.")], None);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].text().starts_with("Be terse."));
+        assert!(messages[0].text().contains("JSON"));
+
+        let format = json_schema_response_format(schema.clone(), false);
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["schema"], schema);
+        assert_eq!(format["json_schema"]["strict"], false);
+        assert_eq!(
+            json_schema_response_format(schema.clone(), true)["json_schema"]["strict"],
+            true
+        );
+        let named = serde_json::json!({"name": "status", "schema": schema});
+        assert_eq!(
+            json_schema_response_format(named.clone(), false)["json_schema"],
+            named
+        );
+
+        assert_eq!(
+            parse_json_content(" {\"ok\": true}\n").unwrap(),
+            serde_json::json!({"ok": true})
+        );
+        assert!(parse_json_content("not json").is_err());
+    }
+
+    #[test]
+    fn system_prompt_leads_and_profile_sets_temperature() {
+        let messages = prepend_system_prompt(
+            "You are terse.".to_string(),
+            vec![ProviderMessage::new("user", "hi")],
+        );
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(messages[0].text(), "You are terse.");
+        assert_eq!(prepend_system_prompt("  ".to_string(), Vec::new()).len(), 0);
+
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let profile_id = {
+            let conn = db.conn().unwrap();
+            let profile = crate::database::profiles::Profile::create(
+                &conn,
+                crate::database::profiles::NewProfile {
+                    name: "Creative".to_string(),
+                    description: None,
+                    default_model: "gpt-4o".to_string(),
+                    default_provider: "openai".to_string(),
+                    system_prompt: None,
+                },
+            )
+            .unwrap();
+            profile.id
+        };
+        assert_eq!(profile_temperature(&db, &profile_id).unwrap(), None);
+
+        {
+            let conn = db.conn().unwrap();
+            crate::database::profiles::Profile::set_temperature(&conn, &profile_id, Some(1.2))
+                .unwrap();
+        }
+        assert_eq!(profile_temperature(&db, &profile_id).unwrap(), Some(1.2));
+        assert!(profile_temperature(&db, "missing").is_err());
+    }
+
+    #[test]
+    fn streamed_reply_saves_the_full_text() {
+        let original = "Here is the fix:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n";
+        // OpenAI replies are split into words for display only
+        let streamed = "Here is the fix: ```rust fn main() { println!(\"hi\"); } ``` ";
+        assert_eq!(finished_reply(streamed, Some(original)), original);
+        assert_eq!(finished_reply("partial reply ", None), "partial reply");
+
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let conversation = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Fix".to_string(),
+                model: "gpt-4o".to_string(),
+                provider: "openai".to_string(),
+                system_prompt: None,
+            },
+        )
+        .unwrap();
+        save_streamed_reply(
+            &conn,
+            &conversation.id,
+            None,
+            finished_reply(streamed, Some(original)),
+            Some(12),
+        )
+        .unwrap();
+
+        let messages = Message::get_by_conversation(&conn, &conversation.id).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "assistant");
+        assert_eq!(messages[0].content, original);
+        assert_eq!(messages[0].tokens_used, Some(12));
+
+        // A regenerated reply replaces the old one only when it is saved
+        let replacement = save_streamed_reply(
+            &conn,
+            &conversation.id,
+            Some(&messages[0].id),
+            "Try this",
+            None,
+        )
+        .unwrap();
+        let messages = Message::get_by_conversation(&conn, &conversation.id).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, replacement.id);
+        assert_eq!(messages[0].content, "Try this");
+    }
+
+    #[test]
+    fn display_chunks_join_back_into_the_reply() {
+        let reply = "  Steps:\n\n1. Run `cargo build`\n    indented\tcode \n";
+        let chunks = display_chunks(reply);
+        assert_eq!(chunks.concat(), reply);
+        assert_eq!(&chunks[..3], ["  ", "Steps:\n\n", "1. "]);
+        assert!(display_chunks("").is_empty());
+        assert_eq!(display_chunks("word"), ["word"]);
+    }
+
+    #[test]
+    fn maps_generation_config_per_provider() {
+        let defaults = GenerationConfig::default();
+        let openai = openai_generation_params(&defaults);
+        assert_eq!(openai.len(), 1);
+        assert_eq!(openai["temperature"], serde_json::json!(0.7));
+        assert_eq!(
+            anthropic_generation_params(&defaults)["max_tokens"],
+            serde_json::json!(1024)
+        );
+        assert!(gemini_generation_config(&defaults).is_none());
+
+        let config = GenerationConfig {
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            max_tokens: Some(256),
+            frequency_penalty: Some(0.5),
+            presence_penalty: None,
+            stop: Some(vec!["END".to_string()]),
+            response_format: Some(serde_json::json!({"type": "json_object"})),
+        };
+        let openai = openai_generation_params(&config);
+        assert_eq!(openai["temperature"], serde_json::json!(0.2));
+        assert_eq!(openai["max_tokens"], serde_json::json!(256));
+        assert_eq!(openai["stop"], serde_json::json!(["END"]));
+        assert!(!openai.contains_key("presence_penalty"));
+        assert_eq!(openai["response_format"]["type"], "json_object");
+
+        let anthropic = anthropic_generation_params(&config);
+        assert_eq!(anthropic["max_tokens"], serde_json::json!(256));
+        assert_eq!(anthropic["stop_sequences"], serde_json::json!(["END"]));
+        assert!(!anthropic.contains_key("frequency_penalty"));
+
+        let gemini = gemini_generation_config(&config).unwrap();
+        assert_eq!(gemini["maxOutputTokens"], serde_json::json!(256));
+        assert_eq!(gemini["topP"], serde_json::json!(0.9));
+        assert_eq!(gemini["frequencyPenalty"], serde_json::json!(0.5));
+        assert!(gemini.get("presencePenalty").is_none());
+    }
+
+    #[test]
+    fn builds_openai_url_from_provider_config() {
+        let mut endpoint = ProviderConfig {
+            provider: "openai".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            openai_url(&endpoint, "chat/completions"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            openai_url(&endpoint, "models"),
+            "https://api.openai.com/v1/models"
+        );
+
+        endpoint.base_url =
+            Some("https://example.openai.azure.com/openai/deployments/gpt4/".to_string());
+        assert_eq!(
+            openai_url(&endpoint, "chat/completions"),
+            "https://example.openai.azure.com/openai/deployments/gpt4/chat/completions"
+        );
+        assert_eq!(
+            openai_url(&endpoint, "embeddings"),
+            "https://example.openai.azure.com/openai/deployments/gpt4/embeddings"
+        );
+    }
+
+    #[test]
+    fn prompt_title_uses_shortened_first_line() {
+        assert_eq!(
+            prompt_title("  Explain lifetimes\nwith examples"),
+            "Explain lifetimes"
+        );
+        let title = prompt_title(&"word ".repeat(30));
+        assert_eq!(title.chars().count(), PROMPT_TITLE_CHARS);
+        assert!(title.ends_with('…'));
+    }
+
+    #[test]
+    fn discard_prompt_removes_what_the_prompt_saved() {
+        let db = Database::new(std::path::PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let conversation = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Chat".to_string(),
+                model: "gpt-4o".to_string(),
+                provider: "openai".to_string(),
+                system_prompt: None,
+            },
+        )
+        .unwrap();
+        let user_turn = |content: &str| {
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conversation.id.clone(),
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                    tokens_used: None,
+                },
+            )
+            .unwrap()
+        };
+        let earlier = user_turn("first");
+        let failed = user_turn("second");
+
+        // Continuing a conversation only drops the new user turn
+        discard_prompt(&conn, &conversation.id, false, &failed.id).unwrap();
+        let remaining = Message::get_by_conversation(&conn, &conversation.id).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, earlier.id);
+        assert!(!Message::exists(&conn, &failed.id).unwrap());
+
+        // A conversation started for the prompt goes away entirely
+        discard_prompt(&conn, &conversation.id, true, &earlier.id).unwrap();
+        assert!(!Conversation::exists(&conn, &conversation.id).unwrap());
+        assert!(!Message::exists(&conn, &earlier.id).unwrap());
+    }
+}
@@ -1,10 +1,23 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+/// Languages accepted by `run_code`
+const SUPPORTED_LANGUAGES: [&str; 6] = ["bash", "sh", "zsh", "python", "node", "javascript"];
+
+/// How long interpreter probes from `list_supported_languages` are reused
+const LANGUAGE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Probe results and when they were taken
+type LanguageCache = Option<(Instant, Vec<LanguageAvailability>)>;
+
+static LANGUAGE_CACHE: OnceLock<Mutex<LanguageCache>> = OnceLock::new();
+
 #[derive(Serialize, Debug)]
 pub struct RunResult {
     pub stdout: String,
@@ -13,6 +26,14 @@ pub struct RunResult {
     pub timed_out: bool,
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct LanguageAvailability {
+    pub language: String,
+    pub is_available: bool,
+    pub version: Option<String>,
+    pub binary_path: Option<String>,
+}
+
 /// Execute user-provided code snippet safely in a temporary file and return output.
 /// Only a small whitelist of languages is supported.
 #[tauri::command]
@@ -24,8 +45,7 @@ pub async fn run_code(
 ) -> Result<RunResult, String> {
     // Whitelist languages we support
     let lang = language.to_lowercase();
-    let supported = ["bash", "sh", "zsh", "python", "node", "javascript"];
-    if !supported.contains(&lang.as_str()) {
+    if !SUPPORTED_LANGUAGES.contains(&lang.as_str()) {
         return Err(format!("Unsupported language: {}", language));
     }
 
@@ -92,7 +112,15 @@ pub async fn run_code(
                 }
                 let code = status.code();
                 // Audit log
-                let _ = append_audit(&language, cwd.as_deref(), code, false, &stdout, &stderr);
+                let _ = append_audit(
+                    &language,
+                    cwd.as_deref(),
+                    code,
+                    false,
+                    start.elapsed(),
+                    &stdout,
+                    &stderr,
+                );
                 return Ok(RunResult {
                     stdout,
                     stderr,
@@ -126,7 +154,15 @@ pub async fn run_code(
     }
 
     // Audit log for timeout
-    let _ = append_audit(&language, cwd.as_deref(), None, true, &stdout, &stderr);
+    let _ = append_audit(
+        &language,
+        cwd.as_deref(),
+        None,
+        true,
+        start.elapsed(),
+        &stdout,
+        &stderr,
+    );
     Ok(RunResult {
         stdout,
         stderr,
@@ -135,6 +171,179 @@ pub async fn run_code(
     })
 }
 
+fn find_on_path(binary: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(binary))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Interpreter `run_code` invokes for a language. Shell snippets all run under `sh`.
+fn interpreter_for(language: &str) -> &'static str {
+    match language {
+        "python" => "python3",
+        "node" | "javascript" => "node",
+        _ => "sh",
+    }
+}
+
+/// First line of `<binary> --version`, checking stderr for interpreters that print there
+fn probe_version(binary: &PathBuf) -> Option<String> {
+    let output = Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    [output.stdout, output.stderr]
+        .iter()
+        .filter_map(|out| {
+            String::from_utf8_lossy(out)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(String::from)
+        })
+        .next()
+}
+
+fn probe_language(language: &str) -> LanguageAvailability {
+    let binary = find_on_path(interpreter_for(language));
+    LanguageAvailability {
+        language: language.to_string(),
+        is_available: binary.is_some(),
+        version: binary.as_ref().and_then(probe_version),
+        binary_path: binary.map(|path| path.to_string_lossy().to_string()),
+    }
+}
+
+/// Probe every supported language, reusing results younger than `LANGUAGE_CACHE_TTL`
+fn language_availability() -> Result<Vec<LanguageAvailability>, String> {
+    let cache = LANGUAGE_CACHE.get_or_init(|| Mutex::new(None));
+    let mut guard = cache.lock().map_err(|e| e.to_string())?;
+    if let Some((probed_at, languages)) = guard.as_ref() {
+        if probed_at.elapsed() < LANGUAGE_CACHE_TTL {
+            return Ok(languages.clone());
+        }
+    }
+
+    let languages: Vec<LanguageAvailability> = SUPPORTED_LANGUAGES
+        .iter()
+        .map(|l| probe_language(l))
+        .collect();
+    *guard = Some((Instant::now(), languages.clone()));
+    Ok(languages)
+}
+
+/// Report which `run_code` languages have an interpreter installed, with versions
+#[tauri::command]
+pub fn list_supported_languages() -> Result<Vec<LanguageAvailability>, String> {
+    language_availability()
+}
+
+#[tauri::command]
+pub fn check_language_available(language: String) -> Result<LanguageAvailability, String> {
+    let lang = language.to_lowercase();
+    language_availability()?
+        .into_iter()
+        .find(|l| l.language == lang)
+        .ok_or_else(|| format!("Unsupported language: {}", language))
+}
+
+/// Run `expression` with the `jq` binary, feeding `input` on stdin
+fn run_jq_binary(jq: &PathBuf, expression: &str, input: &str) -> Result<String, String> {
+    let mut child = Command::new(jq)
+        .arg(expression)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to spawn jq: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("failed to write jq input: {}", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("failed to run jq: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "jq failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+/// Evaluate `expression` with the embedded jaq interpreter. Each result is
+/// pretty-printed on its own, matching the `jq` binary's default output.
+fn run_jaq(expression: &str, input: serde_json::Value) -> Result<String, String> {
+    use jaq_core::load::{Arena, File, Loader};
+    use jaq_core::{Compiler, Ctx, RcIter};
+    use jaq_json::Val;
+
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader
+        .load(
+            &arena,
+            File {
+                code: expression,
+                path: (),
+            },
+        )
+        .map_err(|errs| format!("invalid jq expression: {:?}", errs[0].1))?;
+
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errs| {
+            let undefined: Vec<String> = errs
+                .into_iter()
+                .flat_map(|(_, e)| e)
+                .map(|(name, kind)| format!("{:?} {}", kind, name))
+                .collect();
+            format!("undefined in jq expression: {}", undefined.join(", "))
+        })?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut results = Vec::new();
+    for value in filter.run((Ctx::new([], &inputs), Val::from(input))) {
+        let value = value.map_err(|e| format!("jq error: {}", e))?;
+        let json = serde_json::Value::from(value);
+        results.push(serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?);
+    }
+    Ok(results.join("\n"))
+}
+
+/// Apply a jq expression to a JSON string. Uses the `jq` binary when it is on
+/// PATH and the built-in jaq interpreter otherwise.
+#[tauri::command]
+pub fn run_jq(expression: String, input: String) -> Result<String, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(&input).map_err(|e| format!("input is not valid JSON: {}", e))?;
+
+    match find_on_path("jq") {
+        Some(jq) => run_jq_binary(&jq, &expression, &input),
+        None => run_jaq(&expression, json),
+    }
+}
+
+/// Apply a jq expression to the contents of a JSON file
+#[tauri::command]
+pub fn run_jq_on_file(expression: String, path: String) -> Result<String, String> {
+    let input = fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    run_jq(expression, input)
+}
+
 /// Read the audit log and return the last `lines` lines joined as a string.
 #[tauri::command]
 pub fn read_audit(lines: Option<usize>) -> Result<String, String> {
@@ -165,6 +374,219 @@ pub fn rotate_audit() -> Result<(), String> {
     Ok(())
 }
 
+/// One run as logged; the audit log holds one of these as JSON per line, so captured
+/// output can't be mistaken for log structure
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub timestamp: i64,
+    pub language: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Missing for entries written before durations were logged
+    pub duration_ms: Option<u64>,
+    pub cwd: Option<String>,
+    /// Captured output as logged, already cut to the first 1000 bytes
+    pub stdout_preview: String,
+    pub stderr_preview: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AuditStats {
+    pub total_runs: usize,
+    pub by_language: HashMap<String, usize>,
+    /// Finished with exit code 0
+    pub success_count: usize,
+    /// Finished with a non-zero exit code or was killed by a signal
+    pub failure_count: usize,
+    pub timeout_count: usize,
+    /// Average over runs that logged a duration; 0 if none did
+    pub avg_execution_time_ms: f64,
+}
+
+/// Records from a log's lines, skipping any that aren't a JSON record (such as
+/// entries in the older text format)
+fn parse_audit_log(content: &str) -> Vec<AuditRecord> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Records from the rotated log followed by the current one, oldest first
+fn load_audit_records() -> Result<Vec<AuditRecord>, String> {
+    let log_path = get_audit_log_path();
+    let mut rotated = log_path.clone();
+    rotated.set_extension("log.1");
+
+    let mut records = Vec::new();
+    for path in [rotated, log_path] {
+        match fs::read_to_string(&path) {
+            Ok(content) => records.extend(parse_audit_log(&content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("failed to read audit log: {}", e)),
+        }
+    }
+    Ok(records)
+}
+
+fn audit_stats(records: &[AuditRecord], since_timestamp: Option<i64>) -> AuditStats {
+    let mut stats = AuditStats::default();
+    let mut total_ms = 0u64;
+    let mut timed_runs = 0u64;
+
+    let in_range = |r: &&AuditRecord| match since_timestamp {
+        Some(since) => r.timestamp >= since,
+        None => true,
+    };
+    for record in records.iter().filter(in_range) {
+        stats.total_runs += 1;
+        *stats
+            .by_language
+            .entry(record.language.clone())
+            .or_insert(0) += 1;
+        if record.timed_out {
+            stats.timeout_count += 1;
+        } else if record.exit_code == Some(0) {
+            stats.success_count += 1;
+        } else {
+            stats.failure_count += 1;
+        }
+        if let Some(ms) = record.duration_ms {
+            total_ms += ms;
+            timed_runs += 1;
+        }
+    }
+
+    if timed_runs > 0 {
+        stats.avg_execution_time_ms = total_ms as f64 / timed_runs as f64;
+    }
+    stats
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn audit_csv(records: &[AuditRecord]) -> String {
+    let mut csv = String::from("timestamp,language,exit_code,timed_out,duration_ms,cwd\n");
+    for record in records {
+        let row = [
+            record.timestamp.to_string(),
+            csv_field(&record.language),
+            record.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            record.timed_out.to_string(),
+            record
+                .duration_ms
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            csv_field(record.cwd.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Run counts and timings from the audit log, optionally only for runs at or
+/// after `since_timestamp` (seconds)
+#[tauri::command]
+pub fn get_audit_stats(since_timestamp: Option<i64>) -> Result<AuditStats, String> {
+    let records = load_audit_records()?;
+    Ok(audit_stats(&records, since_timestamp))
+}
+
+/// Every audit log entry as CSV with a header row
+#[tauri::command]
+pub fn export_audit_csv() -> Result<String, String> {
+    let records = load_audit_records()?;
+    Ok(audit_csv(&records))
+}
+
+/// Newest first, filtered by start time and language (case-insensitive)
+fn filter_audit_records(
+    records: Vec<AuditRecord>,
+    since_timestamp: Option<i64>,
+    language: Option<&str>,
+    limit: Option<usize>,
+) -> Vec<AuditRecord> {
+    records
+        .into_iter()
+        .rev()
+        .filter(|r| match since_timestamp {
+            Some(since) => r.timestamp >= since,
+            None => true,
+        })
+        .filter(|r| match language {
+            Some(language) => r.language.eq_ignore_ascii_case(language),
+            None => true,
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Audit log entries as structured records, newest first
+#[tauri::command]
+pub fn get_audit_records(
+    since_timestamp: Option<i64>,
+    language: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditRecord>, String> {
+    let records = load_audit_records()?;
+    Ok(filter_audit_records(
+        records,
+        since_timestamp,
+        language.as_deref(),
+        limit,
+    ))
+}
+
+/// `content` without the entries logged at `timestamp`, and how many were dropped
+fn remove_audit_entries(content: &str, timestamp: i64) -> (String, usize) {
+    let mut removed = 0;
+    let mut kept = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let record: Option<AuditRecord> = serde_json::from_str(line).ok();
+        if record.is_some_and(|r| r.timestamp == timestamp) {
+            removed += 1;
+        } else {
+            kept.push_str(line);
+        }
+    }
+    (kept, removed)
+}
+
+/// Remove the entries logged at `timestamp` (seconds) from the current and rotated
+/// logs. Runs started within the same second share a timestamp and go together.
+#[tauri::command]
+pub fn delete_audit_record(timestamp: i64) -> Result<(), String> {
+    let log_path = get_audit_log_path();
+    let mut rotated = log_path.clone();
+    rotated.set_extension("log.1");
+
+    let mut removed = 0;
+    for path in [rotated, log_path] {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("failed to read audit log: {}", e)),
+        };
+        let (kept, count) = remove_audit_entries(&content, timestamp);
+        if count > 0 {
+            fs::write(&path, kept).map_err(|e| format!("failed to write audit log: {}", e))?;
+            removed += count;
+        }
+    }
+
+    if removed == 0 {
+        return Err(format!("No audit record at {}", timestamp));
+    }
+    Ok(())
+}
+
 fn get_audit_log_path() -> PathBuf {
     // In a real Tauri app context, this would use app.path().app_data_dir()
     // For now, use current directory as fallback (works in tests and when no app handle)
@@ -178,6 +600,7 @@ fn append_audit(
     cwd: Option<&str>,
     exit_code: Option<i32>,
     timed_out: bool,
+    elapsed: Duration,
     stdout: &str,
     stderr: &str,
 ) -> Result<(), String> {
@@ -188,10 +611,6 @@ fn append_audit(
         .map(|d| d.as_secs())
         .unwrap_or(0);
 
-    let mut entry = format!(
-        "{} | lang={} | exit={:?} | timed_out={} | cwd={:?}\n",
-        ts, language, exit_code, timed_out, cwd
-    );
     // Truncate outputs to avoid massive logs
     let take = |s: &str, n: usize| {
         if s.len() > n {
@@ -200,9 +619,18 @@ fn append_audit(
             s.to_string()
         }
     };
-    entry.push_str(&format!("STDOUT: {}\n", take(stdout, 1000)));
-    entry.push_str(&format!("STDERR: {}\n", take(stderr, 1000)));
-    entry.push_str("---\n");
+    let record = AuditRecord {
+        timestamp: ts as i64,
+        language: language.to_string(),
+        exit_code,
+        timed_out,
+        duration_ms: Some(elapsed.as_millis() as u64),
+        cwd: cwd.map(|c| c.to_string()),
+        stdout_preview: take(stdout, 1000),
+        stderr_preview: take(stderr, 1000),
+    };
+    let mut entry = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+    entry.push('\n');
 
     // Append to file
     // Rotate if too large (1 MB)
@@ -289,4 +717,184 @@ mod tests {
         assert!(r.is_err());
         assert!(r.unwrap_err().contains("Unsupported language"));
     }
+
+    #[test]
+    fn test_language_availability() {
+        let languages = list_supported_languages().unwrap();
+        assert_eq!(languages.len(), SUPPORTED_LANGUAGES.len());
+
+        // `sh` is required by the other tests, so it must be found here too
+        let sh = check_language_available("SH".into()).unwrap();
+        assert!(sh.is_available);
+        assert!(sh.binary_path.unwrap().ends_with("sh"));
+
+        let missing = probe_language("definitely-not-a-language");
+        assert_eq!(missing.language, "definitely-not-a-language");
+        assert!(check_language_available("ruby".into())
+            .unwrap_err()
+            .contains("Unsupported language"));
+    }
+
+    const PEOPLE: &str = r#"[
+        {"id": 1, "name": "Ada", "age": 36},
+        {"id": 2, "name": "Linus", "age": 28}
+    ]"#;
+
+    // The embedded interpreter must give the same answers as the jq binary
+    #[test]
+    fn test_run_jaq_expressions() {
+        let people: serde_json::Value = serde_json::from_str(PEOPLE).unwrap();
+
+        let name = run_jaq(".name", serde_json::json!({"name": "lai"})).unwrap();
+        assert_eq!(name, "\"lai\"");
+        assert_eq!(run_jaq(".[].id", people.clone()).unwrap(), "1\n2");
+
+        let older = run_jaq(".[] | select(.age > 30) | .name", people.clone()).unwrap();
+        assert_eq!(older, "\"Ada\"");
+
+        assert!(run_jaq(".[] | nosuchfn", people).is_err());
+    }
+
+    #[test]
+    fn test_run_jq_uses_available_backend() {
+        assert_eq!(run_jq(".[].id".into(), PEOPLE.into()).unwrap(), "1\n2");
+        let selected = run_jq("map(select(.age > 30)) | length".into(), PEOPLE.into()).unwrap();
+        assert_eq!(selected, "1");
+        assert!(run_jq(".".into(), "not json".into()).is_err());
+    }
+
+    #[test]
+    fn test_run_jq_on_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(PEOPLE.as_bytes()).unwrap();
+        let path = file.path().to_string_lossy().to_string();
+        assert_eq!(run_jq_on_file(".[0].name".into(), path).unwrap(), "\"Ada\"");
+    }
+
+    fn audit_record(timestamp: i64, language: &str, exit_code: Option<i32>) -> AuditRecord {
+        AuditRecord {
+            timestamp,
+            language: language.to_string(),
+            exit_code,
+            timed_out: false,
+            duration_ms: None,
+            cwd: None,
+            stdout_preview: String::new(),
+            stderr_preview: String::new(),
+        }
+    }
+
+    fn audit_log(records: &[AuditRecord]) -> String {
+        records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn test_audit_log_parsing_and_stats() {
+        let log = audit_log(&[
+            AuditRecord {
+                duration_ms: Some(10),
+                ..audit_record(100, "sh", Some(0))
+            },
+            AuditRecord {
+                duration_ms: Some(30),
+                cwd: Some("/tmp/a | b".to_string()),
+                stderr_preview: "Traceback".to_string(),
+                ..audit_record(150, "python", Some(1))
+            },
+            AuditRecord {
+                timed_out: true,
+                ..audit_record(200, "python", None)
+            },
+        ]);
+        let records = parse_audit_log(&format!("{}not a record\n", log));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].cwd.as_deref(), Some("/tmp/a | b"));
+        assert_eq!(records[2].duration_ms, None);
+
+        let stats = audit_stats(&records, None);
+        assert_eq!(stats.total_runs, 3);
+        assert_eq!(stats.by_language.get("python"), Some(&2));
+        assert_eq!(stats.success_count, 1);
+        assert_eq!(stats.failure_count, 1);
+        assert_eq!(stats.timeout_count, 1);
+        assert_eq!(stats.avg_execution_time_ms, 20.0);
+
+        let recent = audit_stats(&records, Some(150));
+        assert_eq!(recent.total_runs, 2);
+        assert_eq!(recent.success_count, 0);
+
+        let csv = audit_csv(&records);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "100,sh,0,false,10,");
+        assert_eq!(lines[2], "150,python,1,false,30,/tmp/a | b");
+        assert_eq!(lines[3], "200,python,,true,,");
+    }
+
+    // Output that looks like an old-style entry header is still just output
+    #[test]
+    fn test_audit_stats_ignore_header_like_output() {
+        let log = audit_log(&[AuditRecord {
+            stdout_preview: "build log:\n123 | lang=python | exit=Some(1) | timed_out=true"
+                .to_string(),
+            ..audit_record(100, "sh", Some(0))
+        }]);
+        assert_eq!(log.lines().count(), 1);
+
+        let stats = audit_stats(&parse_audit_log(&log), None);
+        assert_eq!(stats.total_runs, 1);
+        assert_eq!(stats.by_language.get("sh"), Some(&1));
+        assert_eq!(stats.by_language.get("python"), None);
+        assert_eq!(stats.success_count, 1);
+        assert_eq!(stats.timeout_count, 0);
+    }
+
+    #[test]
+    fn test_audit_records_with_output_and_deletion() {
+        let log = audit_log(&[
+            AuditRecord {
+                stdout_preview: "line one\n---\nSTDERR: not stderr\n---".to_string(),
+                stderr_preview: "warn".to_string(),
+                ..audit_record(100, "sh", Some(0))
+            },
+            AuditRecord {
+                stderr_preview: "Traceback".to_string(),
+                ..audit_record(200, "python", Some(1))
+            },
+            AuditRecord {
+                stdout_preview: "done".to_string(),
+                ..audit_record(300, "sh", Some(0))
+            },
+        ]);
+        let records = parse_audit_log(&log);
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0].stdout_preview,
+            "line one\n---\nSTDERR: not stderr\n---"
+        );
+        assert_eq!(records[0].stderr_preview, "warn");
+        assert_eq!(records[1].stdout_preview, "");
+
+        let newest_sh = filter_audit_records(records.clone(), None, Some("SH"), Some(1));
+        assert_eq!(newest_sh.len(), 1);
+        assert_eq!(newest_sh[0].timestamp, 300);
+        let since = filter_audit_records(records, Some(200), None, None);
+        let timestamps: Vec<i64> = since.iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps, vec![300, 200]);
+
+        let (kept, removed) = remove_audit_entries(&log, 200);
+        assert_eq!(removed, 1);
+        assert!(!kept.contains("Traceback"));
+        let remaining = parse_audit_log(&kept);
+        let timestamps: Vec<i64> = remaining.iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps, vec![100, 300]);
+        assert_eq!(
+            remaining[0].stdout_preview,
+            "line one\n---\nSTDERR: not stderr\n---"
+        );
+        assert_eq!(remove_audit_entries(&log, 999).1, 0);
+    }
 }
@@ -1,30 +1,324 @@
+use crate::commands::provider::{
+    clipboard_history_enabled, set_clipboard_watching, CLIPBOARD_HISTORY_ENABLED_KEY,
+};
 use crate::database::{settings::*, Database};
-use tauri::State;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Group a setting is shown under in the settings panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingCategory {
+    Providers,
+    Ui,
+    Shortcuts,
+    Performance,
+}
+
+impl SettingCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingCategory::Providers => "providers",
+            SettingCategory::Ui => "ui",
+            SettingCategory::Shortcuts => "shortcuts",
+            SettingCategory::Performance => "performance",
+        }
+    }
+}
+
+/// Describes a known setting key. A `*` in `key` matches any text, so one
+/// definition can cover per-provider keys.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingDefinition {
+    pub key: &'static str,
+    pub category: SettingCategory,
+    pub description: &'static str,
+    pub default_value: Option<&'static str>,
+}
+
+impl SettingDefinition {
+    fn is_pattern(&self) -> bool {
+        self.key.contains('*')
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        match self.key.split_once('*') {
+            Some((prefix, suffix)) => {
+                key.len() > prefix.len() + suffix.len()
+                    && key.starts_with(prefix)
+                    && key.ends_with(suffix)
+            }
+            None => self.key == key,
+        }
+    }
+}
+
+/// Settings group used for keys without a definition
+const CUSTOM_CATEGORY: &str = "custom";
+
+pub const SETTING_DEFINITIONS: &[SettingDefinition] = &[
+    SettingDefinition {
+        key: "defaultProvider",
+        category: SettingCategory::Providers,
+        description: "Provider used for new conversations",
+        default_value: Some("openai"),
+    },
+    SettingDefinition {
+        key: "defaultModel",
+        category: SettingCategory::Providers,
+        description: "Model used for new conversations",
+        default_value: Some("gpt-4"),
+    },
+    SettingDefinition {
+        key: "apiKeys",
+        category: SettingCategory::Providers,
+        description: "API keys per provider (JSON object)",
+        default_value: Some("{}"),
+    },
+    SettingDefinition {
+        key: "budgetMonthly",
+        category: SettingCategory::Providers,
+        description: "Monthly AI usage budget in USD",
+        default_value: Some("20"),
+    },
+    SettingDefinition {
+        key: "provider_ratelimit_*_rps",
+        category: SettingCategory::Providers,
+        description: "Requests per second allowed for a provider",
+        default_value: None,
+    },
+    SettingDefinition {
+        key: "provider_ratelimit_*_burst",
+        category: SettingCategory::Providers,
+        description: "Burst capacity for a provider's rate limit",
+        default_value: None,
+    },
+    SettingDefinition {
+        key: "provider_ratelimit_max_wait_ms",
+        category: SettingCategory::Providers,
+        description: "Longest time a request waits for a rate limit slot",
+        default_value: Some("30000"),
+    },
+    SettingDefinition {
+        key: "theme",
+        category: SettingCategory::Ui,
+        description: "Colour theme: light, dark or system",
+        default_value: Some("system"),
+    },
+    SettingDefinition {
+        key: "window_state",
+        category: SettingCategory::Ui,
+        description: "Saved main window position and size (JSON)",
+        default_value: None,
+    },
+    SettingDefinition {
+        key: CLIPBOARD_HISTORY_ENABLED_KEY,
+        category: SettingCategory::Ui,
+        description: "Keep recent copied text in the tray's Clipboard History menu",
+        default_value: Some("false"),
+    },
+    SettingDefinition {
+        key: "allowCodeExecution",
+        category: SettingCategory::Ui,
+        description: "Allow running code blocks from conversations",
+        default_value: Some("false"),
+    },
+    SettingDefinition {
+        key: "projectRoot",
+        category: SettingCategory::Ui,
+        description: "Project directory watched for file changes",
+        default_value: None,
+    },
+    SettingDefinition {
+        key: "globalShortcut",
+        category: SettingCategory::Shortcuts,
+        description: "Global shortcut that toggles the main window",
+        default_value: Some("CommandOrControl+Space"),
+    },
+    SettingDefinition {
+        key: "shortcut_config",
+        category: SettingCategory::Shortcuts,
+        description: "Configured application shortcuts (JSON)",
+        default_value: None,
+    },
+    SettingDefinition {
+        key: "fileWatcherIgnorePatterns",
+        category: SettingCategory::Performance,
+        description: "Gitignore-style patterns skipped by the project watcher (JSON array)",
+        default_value: None,
+    },
+];
+
+/// Find the definition for a setting key, preferring exact keys over patterns
+pub fn definition_for(key: &str) -> Option<&'static SettingDefinition> {
+    SETTING_DEFINITIONS
+        .iter()
+        .find(|d| !d.is_pattern() && d.matches(key))
+        .or_else(|| SETTING_DEFINITIONS.iter().find(|d| d.matches(key)))
+}
+
+/// A setting's current value merged with its definition. `value` and
+/// `updated_at` are `None` for known settings that have never been saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingWithDefinition {
+    pub key: String,
+    pub value: Option<String>,
+    pub updated_at: Option<i64>,
+    pub definition: Option<SettingDefinition>,
+}
+
+/// Group stored settings by category. Defined keys that are not stored yet are
+/// included with their definition; keys without a definition go under "custom".
+pub fn group_settings(settings: Vec<Setting>) -> HashMap<String, Vec<SettingWithDefinition>> {
+    let mut grouped: HashMap<String, Vec<SettingWithDefinition>> = HashMap::new();
+
+    for def in SETTING_DEFINITIONS.iter().filter(|d| !d.is_pattern()) {
+        if !settings.iter().any(|s| s.key == def.key) {
+            grouped
+                .entry(def.category.as_str().to_string())
+                .or_default()
+                .push(SettingWithDefinition {
+                    key: def.key.to_string(),
+                    value: None,
+                    updated_at: None,
+                    definition: Some(def.clone()),
+                });
+        }
+    }
+
+    for setting in settings {
+        let definition = definition_for(&setting.key);
+        let category = definition
+            .map(|d| d.category.as_str())
+            .unwrap_or(CUSTOM_CATEGORY);
+        grouped
+            .entry(category.to_string())
+            .or_default()
+            .push(SettingWithDefinition {
+                key: setting.key,
+                value: Some(setting.value),
+                updated_at: Some(setting.updated_at),
+                definition: definition.cloned(),
+            });
+    }
+
+    for entries in grouped.values_mut() {
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+    grouped
+}
+
+/// Apply settings that take effect immediately rather than being read on use
+fn apply_setting(app: &AppHandle, key: &str, value: Option<&str>) {
+    if key == CLIPBOARD_HISTORY_ENABLED_KEY {
+        set_clipboard_watching(app, clipboard_history_enabled(value));
+    }
+}
 
 #[tauri::command]
 pub async fn set_setting(
+    app: AppHandle,
     db: State<'_, Database>,
     key: String,
     value: String,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Setting::set(&conn, &key, &value).map_err(|e| e.to_string())
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Setting::set(&conn, &key, &value).map_err(|e| e.to_string())?;
+    apply_setting(&app, &key, Some(&value));
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_setting(db: State<'_, Database>, key: String) -> Result<Option<String>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Setting::get(&conn, &key).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_all_settings(db: State<'_, Database>) -> Result<Vec<Setting>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Setting::get_all(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn delete_setting(db: State<'_, Database>, key: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    Setting::delete(&conn, &key).map_err(|e| e.to_string())
+pub async fn delete_setting(
+    app: AppHandle,
+    db: State<'_, Database>,
+    key: String,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Setting::delete(&conn, &key).map_err(|e| e.to_string())?;
+    apply_setting(&app, &key, None);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_all_settings_grouped(
+    db: State<'_, Database>,
+) -> Result<HashMap<String, Vec<SettingWithDefinition>>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let settings = Setting::get_all(&conn).map_err(|e| e.to_string())?;
+    Ok(group_settings(settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(key: &str, value: &str) -> Setting {
+        Setting {
+            key: key.to_string(),
+            value: value.to_string(),
+            updated_at: 1,
+        }
+    }
+
+    #[test]
+    fn groups_settings_by_definition_category() {
+        let grouped = group_settings(vec![
+            setting("theme", "dark"),
+            setting("provider_ratelimit_openai_rps", "2"),
+            setting("provider_ratelimit_max_wait_ms", "500"),
+            setting("my_plugin_flag", "on"),
+        ]);
+
+        let ui = &grouped["ui"];
+        let theme = ui.iter().find(|s| s.key == "theme").unwrap();
+        assert_eq!(theme.value.as_deref(), Some("dark"));
+        assert_eq!(
+            theme.definition.as_ref().unwrap().default_value,
+            Some("system")
+        );
+
+        let providers = &grouped["providers"];
+        let rps = providers
+            .iter()
+            .find(|s| s.key == "provider_ratelimit_openai_rps")
+            .unwrap();
+        assert_eq!(
+            rps.definition.as_ref().unwrap().key,
+            "provider_ratelimit_*_rps"
+        );
+        let max_wait = providers
+            .iter()
+            .find(|s| s.key == "provider_ratelimit_max_wait_ms")
+            .unwrap();
+        assert_eq!(
+            max_wait.definition.as_ref().unwrap().key,
+            "provider_ratelimit_max_wait_ms"
+        );
+
+        // Known but unsaved settings are listed without a value
+        let shortcut = grouped["shortcuts"]
+            .iter()
+            .find(|s| s.key == "globalShortcut")
+            .unwrap();
+        assert!(shortcut.value.is_none());
+
+        let custom = &grouped["custom"];
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].key, "my_plugin_flag");
+        assert!(custom[0].definition.is_none());
+    }
 }
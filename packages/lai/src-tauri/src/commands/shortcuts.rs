@@ -1,10 +1,11 @@
+use crate::database::shortcut_events::{ShortcutEvent, ShortcutStats};
 use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
 // Define available shortcut actions
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum ShortcutAction {
     ToggleWindow,
@@ -202,11 +203,53 @@ pub fn initialize_shortcut_manager(_app_handle: AppHandle) {
     });
 }
 
+/// Whether `initialize_shortcut_manager` has populated the shared config
+pub fn is_shortcut_manager_initialized() -> bool {
+    SHORTCUT_CONFIG
+        .lock()
+        .map(|guard| guard.is_some())
+        .unwrap_or(false)
+}
+
+/// Count one invocation of a global shortcut. Failures are logged, never surfaced
+/// to the shortcut handler.
+pub fn record_shortcut_event(app: &AppHandle, action: &ShortcutAction) {
+    let Some(db) = app.try_state::<crate::database::Database>() else {
+        return;
+    };
+    let result = db.conn().map_err(|e| e.to_string()).and_then(|conn| {
+        // Debug output matches the serialized PascalCase action name
+        ShortcutEvent::record(&conn, &format!("{:?}", action)).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("failed to record shortcut event: {}", e);
+    }
+}
+
+/// Invocation counts per action, optionally only for events at or after `since` (seconds)
+#[tauri::command]
+pub async fn get_shortcut_usage_stats(
+    db: tauri::State<'_, crate::database::Database>,
+    since: Option<i64>,
+) -> Result<Vec<ShortcutStats>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ShortcutEvent::stats(&conn, since).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_shortcut_stats(
+    db: tauri::State<'_, crate::database::Database>,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ShortcutEvent::clear(&conn).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_shortcut_config(
     db: tauri::State<'_, crate::database::Database>,
 ) -> Result<ShortcutConfig, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     // Try to get existing config from database
     let config_json: Option<String> = conn
@@ -228,7 +271,7 @@ pub async fn update_shortcut_config(
     db: tauri::State<'_, crate::database::Database>,
     _app: AppHandle,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
 
     // Save config to database
     let config_json =
@@ -277,3 +320,278 @@ pub async fn validate_shortcut(shortcut: String) -> Result<bool, String> {
 pub async fn get_available_actions() -> Result<Vec<ShortcutAction>, String> {
     Ok(ShortcutAction::all_actions())
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub mapped: usize,
+    pub unmapped: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VscodeKeybinding {
+    key: String,
+    command: String,
+}
+
+/// VS Code commands with a close equivalent in this app
+fn action_for_vscode_command(command: &str) -> Option<ShortcutAction> {
+    match command {
+        "workbench.action.newWindow" | "workbench.action.files.newUntitledFile" => {
+            Some(ShortcutAction::NewConversation)
+        }
+        "workbench.action.openSettings" | "workbench.action.openGlobalSettings" => {
+            Some(ShortcutAction::OpenSettings)
+        }
+        "workbench.action.focusActiveEditorGroup" => Some(ShortcutAction::FocusInput),
+        "workbench.action.closeAllEditors" => Some(ShortcutAction::ClearConversation),
+        "workbench.action.files.save" => Some(ShortcutAction::ExportCurrent),
+        "workbench.action.files.saveAs" => Some(ShortcutAction::QuickExport),
+        "workbench.action.findInFiles" | "workbench.view.search" => {
+            Some(ShortcutAction::SearchDocuments)
+        }
+        "workbench.action.openProcessExplorer" => Some(ShortcutAction::ShowPerformance),
+        "workbench.action.toggleFullScreen" => Some(ShortcutAction::ToggleWindow),
+        _ => None,
+    }
+}
+
+/// Convert a VS Code key like `ctrl+shift+n` to `CommandOrControl+Shift+N`.
+/// Chords (`ctrl+k ctrl+s`) have no global-shortcut equivalent and return `None`.
+fn convert_vscode_key(key: &str) -> Option<String> {
+    let key = key.trim();
+    if key.is_empty() || key.contains(' ') {
+        return None;
+    }
+
+    let parts: Vec<String> = key
+        .split('+')
+        .map(|part| match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" => "CommandOrControl".to_string(),
+            "shift" => "Shift".to_string(),
+            "alt" => "Alt".to_string(),
+            "meta" | "win" => "Super".to_string(),
+            "," => "Comma".to_string(),
+            "." => "Period".to_string(),
+            "/" => "Slash".to_string(),
+            ";" => "Semicolon".to_string(),
+            "=" => "Equal".to_string(),
+            "-" => "Minus".to_string(),
+            "`" => "Backquote".to_string(),
+            other => {
+                let mut chars = other.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+        })
+        .collect();
+
+    if parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    Some(parts.join("+"))
+}
+
+/// Remove `//` and `/* */` comments and trailing commas, which VS Code allows
+/// in `keybindings.json` but `serde_json` does not
+fn strip_jsonc(input: &str) -> String {
+    // Comments go first, so one between the last comma and `]` can't hide that comma
+    strip_trailing_commas(&strip_json_comments(input))
+}
+
+/// Copy a string literal that opened just before `chars` to `out`, closing quote included
+fn copy_json_string(chars: &mut std::iter::Peekable<std::str::Chars>, out: &mut String) {
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                out.push(escaped);
+            }
+        } else if c == '"' {
+            return;
+        }
+    }
+}
+
+fn strip_json_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('"', _) => {
+                out.push(c);
+                copy_json_string(&mut chars, &mut out);
+            }
+            ('/', Some('/')) => {
+                for skipped in chars.by_ref() {
+                    if skipped == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = '\0';
+                for skipped in chars.by_ref() {
+                    if prev == '*' && skipped == '/' {
+                        break;
+                    }
+                    prev = skipped;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn strip_trailing_commas(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                copy_json_string(&mut chars, &mut out);
+            }
+            ',' => {
+                let rest = chars.clone().find(|ch| !ch.is_whitespace());
+                if !matches!(rest, Some(']') | Some('}')) {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+type MappedShortcuts = Vec<(ShortcutAction, String)>;
+
+/// Map VS Code keybindings to shortcut actions. Returns the mapped
+/// `(action, shortcut)` pairs and a description of each skipped entry.
+fn map_vscode_keybindings(json: &str) -> Result<(MappedShortcuts, Vec<String>), String> {
+    let bindings: Vec<VscodeKeybinding> = serde_json::from_str(&strip_jsonc(json))
+        .map_err(|e| format!("Invalid keybindings JSON: {}", e))?;
+
+    let mut mapped: MappedShortcuts = Vec::new();
+    let mut unmapped = Vec::new();
+    for binding in bindings {
+        let action = action_for_vscode_command(&binding.command);
+        let shortcut = convert_vscode_key(&binding.key);
+        match (action, shortcut) {
+            (Some(action), Some(shortcut)) => {
+                // Later entries win, as they do in VS Code
+                mapped.retain(|(a, _)| *a != action);
+                mapped.push((action, shortcut));
+            }
+            _ => unmapped.push(format!("{} ({})", binding.command, binding.key)),
+        }
+    }
+    Ok((mapped, unmapped))
+}
+
+/// Import shortcuts from a VS Code `keybindings.json`, enabling each mapped action
+#[tauri::command]
+pub async fn import_shortcuts_from_vscode_keymap(
+    db: tauri::State<'_, crate::database::Database>,
+    app: AppHandle,
+    json: String,
+) -> Result<ImportResult, String> {
+    let (mapped, mut unmapped) = map_vscode_keybindings(&json)?;
+
+    let mut config = get_shortcut_config(db.clone()).await?;
+    let mut applied = 0;
+    for (action, shortcut) in mapped {
+        if let Err(e) = validate_shortcut(shortcut.clone()).await {
+            unmapped.push(format!("{:?} ({}): {}", action, shortcut, e));
+            continue;
+        }
+        match config.shortcuts.iter_mut().find(|s| s.action == action) {
+            Some(existing) => {
+                existing.shortcut = shortcut;
+                existing.enabled = true;
+            }
+            None => config.shortcuts.push(GlobalShortcut {
+                action,
+                shortcut,
+                enabled: true,
+            }),
+        }
+        applied += 1;
+    }
+
+    update_shortcut_config(config, db, app).await?;
+    Ok(ImportResult {
+        mapped: applied,
+        unmapped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_vscode_keys() {
+        assert_eq!(
+            convert_vscode_key("ctrl+shift+n").as_deref(),
+            Some("CommandOrControl+Shift+N")
+        );
+        assert_eq!(
+            convert_vscode_key("cmd+,").as_deref(),
+            Some("CommandOrControl+Comma")
+        );
+        assert_eq!(convert_vscode_key("ctrl+k ctrl+s"), None);
+    }
+
+    #[test]
+    fn maps_keybindings_with_comments() {
+        let json = r#"// Place your key bindings in this file
+        [
+            { "key": "ctrl+alt+n", "command": "workbench.action.newWindow" },
+            /* unsupported */
+            { "key": "ctrl+k ctrl+t", "command": "workbench.action.selectTheme" },
+            { "key": "ctrl+shift+f", "command": "workbench.action.findInFiles", "when": "x" },
+        ]"#;
+
+        let (mapped, unmapped) = map_vscode_keybindings(json).unwrap();
+        assert_eq!(
+            mapped,
+            vec![
+                (
+                    ShortcutAction::NewConversation,
+                    "CommandOrControl+Alt+N".to_string()
+                ),
+                (
+                    ShortcutAction::SearchDocuments,
+                    "CommandOrControl+Shift+F".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            unmapped,
+            vec!["workbench.action.selectTheme (ctrl+k ctrl+t)".to_string()]
+        );
+    }
+
+    #[test]
+    fn strips_trailing_comma_before_a_comment() {
+        let json = r#"[
+            { "key": "ctrl+alt+n", "command": "workbench.action.newWindow" }, // new window
+            /* end */
+        ]"#;
+        let (mapped, _) = map_vscode_keybindings(json).unwrap();
+        assert_eq!(mapped.len(), 1);
+
+        // Comment markers and commas inside strings are left alone
+        assert_eq!(
+            strip_jsonc(r#"{"a": "x, // y", "b": "/* z */",}"#),
+            r#"{"a": "x, // y", "b": "/* z */"}"#
+        );
+    }
+}
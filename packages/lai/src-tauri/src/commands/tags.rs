@@ -7,32 +7,32 @@ pub async fn create_tag(
     name: String,
     color: Option<String>,
 ) -> Result<Tag, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     let new_tag = NewTag { name, color };
     Tag::create(&conn, new_tag).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_tag(db: State<'_, Database>, id: String) -> Result<Option<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::get_by_id(&conn, &id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_tag_by_name(db: State<'_, Database>, name: String) -> Result<Option<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::get_by_name(&conn, &name).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_all_tags(db: State<'_, Database>) -> Result<Vec<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::get_all(&conn).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn search_tags(db: State<'_, Database>, query: String) -> Result<Vec<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::search(&conn, &query).map_err(|e| e.to_string())
 }
 
@@ -43,13 +43,13 @@ pub async fn update_tag(
     name: String,
     color: Option<String>,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::update(&conn, &id, &name, color.as_deref()).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn delete_tag(db: State<'_, Database>, id: String) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::delete(&conn, &id).map_err(|e| e.to_string())
 }
 
@@ -58,7 +58,7 @@ pub async fn get_conversation_tags(
     db: State<'_, Database>,
     conversation_id: String,
 ) -> Result<Vec<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::get_for_conversation(&conn, &conversation_id).map_err(|e| e.to_string())
 }
 
@@ -68,7 +68,7 @@ pub async fn add_tag_to_conversation(
     conversation_id: String,
     tag_id: String,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::add_to_conversation(&conn, &conversation_id, &tag_id).map_err(|e| e.to_string())
 }
 
@@ -78,7 +78,7 @@ pub async fn remove_tag_from_conversation(
     conversation_id: String,
     tag_id: String,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::remove_from_conversation(&conn, &conversation_id, &tag_id).map_err(|e| e.to_string())
 }
 
@@ -87,17 +87,53 @@ pub async fn get_conversations_by_tag(
     db: State<'_, Database>,
     tag_id: String,
 ) -> Result<Vec<String>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::get_conversations_with_tag(&conn, &tag_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_tag_usage_timeline(
+    db: State<'_, Database>,
+    tag_id: String,
+    granularity: String,
+) -> Result<Vec<TagTimelineEntry>, String> {
+    let granularity = TimelineGranularity::parse(&granularity)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Tag::usage_timeline(&conn, &tag_id, granularity).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_tagging_activity_heatmap(
+    db: State<'_, Database>,
+    year: i32,
+) -> Result<Vec<HeatmapEntry>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Tag::activity_heatmap(&conn, year).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_tag_cloud(db: State<'_, Database>) -> Result<Vec<TagCloudItem>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Tag::get_tag_cloud(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_related_tags(
+    db: State<'_, Database>,
+    tag_id: String,
+    limit: usize,
+) -> Result<Vec<Tag>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Tag::get_related(&conn, &tag_id, limit as i64).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn create_or_get_tag(
     db: State<'_, Database>,
     name: String,
     color: Option<String>,
 ) -> Result<Tag, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     Tag::create_or_get(&conn, &name, color.as_deref()).map_err(|e| e.to_string())
 }
 
@@ -107,7 +143,7 @@ pub async fn add_tags_to_conversation_bulk(
     conversation_id: String,
     tag_names: Vec<String>,
 ) -> Result<Vec<Tag>, String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
     let mut created_tags = Vec::new();
 
     for tag_name in tag_names {
@@ -0,0 +1,200 @@
+use crate::database::messages::Message;
+use crate::database::webhooks::{ConversationWebhook, NewConversationWebhook};
+use crate::database::Database;
+use hmac::{Hmac, Mac};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Header carrying `sha256=<hex HMAC of the body>` when the webhook has a secret
+const SIGNATURE_HEADER: &str = "X-LAI-Signature";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookTestResult {
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+fn validate_url(url: &str) -> Result<(), String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err("Webhook URL must start with http:// or https://".to_string())
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// POST a JSON payload, signing it when the webhook has a secret
+async fn deliver(
+    webhook: &ConversationWebhook,
+    payload: &serde_json::Value,
+) -> Result<reqwest::StatusCode, String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body)));
+    }
+
+    let resp = request
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("request error: {}", e))?;
+    Ok(resp.status())
+}
+
+/// Fire matching `message.created` webhooks in the background; failures are logged only
+pub fn dispatch_message_created(conn: &Connection, message: &Message) {
+    let webhooks = match ConversationWebhook::get_matching(
+        conn,
+        "message.created",
+        &message.conversation_id,
+    ) {
+        Ok(w) if !w.is_empty() => w,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("webhooks: failed to load webhooks: {}", e);
+            return;
+        }
+    };
+
+    let payload = serde_json::json!({
+        "event": "message.created",
+        "conversation_id": message.conversation_id,
+        "message": message,
+    });
+    tauri::async_runtime::spawn(async move {
+        for webhook in webhooks {
+            match deliver(&webhook, &payload).await {
+                Ok(status) if status.is_success() => {}
+                Ok(status) => eprintln!("webhooks: {} returned {}", webhook.url, status),
+                Err(e) => eprintln!("webhooks: delivery to {} failed: {}", webhook.url, e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn create_webhook(
+    db: State<'_, Database>,
+    url: String,
+    secret: Option<String>,
+    events: Vec<String>,
+    conversation_id: Option<String>,
+) -> Result<ConversationWebhook, String> {
+    validate_url(&url)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let new_webhook = NewConversationWebhook {
+        url,
+        secret,
+        events,
+        conversation_id,
+    };
+    ConversationWebhook::create(&conn, new_webhook).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_webhooks(db: State<'_, Database>) -> Result<Vec<ConversationWebhook>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ConversationWebhook::get_all(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_webhook(
+    db: State<'_, Database>,
+    id: String,
+    url: String,
+    secret: Option<String>,
+    events: Vec<String>,
+    conversation_id: Option<String>,
+    is_active: bool,
+) -> Result<(), String> {
+    validate_url(&url)?;
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let webhook = NewConversationWebhook {
+        url,
+        secret,
+        events,
+        conversation_id,
+    };
+    ConversationWebhook::update(&conn, &id, &webhook, is_active).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_webhook(db: State<'_, Database>, id: String) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    ConversationWebhook::delete(&conn, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn test_webhook(
+    db: State<'_, Database>,
+    webhook_id: String,
+) -> Result<WebhookTestResult, String> {
+    let webhook = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        ConversationWebhook::get_by_id(&conn, &webhook_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Webhook not found".to_string())?
+    };
+
+    let payload = serde_json::json!({
+        "event": "webhook.test",
+        "conversation_id": webhook.conversation_id,
+        "message": null,
+    });
+    let start = Instant::now();
+    let result = deliver(&webhook, &payload).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(status) => WebhookTestResult {
+            success: status.is_success(),
+            status_code: Some(status.as_u16()),
+            latency_ms,
+            error: (!status.is_success()).then(|| format!("Webhook returned {}", status)),
+        },
+        Err(e) => WebhookTestResult {
+            success: false,
+            status_code: None,
+            latency_ms,
+            error: Some(e),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_known_vector() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -1,7 +1,17 @@
 use crate::database::settings::Setting;
-use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow};
+
+/// Key the main window's state was stored under before per-window keys
+const LEGACY_WINDOW_STATE_KEY: &str = "window_state";
+
+/// Overlay settings for the main window, applied again by `restore_window_state`
+const WINDOW_OPACITY_KEY: &str = "window_opacity";
+const WINDOW_ALWAYS_ON_TOP_KEY: &str = "window_always_on_top";
+
+/// Lowest opacity accepted, so the window can't be made invisible by accident
+const MIN_WINDOW_OPACITY: f64 = 0.1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowState {
@@ -24,6 +34,86 @@ impl Default for WindowState {
     }
 }
 
+/// Settings key holding the saved state of the window labelled `label`
+fn window_state_key(label: &str) -> String {
+    format!("window_state_{}", label)
+}
+
+fn read_window_state<R: Runtime>(window: &WebviewWindow<R>) -> Result<WindowState, String> {
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let maximized = window.is_maximized().map_err(|e| e.to_string())?;
+
+    Ok(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+    })
+}
+
+fn apply_window_state<R: Runtime>(
+    window: &WebviewWindow<R>,
+    window_state: &WindowState,
+) -> Result<(), String> {
+    // Restore size first
+    let size = PhysicalSize::new(window_state.width, window_state.height);
+    window.set_size(size).map_err(|e| e.to_string())?;
+
+    // Then restore position
+    let position = PhysicalPosition::new(window_state.x, window_state.y);
+    window.set_position(position).map_err(|e| e.to_string())?;
+
+    // Finally restore maximized state
+    if window_state.maximized {
+        window.maximize().map_err(|e| e.to_string())?;
+    } else {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Fade the page content. Tauri has no cross-platform window alpha, so the webview
+/// content is faded instead; the desktop only shows through when the window was
+/// created transparent by `create_main_window`.
+fn apply_window_opacity<R: Runtime>(window: &WebviewWindow<R>, opacity: f64) -> Result<(), String> {
+    window
+        .eval(&format!(
+            "document.documentElement.style.opacity = '{}'",
+            opacity
+        ))
+        .map_err(|e| e.to_string())
+}
+
+/// Build the main window from its config entry, which has `create: false`. It is
+/// only made transparent when a saved opacity below 1.0 needs it, so the window
+/// stays opaque for everyone else.
+pub fn create_main_window(app: &AppHandle, db: &crate::database::Database) -> Result<(), String> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == "main")
+        .ok_or_else(|| "Main window config not found".to_string())?;
+    let opacity: Option<f64> = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        Setting::get_json(&conn, WINDOW_OPACITY_KEY).map_err(|e| e.to_string())?
+    };
+    let translucent = opacity.is_some_and(|o| o < 1.0);
+
+    let builder =
+        tauri::WebviewWindowBuilder::from_config(app, config).map_err(|e| e.to_string())?;
+    // Transparent windows need the private API on macOS, so it stays opaque there
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(translucent);
+    #[cfg(target_os = "macos")]
+    let _ = translucent;
+    builder.build().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
 pub fn toggle_main_window(app: AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
@@ -45,21 +135,12 @@ pub async fn save_window_state(
     db: tauri::State<'_, crate::database::Database>,
 ) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("main") {
-        let position = window.outer_position().map_err(|e| e.to_string())?;
-        let size = window.outer_size().map_err(|e| e.to_string())?;
-        let maximized = window.is_maximized().map_err(|e| e.to_string())?;
-
-        let window_state = WindowState {
-            x: position.x,
-            y: position.y,
-            width: size.width,
-            height: size.height,
-            maximized,
-        };
-
-        let conn = db.conn().lock().map_err(|e| e.to_string())?;
+        let window_state = read_window_state(&window)?;
+
+        let conn = db.conn().map_err(|e| e.to_string())?;
         // Use settings helper to ensure updated_at is set to avoid NOT NULL constraint errors
-        Setting::set_json(&conn, "window_state", &window_state).map_err(|e| e.to_string())?;
+        Setting::set_json(&conn, &window_state_key("main"), &window_state)
+            .map_err(|e| e.to_string())?;
 
         Ok(())
     } else {
@@ -67,37 +148,68 @@ pub async fn save_window_state(
     }
 }
 
+/// Save every open window's state under `window_state_<label>`
 #[tauri::command]
-pub async fn restore_window_state(
+pub async fn save_all_window_states(
     app: AppHandle,
     db: tauri::State<'_, crate::database::Database>,
 ) -> Result<(), String> {
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-
-    let state_json: Option<String> = conn
-        .prepare("SELECT value FROM settings WHERE key = 'window_state'")
-        .and_then(|mut stmt| stmt.query_row([], |row| row.get(0)).optional())
-        .map_err(|e| e.to_string())?;
+    let states = get_all_window_states(app).await?;
 
-    drop(conn);
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    for (label, window_state) in states {
+        Setting::set_json(&conn, &window_state_key(&label), &window_state)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
 
-    if let Some(json) = state_json {
-        let window_state: WindowState = serde_json::from_str(&json)
-            .map_err(|e| format!("Failed to parse window state: {}", e))?;
+/// Restore each open window whose state was saved; windows without one are left alone
+#[tauri::command]
+pub async fn restore_window_state(
+    app: AppHandle,
+    db: tauri::State<'_, crate::database::Database>,
+) -> Result<(), String> {
+    let windows = app.webview_windows();
 
-        if let Some(window) = app.get_webview_window("main") {
-            // Restore size first
-            let size = PhysicalSize::new(window_state.width, window_state.height);
-            window.set_size(size).map_err(|e| e.to_string())?;
+    let saved = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        let mut saved = Vec::new();
+        for (label, window) in windows {
+            let mut state: Option<WindowState> =
+                Setting::get_json(&conn, &window_state_key(&label))
+                    .map_err(|e| format!("Failed to parse window state: {}", e))?;
+            if state.is_none() && label == "main" {
+                state = Setting::get_json(&conn, LEGACY_WINDOW_STATE_KEY)
+                    .map_err(|e| format!("Failed to parse window state: {}", e))?;
+            }
+            if let Some(state) = state {
+                saved.push((window, state));
+            }
+        }
+        saved
+    };
 
-            // Then restore position
-            let position = PhysicalPosition::new(window_state.x, window_state.y);
-            window.set_position(position).map_err(|e| e.to_string())?;
+    for (window, window_state) in saved {
+        apply_window_state(&window, &window_state)?;
+    }
 
-            // Finally restore maximized state
-            if window_state.maximized {
-                window.maximize().map_err(|e| e.to_string())?;
-            }
+    let (opacity, always_on_top) = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        let opacity: Option<f64> =
+            Setting::get_json(&conn, WINDOW_OPACITY_KEY).map_err(|e| e.to_string())?;
+        let always_on_top: Option<bool> =
+            Setting::get_json(&conn, WINDOW_ALWAYS_ON_TOP_KEY).map_err(|e| e.to_string())?;
+        (opacity, always_on_top)
+    };
+    if let Some(window) = app.get_webview_window("main") {
+        if let Some(opacity) = opacity.filter(|o| (MIN_WINDOW_OPACITY..=1.0).contains(o)) {
+            apply_window_opacity(&window, opacity)?;
+        }
+        if let Some(always_on_top) = always_on_top {
+            window
+                .set_always_on_top(always_on_top)
+                .map_err(|e| e.to_string())?;
         }
     }
 
@@ -107,49 +219,76 @@ pub async fn restore_window_state(
 #[tauri::command]
 pub async fn get_window_state(app: AppHandle) -> Result<WindowState, String> {
     if let Some(window) = app.get_webview_window("main") {
-        let position = window.outer_position().map_err(|e| e.to_string())?;
-        let size = window.outer_size().map_err(|e| e.to_string())?;
-        let maximized = window.is_maximized().map_err(|e| e.to_string())?;
-
-        Ok(WindowState {
-            x: position.x,
-            y: position.y,
-            width: size.width,
-            height: size.height,
-            maximized,
-        })
+        read_window_state(&window)
     } else {
         Err("Main window not found".to_string())
     }
 }
 
+/// Current state of every open webview window, keyed by window label
 #[tauri::command]
-pub async fn reset_window_state(
+pub async fn get_all_window_states(app: AppHandle) -> Result<HashMap<String, WindowState>, String> {
+    app.webview_windows()
+        .into_iter()
+        .map(|(label, window)| Ok((label, read_window_state(&window)?)))
+        .collect()
+}
+
+/// Make the main window translucent (0.1–1.0) for use as a floating overlay. The
+/// window is created transparent from the next launch after an opacity below 1.0 is
+/// saved; until then the content fades against the window background.
+#[tauri::command]
+pub async fn set_window_opacity(
     app: AppHandle,
     db: tauri::State<'_, crate::database::Database>,
+    opacity: f64,
 ) -> Result<(), String> {
-    // Delete stored window state
-    let conn = db.conn().lock().map_err(|e| e.to_string())?;
-    conn.prepare("DELETE FROM settings WHERE key = 'window_state'")
-        .and_then(|mut stmt| stmt.execute([]))
+    if !(MIN_WINDOW_OPACITY..=1.0).contains(&opacity) {
+        return Err(format!(
+            "Opacity must be between {} and 1.0",
+            MIN_WINDOW_OPACITY
+        ));
+    }
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    apply_window_opacity(&window, opacity)?;
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Setting::set_json(&conn, WINDOW_OPACITY_KEY, &opacity).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_window_always_on_top(
+    app: AppHandle,
+    db: tauri::State<'_, crate::database::Database>,
+    enabled: bool,
+) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    window
+        .set_always_on_top(enabled)
         .map_err(|e| e.to_string())?;
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Setting::set_json(&conn, WINDOW_ALWAYS_ON_TOP_KEY, &enabled).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reset_window_state(
+    app: AppHandle,
+    db: tauri::State<'_, crate::database::Database>,
+) -> Result<(), String> {
+    // Delete stored state for every window, including the pre-multi-window key
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    Setting::delete(&conn, LEGACY_WINDOW_STATE_KEY).map_err(|e| e.to_string())?;
+    Setting::delete_by_prefix(&conn, &window_state_key("")).map_err(|e| e.to_string())?;
     drop(conn);
 
     // Reset to default position and size
     if let Some(window) = app.get_webview_window("main") {
-        let default_state = WindowState::default();
-
-        let size = PhysicalSize::new(default_state.width, default_state.height);
-        window.set_size(size).map_err(|e| e.to_string())?;
-
-        let position = PhysicalPosition::new(default_state.x, default_state.y);
-        window.set_position(position).map_err(|e| e.to_string())?;
-
-        if default_state.maximized {
-            window.maximize().map_err(|e| e.to_string())?;
-        } else {
-            window.unmaximize().map_err(|e| e.to_string())?;
-        }
+        apply_window_state(&window, &WindowState::default())?;
     }
 
     Ok(())
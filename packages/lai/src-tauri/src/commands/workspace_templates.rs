@@ -1,12 +1,32 @@
 use crate::database::{workspace_templates::*, Database};
 use tauri::State;
 
+/// Explain edits refused because the template is built in
+fn map_update_error(e: rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::SqliteFailure(_, Some(ref msg)) if msg == BUILTIN_UPDATE_ERROR => {
+            "Built-in templates can't be edited. Override it to save your own version instead."
+                .to_string()
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Parse clone overrides; `null` means no overrides
+fn parse_overrides(overrides: serde_json::Value) -> Result<PartialWorkspaceTemplate, String> {
+    if overrides.is_null() {
+        Ok(PartialWorkspaceTemplate::default())
+    } else {
+        serde_json::from_value(overrides).map_err(|e| format!("Invalid overrides: {}", e))
+    }
+}
+
 #[tauri::command]
 pub async fn create_workspace_template(
     database: State<'_, Database>,
     template: NewWorkspaceTemplate,
 ) -> Result<WorkspaceTemplate, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::create(&conn, template).map_err(|e| e.to_string())
 }
 
@@ -15,7 +35,7 @@ pub async fn get_workspace_template(
     database: State<'_, Database>,
     id: String,
 ) -> Result<Option<WorkspaceTemplate>, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::get_by_id(&conn, &id).map_err(|e| e.to_string())
 }
 
@@ -23,7 +43,7 @@ pub async fn get_workspace_template(
 pub async fn get_all_workspace_templates(
     database: State<'_, Database>,
 ) -> Result<Vec<WorkspaceTemplate>, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::get_all(&conn).map_err(|e| e.to_string())
 }
 
@@ -32,7 +52,7 @@ pub async fn get_workspace_templates_by_category(
     database: State<'_, Database>,
     category: String,
 ) -> Result<Vec<WorkspaceTemplate>, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::get_by_category(&conn, &category).map_err(|e| e.to_string())
 }
 
@@ -40,7 +60,7 @@ pub async fn get_workspace_templates_by_category(
 pub async fn get_workspace_template_categories(
     database: State<'_, Database>,
 ) -> Result<Vec<String>, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::get_categories(&conn).map_err(|e| e.to_string())
 }
 
@@ -50,8 +70,79 @@ pub async fn update_workspace_template(
     id: String,
     template: NewWorkspaceTemplate,
 ) -> Result<(), String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
-    WorkspaceTemplate::update(&conn, &id, template).map_err(|e| e.to_string())
+    let conn = database.conn().map_err(|e| e.to_string())?;
+    WorkspaceTemplate::update(&conn, &id, template).map_err(map_update_error)
+}
+
+#[tauri::command]
+pub async fn get_workspace_template_settings(
+    database: State<'_, Database>,
+    template_id: String,
+) -> Result<WorkspaceTemplateSettings, String> {
+    let conn = database.conn().map_err(|e| e.to_string())?;
+    let template = WorkspaceTemplate::get_by_id(&conn, &template_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Workspace template not found: {}", template_id))?;
+    template
+        .parse_settings()
+        .map_err(|e| format!("Invalid settings for template {}: {}", template_id, e))
+}
+
+#[tauri::command]
+pub async fn update_workspace_template_settings(
+    database: State<'_, Database>,
+    template_id: String,
+    settings: WorkspaceTemplateSettings,
+) -> Result<(), String> {
+    let conn = database.conn().map_err(|e| e.to_string())?;
+    WorkspaceTemplate::set_settings(&conn, &template_id, settings).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => {
+            format!("Workspace template not found: {}", template_id)
+        }
+        e => map_update_error(e),
+    })
+}
+
+#[tauri::command]
+pub async fn clone_workspace_template(
+    database: State<'_, Database>,
+    source_id: String,
+    name: Option<String>,
+    overrides: serde_json::Value,
+) -> Result<WorkspaceTemplate, String> {
+    let mut overrides = parse_overrides(overrides)?;
+    if name.is_some() {
+        overrides.name = name;
+    }
+
+    let conn = database.conn().map_err(|e| e.to_string())?;
+    WorkspaceTemplate::clone_with_overrides(&conn, &source_id, overrides).map_err(|e| e.to_string())
+}
+
+/// Copy a built-in template with `overrides` applied. Unlike a plain clone,
+/// the copy keeps the built-in's name unless the overrides rename it.
+#[tauri::command]
+pub async fn override_builtin_template(
+    database: State<'_, Database>,
+    source_id: String,
+    overrides: serde_json::Value,
+) -> Result<WorkspaceTemplate, String> {
+    let mut overrides = parse_overrides(overrides)?;
+
+    let conn = database.conn().map_err(|e| e.to_string())?;
+    let source = WorkspaceTemplate::get_by_id(&conn, &source_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Workspace template not found".to_string())?;
+    if !source.is_builtin {
+        return Err(
+            "Only built-in templates can be overridden; edit this one directly".to_string(),
+        );
+    }
+    if overrides.name.is_none() {
+        overrides.name = Some(source.name);
+    }
+
+    WorkspaceTemplate::clone_with_overrides(&conn, &source_id, overrides).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -59,7 +150,7 @@ pub async fn delete_workspace_template(
     database: State<'_, Database>,
     id: String,
 ) -> Result<(), String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::delete(&conn, &id).map_err(|e| e.to_string())
 }
 
@@ -68,6 +159,6 @@ pub async fn search_workspace_templates(
     database: State<'_, Database>,
     query: String,
 ) -> Result<Vec<WorkspaceTemplate>, String> {
-    let conn = database.conn().lock().map_err(|e| e.to_string())?;
+    let conn = database.conn().map_err(|e| e.to_string())?;
     WorkspaceTemplate::search(&conn, &query).map_err(|e| e.to_string())
 }
@@ -1,4 +1,6 @@
-use rusqlite::{params, Connection, Result};
+use super::event_log::EventLog;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result, Row};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -13,7 +15,8 @@ pub struct Conversation {
     pub system_prompt: Option<String>,
     pub parent_conversation_id: Option<String>,
     pub branch_point_message_id: Option<String>,
-    // Note: 'deleted' and 'deleted_at' are stored in DB but are not exposed to the API struct
+    // Note: 'deleted', 'deleted_at', 'archived' and 'archived_at' are stored in DB but are not
+    // exposed to the API struct
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +38,45 @@ pub struct NewConversationWithId {
     pub updated_at: i64,
 }
 
+/// Conversation and message totals for one provider/model pair
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderUsage {
+    pub provider: String,
+    pub model: String,
+    pub conversation_count: i64,
+    pub message_count: i64,
+    pub total_tokens: i64,
+}
+
+/// Message totals and the latest message of one conversation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub message_count: i64,
+    pub total_tokens: i64,
+    pub last_message_at: Option<i64>,
+    pub last_message_role: Option<String>,
+    /// First 100 characters of the latest message, empty if there are no messages
+    pub last_message_snippet: String,
+}
+
+const SELECT_COLUMNS: &str = "SELECT id, title, created_at, updated_at, model, provider, system_prompt, parent_conversation_id, branch_point_message_id FROM conversations";
+
+fn from_row(row: &Row) -> Result<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        model: row.get(4)?,
+        provider: row.get(5)?,
+        system_prompt: row.get(6)?,
+        parent_conversation_id: row.get(7)?,
+        branch_point_message_id: row.get(8)?,
+    })
+}
+
 impl Conversation {
     pub fn create(conn: &Connection, new_conv: NewConversation) -> Result<Self> {
         let now = SystemTime::now()
@@ -120,18 +162,241 @@ impl Conversation {
         conversations.collect()
     }
 
+    /// Non-deleted conversations whose title starts with `prefix` (ASCII case-insensitive),
+    /// most recently updated first
+    pub fn get_by_title_prefix(conn: &Connection, prefix: &str, limit: i64) -> Result<Vec<Self>> {
+        // Escape LIKE wildcards so the prefix only matches itself
+        let escaped = prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE deleted = 0 AND title LIKE ?1 || '%' ESCAPE '\\' ORDER BY updated_at DESC LIMIT ?2",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![escaped, limit], from_row)?;
+        conversations.collect()
+    }
+
+    /// Non-deleted conversations that are not archived, most recently updated first
+    pub fn get_unarchived(conn: &Connection, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE archived = 0 AND deleted = 0 ORDER BY updated_at DESC LIMIT ?1",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![limit], from_row)?;
+        conversations.collect()
+    }
+
+    /// Archived, non-deleted conversations, most recently archived first
+    pub fn get_archived(conn: &Connection, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE archived = 1 AND deleted = 0 ORDER BY archived_at DESC LIMIT ?1",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![limit], from_row)?;
+        conversations.collect()
+    }
+
+    /// Non-deleted conversations without any non-deleted messages, oldest first
+    pub fn get_empty(conn: &Connection, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.title, c.created_at, c.updated_at, c.model, c.provider,
+                    c.system_prompt, c.parent_conversation_id, c.branch_point_message_id
+             FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id AND m.deleted = 0
+             WHERE c.deleted = 0
+             GROUP BY c.id
+             HAVING COUNT(m.id) = 0
+             ORDER BY c.created_at ASC
+             LIMIT ?1",
+        )?;
+        let conversations = stmt.query_map(params![limit], from_row)?;
+        conversations.collect()
+    }
+
+    /// Soft-delete every conversation `get_empty` would return, returning how many
+    pub fn delete_empty(conn: &Connection) -> Result<usize> {
+        let tx = conn.unchecked_transaction()?;
+        let empty = Self::get_empty(&tx, -1)?;
+        for conversation in &empty {
+            Self::delete(&tx, &conversation.id)?;
+        }
+        tx.commit()?;
+        Ok(empty.len())
+    }
+
+    /// Conversations created between `from` and `to` (inclusive, unix seconds), newest first
+    pub fn get_by_date_range(
+        conn: &Connection,
+        from: i64,
+        to: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE created_at BETWEEN ?1 AND ?2 AND deleted = 0 ORDER BY created_at DESC LIMIT ?3",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![from, to, limit], from_row)?;
+        conversations.collect()
+    }
+
+    pub fn get_by_provider(conn: &Connection, provider: &str, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE provider = ?1 AND deleted = 0 ORDER BY updated_at DESC LIMIT ?2",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![provider, limit], from_row)?;
+        conversations.collect()
+    }
+
+    pub fn get_by_model(conn: &Connection, model: &str, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE model = ?1 AND deleted = 0 ORDER BY updated_at DESC LIMIT ?2",
+            SELECT_COLUMNS
+        ))?;
+        let conversations = stmt.query_map(params![model, limit], from_row)?;
+        conversations.collect()
+    }
+
+    /// Per provider/model totals over non-deleted conversations and messages
+    pub fn provider_usage_stats(conn: &Connection) -> Result<Vec<ProviderUsage>> {
+        let mut stmt = conn.prepare(
+            "SELECT c.provider, c.model,
+                    COUNT(DISTINCT c.id),
+                    COUNT(m.id),
+                    COALESCE(SUM(m.tokens_used), 0)
+             FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id AND m.deleted = 0
+             WHERE c.deleted = 0
+             GROUP BY c.provider, c.model
+             ORDER BY COUNT(DISTINCT c.id) DESC, c.provider, c.model",
+        )?;
+        let stats = stmt.query_map([], |row| {
+            Ok(ProviderUsage {
+                provider: row.get(0)?,
+                model: row.get(1)?,
+                conversation_count: row.get(2)?,
+                message_count: row.get(3)?,
+                total_tokens: row.get(4)?,
+            })
+        })?;
+        stats.collect()
+    }
+
+    /// Summaries for the given non-deleted conversations in one query, most
+    /// recently active first. Unknown IDs are skipped.
+    pub fn summary_stats(conn: &Connection, ids: &[String]) -> Result<Vec<ConversationSummary>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Numbered placeholders so the same ID list can be reused in every clause
+        let placeholders = (1..=ids.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "WITH stats AS (
+                 SELECT conversation_id, COUNT(*) AS message_count,
+                        COALESCE(SUM(tokens_used), 0) AS total_tokens
+                 FROM messages
+                 WHERE deleted = 0 AND conversation_id IN ({ids})
+                 GROUP BY conversation_id
+             ),
+             latest AS (
+                 SELECT conversation_id, role, SUBSTR(content, 1, 100) AS snippet, timestamp,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY conversation_id ORDER BY timestamp DESC, rowid DESC
+                        ) AS rn
+                 FROM messages
+                 WHERE deleted = 0 AND conversation_id IN ({ids})
+             )
+             SELECT c.id, c.title,
+                    COALESCE(s.message_count, 0), COALESCE(s.total_tokens, 0),
+                    l.timestamp, l.role, COALESCE(l.snippet, '')
+             FROM conversations c
+             LEFT JOIN stats s ON s.conversation_id = c.id
+             LEFT JOIN latest l ON l.conversation_id = c.id AND l.rn = 1
+             WHERE c.deleted = 0 AND c.id IN ({ids})
+             ORDER BY COALESCE(l.timestamp, c.updated_at) DESC",
+            ids = placeholders
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let summaries = stmt.query_map(params_from_iter(ids), |row| {
+            Ok(ConversationSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                message_count: row.get(2)?,
+                total_tokens: row.get(3)?,
+                last_message_at: row.get(4)?,
+                last_message_role: row.get(5)?,
+                last_message_snippet: row.get(6)?,
+            })
+        })?;
+        summaries.collect()
+    }
+
     pub fn update_title(conn: &Connection, id: &str, new_title: &str) -> Result<()> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
+        let previous: Option<String> = conn
+            .query_row(
+                "SELECT title FROM conversations WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
         conn.execute(
             "UPDATE conversations SET title = ?1, updated_at = ?2 WHERE id = ?3",
             params![new_title, now, id],
         )?;
+        if let Some(previous) = previous {
+            EventLog::record(
+                conn,
+                "conversation",
+                id,
+                "title_updated",
+                &serde_json::json!({ "previous": previous, "title": new_title }),
+            )?;
+        }
         Ok(())
     }
 
+    /// Change the model, provider and/or system prompt, leaving `None` fields as they
+    /// are. `Some(None)` clears the system prompt. Returns the updated conversation.
+    pub fn update_settings(
+        conn: &Connection,
+        id: &str,
+        model: Option<&str>,
+        provider: Option<&str>,
+        system_prompt: Option<Option<&str>>,
+    ) -> Result<Self> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        conn.execute(
+            "UPDATE conversations
+             SET model = COALESCE(?1, model),
+                 provider = COALESCE(?2, provider),
+                 system_prompt = CASE WHEN ?3 THEN ?4 ELSE system_prompt END,
+                 updated_at = ?5
+             WHERE id = ?6 AND deleted = 0",
+            params![
+                model,
+                provider,
+                system_prompt.is_some(),
+                system_prompt.flatten(),
+                now,
+                id
+            ],
+        )?;
+        Self::get_by_id(conn, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
     pub fn touch(conn: &Connection, id: &str) -> Result<()> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -150,22 +415,122 @@ impl Conversation {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
-        conn.execute(
+        let changed = conn.execute(
             "UPDATE conversations SET deleted = 1, deleted_at = ?1 WHERE id = ?2",
             params![now, id],
         )?;
+        if changed > 0 {
+            EventLog::record(conn, "conversation", id, "deleted", &serde_json::json!({}))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a row with this ID exists, including soft-deleted conversations
+    pub fn exists(conn: &Connection, id: &str) -> Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Permanently remove a conversation; messages and tag links go with it
+    pub fn purge(conn: &Connection, id: &str) -> Result<()> {
+        conn.execute("DELETE FROM conversations WHERE id = ?1", params![id])?;
         Ok(())
     }
 
+    /// Permanently remove soft-deleted conversations and soft-deleted messages whose
+    /// `deleted_at` is before `cutoff`, including all messages of those conversations.
+    /// The messages_fts delete trigger drops their search rows. Returns
+    /// `(conversations_deleted, messages_deleted)`.
+    pub fn purge_deleted_before(conn: &Connection, cutoff: i64) -> Result<(usize, usize)> {
+        let tx = conn.unchecked_transaction()?;
+        let messages = tx.execute(
+            "DELETE FROM messages
+             WHERE (deleted = 1 AND deleted_at < ?1)
+                OR conversation_id IN (
+                    SELECT id FROM conversations WHERE deleted = 1 AND deleted_at < ?1
+                )",
+            params![cutoff],
+        )?;
+        let conversations = tx.execute(
+            "DELETE FROM conversations WHERE deleted = 1 AND deleted_at < ?1",
+            params![cutoff],
+        )?;
+        tx.commit()?;
+        Ok((conversations, messages))
+    }
+
     pub fn restore(conn: &Connection, id: &str) -> Result<()> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
-        conn.execute(
+        let changed = conn.execute(
             "UPDATE conversations SET deleted = 0, deleted_at = NULL, updated_at = ?1 WHERE id = ?2",
             params![now, id],
         )?;
+        if changed > 0 {
+            EventLog::record(conn, "conversation", id, "restored", &serde_json::json!({}))?;
+        }
+        Ok(())
+    }
+
+    /// Hide a conversation from the main list without deleting it. The deleted
+    /// flag is left untouched.
+    pub fn archive(conn: &Connection, id: &str) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let changed = conn.execute(
+            "UPDATE conversations SET archived = 1, archived_at = ?1 WHERE id = ?2",
+            params![now, id],
+        )?;
+        if changed > 0 {
+            EventLog::record(conn, "conversation", id, "archived", &serde_json::json!({}))?;
+        }
+        Ok(())
+    }
+
+    /// Archive every visible, unarchived conversation in one update, returning how many
+    pub fn archive_all(conn: &Connection) -> Result<usize> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let tx = conn.unchecked_transaction()?;
+        let ids = {
+            let mut stmt = tx.prepare(
+                "UPDATE conversations SET archived = 1, archived_at = ?1
+                 WHERE deleted = 0 AND archived = 0
+                 RETURNING id",
+            )?;
+            let ids = stmt.query_map(params![now], |row| row.get::<_, String>(0))?;
+            ids.collect::<Result<Vec<_>>>()?
+        };
+        for id in &ids {
+            EventLog::record(&tx, "conversation", id, "archived", &serde_json::json!({}))?;
+        }
+        tx.commit()?;
+        Ok(ids.len())
+    }
+
+    pub fn unarchive(conn: &Connection, id: &str) -> Result<()> {
+        let changed = conn.execute(
+            "UPDATE conversations SET archived = 0, archived_at = NULL WHERE id = ?1",
+            params![id],
+        )?;
+        if changed > 0 {
+            EventLog::record(
+                conn,
+                "conversation",
+                id,
+                "unarchived",
+                &serde_json::json!({}),
+            )?;
+        }
         Ok(())
     }
 
@@ -188,6 +553,214 @@ impl Conversation {
         conversations.collect()
     }
 
+    /// Filter by any combination of tags (a conversation must carry every tag in
+    /// `tag_ids`), a title substring and a provider. Empty filters are skipped.
+    pub fn search_filtered(
+        conn: &Connection,
+        tag_ids: &[String],
+        query: Option<&str>,
+        provider: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Self>> {
+        let mut sql = String::from(SELECT_COLUMNS);
+        let mut values: Vec<Value> = Vec::new();
+
+        // The HAVING count below must match the number of distinct tags
+        let mut tag_ids = tag_ids.to_vec();
+        tag_ids.sort();
+        tag_ids.dedup();
+        if !tag_ids.is_empty() {
+            let placeholders = vec!["?"; tag_ids.len()].join(", ");
+            sql.push_str(&format!(
+                " JOIN (SELECT conversation_id FROM conversation_tags
+                        WHERE tag_id IN ({})
+                        GROUP BY conversation_id
+                        HAVING COUNT(DISTINCT tag_id) = ?) ct
+                   ON ct.conversation_id = conversations.id",
+                placeholders
+            ));
+            values.extend(tag_ids.iter().cloned().map(Value::from));
+            values.push(Value::from(tag_ids.len() as i64));
+        }
+
+        sql.push_str(" WHERE deleted = 0");
+        if let Some(query) = query.filter(|q| !q.trim().is_empty()) {
+            sql.push_str(" AND title LIKE ?");
+            values.push(Value::from(format!("%{}%", query.trim())));
+        }
+        if let Some(provider) = provider.filter(|p| !p.is_empty()) {
+            sql.push_str(" AND provider = ?");
+            values.push(Value::from(provider.to_string()));
+        }
+        sql.push_str(" ORDER BY updated_at DESC LIMIT ?");
+        values.push(Value::from(limit));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let conversations = stmt.query_map(params_from_iter(values), from_row)?;
+        conversations.collect()
+    }
+
+    /// Copy a conversation, its non-deleted messages and its tags under a new ID.
+    /// The title defaults to the source title with " (copy)" appended.
+    pub fn duplicate(
+        conn: &Connection,
+        source_id: &str,
+        new_title: Option<String>,
+    ) -> Result<Self> {
+        let source =
+            Self::get_by_id(conn, source_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let id = uuid::Uuid::new_v4().to_string();
+        let title = new_title.unwrap_or_else(|| format!("{} (copy)", source.title));
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO conversations (id, title, created_at, updated_at, model, provider, system_prompt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                &id,
+                &title,
+                now,
+                now,
+                &source.model,
+                &source.provider,
+                &source.system_prompt
+            ],
+        )?;
+
+        for message in super::messages::Message::get_by_conversation(&tx, source_id)? {
+            tx.execute(
+                "INSERT INTO messages (id, conversation_id, role, content, timestamp, tokens_used)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    &id,
+                    &message.role,
+                    &message.content,
+                    message.timestamp,
+                    message.tokens_used
+                ],
+            )?;
+        }
+
+        tx.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag_id, created_at)
+             SELECT ?1, tag_id, ?2 FROM conversation_tags WHERE conversation_id = ?3",
+            params![&id, now, source_id],
+        )?;
+        tx.commit()?;
+
+        Ok(Conversation {
+            id,
+            title,
+            created_at: now,
+            updated_at: now,
+            model: source.model,
+            provider: source.provider,
+            system_prompt: source.system_prompt,
+            parent_conversation_id: None,
+            branch_point_message_id: None,
+        })
+    }
+
+    /// Move every message after `split_after_message_id` into a new conversation with
+    /// the same model, provider, system prompt and tags. Returns the original
+    /// (with its `updated_at` bumped) and the new conversation.
+    pub fn split_at_message(
+        conn: &Connection,
+        conversation_id: &str,
+        split_after_message_id: &str,
+    ) -> Result<(Self, Self)> {
+        let source =
+            Self::get_by_id(conn, conversation_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        let messages = super::messages::Message::get_by_conversation(conn, conversation_id)?;
+        let split_index = messages
+            .iter()
+            .position(|m| m.id == split_after_message_id)
+            .ok_or_else(|| {
+                rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
+                    Some("Message not found in conversation".to_string()),
+                )
+            })?;
+        let moved = &messages[split_index + 1..];
+        if moved.is_empty() {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+                Some("No messages after the split point".to_string()),
+            ));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let id = uuid::Uuid::new_v4().to_string();
+        let title = format!("{} (continued)", source.title);
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO conversations (id, title, created_at, updated_at, model, provider, system_prompt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                &id,
+                &title,
+                now,
+                now,
+                &source.model,
+                &source.provider,
+                &source.system_prompt
+            ],
+        )?;
+
+        for message in moved {
+            tx.execute(
+                "UPDATE messages SET conversation_id = ?1 WHERE id = ?2",
+                params![&id, &message.id],
+            )?;
+            // The search index keeps its own copy of the conversation ID
+            tx.execute(
+                "UPDATE messages_fts SET conversation_id = ?1
+                 WHERE rowid = (SELECT rowid FROM messages WHERE id = ?2)",
+                params![&id, &message.id],
+            )?;
+        }
+
+        tx.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag_id, created_at)
+             SELECT ?1, tag_id, ?2 FROM conversation_tags WHERE conversation_id = ?3",
+            params![&id, now, conversation_id],
+        )?;
+        tx.execute(
+            "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+            params![now, conversation_id],
+        )?;
+        tx.commit()?;
+
+        let continued = Conversation {
+            id,
+            title,
+            created_at: now,
+            updated_at: now,
+            model: source.model.clone(),
+            provider: source.provider.clone(),
+            system_prompt: source.system_prompt.clone(),
+            parent_conversation_id: None,
+            branch_point_message_id: None,
+        };
+        Ok((
+            Conversation {
+                updated_at: now,
+                ..source
+            },
+            continued,
+        ))
+    }
+
     // Create a new conversation as a branch from a specific message
     pub fn create_branch(
         conn: &Connection,
@@ -197,7 +770,7 @@ impl Conversation {
     ) -> Result<Self> {
         // Get the parent conversation to inherit model and provider
         let parent = Self::get_by_id(conn, parent_conversation_id)?
-            .ok_or_else(|| rusqlite::Error::InvalidPath("Parent conversation not found".into()))?;
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
 
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -270,3 +843,437 @@ impl Conversation {
         conversations.collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::messages::{Message, NewMessage};
+    use crate::database::tags::{NewTag, Tag};
+    use crate::database::test_helpers::{add_test_message, create_test_conversation};
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn create_conversation_and_message_roundtrip() {
+        // Use an in-memory SQLite database for tests
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        // Create a conversation
+        let conv = create_test_conversation(&conn, "Test conv");
+        let fetched = Conversation::get_by_id(&conn, &conv.id).expect("get conv");
+        assert!(fetched.is_some());
+
+        // Create a message and ensure it can be retrieved
+        let msg = add_test_message(&conn, &conv.id, "user", "hello test");
+        let msgs = Message::get_by_conversation(&conn, &conv.id).expect("get msgs");
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].content, msg.content);
+
+        // Soft-delete conversation and ensure it is hidden
+        Conversation::delete(&conn, &conv.id).expect("soft delete conv");
+        let hidden = Conversation::get_by_id(&conn, &conv.id).expect("get after delete");
+        assert!(hidden.is_none());
+
+        // Restore and ensure conversation is visible again and messages remain
+        Conversation::restore(&conn, &conv.id).expect("restore conv");
+        let restored = Conversation::get_by_id(&conn, &conv.id).expect("get after restore");
+        assert!(restored.is_some());
+        let msgs_after =
+            Message::get_by_conversation(&conn, &conv.id).expect("get msgs after restore");
+        assert_eq!(msgs_after.len(), 1);
+    }
+
+    #[test]
+    fn provider_usage_groups_by_provider_and_model() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        for (provider, model, tokens) in [
+            ("openai", "gpt-4", Some(10)),
+            ("openai", "gpt-4", Some(5)),
+            ("ollama", "llama3", None),
+        ] {
+            let conv = Conversation::create(
+                &conn,
+                NewConversation {
+                    title: "Usage".to_string(),
+                    model: model.to_string(),
+                    provider: provider.to_string(),
+                    system_prompt: None,
+                },
+            )
+            .expect("create conv");
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conv.id.clone(),
+                    role: "assistant".to_string(),
+                    content: "reply".to_string(),
+                    tokens_used: tokens,
+                },
+            )
+            .expect("create msg");
+        }
+
+        let stats = Conversation::provider_usage_stats(&conn).expect("stats");
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].provider, "openai");
+        assert_eq!(stats[0].conversation_count, 2);
+        assert_eq!(stats[0].message_count, 2);
+        assert_eq!(stats[0].total_tokens, 15);
+        assert_eq!(stats[1].total_tokens, 0);
+
+        let by_model = Conversation::get_by_model(&conn, "llama3", 10).expect("by model");
+        assert_eq!(by_model.len(), 1);
+        let by_provider = Conversation::get_by_provider(&conn, "openai", 10).expect("by provider");
+        assert_eq!(by_provider.len(), 2);
+    }
+
+    #[test]
+    fn title_prefix_filter_escapes_wildcards() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        create_test_conversation(&conn, "Rust notes");
+        for i in 0..5 {
+            create_test_conversation(&conn, &format!("Other {}", i));
+        }
+        create_test_conversation(&conn, "rust_async");
+        create_test_conversation(&conn, "Rusty 100%");
+
+        let titles = |prefix: &str, limit: i64| -> Vec<String> {
+            Conversation::get_by_title_prefix(&conn, prefix, limit)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.title)
+                .collect()
+        };
+        let mut rust = titles("RUST", 10);
+        rust.sort();
+        assert_eq!(rust, vec!["Rust notes", "Rusty 100%", "rust_async"]);
+        assert_eq!(titles("rust", 1).len(), 1);
+        // Wildcards in the prefix are literal
+        assert_eq!(titles("rust_", 10), vec!["rust_async"]);
+        assert!(titles("%", 10).is_empty());
+        assert_eq!(titles("Rusty 100%", 10), vec!["Rusty 100%"]);
+    }
+
+    #[test]
+    fn duplicate_copies_messages_and_tags() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let source = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Original".to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: Some("be brief".to_string()),
+            },
+        )
+        .expect("create conv");
+        let mut deleted_id = String::new();
+        for content in ["one", "two", "three"] {
+            deleted_id = add_test_message(&conn, &source.id, "user", content).id;
+        }
+        Message::delete(&conn, &deleted_id).expect("delete msg");
+        let tag = Tag::create(
+            &conn,
+            NewTag {
+                name: "work".to_string(),
+                color: None,
+            },
+        )
+        .expect("create tag");
+        Tag::add_to_conversation(&conn, &source.id, &tag.id).expect("tag conv");
+
+        let copy = Conversation::duplicate(&conn, &source.id, None).expect("duplicate");
+        assert_ne!(copy.id, source.id);
+        assert_eq!(copy.title, "Original (copy)");
+        assert_eq!(copy.system_prompt.as_deref(), Some("be brief"));
+
+        let source_msgs = Message::get_by_conversation(&conn, &source.id).expect("msgs");
+        let copy_msgs = Message::get_by_conversation(&conn, &copy.id).expect("copy msgs");
+        assert_eq!(copy_msgs.len(), source_msgs.len());
+        assert_eq!(copy_msgs.len(), 2);
+        assert!(copy_msgs.iter().all(|m| m.conversation_id == copy.id));
+
+        let copy_tags = Tag::get_for_conversation(&conn, &copy.id).expect("tags");
+        assert_eq!(copy_tags.len(), 1);
+
+        assert!(matches!(
+            Conversation::duplicate(&conn, "missing", None),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+    }
+
+    #[test]
+    fn filtered_search_combines_tags_query_and_provider() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let tag = |name: &str| {
+            Tag::create(
+                &conn,
+                NewTag {
+                    name: name.to_string(),
+                    color: None,
+                },
+            )
+            .expect("create tag")
+        };
+        let work = tag("work");
+        let urgent = tag("urgent");
+
+        let mut ids = Vec::new();
+        for (title, provider, tags) in [
+            ("Rust lifetimes", "openai", vec![&work, &urgent]),
+            ("Rust macros", "ollama", vec![&work]),
+            ("Dinner ideas", "openai", vec![&work]),
+            ("Rust async", "openai", vec![]),
+        ] {
+            let conv = Conversation::create(
+                &conn,
+                NewConversation {
+                    title: title.to_string(),
+                    model: "gpt-test".to_string(),
+                    provider: provider.to_string(),
+                    system_prompt: None,
+                },
+            )
+            .expect("create conv");
+            for t in tags {
+                Tag::add_to_conversation(&conn, &conv.id, &t.id).expect("tag conv");
+            }
+            ids.push(conv.id);
+        }
+
+        let work_only = vec![work.id.clone()];
+        let both = vec![work.id.clone(), urgent.id.clone()];
+        let titles = |tag_ids: &[String], query: Option<&str>, provider: Option<&str>| {
+            let mut titles: Vec<String> =
+                Conversation::search_filtered(&conn, tag_ids, query, provider, 50)
+                    .expect("search")
+                    .into_iter()
+                    .map(|c| c.title)
+                    .collect();
+            titles.sort();
+            titles
+        };
+
+        assert_eq!(
+            titles(&work_only, Some("rust"), None),
+            vec!["Rust lifetimes", "Rust macros"]
+        );
+        assert_eq!(titles(&both, None, None), vec!["Rust lifetimes"]);
+        let repeated = vec![work.id.clone(), work.id.clone()];
+        assert_eq!(titles(&repeated, Some("rust"), None).len(), 2);
+        assert_eq!(
+            titles(&work_only, Some("Rust"), Some("ollama")),
+            vec!["Rust macros"]
+        );
+        assert_eq!(titles(&[], Some("Rust"), None).len(), 3);
+        // Quotes in the query are bound as data, not SQL
+        assert!(titles(&[], Some("' OR 1=1 --"), None).is_empty());
+
+        Conversation::delete(&conn, &ids[0]).expect("delete");
+        assert_eq!(titles(&both, None, None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn update_settings_changes_only_given_fields() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let conv = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Switch".to_string(),
+                model: "gpt-4".to_string(),
+                provider: "openai".to_string(),
+                system_prompt: Some("be brief".to_string()),
+            },
+        )
+        .expect("create conv");
+
+        let updated = Conversation::update_settings(&conn, &conv.id, Some("gpt-4o"), None, None)
+            .expect("update model");
+        assert_eq!(updated.model, "gpt-4o");
+        assert_eq!(updated.provider, "openai");
+        assert_eq!(updated.system_prompt.as_deref(), Some("be brief"));
+
+        let cleared = Conversation::update_settings(&conn, &conv.id, None, None, Some(None))
+            .expect("clear prompt");
+        assert_eq!(cleared.model, "gpt-4o");
+        assert!(cleared.system_prompt.is_none());
+
+        assert!(Conversation::update_settings(&conn, "missing", Some("x"), None, None).is_err());
+    }
+
+    #[test]
+    fn summary_stats_cover_each_conversation() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let mut ids = Vec::new();
+        for (title, count) in [("One", 1), ("Two", 2), ("Three", 3)] {
+            let conv = create_test_conversation(&conn, title);
+            for i in 0..count {
+                Message::create(
+                    &conn,
+                    NewMessage {
+                        conversation_id: conv.id.clone(),
+                        role: if i % 2 == 0 { "user" } else { "assistant" }.to_string(),
+                        content: format!("{} {}", "x".repeat(120), i),
+                        tokens_used: Some(10),
+                    },
+                )
+                .expect("create msg");
+            }
+            ids.push(conv.id);
+        }
+        ids.push("missing".to_string());
+
+        let summaries = Conversation::summary_stats(&conn, &ids).unwrap();
+        assert_eq!(summaries.len(), 3);
+        for (title, count) in [("One", 1), ("Two", 2), ("Three", 3)] {
+            let summary = summaries.iter().find(|s| s.title == title).unwrap();
+            assert_eq!(summary.message_count, count);
+            assert_eq!(summary.total_tokens, count * 10);
+            assert!(summary.last_message_at.is_some());
+            assert_eq!(summary.last_message_snippet.chars().count(), 100);
+        }
+        let two = summaries.iter().find(|s| s.title == "Two").unwrap();
+        assert_eq!(two.last_message_role.as_deref(), Some("assistant"));
+
+        assert!(Conversation::summary_stats(&conn, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn archive_is_independent_of_delete() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let mut ids = Vec::new();
+        for title in ["Kept", "Archived"] {
+            ids.push(create_test_conversation(&conn, title).id);
+        }
+
+        Conversation::archive(&conn, &ids[1]).expect("archive");
+        let main: Vec<String> = Conversation::get_unarchived(&conn, 10)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.title)
+            .collect();
+        assert_eq!(main, vec!["Kept"]);
+        let archived = Conversation::get_archived(&conn, 10).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].id, ids[1]);
+        // Archived conversations stay accessible by ID
+        assert!(Conversation::get_by_id(&conn, &ids[1]).unwrap().is_some());
+
+        // Deleting hides it from the archive too; restoring brings it back still archived
+        Conversation::delete(&conn, &ids[1]).expect("delete");
+        assert!(Conversation::get_archived(&conn, 10).unwrap().is_empty());
+        Conversation::restore(&conn, &ids[1]).expect("restore");
+        assert_eq!(Conversation::get_archived(&conn, 10).unwrap().len(), 1);
+
+        Conversation::unarchive(&conn, &ids[1]).expect("unarchive");
+        assert!(Conversation::get_archived(&conn, 10).unwrap().is_empty());
+        assert_eq!(Conversation::get_unarchived(&conn, 10).unwrap().len(), 2);
+
+        // Archiving everything skips deleted conversations and ones already archived
+        let trashed = create_test_conversation(&conn, "Trashed");
+        Conversation::delete(&conn, &trashed.id).expect("delete");
+        Conversation::archive(&conn, &ids[1]).expect("archive");
+        assert_eq!(Conversation::archive_all(&conn).unwrap(), 1);
+        assert!(Conversation::get_unarchived(&conn, 10).unwrap().is_empty());
+        assert_eq!(Conversation::get_archived(&conn, 10).unwrap().len(), 2);
+        assert_eq!(Conversation::archive_all(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn split_conversation_moves_later_messages() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let conv = Conversation::create(
+            &conn,
+            NewConversation {
+                title: "Long chat".to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: Some("be brief".to_string()),
+            },
+        )
+        .expect("create conv");
+        let mut message_ids = Vec::new();
+        for content in ["one", "two", "three", "four", "five"] {
+            let msg = add_test_message(&conn, &conv.id, "user", &format!("message {}", content));
+            message_ids.push(msg.id);
+        }
+        let tag = Tag::create_or_get(&conn, "work", None).unwrap();
+        Tag::add_to_conversation(&conn, &conv.id, &tag.id).unwrap();
+
+        let (original, continued) =
+            Conversation::split_at_message(&conn, &conv.id, &message_ids[1]).expect("split");
+        assert_eq!(original.id, conv.id);
+        assert_eq!(continued.title, "Long chat (continued)");
+        assert_eq!(continued.model, "gpt-test");
+        assert_eq!(continued.system_prompt.as_deref(), Some("be brief"));
+
+        let kept = Message::get_by_conversation(&conn, &conv.id).unwrap();
+        let moved = Message::get_by_conversation(&conn, &continued.id).unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(moved.len(), 3);
+        assert_eq!(moved[0].id, message_ids[2]);
+        assert_eq!(
+            Tag::get_for_conversation(&conn, &continued.id)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Search follows the moved messages
+        let hits = Message::search_within_conversation(&conn, &continued.id, "five", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+
+        // Splitting after the last message leaves nothing to move
+        assert!(Conversation::split_at_message(&conn, &continued.id, &message_ids[4]).is_err());
+        assert!(Conversation::split_at_message(&conn, &conv.id, &message_ids[4]).is_err());
+        assert!(matches!(
+            Conversation::split_at_message(&conn, "missing", &message_ids[0]),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+    }
+
+    #[test]
+    fn finds_and_deletes_only_empty_conversations() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let empty = create_test_conversation(&conn, "Never used");
+        let used = create_test_conversation(&conn, "Has messages");
+        add_test_message(&conn, &used.id, "user", "hello");
+        let cleared = create_test_conversation(&conn, "Messages deleted");
+        let msg = add_test_message(&conn, &cleared.id, "user", "hello");
+        Message::delete(&conn, &msg.id).unwrap();
+
+        let found: Vec<String> = Conversation::get_empty(&conn, 10)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&empty.id));
+        assert!(found.contains(&cleared.id));
+        assert_eq!(Conversation::get_empty(&conn, 1).unwrap().len(), 1);
+
+        assert_eq!(Conversation::delete_empty(&conn).unwrap(), 2);
+        assert!(Conversation::get_empty(&conn, 10).unwrap().is_empty());
+        let remaining = Conversation::get_all(&conn, 10).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, used.id);
+    }
+}
@@ -0,0 +1,118 @@
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One recorded change; `payload` holds event-specific details such as the previous value
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventLogEntry {
+    pub id: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub timestamp: i64,
+}
+
+pub struct EventLog;
+
+impl EventLog {
+    pub fn record(
+        conn: &Connection,
+        entity_type: &str,
+        entity_id: &str,
+        event_type: &str,
+        payload: &serde_json::Value,
+    ) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        conn.execute(
+            "INSERT INTO event_log (id, entity_type, entity_id, event_type, payload, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                entity_type,
+                entity_id,
+                event_type,
+                payload.to_string(),
+                now
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Newest events first, optionally for one entity and/or at or after `since`
+    pub fn list(
+        conn: &Connection,
+        entity_id: Option<&str>,
+        since: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<EventLogEntry>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, entity_type, entity_id, event_type, payload, timestamp
+             FROM event_log
+             WHERE (?1 IS NULL OR entity_id = ?1) AND (?2 IS NULL OR timestamp >= ?2)
+             ORDER BY timestamp DESC, rowid DESC
+             LIMIT ?3",
+        )?;
+        let entries = stmt.query_map(params![entity_id, since, limit], |row| {
+            let payload: String = row.get(4)?;
+            Ok(EventLogEntry {
+                id: row.get(0)?,
+                entity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                event_type: row.get(3)?,
+                payload: serde_json::from_str(&payload).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        4,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })?,
+                timestamp: row.get(5)?,
+            })
+        })?;
+        entries.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conversations::Conversation;
+    use crate::database::messages::Message;
+    use crate::database::test_helpers::{add_test_message, create_test_conversation};
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn records_conversation_and_message_events() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let conv = create_test_conversation(&conn, "Draft");
+        let msg = add_test_message(&conn, &conv.id, "user", "first");
+
+        Conversation::update_title(&conn, &conv.id, "Final").unwrap();
+        Conversation::archive(&conn, &conv.id).unwrap();
+        Message::update(&conn, &msg.id, "second").unwrap();
+        Message::delete(&conn, &msg.id).unwrap();
+        Conversation::delete(&conn, "missing").unwrap();
+
+        let conv_events = EventLog::list(&conn, Some(&conv.id), None, 10).unwrap();
+        let types: Vec<&str> = conv_events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, vec!["archived", "title_updated"]);
+        assert_eq!(conv_events[1].payload["previous"], "Draft");
+        assert_eq!(conv_events[1].payload["title"], "Final");
+
+        let msg_events = EventLog::list(&conn, Some(&msg.id), None, 10).unwrap();
+        assert_eq!(msg_events[0].event_type, "deleted");
+        assert_eq!(msg_events[1].payload["previous"], "first");
+
+        assert_eq!(EventLog::list(&conn, None, None, 10).unwrap().len(), 4);
+        assert_eq!(EventLog::list(&conn, None, None, 1).unwrap().len(), 1);
+        assert!(EventLog::list(&conn, None, Some(i64::MAX), 10)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportSchedule {
+    pub id: String,
+    /// Export format: `json` or `markdown`
+    pub format: String,
+    pub destination_dir: String,
+    /// Run interval: `hourly`, `daily` or `weekly`
+    pub cron_pattern: String,
+    pub last_run_at: Option<i64>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewExportSchedule {
+    pub format: String,
+    pub destination_dir: String,
+    pub cron_pattern: String,
+}
+
+const SELECT_COLUMNS: &str =
+    "SELECT id, format, destination_dir, cron_pattern, last_run_at, is_active FROM export_schedules";
+
+fn from_row(row: &Row) -> Result<ExportSchedule> {
+    Ok(ExportSchedule {
+        id: row.get(0)?,
+        format: row.get(1)?,
+        destination_dir: row.get(2)?,
+        cron_pattern: row.get(3)?,
+        last_run_at: row.get(4)?,
+        is_active: row.get::<_, i64>(5)? != 0,
+    })
+}
+
+impl ExportSchedule {
+    pub fn create(conn: &Connection, new_schedule: NewExportSchedule) -> Result<Self> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        conn.execute(
+            "INSERT INTO export_schedules (id, format, destination_dir, cron_pattern, last_run_at, is_active)
+             VALUES (?1, ?2, ?3, ?4, NULL, 1)",
+            params![
+                &id,
+                &new_schedule.format,
+                &new_schedule.destination_dir,
+                &new_schedule.cron_pattern
+            ],
+        )?;
+
+        Ok(ExportSchedule {
+            id,
+            format: new_schedule.format,
+            destination_dir: new_schedule.destination_dir,
+            cron_pattern: new_schedule.cron_pattern,
+            last_run_at: None,
+            is_active: true,
+        })
+    }
+
+    pub fn get_by_id(conn: &Connection, id: &str) -> Result<Option<Self>> {
+        let mut stmt = conn.prepare(&format!("{} WHERE id = ?1", SELECT_COLUMNS))?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!("{} ORDER BY rowid", SELECT_COLUMNS))?;
+        let schedules = stmt.query_map([], from_row)?;
+        schedules.collect()
+    }
+
+    pub fn get_active(conn: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE is_active = 1 ORDER BY rowid",
+            SELECT_COLUMNS
+        ))?;
+        let schedules = stmt.query_map([], from_row)?;
+        schedules.collect()
+    }
+
+    pub fn update(
+        conn: &Connection,
+        id: &str,
+        schedule: &NewExportSchedule,
+        is_active: bool,
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE export_schedules
+             SET format = ?1, destination_dir = ?2, cron_pattern = ?3, is_active = ?4
+             WHERE id = ?5",
+            params![
+                &schedule.format,
+                &schedule.destination_dir,
+                &schedule.cron_pattern,
+                is_active as i64,
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_last_run(conn: &Connection, id: &str, timestamp: i64) -> Result<()> {
+        conn.execute(
+            "UPDATE export_schedules SET last_run_at = ?1 WHERE id = ?2",
+            params![timestamp, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete(conn: &Connection, id: &str) -> Result<()> {
+        conn.execute("DELETE FROM export_schedules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
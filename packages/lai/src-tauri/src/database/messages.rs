@@ -1,4 +1,5 @@
-use rusqlite::{params, Connection, Result};
+use super::event_log::EventLog;
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -12,6 +13,14 @@ pub struct Message {
     pub tokens_used: Option<i64>,
 }
 
+/// Full-text search hit with its BM25 rank (lower is more relevant) and a highlighted excerpt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchedMessage {
+    pub message: Message,
+    pub rank: f32,
+    pub snippet: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewMessage {
     pub conversation_id: String,
@@ -67,9 +76,77 @@ impl Message {
         })
     }
 
+    /// Insert many messages with one prepared statement. Outside a transaction the
+    /// inserts are wrapped in one, so either all messages are written or none.
+    pub fn create_batch(conn: &Connection, messages: Vec<NewMessageWithId>) -> Result<Vec<Self>> {
+        let tx = if conn.is_autocommit() {
+            Some(conn.unchecked_transaction()?)
+        } else {
+            None
+        };
+
+        let mut created = Vec::with_capacity(messages.len());
+        {
+            let mut stmt = conn.prepare_cached(
+                "INSERT INTO messages (id, conversation_id, role, content, timestamp, tokens_used) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for msg in messages {
+                stmt.execute(params![
+                    &msg.id,
+                    &msg.conversation_id,
+                    &msg.role,
+                    &msg.content,
+                    msg.timestamp,
+                    msg.tokens_used
+                ])?;
+                created.push(Message {
+                    id: msg.id,
+                    conversation_id: msg.conversation_id,
+                    role: msg.role,
+                    content: msg.content,
+                    timestamp: msg.timestamp,
+                    tokens_used: msg.tokens_used,
+                });
+            }
+        }
+
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
+        Ok(created)
+    }
+
+    /// Whether a row with this ID exists, including soft-deleted messages
+    pub fn exists(conn: &Connection, id: &str) -> Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM messages WHERE id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Fetch one message; soft-deleted rows are only returned with `include_deleted`
+    pub fn get_by_id(conn: &Connection, id: &str, include_deleted: bool) -> Result<Option<Self>> {
+        conn.query_row(
+            "SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE id = ?1 AND (deleted = 0 OR ?2 = 1)",
+            params![id, include_deleted],
+            |row| {
+                Ok(Message {
+                    id: row.get(0)?,
+                    conversation_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    tokens_used: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+    }
+
     pub fn get_by_conversation(conn: &Connection, conversation_id: &str) -> Result<Vec<Self>> {
-        // Only return non-deleted messages
-        let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE conversation_id = ?1 AND deleted = 0 ORDER BY timestamp ASC")?;
+        // Only return non-deleted messages; system messages always lead so providers see them first
+        let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE conversation_id = ?1 AND deleted = 0 ORDER BY CASE WHEN role = 'system' THEN 0 ELSE 1 END, timestamp ASC")?;
         let messages = stmt.query_map(params![conversation_id], |row| {
             Ok(Message {
                 id: row.get(0)?,
@@ -83,6 +160,68 @@ impl Message {
         messages.collect()
     }
 
+    /// Number of non-deleted messages in a conversation
+    pub fn count_by_conversation(conn: &Connection, conversation_id: &str) -> Result<i64> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1 AND deleted = 0",
+            params![conversation_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// One page of a conversation in chronological order
+    pub fn get_page(
+        conn: &Connection,
+        conversation_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE conversation_id = ?1 AND deleted = 0 ORDER BY timestamp ASC, rowid ASC LIMIT ?2 OFFSET ?3")?;
+        let messages = stmt.query_map(params![conversation_id, limit, offset], |row| {
+            Ok(Message {
+                id: row.get(0)?,
+                conversation_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                timestamp: row.get(4)?,
+                tokens_used: row.get(5)?,
+            })
+        })?;
+        messages.collect()
+    }
+
+    /// The `limit` newest messages older than `before_timestamp`, in chronological order
+    pub fn get_before(
+        conn: &Connection,
+        conversation_id: &str,
+        before_timestamp: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE conversation_id = ?1 AND deleted = 0 AND timestamp < ?2 ORDER BY timestamp DESC, rowid DESC LIMIT ?3")?;
+        let messages =
+            stmt.query_map(params![conversation_id, before_timestamp, limit], |row| {
+                Ok(Message {
+                    id: row.get(0)?,
+                    conversation_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    tokens_used: row.get(5)?,
+                })
+            })?;
+        let mut result: Vec<Self> = messages.collect::<Result<Vec<_>>>()?;
+        result.reverse();
+        Ok(result)
+    }
+
+    pub fn count_by_role(conn: &Connection, conversation_id: &str, role: &str) -> Result<i64> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1 AND role = ?2 AND deleted = 0",
+            params![conversation_id, role],
+            |row| row.get(0),
+        )
+    }
+
     pub fn get_last_n(conn: &Connection, conversation_id: &str, n: i64) -> Result<Vec<Self>> {
         let mut stmt = conn.prepare("SELECT id, conversation_id, role, content, timestamp, tokens_used FROM messages WHERE conversation_id = ?1 AND deleted = 0 ORDER BY timestamp DESC LIMIT ?2")?;
         let messages = stmt.query_map(params![conversation_id, n], |row| {
@@ -115,7 +254,82 @@ impl Message {
         messages.collect()
     }
 
+    /// Search message content ordered by BM25 relevance, with `<b>`-highlighted snippets
+    pub fn search_ranked(
+        conn: &Connection,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SearchedMessage>> {
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.conversation_id, m.role, m.content, m.timestamp, m.tokens_used,
+                    messages_fts.rank,
+                    snippet(messages_fts, 0, '<b>', '</b>', '…', 15)
+             FROM messages_fts
+             JOIN messages m ON m.rowid = messages_fts.rowid
+             WHERE messages_fts MATCH ?1 AND m.deleted = 0
+             ORDER BY messages_fts.rank
+             LIMIT ?2",
+        )?;
+        let results = stmt.query_map(params![query, limit], |row| {
+            Ok(SearchedMessage {
+                message: Message {
+                    id: row.get(0)?,
+                    conversation_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    tokens_used: row.get(5)?,
+                },
+                rank: row.get::<_, f64>(6)? as f32,
+                snippet: row.get(7)?,
+            })
+        })?;
+        results.collect()
+    }
+
+    /// `search_ranked` restricted to the messages of one conversation
+    pub fn search_within_conversation(
+        conn: &Connection,
+        conversation_id: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SearchedMessage>> {
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.conversation_id, m.role, m.content, m.timestamp, m.tokens_used,
+                    messages_fts.rank,
+                    snippet(messages_fts, 0, '<b>', '</b>', '…', 15)
+             FROM messages_fts
+             JOIN messages m ON m.rowid = messages_fts.rowid
+             WHERE messages_fts MATCH ?1 AND m.conversation_id = ?2 AND m.deleted = 0
+             ORDER BY messages_fts.rank
+             LIMIT ?3",
+        )?;
+        let results = stmt.query_map(params![query, conversation_id, limit], |row| {
+            Ok(SearchedMessage {
+                message: Message {
+                    id: row.get(0)?,
+                    conversation_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    tokens_used: row.get(5)?,
+                },
+                rank: row.get::<_, f64>(6)? as f32,
+                snippet: row.get(7)?,
+            })
+        })?;
+        results.collect()
+    }
+
     pub fn update(conn: &Connection, id: &str, content: &str) -> Result<Self> {
+        let previous: Option<String> = conn
+            .query_row(
+                "SELECT content FROM messages WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
         // Update message content
         conn.execute(
             "UPDATE messages SET content = ?1 WHERE id = ?2",
@@ -138,6 +352,14 @@ impl Message {
         // Touch the conversation to update its timestamp
         super::conversations::Conversation::touch(conn, &message.conversation_id)?;
 
+        EventLog::record(
+            conn,
+            "message",
+            id,
+            "updated",
+            &serde_json::json!({ "previous": previous, "content": content }),
+        )?;
+
         Ok(message)
     }
 
@@ -147,10 +369,19 @@ impl Message {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
-        conn.execute(
+        let changed = conn.execute(
             "UPDATE messages SET deleted = 1, deleted_at = ?1 WHERE id = ?2",
             params![now, id],
         )?;
+        if changed > 0 {
+            EventLog::record(conn, "message", id, "deleted", &serde_json::json!({}))?;
+        }
+        Ok(())
+    }
+
+    /// Permanently remove a message, e.g. one saved for a request that then failed
+    pub fn purge(conn: &Connection, id: &str) -> Result<()> {
+        conn.execute("DELETE FROM messages WHERE id = ?1", params![id])?;
         Ok(())
     }
 
@@ -163,3 +394,189 @@ impl Message {
         Ok(count.unwrap_or(0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations;
+    use crate::database::test_helpers::{add_test_message, create_test_conversation};
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn system_messages_sort_first() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let conv = create_test_conversation(&conn, "System order");
+
+        for (role, content) in [("user", "question"), ("system", "be brief")] {
+            add_test_message(&conn, &conv.id, role, content);
+        }
+
+        let msgs = Message::get_by_conversation(&conn, &conv.id).expect("get msgs");
+        assert_eq!(msgs[0].role, "system");
+        assert_eq!(msgs[1].role, "user");
+        assert_eq!(
+            Message::count_by_role(&conn, &conv.id, "system").expect("count"),
+            1
+        );
+    }
+
+    #[test]
+    fn ranked_search_returns_snippets_after_fts_migration() {
+        let conn = Connection::open_in_memory().expect("open db");
+        conn.execute("PRAGMA foreign_keys = ON", [])
+            .expect("pragma");
+        // A version 1 database still has the old porter tokenizer
+        migrations::apply_migrations(&conn, &migrations::MIGRATIONS[..1]).expect("v1 schema");
+
+        let conv = create_test_conversation(&conn, "Search");
+        for content in ["café au lait recipe", "rust borrow checker", "café café"] {
+            add_test_message(&conn, &conv.id, "user", content);
+        }
+
+        migrations::run_migrations(&conn).expect("migrate");
+        let sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE name = 'messages_fts'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("fts sql");
+        assert!(sql.contains("unicode61"));
+
+        let hits = Message::search_ranked(&conn, "café", 10).expect("search");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].message.content, "café café");
+        assert!(hits[0].rank <= hits[1].rank);
+        assert!(hits[1].snippet.contains("<b>café</b>"));
+    }
+
+    #[test]
+    fn batch_insert_writes_all_rows_in_one_transaction() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let conv = create_test_conversation(&conn, "Bulk");
+
+        let batch = |prefix: &str| -> Vec<NewMessageWithId> {
+            (0..100)
+                .map(|i| NewMessageWithId {
+                    id: format!("{}-{}", prefix, i),
+                    conversation_id: conv.id.clone(),
+                    role: "user".to_string(),
+                    content: format!("message {}", i),
+                    timestamp: i,
+                    tokens_used: None,
+                })
+                .collect()
+        };
+        let stored = |conn: &Connection| {
+            Message::get_by_conversation(conn, &conv.id)
+                .expect("msgs")
+                .len()
+        };
+
+        let created = Message::create_batch(&conn, batch("batch")).expect("batch insert");
+        assert_eq!(created.len(), 100);
+        assert_eq!(created[99].id, "batch-99");
+        assert_eq!(stored(&conn), 100);
+        assert!(conn.is_autocommit());
+
+        // Inside a caller's transaction the batch commits nothing itself
+        conn.execute_batch("BEGIN").expect("begin");
+        Message::create_batch(&conn, batch("nested")).expect("nested batch");
+        assert!(!conn.is_autocommit());
+        conn.execute_batch("ROLLBACK").expect("rollback");
+        assert_eq!(stored(&conn), 100);
+
+        // A failing row rolls back the whole batch
+        let mut dupes = batch("dupe");
+        dupes.push(batch("dupe").remove(0));
+        assert!(Message::create_batch(&conn, dupes).is_err());
+        assert!(!Message::exists(&conn, "dupe-1").expect("exists"));
+        assert_eq!(stored(&conn), 100);
+        assert!(conn.is_autocommit());
+    }
+
+    #[test]
+    fn get_message_by_id_respects_include_deleted() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let conv = create_test_conversation(&conn, "Lookup");
+        let msg = add_test_message(&conn, &conv.id, "user", "find me");
+
+        let found = Message::get_by_id(&conn, &msg.id, false).unwrap();
+        assert_eq!(found.map(|m| m.content), Some("find me".to_string()));
+        assert!(Message::get_by_id(&conn, "missing", true)
+            .unwrap()
+            .is_none());
+
+        Message::delete(&conn, &msg.id).expect("soft delete msg");
+        assert!(Message::get_by_id(&conn, &msg.id, false).unwrap().is_none());
+        let deleted = Message::get_by_id(&conn, &msg.id, true).unwrap();
+        assert_eq!(deleted.map(|m| m.id), Some(msg.id));
+    }
+
+    #[test]
+    fn conversation_search_is_scoped() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let mut ids = Vec::new();
+        for (title, contents) in [
+            ("Kitchen", ["pasta recipe", "pasta sauce tips"]),
+            ("Garden", ["tomato planting", "pasta from garden tomatoes"]),
+        ] {
+            let conv = create_test_conversation(&conn, title);
+            for content in contents {
+                add_test_message(&conn, &conv.id, "user", content);
+            }
+            ids.push(conv.id);
+        }
+
+        let kitchen = Message::search_within_conversation(&conn, &ids[0], "pasta", 10).unwrap();
+        assert_eq!(kitchen.len(), 2);
+        assert!(kitchen
+            .iter()
+            .all(|hit| hit.message.conversation_id == ids[0]));
+        assert!(kitchen[0].snippet.contains("<b>pasta</b>"));
+
+        let garden = Message::search_within_conversation(&conn, &ids[1], "pasta", 10).unwrap();
+        assert_eq!(garden.len(), 1);
+        assert_eq!(garden[0].message.content, "pasta from garden tomatoes");
+
+        assert_eq!(Message::search_ranked(&conn, "pasta", 10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn messages_page_in_order_and_load_before_timestamp() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+        let conv = create_test_conversation(&conn, "Long");
+        let messages = (0..25)
+            .map(|i| NewMessageWithId {
+                id: format!("m{}", i),
+                conversation_id: conv.id.clone(),
+                role: "user".to_string(),
+                content: format!("message {}", i),
+                timestamp: 1000 + i,
+                tokens_used: None,
+            })
+            .collect();
+        Message::create_batch(&conn, messages).expect("insert");
+        Message::delete(&conn, "m24").expect("delete");
+
+        assert_eq!(Message::count_by_conversation(&conn, &conv.id).unwrap(), 24);
+        let second = Message::get_page(&conn, &conv.id, 10, 10).unwrap();
+        let ids: Vec<&str> = second.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids.first(), Some(&"m10"));
+        assert_eq!(ids.last(), Some(&"m19"));
+        assert_eq!(Message::get_page(&conn, &conv.id, 10, 20).unwrap().len(), 4);
+
+        let older = Message::get_before(&conn, &conv.id, 1005, 3).unwrap();
+        let ids: Vec<&str> = older.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m2", "m3", "m4"]);
+    }
+}
@@ -0,0 +1,159 @@
+use super::schema;
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A versioned schema change, applied once and recorded in `schema_migrations`
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations in the order they must be applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Baseline schema",
+        up: schema::create_tables,
+    },
+    Migration {
+        version: 2,
+        description: "Use the unicode61 tokenizer for message search",
+        up: schema::migrate_messages_fts_tokenizer,
+    },
+    Migration {
+        version: 3,
+        description: "Track the profile each conversation uses",
+        up: schema::add_conversation_profile_column,
+    },
+    Migration {
+        version: 4,
+        description: "Archive conversations independently of deletion",
+        up: schema::add_conversation_archive_columns,
+    },
+    Migration {
+        version: 5,
+        description: "Log conversation and message changes",
+        up: schema::create_event_log_table,
+    },
+    Migration {
+        version: 6,
+        description: "Store per-provider endpoint configuration",
+        up: schema::create_provider_configs_table,
+    },
+    Migration {
+        version: 7,
+        description: "Forward conversation events to webhooks",
+        up: schema::create_conversation_webhooks_table,
+    },
+    Migration {
+        version: 8,
+        description: "Schedule background exports",
+        up: schema::create_export_schedules_table,
+    },
+    Migration {
+        version: 9,
+        description: "Index conversations by creation date",
+        up: schema::add_conversation_created_at_index,
+    },
+    Migration {
+        version: 10,
+        description: "Index conversations by provider and model",
+        up: schema::add_conversation_provider_model_indexes,
+    },
+    Migration {
+        version: 11,
+        description: "Record keyboard shortcut usage",
+        up: schema::create_shortcut_events_table,
+    },
+    Migration {
+        version: 12,
+        description: "Store a sampling temperature per profile",
+        up: schema::add_profile_temperature_column,
+    },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationInfo {
+    pub version: u32,
+    pub description: String,
+    pub is_applied: bool,
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL,
+            description TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn applied_versions(conn: &Connection) -> Result<HashSet<u32>> {
+    let mut stmt = conn.prepare("SELECT version FROM schema_migrations")?;
+    let versions = stmt.query_map([], |row| row.get::<_, u32>(0))?;
+    versions.collect()
+}
+
+/// Apply every migration that has not been recorded yet, in version order
+pub fn run_migrations(conn: &Connection) -> Result<()> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+/// Apply the unrecorded entries of `migrations`. Each one runs in its own transaction
+/// with its `schema_migrations` row, so a failing migration leaves the database on
+/// the previous version and stops the ones after it.
+pub(crate) fn apply_migrations(conn: &Connection, migrations: &[Migration]) -> Result<()> {
+    ensure_migrations_table(conn)?;
+    let applied = applied_versions(conn)?;
+
+    let mut pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect();
+    pending.sort_by_key(|m| m.version);
+
+    for migration in pending {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, applied_at, description) VALUES (?1, ?2, ?3)",
+            params![migration.version, now, migration.description],
+        )?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Every known migration with whether this database has applied it
+pub fn list_migrations(conn: &Connection) -> Result<Vec<MigrationInfo>> {
+    ensure_migrations_table(conn)?;
+    let applied = applied_versions(conn)?;
+    Ok(MIGRATIONS
+        .iter()
+        .map(|m| MigrationInfo {
+            version: m.version,
+            description: m.description.to_string(),
+            is_applied: applied.contains(&m.version),
+        })
+        .collect())
+}
+
+/// Highest applied migration version, or 0 for an unversioned database
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    ensure_migrations_table(conn)?;
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+}
@@ -2,93 +2,396 @@
 // Database module: declare submodules and provide the Database manager.
 
 pub mod conversations;
+pub mod event_log;
+pub mod export_schedules;
 pub mod messages;
+pub mod migrations;
 pub mod profiles;
+pub mod provider_configs;
 pub mod schema;
 pub mod settings;
+pub mod shortcut_events;
 pub mod tags;
+pub mod webhooks;
 pub mod workspace_templates;
 
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, Result};
 use std::path::PathBuf;
-use std::sync::Mutex;
 
-/// Database manager that holds the connection
+/// Pooled connections kept open when `LAI_DB_POOL_SIZE` is not set
+pub const DEFAULT_POOL_SIZE: u32 = 4;
+
+/// A connection checked out of the pool; it goes back when dropped
+pub type DbConnection = PooledConnection<SqliteConnectionManager>;
+
+/// Database manager that holds the connection pool
 pub struct Database {
-    conn: Mutex<Connection>,
+    pool: Pool<SqliteConnectionManager>,
+}
+
+/// Pool size from `LAI_DB_POOL_SIZE`, falling back to `DEFAULT_POOL_SIZE`
+fn pool_size() -> u32 {
+    std::env::var("LAI_DB_POOL_SIZE")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_POOL_SIZE)
+}
+
+/// Turn a failed pool checkout into a SQLite error. r2d2 only fails by timing out and
+/// keeps the cause as text, so classify by the pool instead: with every connection
+/// open and checked out the pool was busy, otherwise a connection wouldn't open.
+fn pool_error(pool: &Pool<SqliteConnectionManager>, e: r2d2::Error) -> rusqlite::Error {
+    let state = pool.state();
+    let code = if state.connections == pool.max_size() && state.idle_connections == 0 {
+        rusqlite::ffi::SQLITE_BUSY
+    } else {
+        rusqlite::ffi::SQLITE_CANTOPEN
+    };
+    rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), Some(e.to_string()))
 }
 
 impl Database {
     /// Initialize the database with schema
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
+        let in_memory = db_path.as_os_str() == ":memory:";
+        let manager = if in_memory {
+            // A named shared-cache database, so every pooled connection sees the same data
+            SqliteConnectionManager::file(format!(
+                "file:lai-memory-{}?mode=memory&cache=shared",
+                uuid::Uuid::new_v4()
+            ))
+        } else {
+            SqliteConnectionManager::file(&db_path)
+        }
+        .with_init(|conn: &mut Connection| {
+            // Enable foreign keys and wait for other pooled writers instead of failing
+            conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+        });
 
-        // Enable foreign keys
-        conn.execute("PRAGMA foreign_keys = ON", [])?;
+        // A shared in-memory database is dropped with its last connection, so those
+        // connections are never recycled
+        let builder = if in_memory {
+            Pool::builder()
+                .max_size(pool_size())
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            Pool::builder().max_size(pool_size())
+        };
+        // Building fails only when the initial connections can't be opened
+        let pool = builder.build(manager).map_err(|e| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                Some(e.to_string()),
+            )
+        })?;
+
+        let conn = pool.get().map_err(|e| pool_error(&pool, e))?;
+        if !in_memory {
+            // WAL lets readers on other pooled connections run alongside a writer
+            conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        }
+
+        // Version 1 creates the baseline schema; every later change is a migration
+        migrations::run_migrations(&conn)?;
+        drop(conn);
+
+        Ok(Database { pool })
+    }
+
+    /// Check a connection out of the pool, waiting if all of them are in use
+    pub fn conn(&self) -> std::result::Result<DbConnection, r2d2::Error> {
+        self.pool.get()
+    }
+}
 
-        // Initialize schema
-        schema::create_tables(&conn)?;
+/// Fixtures shared by the model tests
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::conversations::{Conversation, NewConversation};
+    use super::messages::{Message, NewMessage};
+    use rusqlite::Connection;
 
-        Ok(Database {
-            conn: Mutex::new(conn),
-        })
+    /// A local test conversation with no system prompt
+    pub(crate) fn create_test_conversation(conn: &Connection, title: &str) -> Conversation {
+        Conversation::create(
+            conn,
+            NewConversation {
+                title: title.to_string(),
+                model: "gpt-test".to_string(),
+                provider: "local".to_string(),
+                system_prompt: None,
+            },
+        )
+        .expect("create conv")
     }
 
-    /// Get a reference to the connection
-    pub fn conn(&self) -> &Mutex<Connection> {
-        &self.conn
+    /// A message without a token count
+    pub(crate) fn add_test_message(
+        conn: &Connection,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+    ) -> Message {
+        Message::create(
+            conn,
+            NewMessage {
+                conversation_id: conversation_id.to_string(),
+                role: role.to_string(),
+                content: content.to_string(),
+                tokens_used: None,
+            },
+        )
+        .expect("create msg")
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::conversations::{Conversation as DbConversation, NewConversation};
-    use crate::database::messages::{Message as DbMessage, NewMessage};
+    use crate::database::conversations::Conversation;
+    use crate::database::test_helpers::create_test_conversation;
     use std::path::PathBuf;
 
     #[test]
-    fn create_conversation_and_message_roundtrip() {
-        // Use an in-memory SQLite database for tests
+    fn migrations_are_recorded_once() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let latest = migrations::MIGRATIONS.last().unwrap().version;
+        assert_eq!(migrations::schema_version(&conn).unwrap(), latest);
+        assert!(migrations::list_migrations(&conn)
+            .unwrap()
+            .iter()
+            .all(|m| m.is_applied));
+
+        // Re-running is a no-op
+        migrations::run_migrations(&conn).expect("rerun");
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, migrations::MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn profile_temperature_setting_moves_to_column() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        // Pretend the database predates version 12 with a temperature saved as a setting
+        conn.execute("UPDATE profiles SET temperature = NULL", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value, updated_at)
+             VALUES ('profile_default_temperature', '1.2', 0)",
+            [],
+        )
+        .unwrap();
+        schema::add_profile_temperature_column(&conn).expect("rerun migration");
+
+        let temperature: Option<f64> = conn
+            .query_row(
+                "SELECT temperature FROM profiles WHERE id = 'default'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(temperature, Some(1.2));
+        let leftover: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM settings WHERE key = 'profile_default_temperature'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(leftover, 0);
+    }
+
+    #[test]
+    fn failed_migration_rolls_back_to_previous_version() {
+        use migrations::Migration;
+
+        fn add_notes(conn: &Connection) -> Result<()> {
+            conn.execute("CREATE TABLE migration_notes (body TEXT)", [])?;
+            Ok(())
+        }
+        fn broken(conn: &Connection) -> Result<()> {
+            conn.execute("CREATE TABLE half_applied (id TEXT)", [])?;
+            conn.execute("ALTER TABLE missing_table ADD COLUMN x TEXT", [])?;
+            Ok(())
+        }
+
         let db = Database::new(PathBuf::from(":memory:")).expect("db init");
-        let conn = db.conn().lock().expect("lock conn");
-
-        // Create a conversation
-        let new_conv = NewConversation {
-            title: "Test conv".to_string(),
-            model: "gpt-test".to_string(),
-            provider: "local".to_string(),
-            system_prompt: None,
+        let conn = db.conn().expect("lock conn");
+        let latest = migrations::schema_version(&conn).unwrap();
+        let extra = [
+            Migration {
+                version: latest + 1,
+                description: "Add notes",
+                up: add_notes,
+            },
+            Migration {
+                version: latest + 2,
+                description: "Broken",
+                up: broken,
+            },
+        ];
+
+        assert!(migrations::apply_migrations(&conn, &extra).is_err());
+        assert_eq!(migrations::schema_version(&conn).unwrap(), latest + 1);
+        let table_exists = |name: &str| -> bool {
+            conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = ?1)",
+                [name],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert!(table_exists("migration_notes"));
+        assert!(!table_exists("half_applied"));
+    }
+
+    #[test]
+    fn pooled_connections_can_be_held_at_once() {
+        let dir = tempfile::TempDir::new().expect("temp dir");
+        let db = Database::new(dir.path().join("pool.db")).expect("db init");
+
+        let first = db.conn().expect("first conn");
+        let second = db.conn().expect("second conn");
+        let conv = create_test_conversation(&first, "Pooled");
+        assert!(Conversation::get_by_id(&second, &conv.id)
+            .expect("get conv")
+            .is_some());
+        let fk_enabled: i64 = second
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .expect("pragma");
+        assert_eq!(fk_enabled, 1);
+    }
+
+    #[test]
+    fn pool_does_not_serialize_on_a_slow_command() {
+        use std::sync::{mpsc, Arc};
+        use std::thread;
+        use std::time::Duration;
+
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))
+                .expect("count")
         };
 
-        let conv = DbConversation::create(&conn, new_conv).expect("create conv");
-        let fetched = DbConversation::get_by_id(&conn, &conv.id).expect("get conv");
-        assert!(fetched.is_some());
+        let dir = tempfile::TempDir::new().expect("temp dir");
+        let db = Arc::new(Database::new(dir.path().join("throughput.db")).expect("db init"));
 
-        // Create a message and ensure it can be retrieved
-        let new_msg = NewMessage {
-            conversation_id: conv.id.clone(),
-            role: "user".to_string(),
-            content: "hello test".to_string(),
-            tokens_used: None,
+        // A slow command holds its connection mid-write until told to finish
+        let (started_tx, started_rx) = mpsc::channel();
+        let (finish_tx, finish_rx) = mpsc::channel::<()>();
+        let holder = {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                let conn = db.conn().expect("conn");
+                conn.execute_batch(
+                    "BEGIN IMMEDIATE;
+                     INSERT INTO conversations (id, title, created_at, updated_at, model, provider)
+                     VALUES ('slow', 'Slow', 1, 1, 'gpt-4o', 'openai');",
+                )
+                .expect("begin write");
+                started_tx.send(()).expect("signal start");
+                finish_rx.recv().expect("wait for finish");
+                conn.execute_batch("COMMIT").expect("commit");
+            })
         };
+        started_rx.recv().expect("holder started");
+
+        // Another command gets its own connection and, with WAL, reads meanwhile
+        let conn = db
+            .pool
+            .get_timeout(Duration::from_secs(5))
+            .expect("second connection while the first is held");
+        assert_eq!(count(&conn), 0);
+
+        finish_tx.send(()).expect("signal finish");
+        holder.join().expect("holder thread");
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn in_memory_pool_shares_one_database_per_instance() {
+        use std::time::Duration;
+
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let first = db.conn().expect("first conn");
+        let second = db
+            .pool
+            .get_timeout(Duration::from_secs(5))
+            .expect("second connection while the first is held");
+        first
+            .execute(
+                "INSERT INTO settings (key, value, updated_at) VALUES ('shared', 'yes', 0)",
+                [],
+            )
+            .expect("insert");
+        let value: String = second
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'shared'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("visible to the other connection");
+        assert_eq!(value, "yes");
+
+        let other = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let seen: i64 = other
+            .conn()
+            .expect("lock conn")
+            .query_row(
+                "SELECT COUNT(*) FROM settings WHERE key = 'shared'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count");
+        assert_eq!(seen, 0);
+    }
+
+    #[test]
+    fn pool_errors_tell_busy_from_unopenable() {
+        use std::time::Duration;
+
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let _held: Vec<_> = (0..db.pool.max_size())
+            .map(|_| db.conn().expect("lock conn"))
+            .collect();
+        let err = db
+            .pool
+            .get_timeout(Duration::from_millis(50))
+            .map(|_| ())
+            .map_err(|e| pool_error(&db.pool, e))
+            .expect_err("no connection left");
+        assert_eq!(
+            err.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::DatabaseBusy)
+        );
 
-        let msg = DbMessage::create(&conn, new_msg).expect("create msg");
-        let msgs = DbMessage::get_by_conversation(&conn, &conv.id).expect("get msgs");
-        assert_eq!(msgs.len(), 1);
-        assert_eq!(msgs[0].content, msg.content);
-
-        // Soft-delete conversation and ensure it is hidden
-        DbConversation::delete(&conn, &conv.id).expect("soft delete conv");
-        let hidden = DbConversation::get_by_id(&conn, &conv.id).expect("get after delete");
-        assert!(hidden.is_none());
-
-        // Restore and ensure conversation is visible again and messages remain
-        DbConversation::restore(&conn, &conv.id).expect("restore conv");
-        let restored = DbConversation::get_by_id(&conn, &conv.id).expect("get after restore");
-        assert!(restored.is_some());
-        let msgs_after =
-            DbMessage::get_by_conversation(&conn, &conv.id).expect("get msgs after restore");
-        assert_eq!(msgs_after.len(), 1);
+        let dir = tempfile::TempDir::new().expect("temp dir");
+        let broken = Pool::builder()
+            .min_idle(Some(0))
+            .connection_timeout(Duration::from_millis(50))
+            .build(SqliteConnectionManager::file(
+                dir.path().join("missing").join("lai.db"),
+            ))
+            .expect("build pool");
+        let err = broken
+            .get()
+            .map(|_| ())
+            .map_err(|e| pool_error(&broken, e))
+            .expect_err("database can't open");
+        assert_eq!(
+            err.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::CannotOpen)
+        );
     }
 }
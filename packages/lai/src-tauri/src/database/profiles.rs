@@ -2,6 +2,10 @@ use rusqlite::{params, Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Model and provider of the built-in default profile
+pub const DEFAULT_PROFILE_MODEL: &str = "gpt-4o-mini";
+pub const DEFAULT_PROFILE_PROVIDER: &str = "openai";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
     pub id: String,
@@ -10,6 +14,8 @@ pub struct Profile {
     pub default_model: String,
     pub default_provider: String,
     pub system_prompt: Option<String>,
+    /// Sampling temperature (0.0–2.0); `None` uses the provider default
+    pub temperature: Option<f64>,
     pub created_at: i64,
     pub updated_at: i64,
     pub is_active: bool,
@@ -24,6 +30,16 @@ pub struct NewProfile {
     pub system_prompt: Option<String>,
 }
 
+/// Conversation, message and token totals for conversations tagged with a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileStats {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub conversation_count: i64,
+    pub total_messages: i64,
+    pub total_tokens: i64,
+}
+
 impl Profile {
     fn from_row(row: &Row) -> Result<Self> {
         Ok(Profile {
@@ -33,6 +49,7 @@ impl Profile {
             default_model: row.get("default_model")?,
             default_provider: row.get("default_provider")?,
             system_prompt: row.get("system_prompt")?,
+            temperature: row.get("temperature")?,
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
             is_active: row.get::<_, i64>("is_active")? == 1,
@@ -68,7 +85,7 @@ impl Profile {
 
     pub fn get_by_id(conn: &Connection, id: &str) -> Result<Option<Self>> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, default_model, default_provider, system_prompt, created_at, updated_at, is_active
+            "SELECT id, name, description, default_model, default_provider, system_prompt, temperature, created_at, updated_at, is_active
              FROM profiles
              WHERE id = ?1"
         )?;
@@ -82,7 +99,7 @@ impl Profile {
 
     pub fn get_all(conn: &Connection) -> Result<Vec<Self>> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, default_model, default_provider, system_prompt, created_at, updated_at, is_active
+            "SELECT id, name, description, default_model, default_provider, system_prompt, temperature, created_at, updated_at, is_active
              FROM profiles
              ORDER BY is_active DESC, updated_at DESC"
         )?;
@@ -97,7 +114,7 @@ impl Profile {
 
     pub fn get_active(conn: &Connection) -> Result<Option<Self>> {
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, default_model, default_provider, system_prompt, created_at, updated_at, is_active
+            "SELECT id, name, description, default_model, default_provider, system_prompt, temperature, created_at, updated_at, is_active
              FROM profiles
              WHERE is_active = 1
              LIMIT 1"
@@ -164,6 +181,146 @@ impl Profile {
         })
     }
 
+    /// Copy a profile under a new ID and name. The copy is never active.
+    pub fn duplicate(conn: &Connection, source_id: &str, new_name: &str) -> Result<Self> {
+        let source = Self::get_by_id(conn, source_id)?.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Profile not found".to_string()),
+            )
+        })?;
+
+        let copy = Self::create(
+            conn,
+            NewProfile {
+                name: new_name.to_string(),
+                description: source.description,
+                default_model: source.default_model,
+                default_provider: source.default_provider,
+                system_prompt: source.system_prompt,
+            },
+        )?;
+        Self::set_temperature(conn, &copy.id, source.temperature)
+    }
+
+    pub fn get_temperature(conn: &Connection, id: &str) -> Result<Option<f64>> {
+        Self::get_by_id(conn, id)?
+            .map(|profile| profile.temperature)
+            .ok_or_else(|| {
+                rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                    Some("Profile not found".to_string()),
+                )
+            })
+    }
+
+    /// Set or clear the profile's temperature, which must be within 0.0–2.0
+    pub fn set_temperature(conn: &Connection, id: &str, temperature: Option<f64>) -> Result<Self> {
+        if let Some(t) = temperature.filter(|t| !(0.0..=2.0).contains(t)) {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+                Some(format!(
+                    "Temperature must be between 0.0 and 2.0, got {}",
+                    t
+                )),
+            ));
+        }
+
+        let updated = conn.execute(
+            "UPDATE profiles SET temperature = ?1, updated_at = ?2 WHERE id = ?3",
+            params![temperature, chrono::Utc::now().timestamp_millis(), id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Profile not found".to_string()),
+            ));
+        }
+
+        Self::get_by_id(conn, id)?.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Failed to retrieve updated profile".to_string()),
+            )
+        })
+    }
+
+    /// Restore the default model and provider and clear the system prompt and
+    /// temperature, keeping the name and description
+    pub fn reset_to_defaults(conn: &Connection, id: &str) -> Result<Self> {
+        let profile = Self::get_by_id(conn, id)?.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Profile not found".to_string()),
+            )
+        })?;
+
+        Self::update(
+            conn,
+            id,
+            NewProfile {
+                name: profile.name,
+                description: profile.description,
+                default_model: DEFAULT_PROFILE_MODEL.to_string(),
+                default_provider: DEFAULT_PROFILE_PROVIDER.to_string(),
+                system_prompt: None,
+            },
+        )?;
+        Self::set_temperature(conn, id, None)
+    }
+
+    /// Tag a conversation with the profile it uses
+    pub fn assign_to_conversation(
+        conn: &Connection,
+        conversation_id: &str,
+        profile_id: &str,
+    ) -> Result<()> {
+        if Self::get_by_id(conn, profile_id)?.is_none() {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Profile not found".to_string()),
+            ));
+        }
+
+        let updated = conn.execute(
+            "UPDATE conversations SET conversation_profile_id = ?1 WHERE id = ?2 AND deleted = 0",
+            params![profile_id, conversation_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Usage totals for every profile, most-used first. Deleted conversations
+    /// and messages are excluded; profiles with no conversations report zeros.
+    pub fn conversation_stats(conn: &Connection) -> Result<Vec<ProfileStats>> {
+        let mut stmt = conn.prepare(
+            "SELECT p.id, p.name,
+                    COUNT(DISTINCT c.id) AS conversation_count,
+                    COUNT(m.id) AS total_messages,
+                    COALESCE(SUM(m.tokens_used), 0) AS total_tokens
+             FROM profiles p
+             LEFT JOIN conversations c
+                ON c.conversation_profile_id = p.id AND c.deleted = 0
+             LEFT JOIN messages m
+                ON m.conversation_id = c.id AND m.deleted = 0
+             GROUP BY p.id, p.name
+             ORDER BY conversation_count DESC, total_messages DESC, p.name ASC",
+        )?;
+
+        let stats = stmt.query_map([], |row| {
+            Ok(ProfileStats {
+                profile_id: row.get(0)?,
+                profile_name: row.get(1)?,
+                conversation_count: row.get(2)?,
+                total_messages: row.get(3)?,
+                total_tokens: row.get(4)?,
+            })
+        })?;
+        stats.collect()
+    }
+
     pub fn delete(conn: &Connection, id: &str) -> Result<()> {
         // Don't allow deleting the last profile
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM profiles", [], |row| row.get(0))?;
@@ -201,3 +358,112 @@ impl Profile {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conversations::{Conversation, NewConversation};
+    use crate::database::messages::{Message, NewMessage};
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn duplicate_and_reset_profile() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let source = Profile::create(
+            &conn,
+            NewProfile {
+                name: "Writer".to_string(),
+                description: Some("Long-form drafts".to_string()),
+                default_model: "claude-3-opus".to_string(),
+                default_provider: "anthropic".to_string(),
+                system_prompt: Some("Write carefully".to_string()),
+            },
+        )
+        .expect("create profile");
+        Profile::set_active(&conn, &source.id).expect("activate");
+        assert!(Profile::set_temperature(&conn, &source.id, Some(2.5)).is_err());
+        let source = Profile::set_temperature(&conn, &source.id, Some(0.3)).expect("temperature");
+        assert_eq!(
+            Profile::get_temperature(&conn, &source.id).unwrap(),
+            Some(0.3)
+        );
+
+        let copy = Profile::duplicate(&conn, &source.id, "Writer (terse)").expect("duplicate");
+        assert_ne!(copy.id, source.id);
+        assert_eq!(copy.name, "Writer (terse)");
+        assert_eq!(copy.default_model, "claude-3-opus");
+        assert_eq!(copy.system_prompt.as_deref(), Some("Write carefully"));
+        assert_eq!(copy.temperature, Some(0.3));
+        assert!(!copy.is_active);
+        assert!(Profile::duplicate(&conn, "missing", "x").is_err());
+
+        let reset = Profile::reset_to_defaults(&conn, &copy.id).expect("reset");
+        assert_eq!(reset.name, "Writer (terse)");
+        assert_eq!(reset.default_model, "gpt-4o-mini");
+        assert_eq!(reset.default_provider, "openai");
+        assert!(reset.system_prompt.is_none());
+        assert!(reset.temperature.is_none());
+
+        let original = Profile::get_by_id(&conn, &source.id).unwrap().unwrap();
+        assert_eq!(original.default_provider, "anthropic");
+    }
+
+    #[test]
+    fn profile_conversation_stats_group_by_profile() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let coder = Profile::create(
+            &conn,
+            NewProfile {
+                name: "Coder".to_string(),
+                description: None,
+                default_model: "gpt-4o".to_string(),
+                default_provider: "openai".to_string(),
+                system_prompt: None,
+            },
+        )
+        .expect("create profile");
+
+        for tokens in [10, 20] {
+            let conv = Conversation::create(
+                &conn,
+                NewConversation {
+                    title: "Work".to_string(),
+                    model: "gpt-4o".to_string(),
+                    provider: "openai".to_string(),
+                    system_prompt: None,
+                },
+            )
+            .unwrap();
+            Message::create(
+                &conn,
+                NewMessage {
+                    conversation_id: conv.id.clone(),
+                    role: "user".to_string(),
+                    content: "hi".to_string(),
+                    tokens_used: Some(tokens),
+                },
+            )
+            .unwrap();
+            Profile::assign_to_conversation(&conn, &conv.id, &coder.id).expect("assign");
+        }
+
+        assert!(Profile::assign_to_conversation(&conn, "missing", &coder.id).is_err());
+        assert!(Profile::assign_to_conversation(&conn, "missing", "no-profile").is_err());
+
+        let stats = Profile::conversation_stats(&conn).expect("stats");
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].profile_id, coder.id);
+        assert_eq!(stats[0].conversation_count, 2);
+        assert_eq!(stats[0].total_messages, 2);
+        assert_eq!(stats[0].total_tokens, 30);
+
+        let default = stats.iter().find(|s| s.profile_id == "default").unwrap();
+        assert_eq!(default.conversation_count, 0);
+        assert_eq!(default.total_tokens, 0);
+    }
+}
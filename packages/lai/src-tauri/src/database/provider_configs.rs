@@ -0,0 +1,164 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Shown in place of saved header values, which often hold API keys
+pub const REDACTED_HEADER_VALUE: &str = "********";
+
+/// Endpoint settings for one provider. Unset fields fall back to the provider's
+/// public API defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ProviderConfig {
+    pub provider: String,
+    pub base_url: Option<String>,
+    pub api_version: Option<String>,
+    pub timeout_secs: Option<u32>,
+    /// Sent with every request, e.g. `api-key` for Azure OpenAI
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+}
+
+impl ProviderConfig {
+    /// Copy safe to hand to the webview, with every header value masked
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for value in config.extra_headers.values_mut() {
+            *value = REDACTED_HEADER_VALUE.to_string();
+        }
+        config
+    }
+
+    /// Swap masked header values sent back by the webview for the `saved` ones.
+    /// A masked header with no saved value is dropped.
+    pub fn restore_redacted(&mut self, saved: &ProviderConfig) {
+        self.extra_headers.retain(|name, value| {
+            if value != REDACTED_HEADER_VALUE {
+                return true;
+            }
+            match saved.extra_headers.get(name) {
+                Some(saved_value) => {
+                    *value = saved_value.clone();
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    /// Saved configuration for `provider`, or an empty one when nothing is saved
+    pub fn get(conn: &Connection, provider: &str) -> Result<Self> {
+        let config = conn
+            .query_row(
+                "SELECT base_url, api_version, timeout_secs, extra_headers
+                 FROM provider_configs WHERE provider = ?1",
+                params![provider],
+                |row| {
+                    let headers: Option<String> = row.get(3)?;
+                    let extra_headers = match headers {
+                        Some(json) => serde_json::from_str(&json).map_err(|e| {
+                            rusqlite::Error::FromSqlConversionFailure(
+                                3,
+                                rusqlite::types::Type::Text,
+                                Box::new(e),
+                            )
+                        })?,
+                        None => BTreeMap::new(),
+                    };
+                    Ok(ProviderConfig {
+                        provider: provider.to_string(),
+                        base_url: row.get(0)?,
+                        api_version: row.get(1)?,
+                        timeout_secs: row.get(2)?,
+                        extra_headers,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(config.unwrap_or_else(|| ProviderConfig {
+            provider: provider.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    /// Insert or replace the configuration for `self.provider`
+    pub fn set(conn: &Connection, config: &ProviderConfig) -> Result<()> {
+        let headers = serde_json::to_string(&config.extra_headers)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "INSERT INTO provider_configs (provider, base_url, api_version, timeout_secs, extra_headers)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(provider) DO UPDATE SET
+                 base_url = excluded.base_url,
+                 api_version = excluded.api_version,
+                 timeout_secs = excluded.timeout_secs,
+                 extra_headers = excluded.extra_headers",
+            params![
+                config.provider,
+                config.base_url,
+                config.api_version,
+                config.timeout_secs,
+                headers
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn provider_config_defaults_and_upserts() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let empty = ProviderConfig::get(&conn, "openai").unwrap();
+        assert_eq!(empty.provider, "openai");
+        assert!(empty.base_url.is_none());
+        assert!(empty.extra_headers.is_empty());
+
+        let mut config = ProviderConfig {
+            provider: "openai".to_string(),
+            base_url: Some("https://example.openai.azure.com/openai/deployments/gpt4".to_string()),
+            api_version: Some("2024-02-01".to_string()),
+            timeout_secs: Some(30),
+            extra_headers: [("api-key".to_string(), "secret".to_string())].into(),
+        };
+        ProviderConfig::set(&conn, &config).unwrap();
+        assert_eq!(ProviderConfig::get(&conn, "openai").unwrap(), config);
+
+        config.timeout_secs = None;
+        config.extra_headers.clear();
+        ProviderConfig::set(&conn, &config).unwrap();
+        assert_eq!(ProviderConfig::get(&conn, "openai").unwrap(), config);
+        assert!(ProviderConfig::get(&conn, "anthropic")
+            .unwrap()
+            .base_url
+            .is_none());
+    }
+
+    #[test]
+    fn provider_config_masks_and_restores_header_values() {
+        let saved = ProviderConfig {
+            provider: "openai".to_string(),
+            extra_headers: [("api-key".to_string(), "secret".to_string())].into(),
+            ..Default::default()
+        };
+        let mut edited = saved.redacted();
+        assert_eq!(edited.extra_headers["api-key"], REDACTED_HEADER_VALUE);
+
+        edited
+            .extra_headers
+            .insert("x-team".to_string(), "blue".to_string());
+        edited
+            .extra_headers
+            .insert("x-stale".to_string(), REDACTED_HEADER_VALUE.to_string());
+        edited.restore_redacted(&saved);
+        assert_eq!(edited.extra_headers["api-key"], "secret");
+        assert_eq!(edited.extra_headers["x-team"], "blue");
+        assert!(!edited.extra_headers.contains_key("x-stale"));
+    }
+}
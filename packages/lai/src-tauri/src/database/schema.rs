@@ -1,5 +1,7 @@
 use rusqlite::{Connection, Result};
 
+/// The version 1 baseline schema. Later changes belong in their own migration in
+/// `migrations::MIGRATIONS`, not here.
 pub fn create_tables(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS conversations (
@@ -277,3 +279,216 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
 
     Ok(())
 }
+
+/// Databases created before the switch to `unicode61` have a `porter`-tokenized
+/// FTS table. Drop it and rebuild the index from `messages` with the new tokenizer.
+pub(crate) fn migrate_messages_fts_tokenizer(conn: &Connection) -> Result<()> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+
+    if existing.is_some_and(|sql| !sql.contains("unicode61")) {
+        // Runs inside the migration runner's transaction
+        conn.execute_batch(
+            "DROP TABLE messages_fts;
+             CREATE VIRTUAL TABLE messages_fts
+                USING fts5(content, conversation_id, tokenize='unicode61');
+             INSERT INTO messages_fts(rowid, content, conversation_id)
+                SELECT rowid, content, conversation_id FROM messages;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Archiving is tracked separately from soft-delete so a conversation can be both.
+pub(crate) fn add_conversation_archive_columns(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('conversations') WHERE name = 'archived'",
+        [],
+        |row| Ok(row.get::<_, i64>(0)? > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE conversations ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+        conn.execute(
+            "ALTER TABLE conversations ADD COLUMN archived_at INTEGER",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Record which profile a conversation was started with. Nullable and without a
+/// foreign key so conversations outlive the profiles they reference.
+pub(crate) fn add_conversation_profile_column(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('conversations')
+         WHERE name = 'conversation_profile_id'",
+        [],
+        |row| Ok(row.get::<_, i64>(0)? > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE conversations ADD COLUMN conversation_profile_id TEXT",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversations_profile
+         ON conversations(conversation_profile_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Append-only history of conversation and message changes, for undo and auditing
+pub(crate) fn create_event_log_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS event_log (
+            id TEXT PRIMARY KEY,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_event_log_entity
+         ON event_log(entity_id, timestamp)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Endpoint overrides per provider; `extra_headers` is a JSON object
+pub(crate) fn create_provider_configs_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS provider_configs (
+            provider TEXT PRIMARY KEY,
+            base_url TEXT,
+            api_version TEXT,
+            timeout_secs INTEGER,
+            extra_headers TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Webhooks that forward conversation events to external URLs
+pub(crate) fn create_conversation_webhooks_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS conversation_webhooks (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            secret TEXT,
+            events TEXT NOT NULL,
+            conversation_id TEXT,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Periodic background exports
+pub(crate) fn create_export_schedules_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS export_schedules (
+            id TEXT PRIMARY KEY,
+            format TEXT NOT NULL,
+            destination_dir TEXT NOT NULL,
+            cron_pattern TEXT NOT NULL,
+            last_run_at INTEGER,
+            is_active INTEGER NOT NULL DEFAULT 1
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Supports date-range conversation queries
+pub(crate) fn add_conversation_created_at_index(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversations_created_at
+         ON conversations(created_at DESC)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Supports provider and model filters and usage stats
+pub(crate) fn add_conversation_provider_model_indexes(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversations_provider
+         ON conversations(provider)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversations_model
+         ON conversations(model)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Shortcut usage, trimmed to the most recent events on insert
+pub(crate) fn create_shortcut_events_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS shortcut_events (
+            action TEXT NOT NULL,
+            triggered_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_shortcut_events_action
+         ON shortcut_events(action, triggered_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Sampling temperature per profile; NULL leaves the provider default. Values
+/// saved under the old `profile_<id>_temperature` settings keys are carried over.
+pub(crate) fn add_profile_temperature_column(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('profiles') WHERE name = 'temperature'",
+        [],
+        |row| Ok(row.get::<_, i64>(0)? > 0),
+    )?;
+
+    if !has_column {
+        conn.execute("ALTER TABLE profiles ADD COLUMN temperature REAL", [])?;
+    }
+
+    conn.execute(
+        "UPDATE profiles SET temperature = (
+             SELECT CAST(value AS REAL) FROM settings
+             WHERE key = 'profile_' || profiles.id || '_temperature'
+               AND CAST(value AS REAL) BETWEEN 0.0 AND 2.0
+         )
+         WHERE temperature IS NULL",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM settings
+         WHERE key IN (SELECT 'profile_' || id || '_temperature' FROM profiles)",
+        [],
+    )?;
+    Ok(())
+}
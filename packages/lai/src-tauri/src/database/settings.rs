@@ -49,6 +49,15 @@ impl Setting {
         Ok(())
     }
 
+    /// Delete every setting whose key starts with `prefix`, returning how many were removed
+    pub fn delete_by_prefix(conn: &Connection, prefix: &str) -> Result<usize> {
+        // substr rather than LIKE, since `_` in a prefix would match any character
+        conn.execute(
+            "DELETE FROM settings WHERE substr(key, 1, length(?1)) = ?1",
+            params![prefix],
+        )
+    }
+
     pub fn set_json<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
         let json = serde_json::to_string(value)
             .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
@@ -68,3 +77,37 @@ impl Setting {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn delete_by_prefix_matches_the_literal_prefix() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        for key in [
+            "window_state_main",
+            "window_state_chat-2",
+            "windowXstateYmain",
+            "theme",
+        ] {
+            Setting::set(&conn, key, "{}").expect("set");
+        }
+
+        assert_eq!(
+            Setting::delete_by_prefix(&conn, "window_state_").unwrap(),
+            2
+        );
+        let mut keys: Vec<String> = Setting::get_all(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.key)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["theme", "windowXstateYmain"]);
+    }
+}
@@ -0,0 +1,100 @@
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of events kept; older rows are deleted as new ones arrive
+pub const MAX_SHORTCUT_EVENTS: i64 = 10_000;
+
+/// How often one shortcut action fired and when it last did
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShortcutStats {
+    pub action: String,
+    pub invocation_count: i64,
+    pub last_used_at: i64,
+}
+
+pub struct ShortcutEvent;
+
+impl ShortcutEvent {
+    /// Record one invocation of `action` and drop events beyond the retention limit
+    pub fn record(conn: &Connection, action: &str) -> Result<()> {
+        Self::record_capped(conn, action, MAX_SHORTCUT_EVENTS)
+    }
+
+    pub(crate) fn record_capped(conn: &Connection, action: &str, max_events: i64) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        conn.execute(
+            "INSERT INTO shortcut_events (action, triggered_at) VALUES (?1, ?2)",
+            params![action, now],
+        )?;
+        // Rows are only ever appended, so rowids are contiguous and the oldest
+        // events are the ones more than `max_events` behind the newest
+        conn.execute(
+            "DELETE FROM shortcut_events
+             WHERE rowid <= (SELECT MAX(rowid) FROM shortcut_events) - ?1",
+            [max_events],
+        )?;
+        Ok(())
+    }
+
+    /// Per-action counts, most used first, optionally limited to events at or after `since`
+    pub fn stats(conn: &Connection, since: Option<i64>) -> Result<Vec<ShortcutStats>> {
+        let mut stmt = conn.prepare(
+            "SELECT action, COUNT(*), MAX(triggered_at)
+             FROM shortcut_events
+             WHERE ?1 IS NULL OR triggered_at >= ?1
+             GROUP BY action
+             ORDER BY COUNT(*) DESC, action ASC",
+        )?;
+        let stats = stmt.query_map([since], |row| {
+            Ok(ShortcutStats {
+                action: row.get(0)?,
+                invocation_count: row.get(1)?,
+                last_used_at: row.get(2)?,
+            })
+        })?;
+        stats.collect()
+    }
+
+    pub fn clear(conn: &Connection) -> Result<usize> {
+        conn.execute("DELETE FROM shortcut_events", [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn shortcut_events_are_counted_and_capped() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        for _ in 0..3 {
+            ShortcutEvent::record_capped(&conn, "ToggleWindow", 4).unwrap();
+        }
+        for _ in 0..2 {
+            ShortcutEvent::record_capped(&conn, "NewConversation", 4).unwrap();
+        }
+
+        // The oldest ToggleWindow event fell out of the buffer
+        let stats = ShortcutEvent::stats(&conn, None).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert!(stats
+            .iter()
+            .all(|s| s.invocation_count == 2 && s.last_used_at > 0));
+
+        let future = stats[0].last_used_at + 60;
+        assert!(ShortcutEvent::stats(&conn, Some(future))
+            .unwrap()
+            .is_empty());
+
+        ShortcutEvent::clear(&conn).unwrap();
+        assert!(ShortcutEvent::stats(&conn, None).unwrap().is_empty());
+    }
+}
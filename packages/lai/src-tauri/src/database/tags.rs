@@ -24,6 +24,63 @@ pub struct ConversationTag {
     pub created_at: i64,
 }
 
+/// Conversations tagged during one period, and the running total up to its end
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TagTimelineEntry {
+    /// Unix timestamp (UTC) of the first second of the period
+    pub period_start: i64,
+    pub conversations_added: i64,
+    pub cumulative_conversations: i64,
+}
+
+/// Tags added to conversations on one day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeatmapEntry {
+    /// `YYYY-MM-DD` (UTC)
+    pub date: String,
+    pub count: i64,
+}
+
+/// A tag with how many conversations use it, scaled so the most-used tag has weight 1.0
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagCloudItem {
+    pub tag: Tag,
+    pub count: usize,
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineGranularity {
+    Day,
+    /// Weeks start on Monday
+    Week,
+    Month,
+}
+
+impl TimelineGranularity {
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            other => Err(format!(
+                "Invalid granularity '{}'. Supported: day, week, month",
+                other
+            )),
+        }
+    }
+
+    /// SQLite date expression truncating `created_at` to the start of its period
+    fn period_expr(self) -> &'static str {
+        match self {
+            Self::Day => "date(created_at, 'unixepoch')",
+            // 'weekday 0' moves forward to Sunday, so step back six days to Monday
+            Self::Week => "date(created_at, 'unixepoch', 'weekday 0', '-6 days')",
+            Self::Month => "date(created_at, 'unixepoch', 'start of month')",
+        }
+    }
+}
+
 impl Tag {
     pub fn create(conn: &Connection, new_tag: NewTag) -> Result<Self> {
         let now = SystemTime::now()
@@ -212,4 +269,238 @@ impl Tag {
             )
         }
     }
+
+    /// Every tag with its non-deleted conversation count, most used first
+    pub fn get_tag_cloud(conn: &Connection) -> Result<Vec<TagCloudItem>> {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, t.created_at, t.updated_at, COUNT(c.id) AS uses
+             FROM tags t
+             LEFT JOIN conversation_tags ct ON ct.tag_id = t.id
+             LEFT JOIN conversations c ON c.id = ct.conversation_id AND c.deleted = 0
+             GROUP BY t.id
+             ORDER BY uses DESC, t.name",
+        )?;
+        let counted = stmt
+            .query_map([], |row| {
+                Ok((
+                    Tag {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        color: row.get(2)?,
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    },
+                    row.get::<_, i64>(5)? as usize,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let max = counted.first().map_or(0, |(_, count)| *count);
+        Ok(counted
+            .into_iter()
+            .map(|(tag, count)| TagCloudItem {
+                tag,
+                count,
+                weight: if max == 0 {
+                    0.0
+                } else {
+                    count as f32 / max as f32
+                },
+            })
+            .collect())
+    }
+
+    /// Tags that share non-deleted conversations with `tag_id`, most shared first
+    pub fn get_related(conn: &Connection, tag_id: &str, limit: i64) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, t.created_at, t.updated_at
+             FROM conversation_tags base
+             JOIN conversations c ON c.id = base.conversation_id AND c.deleted = 0
+             JOIN conversation_tags other
+               ON other.conversation_id = base.conversation_id AND other.tag_id != base.tag_id
+             JOIN tags t ON t.id = other.tag_id
+             WHERE base.tag_id = ?1
+             GROUP BY t.id
+             ORDER BY COUNT(*) DESC, t.name
+             LIMIT ?2",
+        )?;
+        let tags = stmt.query_map(params![tag_id, limit], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        tags.collect()
+    }
+
+    /// How many conversations received `tag_id` in each period that had any
+    pub fn usage_timeline(
+        conn: &Connection,
+        tag_id: &str,
+        granularity: TimelineGranularity,
+    ) -> Result<Vec<TagTimelineEntry>> {
+        let sql = format!(
+            "SELECT period_start, added, SUM(added) OVER (ORDER BY period_start)
+             FROM (
+                 SELECT CAST(strftime('%s', {}) AS INTEGER) AS period_start,
+                        COUNT(*) AS added
+                 FROM conversation_tags
+                 WHERE tag_id = ?1
+                 GROUP BY period_start
+             )
+             ORDER BY period_start",
+            granularity.period_expr()
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let entries = stmt.query_map(params![tag_id], |row| {
+            Ok(TagTimelineEntry {
+                period_start: row.get(0)?,
+                conversations_added: row.get(1)?,
+                cumulative_conversations: row.get(2)?,
+            })
+        })?;
+        entries.collect()
+    }
+
+    /// One entry per day of `year` (366 in leap years), counting tags added across all tags
+    pub fn activity_heatmap(conn: &Connection, year: i32) -> Result<Vec<HeatmapEntry>> {
+        let mut stmt = conn.prepare(
+            "SELECT date(created_at, 'unixepoch') AS day, COUNT(*)
+             FROM conversation_tags
+             WHERE strftime('%Y', created_at, 'unixepoch') = ?1
+             GROUP BY day",
+        )?;
+        let counts = stmt
+            .query_map(params![format!("{:04}", year)], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<std::collections::HashMap<_, _>>>()?;
+
+        let Some(first_day) = chrono::NaiveDate::from_ymd_opt(year, 1, 1) else {
+            return Ok(Vec::new());
+        };
+        Ok(first_day
+            .iter_days()
+            .take_while(|day| chrono::Datelike::year(day) == year)
+            .map(|day| {
+                let date = day.format("%Y-%m-%d").to_string();
+                let count = counts.get(&date).copied().unwrap_or(0);
+                HeatmapEntry { date, count }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conversations::Conversation;
+    use crate::database::test_helpers::create_test_conversation;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn tag_timeline_and_heatmap() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let tag = Tag::create(
+            &conn,
+            NewTag {
+                name: "research".to_string(),
+                color: None,
+            },
+        )
+        .expect("create tag");
+        // 2024-01-01 (Mon) 10:00, 2024-01-03 (Wed) 12:00, 2024-02-15 (Thu) 00:00 UTC
+        let tagged_at = [1_704_103_200_i64, 1_704_283_200, 1_707_955_200];
+        for (i, ts) in tagged_at.iter().enumerate() {
+            let conv = create_test_conversation(&conn, &format!("Conv {}", i));
+            conn.execute(
+                "INSERT INTO conversation_tags (conversation_id, tag_id, created_at)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![conv.id, tag.id, ts],
+            )
+            .expect("tag conv");
+        }
+
+        let weekly = Tag::usage_timeline(&conn, &tag.id, TimelineGranularity::Week).unwrap();
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].period_start, 1_704_067_200); // 2024-01-01 00:00 UTC
+        assert_eq!(weekly[0].conversations_added, 2);
+        assert_eq!(weekly[1].cumulative_conversations, 3);
+
+        let monthly = Tag::usage_timeline(&conn, &tag.id, TimelineGranularity::Month).unwrap();
+        assert_eq!(monthly.len(), 2);
+        assert_eq!(monthly[1].period_start, 1_706_745_600); // 2024-02-01 00:00 UTC
+        assert!(TimelineGranularity::parse("year").is_err());
+
+        let heatmap = Tag::activity_heatmap(&conn, 2024).unwrap();
+        assert_eq!(heatmap.len(), 366);
+        assert_eq!(heatmap[0].date, "2024-01-01");
+        assert_eq!(heatmap[0].count, 1);
+        assert_eq!(heatmap[1].count, 0);
+        assert_eq!(heatmap[45].count, 1);
+        assert_eq!(Tag::activity_heatmap(&conn, 2023).unwrap().len(), 365);
+    }
+
+    #[test]
+    fn tag_cloud_weights_and_related_tags() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let rust = Tag::create_or_get(&conn, "rust", None).unwrap();
+        let web = Tag::create_or_get(&conn, "web", None).unwrap();
+        let cli = Tag::create_or_get(&conn, "cli", None).unwrap();
+        let unused = Tag::create_or_get(&conn, "unused", None).unwrap();
+
+        // rust: 4 conversations, web: 2 (both with rust), cli: 1 (with rust)
+        let mut conversation_ids = Vec::new();
+        for i in 0..4 {
+            let conv = create_test_conversation(&conn, &format!("Conv {}", i));
+            Tag::add_to_conversation(&conn, &conv.id, &rust.id).unwrap();
+            conversation_ids.push(conv.id);
+        }
+        Tag::add_to_conversation(&conn, &conversation_ids[0], &web.id).unwrap();
+        Tag::add_to_conversation(&conn, &conversation_ids[1], &web.id).unwrap();
+        Tag::add_to_conversation(&conn, &conversation_ids[2], &cli.id).unwrap();
+
+        let cloud = Tag::get_tag_cloud(&conn).unwrap();
+        let summary: Vec<(&str, usize, f32)> = cloud
+            .iter()
+            .map(|item| (item.tag.name.as_str(), item.count, item.weight))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("rust", 4, 1.0),
+                ("web", 2, 0.5),
+                ("cli", 1, 0.25),
+                ("unused", 0, 0.0)
+            ]
+        );
+
+        let related: Vec<String> = Tag::get_related(&conn, &rust.id, 10)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(related, vec!["web", "cli"]);
+        assert_eq!(Tag::get_related(&conn, &rust.id, 1).unwrap().len(), 1);
+        assert!(Tag::get_related(&conn, &unused.id, 10).unwrap().is_empty());
+
+        // Deleted conversations no longer count
+        Conversation::delete(&conn, &conversation_ids[2]).unwrap();
+        assert!(Tag::get_related(&conn, &cli.id, 10).unwrap().is_empty());
+        let cli_count = Tag::get_tag_cloud(&conn)
+            .unwrap()
+            .into_iter()
+            .find(|item| item.tag.id == cli.id)
+            .unwrap()
+            .count;
+        assert_eq!(cli_count, 0);
+    }
 }
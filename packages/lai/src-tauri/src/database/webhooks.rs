@@ -0,0 +1,147 @@
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationWebhook {
+    pub id: String,
+    pub url: String,
+    pub secret: Option<String>,
+    /// Event names this webhook receives, e.g. `message.created`; `*` matches all
+    pub events: Vec<String>,
+    /// Limit deliveries to one conversation; `None` receives events for all of them
+    pub conversation_id: Option<String>,
+    pub is_active: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewConversationWebhook {
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+    pub conversation_id: Option<String>,
+}
+
+const SELECT_COLUMNS: &str =
+    "SELECT id, url, secret, events, conversation_id, is_active, created_at FROM conversation_webhooks";
+
+fn join_events(events: &[String]) -> String {
+    events
+        .iter()
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_events(events: &str) -> Vec<String> {
+    events
+        .split(',')
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_string())
+        .collect()
+}
+
+fn from_row(row: &Row) -> Result<ConversationWebhook> {
+    let events: String = row.get(3)?;
+    Ok(ConversationWebhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        secret: row.get(2)?,
+        events: split_events(&events),
+        conversation_id: row.get(4)?,
+        is_active: row.get::<_, i64>(5)? != 0,
+        created_at: row.get(6)?,
+    })
+}
+
+impl ConversationWebhook {
+    pub fn create(conn: &Connection, new_webhook: NewConversationWebhook) -> Result<Self> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let id = uuid::Uuid::new_v4().to_string();
+        let events = join_events(&new_webhook.events);
+
+        conn.execute(
+            "INSERT INTO conversation_webhooks (id, url, secret, events, conversation_id, is_active, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)",
+            params![&id, &new_webhook.url, &new_webhook.secret, &events, &new_webhook.conversation_id, now],
+        )?;
+
+        Ok(ConversationWebhook {
+            id,
+            url: new_webhook.url,
+            secret: new_webhook.secret,
+            events: split_events(&events),
+            conversation_id: new_webhook.conversation_id,
+            is_active: true,
+            created_at: now,
+        })
+    }
+
+    pub fn get_by_id(conn: &Connection, id: &str) -> Result<Option<Self>> {
+        let mut stmt = conn.prepare(&format!("{} WHERE id = ?1", SELECT_COLUMNS))?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!("{} ORDER BY created_at", SELECT_COLUMNS))?;
+        let webhooks = stmt.query_map([], from_row)?;
+        webhooks.collect()
+    }
+
+    /// Active webhooks subscribed to `event` for the given conversation
+    pub fn get_matching(
+        conn: &Connection,
+        event: &str,
+        conversation_id: &str,
+    ) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE is_active = 1 AND (conversation_id IS NULL OR conversation_id = ?1)",
+            SELECT_COLUMNS
+        ))?;
+        let webhooks = stmt.query_map(params![conversation_id], from_row)?;
+        Ok(webhooks
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|w| w.events.iter().any(|e| e == event || e == "*"))
+            .collect())
+    }
+
+    pub fn update(
+        conn: &Connection,
+        id: &str,
+        webhook: &NewConversationWebhook,
+        is_active: bool,
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE conversation_webhooks
+             SET url = ?1, secret = ?2, events = ?3, conversation_id = ?4, is_active = ?5
+             WHERE id = ?6",
+            params![
+                &webhook.url,
+                &webhook.secret,
+                join_events(&webhook.events),
+                &webhook.conversation_id,
+                is_active as i64,
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete(conn: &Connection, id: &str) -> Result<()> {
+        conn.execute(
+            "DELETE FROM conversation_webhooks WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Error message `update` fails with when the target is a built-in template
+pub const BUILTIN_UPDATE_ERROR: &str = "Cannot modify builtin template";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WorkspaceTemplate {
     pub id: String,
@@ -34,6 +37,33 @@ pub struct NewWorkspaceTemplate {
     pub context_instructions: Option<String>,
 }
 
+/// Editor behaviour stored in `settings_json` (camelCase keys). Missing keys are
+/// `false`; keys not listed here are kept when the settings are saved.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WorkspaceTemplateSettings {
+    pub file_watcher: bool,
+    pub auto_save: bool,
+    pub format_on_save: bool,
+    pub linting: bool,
+}
+
+/// Field overrides applied when cloning a template; `None` keeps the source value
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartialWorkspaceTemplate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub default_model: Option<String>,
+    pub default_provider: Option<String>,
+    pub system_prompt: Option<String>,
+    pub settings_json: Option<String>,
+    pub ignore_patterns: Option<String>,
+    pub file_extensions: Option<String>,
+    pub context_instructions: Option<String>,
+}
+
 impl WorkspaceTemplate {
     pub fn create(conn: &Connection, new_template: NewWorkspaceTemplate) -> Result<Self> {
         let now = SystemTime::now()
@@ -177,11 +207,31 @@ impl WorkspaceTemplate {
         categories.collect()
     }
 
+    /// Fail with `BUILTIN_UPDATE_ERROR` if `id` is a built-in template
+    fn ensure_not_builtin(conn: &Connection, id: &str) -> Result<()> {
+        let is_builtin: Option<i64> = conn
+            .query_row(
+                "SELECT is_builtin FROM workspace_templates WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if is_builtin == Some(1) {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some(BUILTIN_UPDATE_ERROR.to_string()),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn update(
         conn: &Connection,
         id: &str,
         updated_template: NewWorkspaceTemplate,
     ) -> Result<()> {
+        Self::ensure_not_builtin(conn, id)?;
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -212,6 +262,85 @@ impl WorkspaceTemplate {
         Ok(())
     }
 
+    /// Typed view of `settings_json`; a template without settings gets the defaults
+    pub fn parse_settings(&self) -> serde_json::Result<WorkspaceTemplateSettings> {
+        match self.settings_json.as_deref() {
+            Some(json) if !json.trim().is_empty() => serde_json::from_str(json),
+            _ => Ok(WorkspaceTemplateSettings::default()),
+        }
+    }
+
+    /// Write `settings` into `settings_json`, keeping any other keys already there.
+    /// Built-in templates are rejected like in `update`.
+    pub fn set_settings(
+        conn: &Connection,
+        id: &str,
+        settings: WorkspaceTemplateSettings,
+    ) -> Result<()> {
+        Self::ensure_not_builtin(conn, id)?;
+        let template = Self::get_by_id(conn, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+
+        let mut merged = template
+            .settings_json
+            .as_deref()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+            .and_then(|value| match value {
+                serde_json::Value::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default();
+        if let serde_json::Value::Object(typed) = serde_json::json!(settings) {
+            merged.extend(typed);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        conn.execute(
+            "UPDATE workspace_templates SET settings_json = ?1, updated_at = ?2 WHERE id = ?3",
+            params![serde_json::Value::Object(merged).to_string(), now, id],
+        )?;
+        Ok(())
+    }
+
+    /// Create a custom copy of a template with some fields replaced. Without a
+    /// name override the copy is called "<source name> (custom)".
+    pub fn clone_with_overrides(
+        conn: &Connection,
+        source_id: &str,
+        overrides: PartialWorkspaceTemplate,
+    ) -> Result<Self> {
+        let source = Self::get_by_id(conn, source_id)?.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_FAIL),
+                Some("Workspace template not found".to_string()),
+            )
+        })?;
+
+        Self::create(
+            conn,
+            NewWorkspaceTemplate {
+                name: overrides
+                    .name
+                    .unwrap_or_else(|| format!("{} (custom)", source.name)),
+                description: overrides.description.or(source.description),
+                category: overrides.category.unwrap_or(source.category),
+                default_model: overrides.default_model.unwrap_or(source.default_model),
+                default_provider: overrides
+                    .default_provider
+                    .unwrap_or(source.default_provider),
+                system_prompt: overrides.system_prompt.or(source.system_prompt),
+                settings_json: overrides.settings_json.or(source.settings_json),
+                ignore_patterns: overrides.ignore_patterns.or(source.ignore_patterns),
+                file_extensions: overrides.file_extensions.or(source.file_extensions),
+                context_instructions: overrides
+                    .context_instructions
+                    .or(source.context_instructions),
+            },
+        )
+    }
+
     pub fn delete(conn: &Connection, id: &str) -> Result<()> {
         // Only allow deletion of custom templates (not built-in ones)
         conn.execute(
@@ -252,3 +381,131 @@ impl WorkspaceTemplate {
         templates.collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn clone_builtin_template_with_overrides() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let source = WorkspaceTemplate::get_by_id(&conn, "builtin-react")
+            .unwrap()
+            .expect("builtin react template");
+        let clone = WorkspaceTemplate::clone_with_overrides(
+            &conn,
+            "builtin-react",
+            PartialWorkspaceTemplate {
+                default_model: Some("llama3".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_ne!(clone.id, source.id);
+        assert!(!clone.is_builtin);
+        assert_eq!(clone.name, "React Development (custom)");
+        assert_eq!(clone.default_model, "llama3");
+        assert_eq!(clone.system_prompt, source.system_prompt);
+
+        let stored = WorkspaceTemplate::get_by_id(&conn, &clone.id)
+            .unwrap()
+            .expect("clone persisted");
+        assert_eq!(stored.default_model, "llama3");
+
+        assert!(WorkspaceTemplate::clone_with_overrides(
+            &conn,
+            "missing",
+            PartialWorkspaceTemplate::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn builtin_templates_cannot_be_updated() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let edit = |name: &str| NewWorkspaceTemplate {
+            name: name.to_string(),
+            description: None,
+            category: "frontend".to_string(),
+            default_model: "gpt-4o".to_string(),
+            default_provider: "openai".to_string(),
+            system_prompt: None,
+            settings_json: None,
+            ignore_patterns: None,
+            file_extensions: None,
+            context_instructions: None,
+        };
+
+        let err = WorkspaceTemplate::update(&conn, "builtin-react", edit("Hijacked")).unwrap_err();
+        assert_eq!(err.to_string(), BUILTIN_UPDATE_ERROR);
+        let builtin = WorkspaceTemplate::get_by_id(&conn, "builtin-react")
+            .unwrap()
+            .unwrap();
+        assert_eq!(builtin.name, "React Development");
+
+        let custom = WorkspaceTemplate::create(&conn, edit("Mine")).unwrap();
+        WorkspaceTemplate::update(&conn, &custom.id, edit("Mine v2")).unwrap();
+        let custom = WorkspaceTemplate::get_by_id(&conn, &custom.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(custom.name, "Mine v2");
+    }
+
+    #[test]
+    fn workspace_template_settings_are_typed_and_merged() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("lock conn");
+
+        let react = WorkspaceTemplate::get_by_id(&conn, "builtin-react")
+            .unwrap()
+            .unwrap();
+        let settings = react.parse_settings().unwrap();
+        assert!(settings.file_watcher && settings.format_on_save);
+        assert!(!settings.linting);
+        assert!(WorkspaceTemplate::set_settings(&conn, "builtin-react", settings).is_err());
+
+        let custom = WorkspaceTemplate::create(
+            &conn,
+            NewWorkspaceTemplate {
+                name: "Rust custom".to_string(),
+                description: None,
+                category: "systems".to_string(),
+                default_model: "gpt-4o".to_string(),
+                default_provider: "openai".to_string(),
+                system_prompt: None,
+                settings_json: Some(r#"{"autoSave": true, "cargoIntegration": true}"#.to_string()),
+                ignore_patterns: None,
+                file_extensions: None,
+                context_instructions: None,
+            },
+        )
+        .unwrap();
+        WorkspaceTemplate::set_settings(
+            &conn,
+            &custom.id,
+            WorkspaceTemplateSettings {
+                linting: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let custom = WorkspaceTemplate::get_by_id(&conn, &custom.id)
+            .unwrap()
+            .unwrap();
+        let settings = custom.parse_settings().unwrap();
+        assert!(settings.linting);
+        assert!(!settings.auto_save);
+        let raw: serde_json::Value =
+            serde_json::from_str(custom.settings_json.as_deref().unwrap()).unwrap();
+        assert_eq!(raw["cargoIntegration"], true);
+        assert!(WorkspaceTemplate::set_settings(&conn, "missing", settings).is_err());
+    }
+}
@@ -1,15 +1,29 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::Arc;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
 
 /// Configuration for IPC server performance tuning
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
 const BUFFER_SIZE: usize = 8192;
 const MAX_MESSAGE_SIZE: usize = 1024 * 1024; // 1MB limit
+/// How long open connections may keep running after shutdown is requested
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// How long an `ask_stream` reply may go without producing anything
+const ASK_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Responses larger than this are LZ4-compressed for clients advertising `lz4`
+const COMPRESSED_THRESHOLD: usize = 4096;
+/// Wire prefix for a base64-encoded, size-prepended LZ4 block
+const COMPRESSED_PREFIX: &str = "z:";
 
 #[derive(serde::Deserialize, Debug)]
 struct IpcMessage {
@@ -19,6 +33,9 @@ struct IpcMessage {
     message: Option<String>,
     #[serde(default)]
     payload: Option<JsonValue>,
+    /// Optional features the client supports, e.g. `["lz4"]`
+    #[serde(default)]
+    capabilities: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -36,6 +53,114 @@ struct ConnectionMetrics {
     bytes_received: u64,
 }
 
+/// Counters accumulated across all IPC connections since startup or the last reset
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct IpcServerMetricsInner {
+    pub total_connections: u64,
+    pub total_messages_processed: u64,
+    pub total_bytes_received: u64,
+    pub current_active_connections: u64,
+    pub errors: u64,
+}
+
+/// Managed state shared between IPC client threads and the metrics commands
+#[derive(Clone, Default)]
+pub struct IpcServerMetrics(Arc<Mutex<IpcServerMetricsInner>>);
+
+impl IpcServerMetrics {
+    fn update(&self, f: impl FnOnce(&mut IpcServerMetricsInner)) {
+        if let Ok(mut inner) = self.0.lock() {
+            f(&mut inner);
+        }
+    }
+
+    pub fn snapshot(&self) -> Result<IpcServerMetricsInner, String> {
+        self.0
+            .lock()
+            .map(|inner| inner.clone())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Zero the counters; connections that are still open stay counted as active
+    pub fn reset(&self) -> Result<(), String> {
+        let mut inner = self.0.lock().map_err(|e| e.to_string())?;
+        *inner = IpcServerMetricsInner {
+            current_active_connections: inner.current_active_connections,
+            ..Default::default()
+        };
+        Ok(())
+    }
+}
+
+/// Managed state used to stop the IPC server when the app exits
+pub struct IpcShutdownHandle {
+    signal: Mutex<Option<oneshot::Sender<()>>>,
+    finished: Mutex<mpsc::Receiver<()>>,
+}
+
+impl IpcShutdownHandle {
+    /// Stop accepting connections and wait (bounded) for open ones to drain.
+    /// Calling this more than once is a no-op.
+    pub fn shutdown(&self) {
+        let Some(signal) = self.signal.lock().ok().and_then(|mut s| s.take()) else {
+            return;
+        };
+        // The receiver is gone if the server never started
+        if signal.send(()).is_err() {
+            return;
+        }
+        if let Ok(finished) = self.finished.lock() {
+            let _ = finished.recv_timeout(SHUTDOWN_GRACE_PERIOD + Duration::from_millis(500));
+        }
+    }
+}
+
+/// Open client streams, so they can be closed once the grace period runs out
+#[derive(Clone, Default)]
+struct ActiveConnections {
+    next_id: Arc<AtomicU64>,
+    streams: Arc<Mutex<HashMap<u64, TcpStream>>>,
+}
+
+impl ActiveConnections {
+    fn register(&self, stream: &TcpStream) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if let (Ok(clone), Ok(mut streams)) = (stream.try_clone(), self.streams.lock()) {
+            streams.insert(id, clone);
+        }
+        id
+    }
+
+    fn remove(&self, id: u64) {
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.remove(&id);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.streams.lock().map(|s| s.is_empty()).unwrap_or(true)
+    }
+
+    /// Wait up to `grace` for clients to disconnect, then close whatever is left
+    async fn drain(&self, grace: Duration) {
+        let deadline = Instant::now() + grace;
+        while !self.is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        if let Ok(mut streams) = self.streams.lock() {
+            for (_, stream) in streams.drain() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+fn record_metrics(app: &AppHandle, f: impl FnOnce(&mut IpcServerMetricsInner)) {
+    if let Some(metrics) = app.try_state::<IpcServerMetrics>() {
+        metrics.update(f);
+    }
+}
+
 fn handle_client(mut stream: TcpStream, app: AppHandle, dev_mode_enabled: bool) {
     // Set connection timeout and buffer size for performance
     let _ = stream.set_read_timeout(Some(CONNECTION_TIMEOUT));
@@ -48,10 +173,17 @@ fn handle_client(mut stream: TcpStream, app: AppHandle, dev_mode_enabled: bool)
         messages_processed: 0,
         bytes_received: 0,
     };
+    record_metrics(&app, |m| {
+        m.total_connections += 1;
+        m.current_active_connections += 1;
+    });
 
     // Use buffered reader with custom buffer size
     let mut reader = BufReader::with_capacity(BUFFER_SIZE, stream.try_clone().unwrap());
-    let mut line = String::with_capacity(512); // Pre-allocate with reasonable capacity
+    // Pre-allocate with reasonable capacity
+    let mut line = String::with_capacity(512);
+    // Set once the client advertises lz4 support; applies for the rest of the connection
+    let mut compress = false;
 
     loop {
         line.clear();
@@ -59,14 +191,16 @@ fn handle_client(mut stream: TcpStream, app: AppHandle, dev_mode_enabled: bool)
             Ok(0) => break, // EOF
             Ok(bytes_read) => {
                 metrics.bytes_received += bytes_read as u64;
+                record_metrics(&app, |m| m.total_bytes_received += bytes_read as u64);
 
                 // Check message size limit
                 if line.len() > MAX_MESSAGE_SIZE {
+                    record_metrics(&app, |m| m.errors += 1);
                     let response = IpcResponse {
                         status: "error".to_string(),
                         data: Some(serde_json::json!({"error": "Message too large"})),
                     };
-                    let _ = write_response(&mut stream, &response);
+                    let _ = write_response(&mut stream, &response, compress);
                     continue;
                 }
 
@@ -75,24 +209,39 @@ fn handle_client(mut stream: TcpStream, app: AppHandle, dev_mode_enabled: bool)
                     continue;
                 }
 
-                match serde_json::from_str::<IpcMessage>(trimmed) {
+                let parsed = decode_line(trimmed).and_then(|json| {
+                    serde_json::from_str::<IpcMessage>(&json)
+                        .map_err(|_| "Invalid JSON".to_string())
+                });
+                match parsed {
                     Ok(msg) => {
                         metrics.messages_processed += 1;
-                        handle_message(&mut stream, &app, &msg, dev_mode_enabled);
+                        if msg.capabilities.iter().any(|c| c == "lz4") {
+                            compress = true;
+                        }
+                        handle_message(&mut stream, &app, &msg, dev_mode_enabled, compress);
                     }
-                    Err(_) => {
+                    Err(e) => {
+                        record_metrics(&app, |m| m.errors += 1);
                         let response = IpcResponse {
                             status: "error".to_string(),
-                            data: Some(serde_json::json!({"error": "Invalid JSON"})),
+                            data: Some(serde_json::json!({ "error": e })),
                         };
-                        let _ = write_response(&mut stream, &response);
+                        let _ = write_response(&mut stream, &response, compress);
                     }
                 }
             }
-            Err(_) => break,
+            Err(_) => {
+                record_metrics(&app, |m| m.errors += 1);
+                break;
+            }
         }
     }
 
+    record_metrics(&app, |m| {
+        m.current_active_connections = m.current_active_connections.saturating_sub(1)
+    });
+
     // Log performance metrics in debug mode
     if std::env::var("RUST_LOG")
         .unwrap_or_default()
@@ -109,10 +258,47 @@ fn handle_client(mut stream: TcpStream, app: AppHandle, dev_mode_enabled: bool)
     }
 }
 
+/// Decode a wire line, inflating it if it carries the compressed prefix
+fn decode_line(line: &str) -> Result<String, String> {
+    let Some(encoded) = line.strip_prefix(COMPRESSED_PREFIX) else {
+        return Ok(line.to_string());
+    };
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Invalid compressed message: {}", e))?;
+
+    // Check the declared size before allocating for it
+    let declared = bytes
+        .get(..4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| "Invalid compressed message".to_string())?;
+    if declared > MAX_MESSAGE_SIZE {
+        return Err("Message too large".to_string());
+    }
+
+    let raw = lz4_flex::decompress_size_prepended(&bytes)
+        .map_err(|e| format!("Invalid compressed message: {}", e))?;
+    String::from_utf8(raw).map_err(|e| format!("Invalid compressed message: {}", e))
+}
+
+/// Encode JSON for the wire, compressing it when allowed and above the threshold
+fn encode_line(json: String, compress: bool) -> String {
+    if compress && json.len() > COMPRESSED_THRESHOLD {
+        let packed = lz4_flex::compress_prepend_size(json.as_bytes());
+        format!("{}{}", COMPRESSED_PREFIX, BASE64.encode(packed))
+    } else {
+        json
+    }
+}
+
 /// Optimized response writer with error handling
-fn write_response(stream: &mut TcpStream, response: &IpcResponse) -> Result<(), std::io::Error> {
+fn write_response(
+    stream: &mut TcpStream,
+    response: &IpcResponse,
+    compress: bool,
+) -> Result<(), std::io::Error> {
     let json = serde_json::to_string(response)?;
-    stream.write_all(format!("{}\n", json).as_bytes())?;
+    stream.write_all(format!("{}\n", encode_line(json, compress)).as_bytes())?;
     stream.flush()?;
     Ok(())
 }
@@ -123,7 +309,19 @@ fn handle_message(
     app: &AppHandle,
     msg: &IpcMessage,
     dev_mode_enabled: bool,
+    compress: bool,
 ) {
+    if msg.kind == "ask_stream" {
+        let failed = handle_ask_stream(stream, app, msg, compress).is_err();
+        record_metrics(app, |m| {
+            m.total_messages_processed += 1;
+            if failed {
+                m.errors += 1;
+            }
+        });
+        return;
+    }
+
     let response = match msg.kind.as_str() {
         "notify" => {
             let _ = app.emit("cli://notify", msg.message.as_deref().unwrap_or_default());
@@ -145,6 +343,9 @@ fn handle_message(
             }
         }
         "last" => handle_last_message(app),
+        "list_conversations" => handle_list_conversations(app, msg),
+        "messages" => handle_conversation_messages(app, msg),
+        "history" => handle_history(app, msg),
         "create" => {
             if dev_mode_enabled {
                 handle_create_message(app, msg)
@@ -166,7 +367,140 @@ fn handle_message(
         }
     };
 
-    let _ = write_response(stream, &response);
+    let failed = response.status == "error";
+    let write_failed = write_response(stream, &response, compress).is_err();
+    record_metrics(app, |m| {
+        m.total_messages_processed += 1;
+        if failed || write_failed {
+            m.errors += 1;
+        }
+    });
+}
+
+/// One line of an `ask_stream` reply: `{"chunk": ...}` frames, then either
+/// `{"done": true, "conversation_id": ...}` or `{"error": ...}`
+#[derive(serde::Serialize, Default)]
+struct IpcStreamFrame {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunk: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn write_frame(
+    stream: &mut TcpStream,
+    frame: &IpcStreamFrame,
+    compress: bool,
+) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string(frame)?;
+    stream.write_all(format!("{}\n", encode_line(json, compress)).as_bytes())?;
+    stream.flush()
+}
+
+/// Ask `payload.prompt` and write the reply back as it streams. Continues
+/// `payload.conversation_id` if given, or the most recently updated conversation when
+/// `payload.new` is false; `provider` and `model` override the defaults. Replies
+/// `{"status": "no_stream"}` if the provider can't stream, so the client can fall
+/// back to `ask`. Errs if the reply failed or could not be written.
+fn handle_ask_stream(
+    stream: &mut TcpStream,
+    app: &AppHandle,
+    msg: &IpcMessage,
+    compress: bool,
+) -> Result<(), String> {
+    let payload = msg.payload.as_ref();
+    let field = |name: &str| {
+        payload
+            .and_then(|p| p.get(name))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+    let prompt = field("prompt").or_else(|| msg.message.clone());
+    let fail = |stream: &mut TcpStream, error: String| {
+        let frame = IpcStreamFrame {
+            error: Some(error.clone()),
+            ..Default::default()
+        };
+        let _ = write_frame(stream, &frame, compress);
+        Err(error)
+    };
+    let Some(prompt) = prompt.filter(|p| !p.trim().is_empty()) else {
+        return fail(stream, "No prompt provided for ask_stream".to_string());
+    };
+
+    let new = payload
+        .and_then(|p| p.get("new"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let conversation_id = match field("conversation_id") {
+        Some(id) => Some(id),
+        None if !new => match latest_conversation_id(app) {
+            Ok(id) => id,
+            Err(e) => return fail(stream, e),
+        },
+        None => None,
+    };
+
+    let (events, rx) = mpsc::channel();
+    let conversation_id = match crate::commands::provider::stream_prompt(
+        app,
+        &prompt,
+        conversation_id.as_deref(),
+        field("provider"),
+        field("model"),
+        events,
+    ) {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            let response = IpcResponse {
+                status: "no_stream".to_string(),
+                data: None,
+            };
+            return write_response(stream, &response, compress).map_err(|e| e.to_string());
+        }
+        Err(e) => return fail(stream, e),
+    };
+
+    loop {
+        use crate::commands::provider::StreamEvent;
+        match rx.recv_timeout(ASK_STREAM_IDLE_TIMEOUT) {
+            Ok(StreamEvent::Chunk(chunk)) => {
+                let frame = IpcStreamFrame {
+                    chunk: Some(chunk),
+                    ..Default::default()
+                };
+                // If the client went away the reply is still saved once the stream ends
+                write_frame(stream, &frame, compress).map_err(|e| e.to_string())?;
+            }
+            Ok(StreamEvent::End) => {
+                let frame = IpcStreamFrame {
+                    done: true,
+                    conversation_id: Some(conversation_id),
+                    ..Default::default()
+                };
+                return write_frame(stream, &frame, compress).map_err(|e| e.to_string());
+            }
+            Ok(StreamEvent::Error(e)) => return fail(stream, e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return fail(stream, "Timed out waiting for the provider".to_string())
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return fail(stream, "Stream ended unexpectedly".to_string())
+            }
+        }
+    }
+}
+
+fn latest_conversation_id(app: &AppHandle) -> Result<Option<String>, String> {
+    let db = app.state::<crate::database::Database>();
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let latest = crate::database::conversations::Conversation::get_all(&conn, 1)
+        .map_err(|e| e.to_string())?;
+    Ok(latest.into_iter().next().map(|c| c.id))
 }
 
 /// Optimized last message handler
@@ -193,6 +527,128 @@ fn handle_last_message(app: &AppHandle) -> IpcResponse {
     }
 }
 
+/// List recent conversations, optionally filtered by a title prefix (ASCII case-insensitive)
+fn handle_list_conversations(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
+    let limit = msg
+        .payload
+        .as_ref()
+        .and_then(|p| p.get("limit"))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(50);
+    let title_prefix = msg
+        .payload
+        .as_ref()
+        .and_then(|p| p.get("title_prefix"))
+        .and_then(|v| v.as_str());
+
+    let db = app.state::<crate::database::Database>();
+    let result = db.conn().map_err(|e| e.to_string()).and_then(|conn| {
+        use crate::database::conversations::Conversation;
+        match title_prefix {
+            Some(prefix) => Conversation::get_by_title_prefix(&conn, prefix, limit),
+            None => Conversation::get_all(&conn, limit),
+        }
+        .map_err(|e| e.to_string())
+    });
+
+    match result {
+        Ok(conversations) => IpcResponse {
+            status: "ok".to_string(),
+            data: serde_json::to_value(&conversations).ok(),
+        },
+        Err(e) => IpcResponse {
+            status: "error".to_string(),
+            data: Some(serde_json::json!({"error": e})),
+        },
+    }
+}
+
+/// Return a conversation together with all of its messages
+fn handle_conversation_messages(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
+    let Some(conversation_id) = msg
+        .payload
+        .as_ref()
+        .and_then(|p| p.get("conversation_id"))
+        .and_then(|v| v.as_str())
+    else {
+        return IpcResponse {
+            status: "error".to_string(),
+            data: Some(serde_json::json!({"error": "No conversation_id provided"})),
+        };
+    };
+
+    let db = app.state::<crate::database::Database>();
+    let result = db.conn().map_err(|e| e.to_string()).and_then(|conn| {
+        let conversation =
+            crate::database::conversations::Conversation::get_by_id(&conn, conversation_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Conversation not found".to_string())?;
+        let messages =
+            crate::database::messages::Message::get_by_conversation(&conn, conversation_id)
+                .map_err(|e| e.to_string())?;
+        Ok(serde_json::json!({
+            "conversation": conversation,
+            "messages": messages,
+        }))
+    });
+
+    match result {
+        Ok(data) => IpcResponse {
+            status: "ok".to_string(),
+            data: Some(data),
+        },
+        Err(e) => IpcResponse {
+            status: "error".to_string(),
+            data: Some(serde_json::json!({"error": e})),
+        },
+    }
+}
+
+/// Conversations listed by `lai history` when no limit is given
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+
+/// Recent conversations, newest first, optionally only those for one provider
+fn handle_history(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
+    let db = app.state::<crate::database::Database>();
+    let result = db
+        .conn()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| history_data(&conn, msg.payload.as_ref()));
+
+    match result {
+        Ok(data) => IpcResponse {
+            status: "ok".to_string(),
+            data: Some(data),
+        },
+        Err(e) => IpcResponse {
+            status: "error".to_string(),
+            data: Some(serde_json::json!({"error": e})),
+        },
+    }
+}
+
+fn history_data(
+    conn: &rusqlite::Connection,
+    payload: Option<&JsonValue>,
+) -> Result<JsonValue, String> {
+    let limit = payload
+        .and_then(|p| p.get("limit"))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let provider = payload
+        .and_then(|p| p.get("provider"))
+        .and_then(|v| v.as_str());
+
+    let conversations = match provider {
+        Some(provider) => {
+            crate::database::conversations::Conversation::get_by_provider(conn, provider, limit)
+        }
+        None => crate::database::conversations::Conversation::get_all(conn, limit),
+    }
+    .map_err(|e| e.to_string())?;
+    serde_json::to_value(&conversations).map_err(|e| e.to_string())
+}
+
 /// Optimized create message handler with transaction management
 fn handle_create_message(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
     let Some(ref payload) = msg.payload else {
@@ -220,7 +676,7 @@ fn handle_create_message(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
             cid
         } else {
             // Create conversation in a single transaction
-            let conn = db.conn().lock().map_err(|e| e.to_string())?;
+            let conn = db.conn().map_err(|e| e.to_string())?;
             let new_conv = crate::database::conversations::NewConversation {
                 title: "Dev Test Conversation".to_string(),
                 model: "dev-model".to_string(),
@@ -254,7 +710,18 @@ fn handle_create_message(app: &AppHandle, msg: &IpcMessage) -> IpcResponse {
     }
 }
 
-pub fn start_ipc_server(app: AppHandle) {
+/// Localhost port the IPC server listens on (shared with the `lai` CLI)
+pub const IPC_PORT: u16 = 39871;
+
+/// Start the IPC server in the background. The returned handle stops it.
+pub fn start_ipc_server(app: AppHandle) -> IpcShutdownHandle {
+    let (signal_tx, mut signal_rx) = oneshot::channel();
+    let (finished_tx, finished_rx) = mpsc::channel();
+    let handle = IpcShutdownHandle {
+        signal: Mutex::new(Some(signal_tx)),
+        finished: Mutex::new(finished_rx),
+    };
+
     // Check if dev mode is enabled at startup
     let dev_mode_enabled = match std::env::var("DEV_MODE") {
         Ok(val) => {
@@ -265,47 +732,173 @@ pub fn start_ipc_server(app: AppHandle) {
     };
 
     // Fixed localhost port; can be made configurable later
-    let addr = "127.0.0.1:39871";
-    let listener = match TcpListener::bind(addr) {
+    let addr = format!("127.0.0.1:{}", IPC_PORT);
+    let listener = match TcpListener::bind(&addr) {
         Ok(l) => l,
         Err(e) => {
             eprintln!("IPC: failed to bind {}: {}", addr, e);
-            return;
+            return handle;
         }
     };
 
-    // Configure listener for performance
-    if let Err(e) = listener.set_nonblocking(false) {
-        eprintln!("IPC: failed to set blocking mode: {}", e);
+    // The accept loop runs on tokio so it can also wait for the shutdown signal
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("IPC: failed to set non-blocking mode: {}", e);
+        return handle;
     }
 
     println!("IPC: server listening on {}", addr);
 
     // Use Arc to share the app handle efficiently across threads
     let app = Arc::new(app);
+    let connections = ActiveConnections::default();
+
+    tauri::async_runtime::spawn(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("IPC: failed to start listener: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = &mut signal_rx => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        // Clients are served on blocking threads
+                        let Ok(s) = stream.into_std() else {
+                            continue;
+                        };
+                        let _ = s.set_nonblocking(false);
+
+                        let app_clone = Arc::clone(&app);
+                        let connections_clone = connections.clone();
+                        let id = connections.register(&s);
+                        // Spawn thread with optimized stack size for better memory usage
+                        let builder = thread::Builder::new()
+                            .name("ipc-client".to_string())
+                            .stack_size(2 * 1024 * 1024); // 2MB stack, default size
 
-    thread::spawn(move || {
-        for stream in listener.incoming() {
-            match stream {
-                Ok(s) => {
-                    let app_clone = Arc::clone(&app);
-                    // Spawn thread with optimized stack size for better memory usage
-                    let builder = thread::Builder::new()
-                        .name("ipc-client".to_string())
-                        .stack_size(2 * 1024 * 1024); // 2MB stack, default size
-
-                    if let Ok(_handle) = builder
-                        .spawn(move || handle_client(s, (*app_clone).clone(), dev_mode_enabled))
-                    {
-                        // Thread is detached when JoinHandle is dropped
-                    } else {
-                        eprintln!("IPC: failed to spawn client thread");
+                        if let Ok(_handle) = builder.spawn(move || {
+                            handle_client(s, (*app_clone).clone(), dev_mode_enabled);
+                            connections_clone.remove(id);
+                        }) {
+                            // Thread is detached when JoinHandle is dropped
+                        } else {
+                            connections.remove(id);
+                            eprintln!("IPC: failed to spawn client thread");
+                        }
                     }
-                }
-                Err(e) => {
-                    eprintln!("IPC: connection failed: {}", e);
-                }
+                    Err(e) => {
+                        eprintln!("IPC: connection failed: {}", e);
+                    }
+                },
             }
         }
+
+        // Stop accepting before waiting on the connections that are still open
+        drop(listener);
+        connections.drain(SHUTDOWN_GRACE_PERIOD).await;
+        println!("IPC: server stopped");
+        let _ = finished_tx.send(());
     });
+
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::conversations::{Conversation, NewConversation};
+    use crate::database::Database;
+    use std::path::PathBuf;
+
+    #[test]
+    fn history_request_round_trip() {
+        let db = Database::new(PathBuf::from(":memory:")).expect("db init");
+        let conn = db.conn().expect("conn");
+        for (title, provider) in [("One", "openai"), ("Two", "ollama"), ("Three", "openai")] {
+            Conversation::create(
+                &conn,
+                NewConversation {
+                    title: title.to_string(),
+                    model: "model".to_string(),
+                    provider: provider.to_string(),
+                    system_prompt: None,
+                },
+            )
+            .expect("create conv");
+        }
+
+        // As sent by `lai history --limit 5 --provider openai`
+        let request: IpcMessage = serde_json::from_str(
+            r#"{"type":"history","payload":{"limit":5,"provider":"openai"},"capabilities":["lz4"]}"#,
+        )
+        .unwrap();
+        let data = history_data(&conn, request.payload.as_ref()).unwrap();
+        let response = IpcResponse {
+            status: "ok".to_string(),
+            data: Some(data),
+        };
+        let line = encode_line(serde_json::to_string(&response).unwrap(), true);
+        let decoded: JsonValue = serde_json::from_str(&decode_line(&line).unwrap()).unwrap();
+
+        let conversations = decoded["data"].as_array().unwrap();
+        assert_eq!(conversations.len(), 2);
+        assert!(conversations.iter().all(|c| c["provider"] == "openai"));
+
+        let limited = history_data(&conn, Some(&serde_json::json!({ "limit": 1 }))).unwrap();
+        assert_eq!(limited.as_array().unwrap().len(), 1);
+        let all = history_data(&conn, None).unwrap();
+        assert_eq!(all.as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn encode_line_compresses_only_large_payloads() {
+        let large = format!(
+            r#"{{"status":"ok","data":{{"content":"{}"}}}}"#,
+            "x".repeat(COMPRESSED_THRESHOLD)
+        );
+        let line = encode_line(large.clone(), true);
+        let encoded = line.strip_prefix(COMPRESSED_PREFIX).expect("compressed");
+        assert!(line.len() < large.len());
+        assert!(!line.contains('\n'));
+        // Decoded the way the CLI does
+        let raw = lz4_flex::decompress_size_prepended(&BASE64.decode(encoded).unwrap()).unwrap();
+        assert_eq!(String::from_utf8(raw).unwrap(), large);
+        assert_eq!(decode_line(&line).unwrap(), large);
+
+        // Uncompressed when the client didn't ask for it or the payload is small
+        assert_eq!(encode_line(large.clone(), false), large);
+        let small = r#"{"status":"ok"}"#.to_string();
+        assert_eq!(encode_line(small.clone(), true), small);
+    }
+
+    #[test]
+    fn stream_frames_carry_only_their_fields() {
+        let chunk = IpcStreamFrame {
+            chunk: Some("Hel".to_string()),
+            ..Default::default()
+        };
+        let done = IpcStreamFrame {
+            done: true,
+            conversation_id: Some("c1".to_string()),
+            ..Default::default()
+        };
+        let error = IpcStreamFrame {
+            error: Some("rate limited".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_string(&chunk).unwrap(), r#"{"chunk":"Hel"}"#);
+        assert_eq!(
+            serde_json::to_string(&done).unwrap(),
+            r#"{"done":true,"conversation_id":"c1"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"error":"rate limited"}"#
+        );
+    }
 }
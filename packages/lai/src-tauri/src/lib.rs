@@ -5,6 +5,7 @@ pub mod database;
 pub mod git;
 mod ipc;
 pub mod project;
+mod tray;
 
 use std::path::PathBuf;
 use tauri::{Emitter, Manager};
@@ -38,6 +39,15 @@ pub fn run() {
             let db_path: PathBuf = app_data_dir.join("database.db");
             let db = database::Database::new(db_path).expect("Failed to initialize database");
             app.manage(db);
+            app.manage(commands::provider::ProviderRateLimiter::default());
+            app.manage(commands::performance::ProviderLatencyStore::default());
+            app.manage(ipc::IpcServerMetrics::default());
+            app.manage(commands::project::ProjectState::default());
+            app.manage(commands::project::FileWatches::default());
+
+            // Built here rather than from the config so only users who lowered the
+            // window opacity get a transparent window
+            commands::window::create_main_window(app.handle(), &app.state::<database::Database>())?;
 
             // Register a global shortcut (CommandOrControl+Space) to toggle main window.
             // Do this by constructing the plugin with its handler here (registering it once).
@@ -53,6 +63,10 @@ pub fn run() {
                     })
                     .with_handler(|app, _shortcut, event| {
                         if event.state == ShortcutState::Pressed {
+                            commands::shortcuts::record_shortcut_event(
+                                app,
+                                &commands::shortcuts::ShortcutAction::ToggleWindow,
+                            );
                             if let Some(window) = app.get_webview_window("main") {
                                 match window.is_visible() {
                                     Ok(true) => {
@@ -73,18 +87,15 @@ pub fn run() {
             // Create a system tray (desktop only)
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             {
-                use tauri::{menu::MenuBuilder, tray::TrayIconBuilder, Manager};
+                use tauri::{tray::TrayIconBuilder, Manager};
 
                 let handle = app.handle();
 
-                // Build a small context menu with Toggle, New Conversation, Settings, and Quit actions.
-                if let Ok(menu) = MenuBuilder::new(handle)
-                    .text("toggle", "Show/Hide")
-                    .text("new-convo", "New Conversation")
-                    .text("settings", "Settings")
-                    .text("quit", "Quit")
-                    .build()
-                {
+                // Build the context menu with Toggle, New Conversation, Settings, clipboard
+                // history, recent conversations, and Quit actions.
+                let recent = tray::recent_conversations(handle);
+                let clipboard = commands::provider::clipboard_history();
+                if let Ok(menu) = tray::build_tray_menu(handle, &recent, &clipboard) {
                     // Make the builder mutable so we can optionally attach an icon at runtime
                     let mut tray_builder = TrayIconBuilder::with_id("main")
                         .menu(&menu)
@@ -131,9 +142,41 @@ pub fn run() {
                                     );
                                 }
                                 "quit" => {
-                                    std::process::exit(0);
+                                    // Runs the exit handler, which stops the IPC server
+                                    app.exit(0);
+                                }
+                                other => {
+                                    if let Some(index) = other
+                                        .strip_prefix(tray::CLIPBOARD_ITEM_PREFIX)
+                                        .and_then(|i| i.parse::<usize>().ok())
+                                    {
+                                        if let Some(text) =
+                                            commands::provider::clipboard_history().get(index)
+                                        {
+                                            if let Some(window) = app.get_webview_window("main") {
+                                                let _ = window.show();
+                                                let _ = window.set_focus();
+                                            }
+                                            let _ = app.emit_to(
+                                                tauri::EventTarget::any(),
+                                                "tray://clipboard-selected",
+                                                serde_json::json!({ "text": text }),
+                                            );
+                                        }
+                                    } else if let Some(conversation_id) =
+                                        other.strip_prefix(tray::CONVERSATION_ITEM_PREFIX)
+                                    {
+                                        if let Some(window) = app.get_webview_window("main") {
+                                            let _ = window.show();
+                                            let _ = window.set_focus();
+                                        }
+                                        let _ = app.emit_to(
+                                            tauri::EventTarget::any(),
+                                            "tray://open-conversation",
+                                            serde_json::json!({ "id": conversation_id }),
+                                        );
+                                    }
                                 }
-                                _ => {}
                             }
                         });
 
@@ -186,6 +229,9 @@ pub fn run() {
                         eprintln!("failed to build tray icon: {}", e);
                     }
                 }
+
+                // Record copied text for the Clipboard History submenu, if the user opted in
+                commands::provider::init_clipboard_watcher(handle);
             }
 
             println!("Database initialized successfully!");
@@ -193,6 +239,9 @@ pub fn run() {
             // Initialize shortcut manager
             commands::shortcuts::initialize_shortcut_manager(app.handle().clone());
 
+            // Run scheduled exports in the background
+            commands::export_schedules::start_export_scheduler(app.handle().clone());
+
             // Restore window state on startup
             let app_handle = app.handle().clone();
             let db_handle = app.handle().clone();
@@ -237,7 +286,8 @@ pub fn run() {
                     }
                 });
             } // Start CLI IPC server
-            crate::ipc::start_ipc_server(app.handle().clone());
+            let ipc_shutdown = crate::ipc::start_ipc_server(app.handle().clone());
+            app.manage(ipc_shutdown);
             Ok(())
         })
         // Register Tauri commands implemented in `src-tauri/src/commands`
@@ -245,27 +295,61 @@ pub fn run() {
             // conversations
             commands::conversations::create_conversation,
             commands::conversations::get_conversation,
+            commands::conversations::get_conversation_with_messages,
             commands::conversations::get_all_conversations,
+            commands::conversations::get_conversations_by_date_range,
+            commands::conversations::get_conversations_created_today,
+            commands::conversations::get_conversations_created_this_week,
+            commands::conversations::get_conversations_by_provider,
+            commands::conversations::get_conversations_by_model,
+            commands::conversations::get_provider_usage_stats,
+            commands::conversations::get_conversations_summary_stats,
             commands::conversations::update_conversation_title,
+            commands::conversations::update_conversation_settings,
             commands::conversations::delete_conversation,
             commands::conversations::restore_conversation,
+            commands::conversations::archive_conversation,
+            commands::conversations::unarchive_conversation,
+            commands::conversations::get_archived_conversations,
+            commands::conversations::get_conversations_without_messages,
+            commands::conversations::delete_empty_conversations,
+            commands::conversations::generate_title_from_content,
+            commands::conversations::undo_last_action,
             commands::conversations::search_conversations,
-            commands::conversations::cleanup_conversations,
+            commands::conversations::search_conversations_by_tag_and_query,
+            commands::conversations::archive_all_conversations,
+            commands::conversations::permanently_cleanup_conversations,
+            commands::conversations::duplicate_conversation,
             commands::conversations::create_conversation_branch,
             commands::conversations::get_conversation_branches,
             // messages
             commands::messages::create_message,
+            commands::messages::create_message_batch,
             commands::messages::get_conversation_messages,
+            commands::messages::get_conversation_messages_paginated,
+            commands::messages::get_conversation_messages_before,
+            commands::messages::get_message,
             commands::messages::get_last_messages,
             commands::messages::search_messages,
+            commands::messages::search_messages_in_conversation,
+            commands::messages::get_messages_with_code_blocks,
+            commands::messages::export_code_blocks,
+            commands::messages::split_conversation_at_message,
             commands::messages::update_message,
             commands::messages::delete_message,
             commands::messages::get_conversation_token_count,
             commands::messages::get_last_assistant_message,
+            commands::messages::create_message_with_conversation_context,
+            commands::messages::regenerate_message,
+            commands::messages::insert_system_message,
+            commands::messages::create_message_from_clipboard,
+            commands::messages::paste_clipboard_and_ask,
+            commands::messages::get_message_context_window,
             // settings
             commands::settings::set_setting,
             commands::settings::get_setting,
             commands::settings::get_all_settings,
+            commands::settings::get_all_settings_grouped,
             commands::settings::delete_setting,
             // window
             commands::window::toggle_main_window,
@@ -273,18 +357,50 @@ pub fn run() {
             commands::window::restore_window_state,
             commands::window::get_window_state,
             commands::window::reset_window_state,
+            commands::window::get_all_window_states,
+            commands::window::save_all_window_states,
+            commands::window::set_window_opacity,
+            commands::window::set_window_always_on_top,
+            // database diagnostics
+            commands::database::list_pending_migrations,
+            commands::database::get_schema_version,
+            commands::database::check_database_integrity,
+            commands::database::get_event_log,
             // health
             commands::health::ping,
+            commands::health::deep_health_check,
             // provider
             commands::provider::provider_openai_generate,
+            commands::provider::provider_openai_vision_generate,
+            commands::provider::provider_openai_generate_with_system,
+            commands::provider::provider_openai_json_mode,
+            commands::provider::provider_openai_structured_output,
             commands::provider::provider_openai_stream,
             commands::provider::provider_anthropic_generate,
+            commands::provider::provider_anthropic_stream,
             commands::provider::provider_gemini_generate,
+            commands::provider::provider_gemini_stream,
             commands::provider::provider_ollama_generate,
             commands::provider::provider_ollama_stream,
+            commands::provider::stream_to_conversation,
             commands::provider::ollama_list_models,
+            commands::provider::list_openai_models,
+            commands::provider::list_anthropic_models,
+            commands::provider::list_gemini_models,
+            commands::provider::list_all_provider_models,
             commands::provider::ollama_pull_model,
+            commands::provider::ollama_pull_model_stream,
             commands::provider::ollama_check_connection,
+            commands::provider::check_all_providers_health,
+            commands::provider::generate_embedding,
+            commands::provider::generate_embeddings_batch,
+            commands::provider::get_rate_limit_status,
+            commands::provider::get_provider_config,
+            commands::provider::set_provider_config,
+            commands::provider::token_count_estimate,
+            commands::provider::context_limit_for_model,
+            commands::provider::get_clipboard_history,
+            commands::provider::clear_clipboard_history,
             commands::provider::set_api_key,
             commands::provider::get_api_key,
             // export/import
@@ -298,20 +414,67 @@ pub fn run() {
             commands::export::load_import_file,
             commands::export::export_single_conversation_json,
             commands::export::save_single_conversation_export,
+            commands::export::export_conversation_notion,
+            commands::export::export_conversation_obsidian,
+            commands::export::export_conversation_as_training_data,
+            commands::export::generate_conversation_share_link,
+            commands::export::decode_share_link,
             // git
             commands::git::get_git_context,
             commands::git::format_git_context,
+            commands::git::get_git_log_graph,
+            commands::git::get_repository_contributors,
+            commands::git::list_worktrees,
+            commands::git::create_worktree,
+            commands::git::remove_worktree,
+            commands::git::get_git_diff,
+            commands::git::get_git_file_history,
+            commands::git::get_git_file_at_commit,
+            commands::git::get_cherry_pick_candidates,
+            commands::git::cherry_pick_commit,
+            commands::git::generate_commit_message,
+            commands::git::get_git_config,
+            commands::git::set_git_config,
+            // code execution helpers
+            commands::run::run_jq,
+            commands::run::run_jq_on_file,
+            commands::run::list_supported_languages,
+            commands::run::check_language_available,
+            commands::run::get_audit_stats,
+            commands::run::export_audit_csv,
+            commands::run::get_audit_records,
+            commands::run::delete_audit_record,
             // project watcher
             commands::project::set_project_root,
             commands::project::stop_project_watch,
+            commands::project::watch_file,
+            commands::project::unwatch_file,
             commands::project::update_ignore_patterns,
             commands::project::search_project_files,
             commands::project::search_project_files_in_path,
+            commands::project::get_largest_files,
+            commands::project::get_recently_modified_files,
+            commands::project::list_files_by_extension,
+            commands::project::count_files_by_extension,
+            commands::project::rename_project_file,
+            commands::project::delete_project_file,
+            commands::project::create_project_file,
             commands::project::detect_project_type,
+            commands::project::build_project,
+            commands::project::run_project_tests,
+            commands::project::get_project_vulnerabilities,
             // performance monitoring
             commands::performance::get_performance_metrics,
             commands::performance::get_database_metrics,
+            commands::performance::get_ipc_metrics,
+            commands::performance::reset_ipc_metrics,
+            commands::performance::benchmark_provider,
+            commands::performance::get_response_latency_stats,
             commands::performance::get_full_performance_snapshot,
+            commands::performance::get_usage_over_time,
+            commands::performance::get_cost_over_time,
+            commands::performance::get_top_token_conversations,
+            commands::performance::get_daily_token_usage,
             // profiles
             commands::profiles::create_profile,
             commands::profiles::get_profile,
@@ -320,11 +483,19 @@ pub fn run() {
             commands::profiles::set_active_profile,
             commands::profiles::update_profile,
             commands::profiles::delete_profile,
+            commands::profiles::duplicate_profile,
+            commands::profiles::reset_profile_to_defaults,
+            commands::profiles::set_profile_temperature,
+            commands::profiles::set_conversation_profile,
+            commands::profiles::get_profile_conversation_stats,
             // shortcuts
             commands::shortcuts::get_shortcut_config,
             commands::shortcuts::update_shortcut_config,
             commands::shortcuts::validate_shortcut,
             commands::shortcuts::get_available_actions,
+            commands::shortcuts::import_shortcuts_from_vscode_keymap,
+            commands::shortcuts::get_shortcut_usage_stats,
+            commands::shortcuts::clear_shortcut_stats,
             // tags
             commands::tags::create_tag,
             commands::tags::get_tag,
@@ -337,8 +508,24 @@ pub fn run() {
             commands::tags::add_tag_to_conversation,
             commands::tags::remove_tag_from_conversation,
             commands::tags::get_conversations_by_tag,
+            commands::tags::get_tag_usage_timeline,
+            commands::tags::get_tagging_activity_heatmap,
+            commands::tags::get_tag_cloud,
+            commands::tags::get_related_tags,
             commands::tags::create_or_get_tag,
             commands::tags::add_tags_to_conversation_bulk,
+            // webhooks
+            commands::webhooks::create_webhook,
+            commands::webhooks::get_webhooks,
+            commands::webhooks::update_webhook,
+            commands::webhooks::delete_webhook,
+            commands::webhooks::test_webhook,
+            // scheduled exports
+            commands::export_schedules::create_export_schedule,
+            commands::export_schedules::get_export_schedules,
+            commands::export_schedules::get_export_schedule,
+            commands::export_schedules::update_export_schedule,
+            commands::export_schedules::delete_export_schedule,
             // workspace templates
             commands::workspace_templates::create_workspace_template,
             commands::workspace_templates::get_workspace_template,
@@ -346,6 +533,10 @@ pub fn run() {
             commands::workspace_templates::get_workspace_templates_by_category,
             commands::workspace_templates::get_workspace_template_categories,
             commands::workspace_templates::update_workspace_template,
+            commands::workspace_templates::get_workspace_template_settings,
+            commands::workspace_templates::update_workspace_template_settings,
+            commands::workspace_templates::clone_workspace_template,
+            commands::workspace_templates::override_builtin_template,
             commands::workspace_templates::delete_workspace_template,
             commands::workspace_templates::search_workspace_templates,
             // updater
@@ -353,6 +544,13 @@ pub fn run() {
             commands::updater::download_and_install_update,
             commands::updater::get_current_version,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(ipc) = app.try_state::<ipc::IpcShutdownHandle>() {
+                    ipc.shutdown();
+                }
+            }
+        });
 }
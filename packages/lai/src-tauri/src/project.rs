@@ -21,6 +21,9 @@ pub enum ProjectType {
     Ruby,
     Php,
     CSharp,
+    Elixir,
+    Haskell,
+    Zig,
     Unknown,
 }
 
@@ -62,6 +65,18 @@ impl ProjectInfo {
             return Self::detect_php(path);
         }
 
+        if path.join("mix.exs").exists() {
+            return Self::detect_elixir(path);
+        }
+
+        if find_cabal_file(path).is_some() {
+            return Self::detect_haskell(path);
+        }
+
+        if path.join("build.zig").exists() {
+            return Self::detect_zig(path);
+        }
+
         if path.join("*.csproj").exists() || path.join("*.sln").exists() {
             return Self::detect_csharp(path);
         }
@@ -278,6 +293,114 @@ impl ProjectInfo {
         }
     }
 
+    fn detect_elixir(path: &Path) -> Self {
+        let mix_exs_path = path.join("mix.exs");
+
+        if let Ok(content) = fs::read_to_string(&mix_exs_path) {
+            // `project/0` returns a keyword list; values are often module attributes
+            let resolve = |value: &str| -> Option<String> {
+                match value.strip_prefix('@') {
+                    Some(attr) => content.lines().find_map(|line| {
+                        let rest = line.trim().strip_prefix('@')?.strip_prefix(attr)?;
+                        if !rest.starts_with(char::is_whitespace) {
+                            return None;
+                        }
+                        quoted_value(rest.trim())
+                    }),
+                    None => quoted_value(value),
+                }
+            };
+
+            return ProjectInfo {
+                project_type: ProjectType::Elixir,
+                version: keyword_value(&content, "version").and_then(|v| resolve(&v)),
+                name: keyword_value(&content, "app")
+                    .and_then(|v| v.strip_prefix(':').map(String::from)),
+                description: keyword_value(&content, "description").and_then(|v| resolve(&v)),
+            };
+        }
+
+        ProjectInfo {
+            project_type: ProjectType::Elixir,
+            version: None,
+            name: None,
+            description: None,
+        }
+    }
+
+    fn detect_haskell(path: &Path) -> Self {
+        if let Some(content) = find_cabal_file(path).and_then(|p| fs::read_to_string(p).ok()) {
+            // Top-level cabal fields are unindented and case-insensitive
+            let field = |key: &str| -> Option<String> {
+                content.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    if line.starts_with(char::is_whitespace)
+                        || !name.trim().eq_ignore_ascii_case(key)
+                    {
+                        return None;
+                    }
+                    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+                })
+            };
+
+            return ProjectInfo {
+                project_type: ProjectType::Haskell,
+                version: field("version"),
+                name: field("name"),
+                description: field("synopsis"),
+            };
+        }
+
+        ProjectInfo {
+            project_type: ProjectType::Haskell,
+            version: None,
+            name: None,
+            description: None,
+        }
+    }
+
+    fn detect_zig(path: &Path) -> Self {
+        // build.zig.zon is the package manifest; older projects only declare a version in build.zig
+        let zon = fs::read_to_string(path.join("build.zig.zon")).unwrap_or_default();
+        let zon_field = |key: &str| -> Option<String> {
+            zon.lines().find_map(|line| {
+                let rest = line
+                    .trim()
+                    .strip_prefix(key)?
+                    .trim_start()
+                    .strip_prefix('=')?;
+                let value = rest.trim().trim_end_matches(',');
+                // Zig 0.14 writes the name as an enum literal: `.name = .my_app`
+                quoted_value(value).or_else(|| {
+                    value
+                        .strip_prefix('.')
+                        .filter(|v| !v.is_empty())
+                        .map(String::from)
+                })
+            })
+        };
+
+        let version = zon_field(".version").or_else(|| {
+            let build_zig = fs::read_to_string(path.join("build.zig")).ok()?;
+            build_zig.lines().find_map(|line| {
+                let rest = line
+                    .trim()
+                    .strip_prefix("pub ")
+                    .unwrap_or(line.trim())
+                    .strip_prefix("const version")?;
+                let rest = rest.split_once('=')?.1;
+                quoted_value(rest.trim().trim_end_matches(';'))
+            })
+        });
+
+        ProjectInfo {
+            project_type: ProjectType::Zig,
+            version,
+            name: zon_field(".name"),
+            description: None,
+        }
+    }
+
     /// Format project info as human-readable string
     pub fn format(&self) -> String {
         let type_name = match self.project_type {
@@ -289,6 +412,9 @@ impl ProjectInfo {
             ProjectType::Ruby => "Ruby",
             ProjectType::Php => "PHP",
             ProjectType::CSharp => "C#",
+            ProjectType::Elixir => "Elixir",
+            ProjectType::Haskell => "Haskell",
+            ProjectType::Zig => "Zig",
             ProjectType::Unknown => return String::from("Unknown project type"),
         };
 
@@ -316,11 +442,40 @@ impl ProjectInfo {
             ProjectType::Ruby => "💎",
             ProjectType::Php => "🐘",
             ProjectType::CSharp => "#️⃣",
+            ProjectType::Elixir => "💧",
+            ProjectType::Haskell => "λ",
+            ProjectType::Zig => "⚡",
             ProjectType::Unknown => "📁",
         }
     }
 }
 
+/// First `*.cabal` package description in the directory, if any
+fn find_cabal_file(path: &Path) -> Option<std::path::PathBuf> {
+    fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .find(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "cabal"))
+}
+
+/// Contents of a double-quoted string literal at the start of `value`
+fn quoted_value(value: &str) -> Option<String> {
+    let rest = value.strip_prefix('"')?;
+    rest.find('"').map(|end| rest[..end].to_string())
+}
+
+/// Raw value of the first `key: value` entry in an Elixir keyword list
+fn keyword_value(content: &str, key: &str) -> Option<String> {
+    let prefix = format!("{}:", key);
+    content.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(&prefix)?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some(rest.trim().trim_end_matches(',').trim().to_string())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,4 +572,76 @@ mod tests {
             "🐍"
         );
     }
+
+    #[test]
+    fn test_detect_elixir_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let mix_exs = r#"
+defmodule MyApp.MixProject do
+  use Mix.Project
+
+  @version "0.3.1"
+
+  def project do
+    [
+      app: :my_app,
+      version: @version,
+      elixir: "~> 1.15",
+      description: "An Elixir app",
+      deps: deps()
+    ]
+  end
+end
+"#;
+        fs::write(temp_dir.path().join("mix.exs"), mix_exs).unwrap();
+
+        let info = ProjectInfo::detect(temp_dir.path());
+        assert_eq!(info.project_type, ProjectType::Elixir);
+        assert_eq!(info.name, Some("my_app".to_string()));
+        assert_eq!(info.version, Some("0.3.1".to_string()));
+        assert_eq!(info.description, Some("An Elixir app".to_string()));
+    }
+
+    #[test]
+    fn test_detect_haskell_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let cabal = "cabal-version:      3.0\nName:               my-hs-app\nversion:            1.2.0.0\nsynopsis:           A Haskell app\n\nexecutable my-hs-app\n    main-is:          Main.hs\n";
+        fs::write(temp_dir.path().join("my-hs-app.cabal"), cabal).unwrap();
+
+        let info = ProjectInfo::detect(temp_dir.path());
+        assert_eq!(info.project_type, ProjectType::Haskell);
+        assert_eq!(info.name, Some("my-hs-app".to_string()));
+        assert_eq!(info.version, Some("1.2.0.0".to_string()));
+        assert_eq!(info.description, Some("A Haskell app".to_string()));
+    }
+
+    #[test]
+    fn test_detect_zig_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("build.zig"),
+            "const std = @import(\"std\");\nconst version = \"0.0.9\";\n",
+        )
+        .unwrap();
+
+        // Version from build.zig when there is no manifest
+        let info = ProjectInfo::detect(temp_dir.path());
+        assert_eq!(info.project_type, ProjectType::Zig);
+        assert_eq!(info.version, Some("0.0.9".to_string()));
+        assert_eq!(info.name, None);
+
+        let zon = r#"
+.{
+    .name = .my_zig_app,
+    .version = "0.1.0",
+    .dependencies = .{},
+}
+"#;
+        fs::write(temp_dir.path().join("build.zig.zon"), zon).unwrap();
+
+        let info = ProjectInfo::detect(temp_dir.path());
+        assert_eq!(info.name, Some("my_zig_app".to_string()));
+        assert_eq!(info.version, Some("0.1.0".to_string()));
+        assert_eq!(info.format(), "Zig (my_zig_app) v0.1.0");
+    }
 }
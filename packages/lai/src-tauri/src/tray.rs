@@ -0,0 +1,155 @@
+use crate::commands::provider::clipboard_history;
+use crate::database::{conversations::Conversation, Database};
+use serde::{Deserialize, Serialize};
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// How many recent conversations the tray menu lists
+const RECENT_CONVERSATION_LIMIT: usize = 5;
+const MAX_TITLE_CHARS: usize = 40;
+/// Menu item IDs for conversations are this prefix followed by the conversation ID
+pub const CONVERSATION_ITEM_PREFIX: &str = "conversation:";
+/// How many clipboard entries the Clipboard History submenu lists
+const CLIPBOARD_MENU_LIMIT: usize = 5;
+/// Menu item IDs for clipboard entries are this prefix followed by the history index
+pub const CLIPBOARD_ITEM_PREFIX: &str = "clipboard:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrayConversation {
+    pub id: String,
+    pub title: String,
+}
+
+fn truncate_title(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        title.to_string()
+    } else {
+        let mut truncated: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// Single-line menu label for a clipboard entry
+fn clipboard_label(text: &str) -> String {
+    truncate_title(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Build the tray menu: fixed actions, clipboard history, recent conversations, then Quit.
+/// `clipboard` is newest first.
+pub fn build_tray_menu<R: Runtime>(
+    handle: &AppHandle<R>,
+    conversations: &[TrayConversation],
+    clipboard: &[String],
+) -> tauri::Result<Menu<R>> {
+    let mut builder = MenuBuilder::new(handle)
+        .text("toggle", "Show/Hide")
+        .text("new-convo", "New Conversation")
+        .text("settings", "Settings");
+
+    let mut clipboard_menu = SubmenuBuilder::new(handle, "Clipboard History");
+    if clipboard.is_empty() {
+        let empty = MenuItemBuilder::with_id("clipboard-empty", "(empty)")
+            .enabled(false)
+            .build(handle)?;
+        clipboard_menu = clipboard_menu.item(&empty);
+    }
+    for (index, text) in clipboard.iter().take(CLIPBOARD_MENU_LIMIT).enumerate() {
+        let item = MenuItemBuilder::with_id(
+            format!("{}{}", CLIPBOARD_ITEM_PREFIX, index),
+            clipboard_label(text),
+        )
+        .build(handle)?;
+        clipboard_menu = clipboard_menu.item(&item);
+    }
+    builder = builder.item(&clipboard_menu.build()?);
+
+    if !conversations.is_empty() {
+        builder = builder.separator();
+        for conv in conversations.iter().take(RECENT_CONVERSATION_LIMIT) {
+            let item = MenuItemBuilder::with_id(
+                format!("{}{}", CONVERSATION_ITEM_PREFIX, conv.id),
+                truncate_title(&conv.title),
+            )
+            .build(handle)?;
+            builder = builder.item(&item);
+        }
+        builder = builder.separator();
+    }
+
+    builder.text("quit", "Quit").build()
+}
+
+/// Most recently updated conversations, for the tray menu
+pub fn recent_conversations<R: Runtime>(app: &AppHandle<R>) -> Vec<TrayConversation> {
+    let db = app.state::<Database>();
+    let Ok(conn) = db.conn() else {
+        return Vec::new();
+    };
+    Conversation::get_unarchived(&conn, RECENT_CONVERSATION_LIMIT as i64)
+        .map(|convs| {
+            convs
+                .into_iter()
+                .map(|c| TrayConversation {
+                    id: c.id,
+                    title: c.title,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Replace the tray menu so it lists the given conversations
+pub fn update_tray_menu_conversations<R: Runtime>(
+    app: &AppHandle<R>,
+    conversations: Vec<TrayConversation>,
+) {
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+    match build_tray_menu(app, &conversations, &clipboard_history()) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                eprintln!("failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => eprintln!("failed to build tray menu: {}", e),
+    }
+}
+
+/// Rebuild the tray menu with current conversations and clipboard history.
+/// Callers must not hold the database lock.
+pub fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) {
+    update_tray_menu_conversations(app, recent_conversations(app));
+}
+
+/// Reload recent conversations into the tray and notify the frontend.
+/// Callers must not hold the database lock.
+pub fn refresh_recent_conversations<R: Runtime>(app: &AppHandle<R>) {
+    let conversations = recent_conversations(app);
+    let _ = app.emit("conversations://updated", &conversations);
+    update_tray_menu_conversations(app, conversations);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_title() {
+        assert_eq!(truncate_title("Short title"), "Short title");
+
+        let long = "a".repeat(50);
+        let truncated = truncate_title(&long);
+        assert_eq!(truncated.chars().count(), MAX_TITLE_CHARS);
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[test]
+    fn test_clipboard_label_is_single_line() {
+        assert_eq!(
+            clipboard_label("fn main() {\n    run();\n}"),
+            "fn main() { run(); }"
+        );
+    }
+}
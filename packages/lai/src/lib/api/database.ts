@@ -7,7 +7,9 @@ import type {
   NewConversation,
   NewMessage,
   ApiConversation,
+  ApiConversationWithMessages,
   ApiMessage,
+  ApiSearchedMessage,
   Setting,
   ApiTag,
   NewTag,
@@ -39,6 +41,7 @@ async function callInvoke<T>(
       case "get_all_conversations":
         return [] as unknown as T;
       case "get_conversation":
+      case "get_conversation_with_messages":
         return null as unknown as T;
       case "create_conversation":
         return {
@@ -351,6 +354,16 @@ export const database = {
       return callInvoke<Conversation | null>("get_conversation", { id });
     },
 
+    getWithMessages: async (
+      id: string,
+      includeTokenCount: boolean = false,
+    ): Promise<ApiConversationWithMessages | null> => {
+      return callInvoke<ApiConversationWithMessages | null>(
+        "get_conversation_with_messages",
+        { id, include_token_count: includeTokenCount },
+      );
+    },
+
     getAll: async (limit: number = 50): Promise<Conversation[]> => {
       return callInvoke<Conversation[]>("get_all_conversations", { limit });
     },
@@ -443,7 +456,21 @@ export const database = {
     },
 
     search: async (query: string, limit: number = 50): Promise<Message[]> => {
-      return callInvoke<Message[]>("search_messages", { query, limit });
+      const results = await callInvoke<ApiSearchedMessage[]>(
+        "search_messages",
+        { query, limit },
+      );
+      return results.map((r) => r.message);
+    },
+
+    searchRanked: async (
+      query: string,
+      limit: number = 50,
+    ): Promise<ApiSearchedMessage[]> => {
+      return callInvoke<ApiSearchedMessage[]>("search_messages", {
+        query,
+        limit,
+      });
     },
 
     delete: async (id: string): Promise<void> => {
@@ -23,6 +23,21 @@ export interface ApiMessage {
   status?: "pending" | "sent" | "failed";
 }
 
+// A conversation with its messages and tags, loaded in one call
+export interface ApiConversationWithMessages {
+  conversation: ApiConversation;
+  messages: ApiMessage[];
+  total_tokens?: number | null;
+  tags: ApiTag[];
+}
+
+// Full-text message search hit with BM25 rank and highlighted excerpt
+export interface ApiSearchedMessage {
+  message: ApiMessage;
+  rank: number;
+  snippet: string;
+}
+
 // Document search types
 export interface FileMatch {
   path: string;
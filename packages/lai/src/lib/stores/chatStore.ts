@@ -104,16 +104,14 @@ export const useChatStore = create<ChatState>((set, get) => ({
     try {
       set({ isLoading: true, error: null });
 
-      const conversation = await db.conversations.get(id);
-      if (!conversation) {
+      const loaded = await db.conversations.getWithMessages(id);
+      if (!loaded) {
         throw new Error("Conversation not found");
       }
 
-      const messages = await db.messages.getByConversation(id);
-
       set({
-        currentConversation: conversation,
-        messages,
+        currentConversation: loaded.conversation,
+        messages: loaded.messages,
         isLoading: false,
       });
     } catch (error) {